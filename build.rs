@@ -0,0 +1,31 @@
+// Build script that generates the C header for the FFI layer
+//
+// The header goes to OUT_DIR so a build never touches the source tree. Set RUSTMERGER_HEADER_DIR
+// (for example to `include`) to also copy it there when the checked-in header needs refreshing.
+const HEADER_SOURCES: &[&str] = &["src/ffi.rs", "src/plugin.rs"]; // Files making up the C interface; nothing else reaches the header
+
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+
+    for source in HEADER_SOURCES {
+        println!("cargo:rerun-if-changed={}", source);
+    }
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=RUSTMERGER_HEADER_DIR");
+
+    // Load header settings from cbindgen.toml and write rustmerger.h from the C interface files only
+    let config = cbindgen::Config::from_root_or_default(&crate_dir);
+    let builder = HEADER_SOURCES.iter()
+        .fold(cbindgen::Builder::new().with_config(config), |builder, source| builder.with_src(std::path::Path::new(&crate_dir).join(source)));
+    match builder.generate() {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/rustmerger.h", out_dir));
+            if let Ok(header_dir) = std::env::var("RUSTMERGER_HEADER_DIR") {
+                let header_dir = std::path::Path::new(&crate_dir).join(header_dir);
+                bindings.write_to_file(header_dir.join("rustmerger.h"));
+            }
+        }
+        Err(e) => println!("cargo:warning=Failed to generate C header: {}", e),
+    }
+}
@@ -0,0 +1,167 @@
+/* Generated by cbindgen from src/ffi.rs and src/plugin.rs. Do not edit by hand. */
+
+#ifndef RUSTMERGER_H
+#define RUSTMERGER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Version of the plugin interface; plugins built for another version are refused.
+ */
+#define RUSTMERGER_PLUGIN_ABI_VERSION 1
+
+/**
+ * Keep the candidate as it is.
+ */
+#define RUSTMERGER_PLUGIN_KEEP 0
+
+/**
+ * Drop the candidate.
+ */
+#define RUSTMERGER_PLUGIN_DROP 1
+
+/**
+ * Replace the candidate with the decision's bytes.
+ */
+#define RUSTMERGER_PLUGIN_REPLACE 2
+
+typedef enum RustmergerStatus {
+  RUSTMERGER_STATUS_RUNNING = 0,
+  RUSTMERGER_STATUS_COMPLETED = 1,
+  RUSTMERGER_STATUS_FAILED = 2,
+  RUSTMERGER_STATUS_CANCELLED = 3,
+  RUSTMERGER_STATUS_INVALID_ARGUMENT = 4,
+} RustmergerStatus;
+
+typedef struct RustmergerJob RustmergerJob;
+
+typedef struct RustmergerProgress {
+  uint64_t files_total;
+  uint64_t files_processed;
+} RustmergerProgress;
+
+typedef struct RustmergerStats {
+  uint64_t lines_processed;
+  uint64_t unique_lines;
+  uint64_t elapsed_ms;
+} RustmergerStats;
+
+/**
+ * One candidate handed to a plugin.
+ */
+typedef struct RustmergerPluginLine {
+  /**
+   * Bytes of the candidate, without a newline.
+   */
+  const uint8_t *data;
+  /**
+   * Length of the candidate in bytes.
+   */
+  uintptr_t len;
+} RustmergerPluginLine;
+
+/**
+ * What a plugin decided for one candidate.
+ */
+typedef struct RustmergerPluginDecision {
+  /**
+   * `RUSTMERGER_PLUGIN_KEEP`, `RUSTMERGER_PLUGIN_DROP` or `RUSTMERGER_PLUGIN_REPLACE`.
+   */
+  uint32_t action;
+  /**
+   * Replacement bytes, read only for `RUSTMERGER_PLUGIN_REPLACE`.
+   */
+  const uint8_t *data;
+  /**
+   * Length of the replacement in bytes.
+   */
+  uintptr_t len;
+} RustmergerPluginDecision;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Start merging the files listed in `input_list` into `output_file`.
+ *
+ * Returns a job handle, or null if an argument is invalid or the input
+ * list cannot be read. The handle must be released with
+ * `rustmerger_job_free`.
+ *
+ * # Safety
+ *
+ * `input_list` and `output_file` must be null or valid NUL-terminated
+ * UTF-8 strings.
+ */
+struct RustmergerJob *rustmerger_merge_start(const char *input_list,
+                                             const char *output_file,
+                                             uint32_t threads);
+
+/**
+ * Report the job status and, if `progress` is not null, its file progress.
+ *
+ * # Safety
+ *
+ * `job` must be null or a handle returned by `rustmerger_merge_start` that
+ * has not been freed. `progress` must be null or point to writable memory
+ * for a `RustmergerProgress`.
+ */
+enum RustmergerStatus rustmerger_poll(const struct RustmergerJob *job,
+                                      struct RustmergerProgress *progress);
+
+/**
+ * Fill `stats` with the job's line statistics.
+ *
+ * Returns `RustmergerStatus::InvalidArgument` if either pointer is null,
+ * otherwise the current job status.
+ *
+ * # Safety
+ *
+ * `job` must be null or a live handle from `rustmerger_merge_start`.
+ * `stats` must be null or point to writable memory for a `RustmergerStats`.
+ */
+enum RustmergerStatus rustmerger_stats(const struct RustmergerJob *job,
+                                       struct RustmergerStats *stats);
+
+/**
+ * Ask the job to stop. Cancellation is checked between input files, so
+ * the job may take a moment to reach `RustmergerStatus::Cancelled`.
+ *
+ * # Safety
+ *
+ * `job` must be null or a live handle from `rustmerger_merge_start`.
+ */
+void rustmerger_cancel(const struct RustmergerJob *job);
+
+/**
+ * Error message of a failed job, or null if the job has not failed.
+ *
+ * The string is owned by the job and stays valid until
+ * `rustmerger_job_free`.
+ *
+ * # Safety
+ *
+ * `job` must be null or a live handle from `rustmerger_merge_start`.
+ */
+const char *rustmerger_error_message(const struct RustmergerJob *job);
+
+/**
+ * Release a job handle, cancelling and waiting for the job if it is still
+ * running. Passing null is a no-op.
+ *
+ * # Safety
+ *
+ * `job` must be null or a handle from `rustmerger_merge_start` that has
+ * not already been freed.
+ */
+void rustmerger_job_free(struct RustmergerJob *job);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUSTMERGER_H */
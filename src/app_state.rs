@@ -2,7 +2,10 @@ use anyhow::Result; // Importing Result type from anyhow crate for error handlin
 use std::path::PathBuf; // Importing PathBuf to handle file paths
 use tokio::sync::RwLock; // Importing RwLock from tokio for async read-write lock
 use std::sync::Arc; // Importing Arc for atomic reference counting
-use crate::progress::Progress; // Importing Progress struct from the local crate
+use std::sync::atomic::AtomicUsize; // Importing AtomicUsize for lock-free merge statistics
+use crate::progress::{PathRemap, Progress}; // Importing Progress and path remapping from the local crate
+use crate::pause::PauseGate; // Importing PauseGate to hold readers while paused
+use crate::config::Config; // Importing Config, the options a checkpoint records for its resume
 
 #[allow(dead_code)]
 // AppState struct holds the state of the application
@@ -12,6 +15,9 @@ pub struct AppState {
     pub threads: usize, // Number of threads to use for processing
     pub progress: Arc<RwLock<Progress>>, // Progress tracking wrapped in an async read-write lock and atomic reference counter
     pub shutdown_requested: Arc<RwLock<bool>>, // Flag to indicate if shutdown is requested, wrapped in an async read-write lock and atomic reference counter
+    pub lines_processed: AtomicUsize, // Number of lines fed into deduplication so far
+    pub unique_lines: AtomicUsize, // Number of unique lines found so far
+    pub pause: PauseGate, // Holds readers between chunks while the merge is paused
 }
 
 impl AppState {
@@ -23,18 +29,29 @@ impl AppState {
             threads, // Set number of threads
             progress: Arc::new(RwLock::new(Progress::default())), // Initialize progress with default value, wrapped in Arc and RwLock
             shutdown_requested: Arc::new(RwLock::new(false)), // Initialize shutdown_requested to false, wrapped in Arc and RwLock
+            lines_processed: AtomicUsize::new(0), // No lines processed yet
+            unique_lines: AtomicUsize::new(0), // No unique lines found yet
+            pause: PauseGate::new(), // Not paused
         })
     }
 
-    // Asynchronous function to create an AppState instance from a resume file
-    pub async fn from_resume(resume_file: PathBuf) -> Result<Self> {
-        let progress = Progress::load(&resume_file).await?; // Load progress from the resume file
+    // Asynchronous function to create an AppState instance from a resume file, optionally
+    // resolving its relative paths against a new `root` and rewriting them with `remaps`
+    pub async fn from_resume(resume_file: PathBuf, root: Option<PathBuf>, remaps: &[PathRemap]) -> Result<Self> {
+        let mut progress = Progress::load_with_root(&resume_file, root).await?; // Load progress from the resume file
+        if !remaps.is_empty() {
+            let changed = progress.remap(remaps); // Point the checkpoint at where the data lives now
+            log::info!("Remapped {} checkpoint paths", changed);
+        }
         Ok(Self {
             input_file: progress.input_file.clone(), // Set input file path from progress
             output_file: progress.output_file.clone(), // Set output file path from progress
             threads: progress.threads, // Set number of threads from progress
             progress: Arc::new(RwLock::new(progress)), // Wrap loaded progress in Arc and RwLock
             shutdown_requested: Arc::new(RwLock::new(false)), // Initialize shutdown_requested to false, wrapped in Arc and RwLock
+            lines_processed: AtomicUsize::new(0), // No lines processed yet
+            unique_lines: AtomicUsize::new(0), // No unique lines found yet
+            pause: PauseGate::new(), // Not paused
         })
     }
 
@@ -44,9 +61,33 @@ impl AppState {
         progress.save().await // Save the progress
     }
 
+    // Checkpoint progress to `path` after every file, so the merge can be resumed with the
+    // options in `config`, saving paths under `root` relative to it
+    pub async fn checkpoint_to(&self, path: PathBuf, root: Option<PathBuf>, config: Config) -> Result<()> {
+        let mut progress = self.progress.write().await; // Acquire write lock on progress
+        progress.root = root.map(std::path::absolute).transpose()?; // Record the root, absolute so it is unambiguous
+        progress.input_file = self.input_file.clone(); // Record what the merge reads
+        progress.output_file = self.output_file.clone(); // Record where the merge writes
+        progress.threads = self.threads; // Record the thread count
+        progress.config = Some(config); // Record how the merge runs
+        progress.save_path = Some(path); // Enable saving
+        Ok(())
+    }
+
+    // Mark the checkpoint finished, so it is no longer offered for resume
+    pub async fn finish_progress(&self) -> Result<()> {
+        let mut progress = self.progress.write().await; // Acquire write lock on progress
+        progress.finished = true; // Nothing is left to resume
+        if let Some(snapshot) = progress.snapshot.take() {
+            let _ = tokio::fs::remove_file(snapshot).await; // The saved lines are in the output now
+        }
+        progress.save().await // Save the progress
+    }
+
     // Asynchronous function to request shutdown
     pub async fn request_shutdown(&self) {
         *self.shutdown_requested.write().await = true; // Acquire write lock and set shutdown_requested to true
+        self.pause.resume(); // Let paused readers reach the shutdown check
     }
 
     // Asynchronous function to check if shutdown is requested
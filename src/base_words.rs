@@ -0,0 +1,164 @@
+use std::collections::hash_map::RandomState; // Import RandomState to hash base words for the set
+use std::fs::File; // Import File for the base word list
+use std::hash::BuildHasher; // Import BuildHasher to hash byte slices
+use std::io::{self, BufWriter, Write}; // Import buffered writing for the base word list
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the list and spill paths
+use crate::dedup_key::DedupKey; // Import DedupKey, whole base words identify themselves
+use crate::estimate::format_bytes; // Reuse the byte formatter for the summary
+use crate::line_store::{LineStore, Tally}; // Import LineStore, the set of base words, and Tally, passed along with each line
+use crate::output_format::RecordSink; // Import RecordSink, wrapped by the base word writer
+use crate::spill::{self, SpillWatchdog}; // Import the spill runs that bound the set's memory
+
+const OUTPUT_BUFFER_SIZE: usize = 1024 * 1024; // Bytes buffered for the base word list
+const MEMORY_SHARE: u64 = 4; // The set may use a quarter of the memory available when writing starts
+
+// `candidate` without its trailing ASCII digits and punctuation, or None when nothing is left
+//
+// `password`, `password!` and `password123!` all share the base `password`.
+pub fn base(candidate: &[u8]) -> Option<&[u8]> {
+    let end = candidate.iter().rposition(|b| !b.is_ascii_digit() && !b.is_ascii_punctuation())?;
+    Some(&candidate[..=end])
+}
+
+// Output that writes every line on to the main output and its base word, once, to a list
+//
+// Base words are collected in a `LineStore` and written when the output finishes, in
+// first-seen order. A set that outgrows its budget is spilled as sorted runs under the
+// temp directory, and the list is then written in byte order from the merged runs.
+pub struct BaseWordSink {
+    inner: Box<dyn RecordSink + Send>, // Main output
+    path: PathBuf,                     // Base word list
+    writer: BufWriter<File>,           // Writer of the base word list
+    hasher: RandomState,               // Hasher of the base words in `seen`
+    seen: LineStore,                   // Base words not yet spilled
+    budget: u64,                       // Footprint of `seen` that triggers a spill
+    spill: SpillWatchdog,              // Writer of the spill runs, never spawned
+    runs: Vec<PathBuf>,                // Sorted runs spilled so far
+    max_open: usize,                   // Runs merged at once
+    lines: u64,                        // Lines that had a base word
+}
+
+impl BaseWordSink {
+    // Wrap `inner` so base words are also written to `path`; without a path `inner` is returned as is
+    //
+    // Spill runs go under `temp_dir`, and at most `max_open` of them are read at once.
+    pub fn wrap(inner: Box<dyn RecordSink + Send>, path: Option<&Path>, temp_dir: &Path, max_open: usize) -> io::Result<Box<dyn RecordSink + Send>> {
+        let Some(path) = path else { return Ok(inner) };
+        let budget = SpillWatchdog::default_high_water() / MEMORY_SHARE;
+        Ok(Box::new(Self::create(inner, path, temp_dir, max_open, budget)?))
+    }
+
+    // Base word writer whose set spills once its footprint passes `budget` bytes
+    fn create(inner: Box<dyn RecordSink + Send>, path: &Path, temp_dir: &Path, max_open: usize, budget: u64) -> io::Result<Self> {
+        let writer = BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, File::create(path)?);
+        Ok(Self {
+            inner,
+            path: path.to_path_buf(),
+            writer,
+            hasher: RandomState::new(),
+            seen: LineStore::with_capacity(0),
+            budget,
+            spill: SpillWatchdog::new(1, budget, true, temp_dir.to_path_buf()),
+            runs: Vec::new(),
+            max_open,
+            lines: 0,
+        })
+    }
+
+    // Write the collected base words to the list; returns how many were written
+    fn write_list(&mut self) -> io::Result<usize> {
+        let writer = &mut self.writer;
+        if self.runs.is_empty() {
+            for word in self.seen.iter() {
+                writer.write_all(word)?;
+                writer.write_all(b"\n")?;
+            }
+            return Ok(self.seen.len());
+        }
+
+        if !self.seen.is_empty() {
+            self.runs.push(self.spill.spill(0, &mut self.seen)?);
+        }
+        self.spill.compact(0, &mut self.runs, self.max_open, false, DedupKey::default())?;
+        let written = spill::merge_runs(&self.runs, false, DedupKey::default(), |word, _| {
+            writer.write_all(word)?;
+            writer.write_all(b"\n")
+        })?;
+        self.runs.iter().for_each(|run| spill::remove_run(run));
+        Ok(written)
+    }
+}
+
+impl RecordSink for BaseWordSink {
+    fn write(&mut self, candidate: &[u8], tally: Tally) -> io::Result<()> {
+        if let Some(base) = base(candidate) {
+            self.lines += 1;
+            if self.seen.insert(self.hasher.hash_one(base), base) && self.seen.footprint() > self.budget {
+                self.runs.push(self.spill.spill(0, &mut self.seen)?);
+            }
+        }
+        self.inner.write(candidate, tally)
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        let written = self.write_list()?;
+        let Self { inner, path, mut writer, lines, .. } = *self;
+        inner.finish()?;
+        writer.flush()?;
+        let size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        log::info!("{} base words ({}) from {} lines written to {}", written, format_bytes(size), lines, path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base, BaseWordSink};
+    use crate::line_store::Tally;
+    use crate::output_format::RecordSink;
+    use std::io;
+
+    // Main output that drops every line
+    struct Discard;
+
+    impl RecordSink for Discard {
+        fn write(&mut self, _candidate: &[u8], _tally: Tally) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn finish(self: Box<Self>) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn write_all(budget: u64, candidates: &[&str]) -> String {
+        let temp = tempfile::tempdir().unwrap();
+        let list = temp.path().join("bases.txt");
+        let mut sink = Box::new(BaseWordSink::create(Box::new(Discard), &list, temp.path(), 2, budget).unwrap());
+        for candidate in candidates {
+            sink.write(candidate.as_bytes(), Tally::once(0)).unwrap();
+        }
+        sink.finish().unwrap();
+        std::fs::read_to_string(&list).unwrap()
+    }
+
+    #[test]
+    fn strips_trailing_digits_and_punctuation() {
+        assert_eq!(base(b"password123!"), Some(&b"password"[..]));
+        assert_eq!(base(b"pass1word"), Some(&b"pass1word"[..]));
+        assert_eq!(base(b"2024!!"), None);
+    }
+
+    #[test]
+    fn writes_each_base_once_in_first_seen_order() {
+        let list = write_all(u64::MAX, &["summer2024", "password1", "summer!", "123", "password"]);
+        assert_eq!(list, "summer\npassword\n");
+    }
+
+    #[test]
+    fn spilled_bases_are_merged_without_repeats() {
+        // A zero budget spills after every new base word
+        let list = write_all(0, &["zeta1", "alpha", "zeta!", "mid99", "alpha2", "beta"]);
+        assert_eq!(list, "alpha\nbeta\nmid\nzeta\n");
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicU8, Ordering}; // Import atomics so every shard can update one sketch
+
+const PRECISION: u32 = 14; // Register index bits; 16384 registers give about 0.8% standard error
+const REGISTERS: usize = 1 << PRECISION; // Number of registers
+const FORECAST_SMOOTHING: f64 = 0.3; // Weight of the latest input in the new-lines-per-byte average
+
+// HyperLogLog sketch of the distinct line hashes seen, kept alongside the exact sets
+//
+// Unlike the shard sets it is never emptied by a spill, so it keeps counting lines seen before one.
+pub struct HyperLogLog {
+    registers: Vec<AtomicU8>, // Longest run of leading zeros seen per register, plus one
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyperLogLog {
+    // Create an empty sketch
+    pub fn new() -> Self {
+        Self { registers: (0..REGISTERS).map(|_| AtomicU8::new(0)).collect() }
+    }
+
+    // Add the line hashed to `hash`
+    pub fn insert(&self, hash: u64) {
+        // Shards are chosen from the middle bits, so the hash is mixed again before it is split
+        let hash = mix(hash);
+        let index = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+        self.registers[index].fetch_max(rank, Ordering::Relaxed);
+    }
+
+    // Estimated number of distinct lines added so far
+    pub fn estimate(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let mut sum = 0.0;
+        let mut zeros = 0;
+        for register in &self.registers {
+            let rank = register.load(Ordering::Relaxed);
+            sum += 1.0 / (1u64 << rank) as f64;
+            if rank == 0 {
+                zeros += 1;
+            }
+        }
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let raw = alpha * m * m / sum;
+
+        // Linear counting is more accurate while many registers are still empty
+        if raw <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()) as u64
+        } else {
+            raw as u64
+        }
+    }
+}
+
+// Forecast of the unique lines a merge will end with, from how fast new lines still appear
+#[derive(Debug, Default)]
+pub struct UniqueForecast {
+    distinct: u64,              // Distinct lines at the latest update
+    bytes_read: u64,            // Input bytes read at the latest update
+    new_per_byte: Option<f64>,  // Smoothed distinct lines added per input byte
+}
+
+impl UniqueForecast {
+    // Record that `bytes_read` input bytes held `distinct` distinct lines, and forecast the final count
+    pub fn update(&mut self, distinct: u64, bytes_read: u64, bytes_total: u64) -> u64 {
+        let bytes = bytes_read.saturating_sub(self.bytes_read);
+        if bytes > 0 {
+            let latest = distinct.saturating_sub(self.distinct) as f64 / bytes as f64;
+            self.new_per_byte = Some(match self.new_per_byte {
+                Some(average) => average + FORECAST_SMOOTHING * (latest - average),
+                None => latest,
+            });
+        }
+        self.distinct = distinct;
+        self.bytes_read = bytes_read;
+
+        let remaining = bytes_total.saturating_sub(bytes_read) as f64;
+        distinct + (remaining * self.new_per_byte.unwrap_or(0.0)) as u64
+    }
+}
+
+// Finalizer of SplitMix64, spreading every input bit over the whole hash
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HyperLogLog, UniqueForecast};
+
+    #[test]
+    fn estimates_stay_close_to_the_distinct_count() {
+        let sketch = HyperLogLog::new();
+        assert_eq!(sketch.estimate(), 0);
+        for round in 0..2 {
+            // Repeats in the second round add nothing
+            for hash in 0..100_000u64 {
+                sketch.insert(hash);
+            }
+            let estimate = sketch.estimate() as f64;
+            assert!((estimate - 100_000.0).abs() < 3_000.0, "round {}: {}", round, estimate);
+        }
+    }
+
+    #[test]
+    fn forecasts_extrapolate_the_recent_rate_of_new_lines() {
+        let mut forecast = UniqueForecast::default();
+        assert_eq!(forecast.update(1_000, 1_000, 10_000), 10_000); // One new line per byte so far
+        // The next input adds nothing new, pulling the smoothed rate down by 30%
+        assert_eq!(forecast.update(1_000, 2_000, 10_000), 6_600);
+        assert_eq!(forecast.update(1_000, 10_000, 10_000), 1_000); // Nothing left to read
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::hash_map::RandomState; // Import RandomState to hash lines for the duplicate check
+use std::hash::BuildHasher; // Import BuildHasher to hash byte slices
+use std::io::{BufRead, BufReader}; // Import buffered reading to stream the file
+use std::path::Path; // Import Path for the checked file
+use crate::line_store::LineStore; // Import LineStore, the compact set used to spot repeats
+
+const READ_BUFFER_SIZE: usize = 1024 * 1024; // Read the checked file 1MB at a time
+const MAX_SHOWN_LINE: usize = 80; // Offending lines are shortened to this many characters
+
+// Properties a merged file is expected to have
+#[derive(Debug, Clone, Copy)]
+pub struct CheckOptions {
+    pub expect_sorted: bool, // Every line sorts at or after the previous one (byte order)
+    pub expect_unique: bool, // No line appears twice
+    pub max_offenders: usize, // Offending lines kept for the report
+}
+
+// Why a line was flagged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffenceKind {
+    Duplicate,  // Same as an earlier line
+    OutOfOrder, // Sorts before the previous line
+}
+
+// One flagged line
+#[derive(Debug, Clone)]
+pub struct Offence {
+    pub line_number: u64,  // 1-based line number
+    pub kind: OffenceKind, // What is wrong with it
+    pub line: String,      // The line, lossily decoded and shortened
+}
+
+// Outcome of checking a file
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub lines: u64,           // Lines read
+    pub duplicates: u64,      // Lines that repeat an earlier line
+    pub out_of_order: u64,    // Lines that sort before their predecessor
+    pub offenders: Vec<Offence>, // The first offending lines, in file order
+}
+
+impl CheckReport {
+    // Whether the file has every expected property
+    pub fn passed(&self) -> bool {
+        self.duplicates == 0 && self.out_of_order == 0
+    }
+
+    fn flag(&mut self, options: &CheckOptions, line_number: u64, kind: OffenceKind, line: &[u8]) {
+        match kind {
+            OffenceKind::Duplicate => self.duplicates += 1,
+            OffenceKind::OutOfOrder => self.out_of_order += 1,
+        }
+        if self.offenders.len() < options.max_offenders {
+            let text = String::from_utf8_lossy(line);
+            let mut shown: String = text.chars().take(MAX_SHOWN_LINE).collect();
+            if text.chars().count() > MAX_SHOWN_LINE {
+                shown.push('…');
+            }
+            self.offenders.push(Offence { line_number, kind, line: shown });
+        }
+    }
+}
+
+// Stream `path` and flag duplicate and out-of-order lines
+//
+// Sorted input only needs the previous line; the duplicate check keeps every distinct
+// line in a compact arena, so it needs memory on the order of the file size.
+pub fn check_file(path: &Path, options: &CheckOptions) -> std::io::Result<CheckReport> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, file);
+    let hasher = RandomState::new();
+    let mut seen = options.expect_unique.then(|| LineStore::with_capacity(0));
+    let mut report = CheckReport::default();
+    let mut previous = Vec::new();
+    let mut buffer = Vec::new();
+
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+        report.lines += 1;
+        let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+
+        if options.expect_sorted {
+            if report.lines > 1 && line < previous.as_slice() {
+                report.flag(options, report.lines, OffenceKind::OutOfOrder, line);
+            }
+            previous.clear();
+            previous.extend_from_slice(line);
+        }
+        if let Some(seen) = seen.as_mut() {
+            if !seen.insert(hasher.hash_one(line), line) {
+                report.flag(options, report.lines, OffenceKind::Duplicate, line);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_file, CheckOptions, OffenceKind};
+
+    #[test]
+    fn duplicates_and_order_breaks_are_flagged_by_line() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("merged.txt");
+        std::fs::write(&path, "alpha\ncharlie\nbravo\ncharlie\ndelta\n").unwrap();
+
+        let options = CheckOptions { expect_sorted: true, expect_unique: true, max_offenders: 2 };
+        let report = check_file(&path, &options).unwrap();
+        assert!(!report.passed());
+        assert_eq!((report.lines, report.duplicates, report.out_of_order), (5, 1, 1));
+        let offenders: Vec<(u64, OffenceKind, &str)> = report.offenders.iter()
+            .map(|offence| (offence.line_number, offence.kind, offence.line.as_str()))
+            .collect();
+        assert_eq!(offenders, [(3, OffenceKind::OutOfOrder, "bravo"), (4, OffenceKind::Duplicate, "charlie")]);
+    }
+
+    #[test]
+    fn unchecked_properties_are_not_flagged() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("merged.txt");
+        std::fs::write(&path, "bravo\nalpha\nalpha\n").unwrap();
+
+        let unique_only = CheckOptions { expect_sorted: false, expect_unique: true, max_offenders: 0 };
+        let report = check_file(&path, &unique_only).unwrap();
+        assert_eq!((report.duplicates, report.out_of_order, report.offenders.len()), (1, 0, 0));
+
+        let sorted_only = CheckOptions { expect_sorted: true, expect_unique: false, max_offenders: 10 };
+        assert_eq!(check_file(&path, &sorted_only).unwrap().out_of_order, 1);
+    }
+}
@@ -1,7 +1,28 @@
 // Import required dependencies
-use clap::{Parser, Subcommand}; // For command-line argument parsing
+use clap::{Parser, Subcommand, ValueEnum}; // For command-line argument parsing
 use std::path::PathBuf;         // For handling file paths
+use std::net::SocketAddr;       // For the status page address
+use url::Url;                   // For the completion webhook address
+use crate::notify::parse_webhook; // For webhook arguments
 use log::LevelFilter;           // For controlling log levels
+use crate::io_backend::IoBackend; // For selecting the file I/O strategy
+use crate::dedup_backend::DedupBackend; // For selecting how seen lines are kept
+use crate::tuning::parse_size;     // For size arguments with K/M/G suffixes
+use crate::throttle::parse_rate;   // For I/O rate limits in MB/s
+use crate::priority::{parse_io_priority, IoPriority}; // For the I/O scheduling class
+use crate::rules::{parse_rule_function, RuleComments}; // For choosing how rule comments are merged and which rules are dropped
+use crate::input_format::InputFormat; // For choosing how input lines are read
+use crate::line_options::{BinaryMode, StripControl}; // For choosing how lines with NUL bytes are kept
+use crate::output_format::{CapOrder, OutputFormat}; // For choosing how the output is written and capped
+use crate::dedup_key::DedupKey; // For choosing which part of combo lines is deduplicated
+use crate::transform::{parse_years, Transform, YearRange}; // For choosing the variants generated from each word
+use crate::routing::{parse_digit_lengths, DigitLengths}; // For choosing the digit counts routed to their own file
+use crate::tee::{parse_tee, TeeOutput}; // For declaring extra filtered outputs
+use crate::config::{parse_override, ConfigOverride}; // For --set overrides of config keys
+use crate::progress::{parse_remap, PathRemap}; // For rewriting checkpoint paths on resume
+use crate::preset::Preset; // For bundles of settings chosen by name
+use crate::encoding::{parse_encoding, parse_source_encoding, SourceEncoding}; // For encoding labels
+use encoding_rs::Encoding;        // For parsed encodings
 
 // Main CLI structure that defines the application's command-line interface
 #[derive(Parser)]
@@ -10,7 +31,8 @@ use log::LevelFilter;           // For controlling log levels
     about = "Fast parallel merging and deduplication of wordlists and rules",
     version,
     author,
-    long_about = None
+    long_about = None,
+    after_help = "Exit codes:\n  0    Success, every input merged\n  1    Fatal error\n  3    Completed, but some inputs were skipped (see failed_files.json)\n  130  Interrupted by a signal; progress was saved for resume"
 )]
 pub struct Cli {
     // Global verbose flag that can be used multiple times (-v, -vv, etc.)
@@ -33,6 +55,7 @@ pub struct Cli {
 
 // Enum defining all available subcommands
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // Parsed once per run; boxing MergeArgs buys nothing
 pub enum Commands {
     // Merge subcommand for combining wordlists and rules
     #[command(about = "Merge wordlists and rules")]
@@ -49,6 +72,66 @@ pub enum Commands {
     // Resume interrupted operations subcommand
     #[command(about = "Resume interrupted operation")]
     Resume(ResumeArgs),
+
+    // Documentation generation subcommand for packagers
+    #[command(about = "Generate man pages and markdown CLI reference")]
+    Mangen(MangenArgs),
+
+    // Capacity planning subcommand, sampling the inputs like --dry-run
+    #[command(about = "Estimate unique lines, output size, RAM per dedup backend and temp disk")]
+    Estimate(EstimateArgs),
+
+    // Processing schedule subcommand for sanity-checking large jobs
+    #[command(about = "Show the processing schedule without merging")]
+    Plan(PlanArgs),
+
+    // Built-in self-test subcommand for verifying a deployment
+    #[command(about = "Run the built-in self-test suite")]
+    Selftest(SelftestArgs),
+
+    // Reprocess inputs that failed in an earlier merge
+    #[command(about = "Retry the files listed in a failed_files.json skip list")]
+    RetryFailed(RetryFailedArgs),
+
+    // Checksummed record of the inputs for reproducible builds
+    #[command(about = "Record size, modification time and SHA-256 of every input")]
+    Manifest(ManifestArgs),
+
+    // Integrity check of a merged output
+    #[command(about = "Verify that a merged file has no duplicate or out-of-order lines")]
+    Check(CheckArgs),
+
+    // Encoding detection and conversion without a merge
+    #[command(about = "Convert files between text encodings")]
+    ConvertEncoding(ConvertEncodingArgs),
+
+    // Conversion between raw lines and hashcat's `$HEX[...]` notation
+    #[command(about = "Convert lines to or from hashcat $HEX[...] notation")]
+    Hex(HexArgs),
+
+    // Ranking of inputs by what they add that no other input has
+    #[command(about = "Rank inputs by unique contribution per MB and flag redundant ones")]
+    Redundancy(RedundancyArgs),
+
+    // Analyses of the lines of one file
+    #[command(about = "Analyze a wordlist, such as clustering near-duplicate lines")]
+    Analyze(AnalyzeArgs),
+
+    // Worker of a distributed merge, serving partitions to a coordinator
+    #[command(about = "Serve merge jobs for a coordinating `merge --workers`")]
+    Worker(WorkerArgs),
+
+    // Journal of past runs for auditability
+    #[command(about = "List or inspect past runs recorded in the run history")]
+    History(HistoryArgs),
+
+    // Named checkpoints left by merges run with --session
+    #[command(about = "List, inspect or remove saved merge sessions")]
+    Sessions(SessionsArgs),
+
+    // Downloads of well-known public lists for lab setup
+    #[command(about = "Download well-known lists, such as seclists/passwords/rockyou, into a local cache and print their paths")]
+    Fetch(FetchArgs),
 }
 
 // Structure defining all possible arguments for the merge command
@@ -63,6 +146,60 @@ pub struct MergeArgs {
     )]
     pub wordlists_file: Option<PathBuf>,
 
+    // Symlink handling inside listed directories
+    #[arg(
+        long = "follow-symlinks",
+        help = "Follow symlinks inside directories of the wordlists file; every file and directory is still read once"
+    )]
+    pub follow_symlinks: bool,
+
+    // Recursion into listed directories
+    #[arg(
+        long = "no-recurse",
+        help = "Take only the files directly inside directories of the wordlists file, not those in their subdirectories"
+    )]
+    pub no_recurse: bool,
+
+    // Extension filter for listed directories
+    #[arg(
+        long = "extensions",
+        help = "Take only files with these extensions from directories of the wordlists file, e.g. txt,lst,dic (comma-separated)",
+        value_name = "LIST",
+        value_delimiter = ','
+    )]
+    pub extensions: Vec<String>,
+
+    // Size ceiling for single inputs
+    #[arg(
+        long = "skip-larger-than",
+        help = "Skip inputs larger than this, e.g. 20G, reporting each one instead of reading it",
+        value_name = "SIZE",
+        value_parser = parse_size
+    )]
+    pub skip_larger_than: Option<usize>,
+
+    // Content sniffing opt-out
+    #[arg(
+        long = "no-content-sniff",
+        help = "Read inputs even when their magic bytes mark them as executables, images, PDFs, databases or archives"
+    )]
+    pub no_content_sniff: bool,
+
+    // Cache for URL inputs
+    #[arg(
+        long = "download-dir",
+        help = "Directory URL inputs are downloaded to and reused from (default $XDG_CACHE_HOME/rustmerger/downloads or ~/.cache/rustmerger/downloads)",
+        value_name = "DIR"
+    )]
+    pub download_dir: Option<PathBuf>,
+
+    // Revalidation of cached URL inputs
+    #[arg(
+        long = "refresh",
+        help = "Ask the servers of URL inputs whether the cached copies changed, downloading the ones that did"
+    )]
+    pub refresh: bool,
+
     // Input file containing list of rule paths
     #[arg(
         short = 'r',
@@ -97,6 +234,24 @@ pub struct MergeArgs {
     )]
     pub config: Option<PathBuf>,
 
+    // Named bundle of settings for a common job
+    #[arg(
+        long = "preset",
+        help = "Start from the settings for a common job, applied over the config file; --set and other options still override them",
+        value_name = "PRESET",
+        value_enum
+    )]
+    pub preset: Option<Preset>,
+
+    // Single config keys set from the command line
+    #[arg(
+        long = "set",
+        help = "Set a config key after loading the config, as KEY=VALUE with dotted paths such as tuning.chunk_size=4194304; the value is JSON or a plain string; repeatable",
+        value_name = "KEY=VALUE",
+        value_parser = parse_override
+    )]
+    pub set: Vec<ConfigOverride>,
+
     // Progress state file for resume capability
     #[arg(
         long = "progress-file",
@@ -105,6 +260,23 @@ pub struct MergeArgs {
     )]
     pub progress_file: Option<PathBuf>,
 
+    // Named checkpoint under the sessions directory
+    #[arg(
+        long = "session",
+        help = "Save progress in the named session, resumable with `resume --session NAME` and listed by `sessions list`",
+        value_name = "NAME",
+        conflicts_with = "progress_file"
+    )]
+    pub session: Option<String>,
+
+    // Directory the checkpoint's paths are saved relative to
+    #[arg(
+        long = "checkpoint-root",
+        help = "Save the paths in the checkpoint relative to DIR, so it can be resumed after the data directory is copied elsewhere",
+        value_name = "DIR"
+    )]
+    pub checkpoint_root: Option<PathBuf>,
+
     // Debug mode flag
     #[arg(
         short = 'd',
@@ -112,6 +284,589 @@ pub struct MergeArgs {
         help = "Enable detailed progress output"
     )]
     pub debug: bool,
+
+    // Dry-run flag to validate inputs and estimate resources without writing
+    #[arg(
+        long = "dry-run",
+        help = "Validate inputs and estimate RAM, disk and runtime without writing output"
+    )]
+    pub dry_run: bool,
+
+    // Streaming flag to write unique lines as soon as they are found
+    #[arg(
+        long = "stream-output",
+        help = "Write unique lines to the output as they are found instead of at the end"
+    )]
+    pub stream_output: bool,
+
+    // File I/O backend used for reading inputs and writing output
+    #[arg(
+        long = "io-backend",
+        help = "File I/O backend (uring requires Linux and the io-uring feature)",
+        value_name = "BACKEND"
+    )]
+    pub io_backend: Option<IoBackend>,
+
+    // Structure holding the set of lines seen so far
+    #[arg(
+        long = "dedup-backend",
+        help = "How seen lines are kept: auto picks from the sampled inputs and available memory and says why; bloom streams its output and may drop about 0.1% of unique lines (default auto)",
+        value_name = "BACKEND"
+    )]
+    pub dedup_backend: Option<DedupBackend>,
+
+    // Bytes a reader buffers before handing lines to deduplication
+    #[arg(
+        long = "chunk-size",
+        help = "Bytes buffered per reader chunk, e.g. 10M (default 10M)",
+        value_name = "SIZE",
+        value_parser = parse_size
+    )]
+    pub chunk_size: Option<usize>,
+
+    // Read buffer size for inputs that cannot be memory-mapped
+    #[arg(
+        long = "buffer-size",
+        help = "Read buffer size for non-mappable inputs, e.g. 32M (default 32M)",
+        value_name = "SIZE",
+        value_parser = parse_size
+    )]
+    pub buffer_size: Option<usize>,
+
+    // Number of chunks queued per shard before readers wait
+    #[arg(
+        long = "channel-size",
+        help = "Chunks queued per deduplication shard (default 1000)",
+        value_name = "COUNT"
+    )]
+    pub channel_size: Option<usize>,
+
+    // Number of files scheduled per batch
+    #[arg(
+        long = "parallel-files",
+        help = "Files scheduled per processing batch (default 4)",
+        value_name = "COUNT"
+    )]
+    pub parallel_files: Option<usize>,
+
+    // Number of inputs read at the same time
+    #[arg(
+        long = "read-concurrency",
+        help = "Inputs read at the same time; raise for SSDs, keep at 1 for spinning disks (default 1)",
+        value_name = "COUNT"
+    )]
+    pub read_concurrency: Option<usize>,
+
+    // Number of shards hashing and deduplicating lines
+    #[arg(
+        long = "cpu-workers",
+        help = "Shards hashing and deduplicating lines (default: the thread count, at most one per core)",
+        value_name = "COUNT"
+    )]
+    pub cpu_workers: Option<usize>,
+
+    // Pace of reading the inputs
+    #[arg(
+        long = "max-read-mbps",
+        help = "Read inputs at no more than this many MB/s, e.g. 50 or 0.5",
+        value_name = "MBPS",
+        value_parser = parse_rate
+    )]
+    pub max_read_mbps: Option<f64>,
+
+    // Pace of writing the output
+    #[arg(
+        long = "max-write-mbps",
+        help = "Write the output at no more than this many MB/s; spill runs are not slowed",
+        value_name = "MBPS",
+        value_parser = parse_rate
+    )]
+    pub max_write_mbps: Option<f64>,
+
+    // CPU scheduling priority
+    #[arg(
+        long = "nice",
+        help = "Run at this niceness, from -20 (highest priority) to 19 (lowest); raising priority needs root",
+        value_name = "N",
+        value_parser = clap::value_parser!(i32).range(-20..=19),
+        allow_negative_numbers = true
+    )]
+    pub nice: Option<i32>,
+
+    // Disk scheduling priority
+    #[arg(
+        long = "ionice",
+        help = "Linux I/O scheduling class: idle, best-effort[:0-7] or realtime[:0-7] (realtime needs root)",
+        value_name = "CLASS[:LEVEL]",
+        value_parser = parse_io_priority
+    )]
+    pub ionice: Option<IoPriority>,
+
+    // Directory for spill runs and other temporary files
+    #[arg(
+        long = "temp-dir",
+        help = "Directory for spill runs and other temporary files (default: OS temp dir)",
+        value_name = "DIR"
+    )]
+    pub temp_dir: Option<PathBuf>,
+
+    // Stages of a hierarchical merge
+    #[arg(
+        long = "stages",
+        help = "Merge inputs in groups over N stages through deduplicated intermediates in the temp dir, so each merge holds one group's set (default 1)",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub stages: Option<u64>,
+
+    // Machines running `rustmerger worker`
+    #[arg(
+        long = "workers",
+        help = "Partition the inputs across these workers, as comma-separated HOST:PORT, and merge what they send back; workers read the inputs at the same paths",
+        value_name = "HOST:PORT",
+        value_delimiter = ','
+    )]
+    pub workers: Vec<String>,
+
+    // Secret the workers were started with
+    #[arg(
+        long = "worker-token",
+        help = "Shared secret the workers were started with, sent as plaintext JSON over TCP",
+        value_name = "TOKEN"
+    )]
+    pub worker_token: Option<String>,
+
+    // Start even when the disk-space or memory preflight fails
+    #[arg(
+        long = "force",
+        help = "Start even if the output or temp filesystem, or memory with --stream-output, looks too small"
+    )]
+    pub force: bool,
+
+    // Abort instead of skipping inputs that fail
+    #[arg(
+        long = "strict",
+        help = "Abort, after saving progress, when any input is skipped or only partly merged"
+    )]
+    pub strict: bool,
+
+    // Abort once this many inputs failed
+    #[arg(
+        long = "max-failed-files",
+        help = "Abort once more than this many inputs failed",
+        value_name = "COUNT"
+    )]
+    pub max_failed_files: Option<usize>,
+
+    // Abort once this share of inputs failed
+    #[arg(
+        long = "max-failed-percent",
+        help = "Abort once more than this percentage of all inputs failed",
+        value_name = "PERCENT"
+    )]
+    pub max_failed_percent: Option<f64>,
+
+    // Retries for transient read errors
+    #[arg(
+        long = "max-retries",
+        help = "Retries per file for transient read errors such as EIO or timeouts (default 3)",
+        value_name = "COUNT"
+    )]
+    pub max_retries: Option<u32>,
+
+    // Delay before the first retry
+    #[arg(
+        long = "retry-backoff-ms",
+        help = "Delay before the first retry in milliseconds, doubled per retry (default 500)",
+        value_name = "MS"
+    )]
+    pub retry_backoff_ms: Option<u64>,
+
+    // Handling of `#` comment lines inside rule files
+    #[arg(
+        long = "rule-comments",
+        help = "How to merge `#` comment lines in rule files (default preserve)",
+        value_name = "MODE"
+    )]
+    pub rule_comments: Option<RuleComments>,
+
+    // Longest merged rule
+    #[arg(
+        long = "max-rule-ops",
+        help = "Drop rules with more than this many functions",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub max_rule_ops: Option<u64>,
+
+    // Functions merged rules may not use
+    #[arg(
+        long = "reject-rule-functions",
+        help = "Drop rules using any of these functions, such as X,M,4,6 for memory functions (comma-separated function characters)",
+        value_name = "LIST",
+        value_delimiter = ',',
+        value_parser = parse_rule_function
+    )]
+    pub reject_rule_functions: Vec<u8>,
+
+    // Whitespace-insensitive rule deduplication
+    #[arg(
+        long = "rule-ignore-whitespace",
+        help = "Treat rules differing only in the spaces between their functions, or trailing spaces, as duplicates"
+    )]
+    pub rule_ignore_whitespace: bool,
+
+    // JSON summary of the run, including skipped and degraded files
+    #[arg(
+        long = "report",
+        help = "Write a JSON report of the run, including every skipped or degraded file",
+        value_name = "FILE"
+    )]
+    pub report: Option<PathBuf>,
+
+    // Config capturing this run
+    #[arg(
+        long = "emit-config",
+        help = "Write the fully resolved options of this run, after merging the command line into the config, as a config file",
+        value_name = "FILE"
+    )]
+    pub emit_config: Option<PathBuf>,
+
+    // Live status page
+    #[arg(
+        long = "web-ui",
+        help = "Serve a status page with files done, throughput, unique count, ETA and recent errors, e.g. 127.0.0.1:7878",
+        value_name = "ADDR"
+    )]
+    pub web_ui: Option<SocketAddr>,
+
+    // Full-screen dashboard
+    #[arg(
+        long = "tui",
+        help = "Show a full-screen dashboard with overall progress, per-file status, errors and memory use instead of progress bars"
+    )]
+    pub tui: bool,
+
+    // Completion webhook
+    #[arg(
+        long = "notify-webhook",
+        help = "POST the final JSON report, or the failure, to this URL when the merge ends; Slack, Discord and Teams webhooks show its summary",
+        value_name = "URL",
+        value_parser = parse_webhook
+    )]
+    pub notify_webhook: Option<Url>,
+
+    // Run history opt-out
+    #[arg(
+        long = "no-history",
+        help = "Do not record this run in the run history"
+    )]
+    pub no_history: bool,
+
+    // Per-file contribution table
+    #[arg(
+        long = "file-stats",
+        help = "Print lines read, unique lines contributed, duplicate rate and timing for every input"
+    )]
+    pub file_stats: bool,
+
+    // Keep lines that are not valid UTF-8
+    #[arg(
+        long = "hex-invalid",
+        help = "Keep lines that are not valid UTF-8 as $HEX[...] instead of dropping them"
+    )]
+    pub hex_invalid: bool,
+
+    // Handling of lines with NUL bytes
+    #[arg(
+        long = "binary-mode",
+        help = "Lines with NUL bytes: keep merges them as read, hex writes them as $HEX[...] (default keep)",
+        value_name = "MODE"
+    )]
+    pub binary_mode: Option<BinaryMode>,
+
+    // Control characters removed before deduplication
+    #[arg(
+        long = "strip-control",
+        help = "Remove NUL bytes (nul), every C0 control character (all-control) or all but tab (all-control-except-tab) from lines before deduplication",
+        value_name = "MODE"
+    )]
+    pub strip_control: Option<StripControl>,
+
+    // Fold look-alike characters before deduplication
+    #[arg(
+        long = "normalize-homoglyphs",
+        help = "Fold fullwidth forms and Cyrillic or Greek look-alike letters to ASCII before deduplication"
+    )]
+    pub normalize_homoglyphs: bool,
+
+    // Layout of the input files
+    #[arg(
+        long = "input-format",
+        help = "Layout of input lines; auto reads .dic files as hunspell/aspell dictionaries and .jsonl/.ndjson as JSON Lines (default auto)",
+        value_name = "FORMAT"
+    )]
+    pub input_format: Option<InputFormat>,
+
+    // Field holding the candidate in JSON Lines inputs
+    #[arg(
+        long = "json-field",
+        help = "Field of each JSON Lines record (.jsonl, .ndjson) to take candidates from; nested fields as user.password",
+        value_name = "FIELD"
+    )]
+    pub json_field: Option<String>,
+
+    // Part of combo lines that identifies duplicates
+    #[arg(
+        long = "dedup-key",
+        help = "Deduplicate user:pass combo lines on the whole pair, the password or the username, keeping the first line per key (default pair)",
+        value_name = "KEY"
+    )]
+    pub dedup_key: Option<DedupKey>,
+
+    // Weakest candidates kept
+    #[arg(
+        long = "min-strength",
+        help = "Drop candidates whose zxcvbn-style strength score (0-4) is below this",
+        value_name = "SCORE",
+        value_parser = clap::value_parser!(u8).range(0..=4)
+    )]
+    pub min_strength: Option<u8>,
+
+    // Strongest candidates kept
+    #[arg(
+        long = "max-strength",
+        help = "Drop candidates whose zxcvbn-style strength score (0-4) is above this",
+        value_name = "SCORE",
+        value_parser = clap::value_parser!(u8).range(0..=4)
+    )]
+    pub max_strength: Option<u8>,
+
+    // Variants generated from each word
+    #[arg(
+        long = "transforms",
+        help = "Also emit these rewrites of every word, deduplicated with the rest (comma-separated)",
+        value_name = "LIST",
+        value_delimiter = ','
+    )]
+    pub transforms: Vec<Transform>,
+
+    // Reversed form of each word
+    #[arg(
+        long = "emit-reversed",
+        help = "Also emit every word reversed, deduplicated with the rest (same as adding the reverse transform)"
+    )]
+    pub emit_reversed: bool,
+
+    // Years appended to each word
+    #[arg(
+        long = "append-years",
+        help = "Also emit every word followed by each year of this range, such as 2015-2025",
+        value_name = "RANGE",
+        value_parser = parse_years
+    )]
+    pub append_years: Option<YearRange>,
+
+    // Strings appended to each word
+    #[arg(
+        long = "append-strings",
+        help = "Also emit every word followed by each of these strings (comma-separated)",
+        value_name = "LIST",
+        value_delimiter = ','
+    )]
+    pub append_strings: Vec<String>,
+
+    // Templates building hybrid candidates
+    #[arg(
+        long = "template",
+        help = "Also emit this template with {word} replaced by each word and {suffix} by each appended year or string; repeatable (default {word}{suffix})",
+        value_name = "TEMPLATE"
+    )]
+    pub templates: Vec<String>,
+
+    // Keyboard walk filter
+    #[arg(
+        long = "exclude-walks",
+        help = "Drop keyboard walks and sequences such as qwerty, 1qaz2wsx, abcd or 13579",
+        conflicts_with = "route_walks"
+    )]
+    pub exclude_walks: bool,
+
+    // Keyboard walk routing
+    #[arg(
+        long = "route-walks",
+        help = "Write keyboard walks and sequences to this file instead of the output",
+        value_name = "FILE"
+    )]
+    pub route_walks: Option<PathBuf>,
+
+    // All-digit candidate routing
+    #[arg(
+        long = "route-numeric",
+        help = "Write all-digit candidates such as PINs, dates and phone numbers to this file instead of the output",
+        value_name = "FILE"
+    )]
+    pub route_numeric: Option<PathBuf>,
+
+    // Digit counts routed
+    #[arg(
+        long = "numeric-lengths",
+        help = "Only route all-digit candidates with these digit counts, such as 4-8 (comma-separated; default any)",
+        value_name = "RANGES",
+        value_delimiter = ',',
+        value_parser = parse_digit_lengths,
+        requires = "route_numeric"
+    )]
+    pub numeric_lengths: Vec<DigitLengths>,
+
+    // Shared-library plugins
+    #[arg(
+        long = "plugin",
+        help = "Run every candidate through this filter/transform plugin (a shared library); repeatable, applied in order",
+        value_name = "LIBRARY"
+    )]
+    pub plugins: Vec<PathBuf>,
+
+    // Rhai line script
+    #[arg(
+        long = "script",
+        help = "Run this Rhai script for every candidate; true keeps it, false drops it and a string replaces it",
+        value_name = "FILE"
+    )]
+    pub script: Option<PathBuf>,
+
+    // Extra filtered outputs
+    #[arg(
+        long = "tee",
+        help = "Also write the unique lines passing these filters to FILE, as FILE=FILTER,...; filters are length:MIN-MAX, ascii, min-strength:N, max-strength:N, walks, no-walks, numeric and no-numeric; repeatable",
+        value_name = "FILE[=FILTERS]",
+        value_parser = parse_tee
+    )]
+    pub tee: Vec<TeeOutput>,
+
+    // Base word list
+    #[arg(
+        long = "base-words",
+        help = "Also write each unique line without its trailing digits and punctuation to FILE, once per base word, so password, password! and password1 give password",
+        value_name = "FILE"
+    )]
+    pub base_words: Option<PathBuf>,
+
+    // Size of a trimmed output
+    #[arg(
+        long = "target-lines",
+        help = "Keep only this many unique lines, the most frequent across all inputs first",
+        value_name = "COUNT",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub target_lines: Option<u64>,
+
+    // Line budget of the output
+    #[arg(
+        long = "max-output-lines",
+        help = "Stop writing once the output holds this many unique lines",
+        value_name = "COUNT",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "target_lines"
+    )]
+    pub max_output_lines: Option<u64>,
+
+    // Byte budget of the output
+    #[arg(
+        long = "max-output-bytes",
+        help = "Stop writing before the output exceeds this size, e.g. 64M, counting each line with its newline",
+        value_name = "SIZE",
+        value_parser = parse_size,
+        conflicts_with = "target_lines"
+    )]
+    pub max_output_bytes: Option<usize>,
+
+    // Lines kept under a cap
+    #[arg(
+        long = "cap-order",
+        help = "Which lines make the cut under --max-output-lines or --max-output-bytes: the first in output order, or the most frequent (default stable)",
+        value_name = "ORDER",
+        value_enum
+    )]
+    pub cap_order: Option<CapOrder>,
+
+    // Per-language output files
+    #[arg(
+        long = "split-by-language",
+        help = "Write lines detected as a language to their own file next to the output, such as out.en.txt"
+    )]
+    pub split_by_language: bool,
+
+    // Languages considered when splitting
+    #[arg(
+        long = "languages",
+        help = "Only detect these languages when splitting, as ISO 639-1 or 639-3 codes (comma-separated; default all)",
+        value_name = "LIST",
+        value_delimiter = ','
+    )]
+    pub languages: Vec<String>,
+
+    // Detection confidence needed to tag a line
+    #[arg(
+        long = "language-confidence",
+        help = "Detection confidence from 0 to 1 needed to move a line to a language file (default 0.2)",
+        value_name = "CONFIDENCE"
+    )]
+    pub language_confidence: Option<f64>,
+
+    // Encoding of the merged output
+    #[arg(
+        long = "output-encoding",
+        help = "Write text output in this encoding, such as windows-1252, latin-1 or utf-16le; lines it cannot represent are left out (default utf-8)",
+        value_name = "ENCODING",
+        value_parser = parse_encoding
+    )]
+    pub output_encoding: Option<&'static Encoding>,
+
+    // Byte order mark
+    #[arg(
+        long = "bom",
+        help = "Start UTF-8 or UTF-16 text output with a byte order mark (default only for UTF-16)",
+        conflicts_with = "no_bom"
+    )]
+    pub bom: bool,
+
+    // No byte order mark
+    #[arg(
+        long = "no-bom",
+        help = "Never start text output with a byte order mark, even for UTF-16"
+    )]
+    pub no_bom: bool,
+
+    // Shape of the merged output
+    #[arg(
+        long = "output-format",
+        help = "Write the unique lines as text, count<TAB>line text, a SQLite database or a Parquet file (default text)",
+        value_name = "FORMAT"
+    )]
+    pub output_format: Option<OutputFormat>,
+
+    // Occurrence count column
+    #[arg(
+        long = "with-frequency",
+        help = "Add how often each line occurred across all inputs (sqlite and parquet output), and rank merged rules by how often they occur"
+    )]
+    pub with_frequency: bool,
+
+    // First-source column
+    #[arg(
+        long = "with-provenance",
+        help = "Add the first input, in processing order, that contained each line (sqlite and parquet output)"
+    )]
+    pub with_provenance: bool,
+
+    // Manifest the inputs must still match
+    #[arg(
+        long = "verify-manifest",
+        help = "Refuse to merge if any input differs from this manifest",
+        value_name = "FILE"
+    )]
+    pub verify_manifest: Option<PathBuf>,
 }
 
 // Arguments for the generate-config command
@@ -131,6 +886,13 @@ pub struct GenerateConfigArgs {
         help = "Generate default configuration template"
     )]
     pub template: bool,
+
+    // Explanations next to every key
+    #[arg(
+        long = "commented",
+        help = "Write a comment above every key explaining it, its valid values and its performance cost (JSON with // comments, which --config accepts)"
+    )]
+    pub commented: bool,
 }
 
 // Arguments for the guided-setup command
@@ -142,6 +904,14 @@ pub struct GuidedSetupArgs {
         value_name = "FILE"
     )]
     pub output: PathBuf,
+
+    // Predetermined answers for unattended setup
+    #[arg(
+        long = "answers",
+        help = "Take the answers from this JSON object, keyed by prompt, instead of asking; missing answers take their defaults",
+        value_name = "FILE"
+    )]
+    pub answers: Option<PathBuf>,
 }
 
 // Arguments for the resume command
@@ -150,9 +920,247 @@ pub struct ResumeArgs {
     // Path to the progress state file
     #[arg(
         help = "Path to saved progress state file",
+        value_name = "FILE",
+        required_unless_present = "session",
+        conflicts_with = "session"
+    )]
+    pub progress_file: Option<PathBuf>,
+
+    // Named checkpoint under the sessions directory
+    #[arg(
+        long = "session",
+        help = "Resume the named session instead of a progress file",
+        value_name = "NAME"
+    )]
+    pub session: Option<String>,
+
+    // Where the data directory of a portable checkpoint is now
+    #[arg(
+        long = "checkpoint-root",
+        help = "Resolve the checkpoint's relative paths against DIR instead of the root it was saved with",
+        value_name = "DIR"
+    )]
+    pub checkpoint_root: Option<PathBuf>,
+
+    // Prefix rewrites for data that moved since the checkpoint was saved
+    #[arg(
+        long = "remap",
+        help = "Rewrite checkpoint and input list paths starting with OLD to start with NEW, for data moved to another drive or mount point; repeatable, the first matching prefix wins",
+        value_name = "OLD=NEW",
+        value_parser = parse_remap
+    )]
+    pub remap: Vec<PathRemap>,
+}
+
+// Arguments for the mangen command
+#[derive(Parser, Clone)]
+pub struct MangenArgs {
+    // Directory where the generated documentation is written
+    #[arg(
+        help = "Destination directory for generated man pages",
+        value_name = "DIR"
+    )]
+    pub output_dir: PathBuf,
+
+    // Flag to also generate a markdown CLI reference
+    #[arg(
+        short = 'm',
+        long = "markdown",
+        help = "Also generate a markdown CLI reference"
+    )]
+    pub markdown: bool,
+}
+
+// Arguments for the plan command
+#[derive(Parser, Clone)]
+pub struct PlanArgs {
+    // Input file containing list of wordlist paths
+    #[arg(
+        short = 'w',
+        long = "wordlists-file",
+        help = "Text file containing one wordlist path per line",
+        value_name = "FILE"
+    )]
+    pub wordlists_file: Option<PathBuf>,
+
+    // Configuration file path
+    #[arg(
+        short = 'c',
+        long = "config",
+        help = "JSON configuration file with default settings",
+        value_name = "FILE"
+    )]
+    pub config: Option<PathBuf>,
+}
+
+// Arguments for the estimate command
+#[derive(Parser, Clone)]
+pub struct EstimateArgs {
+    // Input file containing list of wordlist paths
+    #[arg(
+        short = 'w',
+        long = "wordlists-file",
+        help = "Text file containing one wordlist path per line",
+        value_name = "FILE"
+    )]
+    pub wordlists_file: Option<PathBuf>,
+
+    // Configuration file path
+    #[arg(
+        short = 'c',
+        long = "config",
+        help = "JSON configuration file with default settings",
+        value_name = "FILE"
+    )]
+    pub config: Option<PathBuf>,
+}
+
+// Arguments for the selftest command
+#[derive(Parser, Clone)]
+pub struct SelftestArgs {
+    // Flag to keep the scratch directory for inspection
+    #[arg(
+        short = 'k',
+        long = "keep",
+        help = "Keep the temporary directory with inputs and outputs"
+    )]
+    pub keep: bool,
+}
+
+// Arguments for the retry-failed command
+#[derive(Parser, Clone)]
+pub struct RetryFailedArgs {
+    // Skip list written by an earlier merge
+    #[arg(
+        help = "failed_files.json written by an earlier merge",
+        value_name = "FILE"
+    )]
+    pub failed_files: PathBuf,
+
+    // Output of the earlier merge that recovered lines are merged into
+    #[arg(
+        long = "append-to",
+        help = "Merged output to add the recovered unique lines to",
+        value_name = "FILE"
+    )]
+    pub append_to: PathBuf,
+}
+
+// Arguments for the manifest command
+#[derive(Parser, Clone)]
+pub struct ManifestArgs {
+    // Input file containing list of wordlist paths
+    #[arg(
+        short = 'w',
+        long = "wordlists-file",
+        help = "Text file containing one wordlist path per line",
+        value_name = "FILE"
+    )]
+    pub wordlists_file: PathBuf,
+
+    // Output path for the manifest
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "Destination path for the JSON manifest",
+        value_name = "FILE"
+    )]
+    pub output: PathBuf,
+}
+
+// Arguments for the check command
+#[derive(Parser, Clone)]
+pub struct CheckArgs {
+    // File to verify
+    #[arg(
+        help = "Merged file to verify",
+        value_name = "FILE"
+    )]
+    pub file: PathBuf,
+
+    // Require byte-order sorting
+    #[arg(
+        long = "expect-sorted",
+        help = "Flag lines that sort before the previous line (byte order)"
+    )]
+    pub expect_sorted: bool,
+
+    // Require unique lines
+    #[arg(
+        long = "expect-unique",
+        help = "Flag lines that repeat an earlier line (the default when no check is chosen)"
+    )]
+    pub expect_unique: bool,
+
+    // Number of offending lines to list
+    #[arg(
+        long = "max-offenders",
+        help = "Offending lines to list",
+        value_name = "COUNT",
+        default_value_t = 20
+    )]
+    pub max_offenders: usize,
+}
+
+// Arguments for the convert-encoding command
+#[derive(Parser, Clone)]
+pub struct ConvertEncodingArgs {
+    // Single file to convert
+    #[arg(
+        help = "File to convert",
+        value_name = "FILE",
+        required_unless_present = "wordlists_file",
+        conflicts_with = "wordlists_file",
+        requires = "output"
+    )]
+    pub input: Option<PathBuf>,
+
+    // Output for a single file
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "Destination path for the converted file",
         value_name = "FILE"
     )]
-    pub progress_file: PathBuf,
+    pub output: Option<PathBuf>,
+
+    // Batch mode: convert every listed file
+    #[arg(
+        short = 'w',
+        long = "wordlists-file",
+        help = "Convert every file listed in this file (one path per line)",
+        value_name = "FILE",
+        requires = "output_dir"
+    )]
+    pub wordlists_file: Option<PathBuf>,
+
+    // Output directory for batch mode
+    #[arg(
+        long = "output-dir",
+        help = "Directory receiving the converted files in batch mode, under their original names",
+        value_name = "DIR"
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    // Source encoding
+    #[arg(
+        long = "from",
+        help = "Source encoding, or `auto` to detect it per file",
+        value_name = "ENCODING",
+        default_value = "auto",
+        value_parser = parse_source_encoding
+    )]
+    pub from: SourceEncoding,
+
+    // Target encoding
+    #[arg(
+        long = "to",
+        help = "Target encoding",
+        value_name = "ENCODING",
+        default_value = "utf-8",
+        value_parser = parse_encoding
+    )]
+    pub to: &'static Encoding,
 }
 
 // Implementation of helper methods for the Cli struct
@@ -173,4 +1181,302 @@ impl Cli {
     pub fn verbose_count(&self) -> u8 {
         self.verbose
     }
-} 
\ No newline at end of file
+} 
+
+// Direction of a `hex` conversion
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexMode {
+    Encode, // Raw lines to `$HEX[...]`
+    Decode, // `$HEX[...]` lines back to raw bytes
+}
+
+// Arguments for the hex command
+#[derive(Parser, Clone)]
+pub struct HexArgs {
+    // Conversion direction
+    #[arg(
+        help = "Whether to encode lines as $HEX[...] or decode them",
+        value_name = "MODE"
+    )]
+    pub mode: HexMode,
+
+    // File to convert
+    #[arg(
+        help = "File to convert",
+        value_name = "FILE"
+    )]
+    pub input: PathBuf,
+
+    // Destination, stdout when absent
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "Destination path for the converted lines (default stdout)",
+        value_name = "FILE"
+    )]
+    pub output: Option<PathBuf>,
+
+    // Encode every line rather than only the unsafe ones
+    #[arg(
+        long = "all",
+        help = "Encode every line, not only invalid UTF-8, control characters and literal $HEX[...] lines"
+    )]
+    pub all: bool,
+}
+
+// Arguments for the redundancy command
+#[derive(Parser, Clone)]
+pub struct RedundancyArgs {
+    // Input file containing list of wordlist paths
+    #[arg(
+        short = 'w',
+        long = "wordlists-file",
+        help = "Text file containing one wordlist path per line",
+        value_name = "FILE"
+    )]
+    pub wordlists_file: PathBuf,
+
+    // Coverage at which an input is flagged
+    #[arg(
+        long = "threshold",
+        help = "Flag inputs whose lines are at least this many percent found in other inputs",
+        value_name = "PERCENT",
+        default_value_t = 99.0
+    )]
+    pub threshold: f64,
+
+    // Machine-readable copy of the ranking
+    #[arg(
+        long = "report",
+        help = "Also write the ranking as JSON",
+        value_name = "FILE"
+    )]
+    pub report: Option<PathBuf>,
+}
+
+// Arguments for the analyze command
+#[derive(Parser, Clone)]
+pub struct AnalyzeArgs {
+    // File to analyze
+    #[arg(
+        help = "Wordlist to analyze",
+        value_name = "FILE"
+    )]
+    pub file: PathBuf,
+
+    // Near-duplicate clustering
+    #[arg(
+        long = "near-dupes",
+        help = "Cluster lines within one insertion, deletion or substitution of each other (the default when no analysis is chosen)"
+    )]
+    pub near_dupes: bool,
+
+    // Shortest line compared
+    #[arg(
+        long = "min-length",
+        help = "Skip lines shorter than this many characters, where one edit makes most lines alike",
+        value_name = "CHARS",
+        default_value_t = 4
+    )]
+    pub min_length: usize,
+
+    // Longest line compared
+    #[arg(
+        long = "max-length",
+        help = "Skip lines longer than this many characters, bounding the memory used",
+        value_name = "CHARS",
+        default_value_t = 64
+    )]
+    pub max_length: usize,
+
+    // Number of clusters to print
+    #[arg(
+        long = "show",
+        help = "Clusters to print, largest first",
+        value_name = "COUNT",
+        default_value_t = 20
+    )]
+    pub show: usize,
+
+    // Machine-readable copy of the analysis
+    #[arg(
+        long = "report",
+        help = "Also write every cluster as JSON",
+        value_name = "FILE"
+    )]
+    pub report: Option<PathBuf>,
+}
+
+// Arguments for the worker command
+#[derive(Parser, Clone)]
+pub struct WorkerArgs {
+    // Address to accept jobs on
+    #[arg(
+        short = 'l',
+        long = "listen",
+        help = "Address to accept jobs on",
+        value_name = "ADDR",
+        default_value = "0.0.0.0:7879"
+    )]
+    pub listen: SocketAddr,
+
+    // Secret a job must carry
+    #[arg(
+        long = "token",
+        help = "Shared secret every job must carry, sent as plaintext JSON over TCP; anyone holding it can read files this user can",
+        value_name = "TOKEN"
+    )]
+    pub token: String,
+
+    // Directory for intermediates and spill runs
+    #[arg(
+        long = "temp-dir",
+        help = "Directory for intermediates, spill runs and other temporary files (default: OS temp dir)",
+        value_name = "DIR"
+    )]
+    pub temp_dir: Option<PathBuf>,
+
+    // Deduplication shards per job
+    #[arg(
+        short = 't',
+        long = "threads",
+        help = "Worker threads per job (default: available CPUs)",
+        value_name = "N"
+    )]
+    pub threads: Option<usize>,
+}
+
+// Arguments for the fetch command
+#[derive(Parser, Clone)]
+pub struct FetchArgs {
+    // Lists to fetch
+    #[arg(
+        help = "Names of the lists to fetch, such as seclists/passwords/rockyou",
+        value_name = "NAME",
+        required_unless_present = "list"
+    )]
+    pub names: Vec<String>,
+
+    // Catalog of known lists
+    #[arg(
+        long = "list",
+        help = "Show the lists that can be fetched and exit"
+    )]
+    pub list: bool,
+
+    // Where downloads are kept
+    #[arg(
+        long = "cache-dir",
+        help = "Directory the lists are kept in (default $XDG_CACHE_HOME/rustmerger/lists or ~/.cache/rustmerger/lists)",
+        value_name = "DIR"
+    )]
+    pub cache_dir: Option<PathBuf>,
+
+    // Forced download
+    #[arg(
+        long = "refresh",
+        help = "Download the lists again even when the cached copies match their checksums"
+    )]
+    pub refresh: bool,
+
+    // Ready-made wordlists file
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "Also write the paths to this file, one per line, for merge --wordlists-file",
+        value_name = "FILE"
+    )]
+    pub output: Option<PathBuf>,
+}
+
+// Arguments for the history command
+#[derive(Parser, Clone)]
+pub struct HistoryArgs {
+    // Journal to read
+    #[arg(
+        long = "file",
+        help = "Run history to read (default $XDG_DATA_HOME/rustmerger/runs.jsonl or ~/.local/share/rustmerger/runs.jsonl)",
+        value_name = "FILE",
+        global = true
+    )]
+    pub file: Option<PathBuf>,
+
+    // What to show; the list when absent
+    #[command(subcommand)]
+    pub action: Option<HistoryAction>,
+}
+
+// Views of the run history
+#[derive(Subcommand, Clone)]
+pub enum HistoryAction {
+    // Latest runs, newest first
+    #[command(about = "List past runs, newest first (the default)")]
+    List {
+        // Number of runs listed
+        #[arg(
+            long = "limit",
+            help = "Show at most this many runs",
+            value_name = "COUNT",
+            default_value_t = 20
+        )]
+        limit: usize,
+
+        // Raw records instead of the table
+        #[arg(
+            long = "json",
+            help = "Print the records as JSON Lines instead of a table"
+        )]
+        json: bool,
+    },
+
+    // Every recorded detail of one run
+    #[command(about = "Show every recorded detail of one run")]
+    Show {
+        // Run to show
+        #[arg(
+            help = "Run id, or enough of its first characters to be unique",
+            value_name = "ID"
+        )]
+        id: String,
+    },
+}
+
+// Arguments for the sessions command
+#[derive(Parser, Clone)]
+pub struct SessionsArgs {
+    // What to do; the list when absent
+    #[command(subcommand)]
+    pub action: Option<SessionsAction>,
+}
+
+// Views and cleanup of saved sessions
+#[derive(Subcommand, Clone)]
+pub enum SessionsAction {
+    // Every session with its state
+    #[command(about = "List saved sessions (the default)")]
+    List,
+
+    // Checkpoint of one session
+    #[command(about = "Show the saved state of one session")]
+    Show {
+        // Session to show
+        #[arg(help = "Session name", value_name = "NAME")]
+        name: String,
+    },
+
+    // Removal of checkpoints
+    #[command(about = "Remove the named sessions, or every finished one when none are named")]
+    Clean {
+        // Sessions to remove
+        #[arg(help = "Sessions to remove, finished or not", value_name = "NAME")]
+        names: Vec<String>,
+
+        // Every session, interrupted ones included
+        #[arg(
+            long = "all",
+            help = "Remove every session, including interrupted ones",
+            conflicts_with = "names"
+        )]
+        all: bool,
+    },
+}
@@ -1,52 +1,482 @@
 // Import required dependencies
 use anyhow::Result;                // For error handling
-use std::path::PathBuf;           // For file path operations
+use clap::Parser;                  // For the defaults of run-only merge options on resume
+use std::path::{Path, PathBuf};   // For file path operations
 use std::sync::Arc;               // For thread-safe reference counting
+use std::time::Instant;           // For timing runs recorded in the history
 use log::{info, warn};            // For logging
-use crate::errors::{MergerError, MergerResult};
 
 // Import local modules
 use crate::{
     app_state::AppState,          // Application state management
     config::Config,               // Configuration handling
     core::ProcessingCore,         // Core processing logic
-    cli::{Cli, MergeArgs, GenerateConfigArgs, GuidedSetupArgs, ResumeArgs}, // CLI arguments
+    cli::{Cli, AnalyzeArgs, CheckArgs, ConvertEncodingArgs, EstimateArgs, HexArgs, HexMode, MergeArgs, RedundancyArgs, GenerateConfigArgs, GuidedSetupArgs, HistoryAction, HistoryArgs, WorkerArgs, FetchArgs, MangenArgs, ManifestArgs, PlanArgs, ResumeArgs, RetryFailedArgs, SelftestArgs, SessionsAction, SessionsArgs}, // CLI arguments
+    docs::DocsGenerator,          // Man page and markdown generation
+    estimate::{format_bytes, ResourceEstimator}, // Sampling-based resource estimates
+    plan::ProcessingPlan,         // Processing schedule preview
+    preflight::{DiskPreflight, MemoryPreflight}, // Disk-space and memory checks before merging
+    dedup_backend::DedupBackend,  // How the set of lines seen is kept
+    errors::{ConfigError, MergerError}, // Error types for invalid options and refused merges
+    scratch,                      // Temporary directory validation
+    distributed,                  // Worker side of distributed merges
+    fetch::{self, KnownList},     // Downloads of well-known lists
+    download_cache::DownloadCache, // Cache of URL inputs
+    selftest::{self, SelfTest},   // Built-in self-test suite
     signal_handler::SignalHandler,         // Add this with other imports
+    io_backend::IoBackend,        // File I/O strategies
+    rules::{self, RuleFilter, RuleMerger}, // In-memory rule file merging
+    failed_files::{FailedFiles, DEFAULT_FAILED_FILES_PATH}, // Skip list of inputs that failed
+    exit_code::RunOutcome,        // How a merge ended, for the exit code
+    manifest::Manifest,           // Checksummed input manifests
+    check::{self, CheckOptions, OffenceKind}, // Output integrity checks
+    encoding::{self, EncodingStats, OutputEncoding}, // Encoding detection and conversion
+    input_list::{self, InputList, ListOptions}, // Normalized input listings and how entries resolve
+    hex_format,                   // Hashcat $HEX[...] notation
+    line_options::LineOptions,    // How raw lines become candidates
+    input_format::InputFormat,    // How the earlier output of a retry reads back
+    report::RunReport,            // Summary of a run, for the report file, notifications and history
+    output_format::{CapOrder, OutputCap, OutputColumns, OutputFormat}, // Output shape, cap and extra columns of analytical output
+    redundancy::RedundancyReport, // Ranking of inputs by exclusive lines
+    near_dupes::NearDupeReport,   // Clusters of lines within one edit of each other
+    strength::{self, StrengthRange}, // Password strength filter
+    transform::{self, Expansion, Transform}, // Variants and hybrid candidates of every word
+    language::{self, LanguageSplit}, // Per-language output files
+    routing::{self, Route, RouteMatch}, // Kinds of lines diverted to their own files
+    plugin::PluginSet,            // Shared-library filter and transform plugins
+    script::LineScript,           // Rhai scripts deciding each line
+    pipeline::{self, Pipeline, StageConfig}, // Ordered filter and transform stages
+    tee::{self, TeeOutput},       // Extra filtered outputs written in the same pass
+    web_ui::WebUi,                // Live status page
+    tui::Dashboard,               // Full-screen progress dashboard
+    notify::{self, Notification}, // Completion webhooks
+    history,                      // Journal of finished runs
+    throttle::RateLimit,          // Read and write rate limits
+    priority,                     // Niceness and I/O scheduling class
+    session,                      // Named resume checkpoints
+    emit_config,                  // Merge options folded into a reusable config
+    config_template,              // Commented configuration files
 };
 
+const RULE_RANKING_SHOWN: usize = 10; // Most common rules printed after a rule merge with frequency tracking
+
+// Where a merge takes its inputs and output from
+enum MergeStart {
+    New,                   // The input list and output of the config
+    Resume(Arc<AppState>), // The checkpoint of an interrupted merge
+    Retry {                // The failed inputs of an earlier merge, added to its output
+        state: Arc<AppState>,  // Merges the failed inputs into a temporary file
+        output: PathBuf,       // Output of the earlier merge, read back first
+        failed_files: PathBuf, // Skip list the inputs came from, rewritten with those failing again
+    },
+}
+
 // Command handler for processing CLI commands
 pub struct CommandHandler;
 
 impl CommandHandler {
     // Handle the merge command - combines wordlists and rules
-    pub async fn handle_merge(cli: &Cli, args: MergeArgs) -> Result<()> {
+    pub async fn handle_merge(cli: &Cli, args: MergeArgs) -> Result<RunOutcome> {
         info!("Starting merge operation");
+        let mut config = Self::resolve_merge_config(&args, cli.verbose_count() > 0).await?;
+
+        // Capture the options as resolved, so the run can be repeated from the config alone
+        if let Some(path) = &args.emit_config {
+            let worker_token = config.worker_token.take(); // The secret stays out of the file
+            config.save(path).await?;
+            config.worker_token = worker_token;
+            info!("Resolved options written to {:?}", path);
+        }
 
+        Self::run_merge(args, config, MergeStart::New).await
+    }
+
+    // The config of a merge: the file, the preset, single keys, then every command-line option
+    //
+    // Each layer overrides only the keys it sets. The decode, dedup and output settings of a
+    // pipeline section are folded into their keys, so only its line stages are left in it.
+    pub async fn resolve_merge_config(args: &MergeArgs, verbose: bool) -> Result<Config> {
         // Load existing config or create default template
-        let config = if let Some(config_path) = args.config {
-            Config::load(&config_path).await?
+        let mut config = if let Some(config_path) = &args.config {
+            Config::load(config_path).await?
         } else {
             Config::default()
         };
+        // A preset sets only the keys it covers, on top of the file
+        if let Some(preset) = args.preset {
+            info!("Using the {} preset", preset.label());
+            preset.apply(&mut config);
+        }
+        // Single keys set on the command line go on top of the file and preset
+        let mut config = config.with_overrides(&args.set)?;
+        // A pipeline section sets the decode, dedup and output keys its stages cover
+        let pipeline_stages = std::mem::take(&mut config.pipeline);
+        pipeline::apply_settings(&pipeline_stages, &mut config)?;
+        config.pipeline = pipeline_stages.into_iter().filter(StageConfig::is_line_stage).collect();
+        // Command-line options go on top of everything
+        emit_config::apply_args(&mut config, args, verbose);
+        Ok(config)
+    }
 
-        // Create thread-safe application state
-        let app_state = Arc::new(AppState::new(
-            args.wordlists_file
-                .or(config.input_files)
-                .ok_or_else(|| anyhow::anyhow!("No wordlists file specified"))?,
-            args.output_wordlist
-                .or(config.output_files)
-                .ok_or_else(|| anyhow::anyhow!("No output file specified"))?,
-            if let Some(threads) = config.threads {
-                threads
-            } else {
-                10 // Default to 10 threads if not specified
+    // Run a merge with the options resolved into `config`
+    //
+    // `args` supplies only the options of this run that have no config key, such as
+    // --session, --dry-run or --report. A resume or retry passes the state it set up, which
+    // names the inputs and output instead of the config.
+    async fn run_merge(args: MergeArgs, config: Config, start: MergeStart) -> Result<RunOutcome> {
+        let started = Instant::now();
+        let command = match &start {
+            MergeStart::New => "merge",
+            MergeStart::Resume(_) => "resume",
+            MergeStart::Retry { .. } => "retry-failed",
+        };
+        let checkpointed = matches!(start, MergeStart::Resume(_)) || args.session.is_some() || args.progress_file.is_some();
+        // The checkpoint and the failed files record the options, so a resume or retry runs the same merge; the secret stays out
+        let recorded = Config { worker_token: None, ..config.clone() };
+
+        // Lower (or raise) priority before any heavy work starts
+        let nice = config.nice.map(priority::validate_nice).transpose().map_err(ConfigError::InvalidPriority)?;
+        let ionice = config.ionice.as_deref().map(priority::parse_io_priority).transpose().map_err(ConfigError::InvalidPriority)?;
+        priority::apply(nice, ionice);
+
+        // Rule lists are small, so they are merged in memory before the wordlists
+        if let (Some(rules_file), false) = (&args.rules_file, args.dry_run) {
+            let output_rules = args.output_rules.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("--rules-file requires --output-rules"))?;
+            let frequency = config.output_columns.frequency;
+            let rejected = config.reject_rule_functions.iter().map(|function| rules::parse_rule_function(function)).collect::<Result<_, _>>()
+                .map_err(|e| anyhow::anyhow!("reject_rule_functions: {}", e))?;
+            let filter = RuleFilter {
+                max_ops: config.max_rule_ops.map(|ops| ops as usize),
+                rejected,
+            };
+            let summary = RuleMerger::new(config.rule_comments)
+                .with_frequency(frequency)
+                .with_filter(filter)
+                .with_ignore_whitespace(config.rule_ignore_whitespace)
+                .merge(rules_file, output_rules).await?;
+            println!(
+                "Merged {} rules from {} files into {} unique rules ({} comment lines kept)",
+                summary.rules, summary.files, summary.unique_rules, summary.comments
+            );
+            if summary.rejected > 0 {
+                println!("Dropped {} rules over the complexity limits", summary.rejected);
             }
-        ).await?);
+            if frequency {
+                println!("Most common rules (occurrences, files):");
+                for entry in summary.ranking.iter().take(RULE_RANKING_SHOWN) {
+                    println!("  {:>8} {:>5}  {}", entry.occurrences, entry.files, String::from_utf8_lossy(&entry.rule));
+                }
+                let ranking_path = rules::ranking_path(output_rules);
+                summary.write_ranking(&ranking_path).await?;
+                println!("Rule ranking written to {}", ranking_path.display());
+            }
+
+            if config.input_files.is_none() {
+                info!("Merge operation completed");
+                return Ok(RunOutcome::Success);
+            }
+        }
+
+        let input_file = match &start {
+            MergeStart::New => config.input_files.ok_or_else(|| anyhow::anyhow!("No wordlists file specified"))?,
+            MergeStart::Resume(state) | MergeStart::Retry { state, .. } => state.input_file.clone(),
+        };
+        let skip_larger_than = config.skip_larger_than;
+        if skip_larger_than == Some(0) {
+            return Err(ConfigError::InvalidTuning("--skip-larger-than must be at least 1 byte".to_string()).into());
+        }
+        // How directories in the input list are walked, for the estimates and the merge
+        let list_options = ListOptions {
+            follow_symlinks: config.follow_symlinks,
+            recursive: !config.no_recurse,
+            extensions: input_list::normalize_extensions(&config.extensions),
+            remaps: match &start {
+                MergeStart::Resume(state) => state.progress.read().await.remaps.clone(),
+                _ => Vec::new(),
+            },
+        };
+
+        // Refuse to build from inputs that no longer match the recorded manifest
+        if let Some(manifest_path) = &args.verify_manifest {
+            let problems = Manifest::load(manifest_path).await?.verify(&input_file).await?;
+            if !problems.is_empty() {
+                return Err(MergerError::InputValidation(format!(
+                    "inputs differ from {}:\n  {}",
+                    manifest_path.display(),
+                    problems.join("\n  ")
+                )).into());
+            }
+            info!("All inputs match manifest {:?}", manifest_path);
+        }
+
+        // Dry run: sample the inputs, report estimates and exit without writing
+        if args.dry_run {
+            let estimate = ResourceEstimator::estimate_with(&input_file, list_options, skip_larger_than).await?;
+            println!("Dry run: no output will be written");
+            estimate.print_report();
+            return Ok(RunOutcome::Success);
+        }
+
+        let (app_state, retry) = match start {
+            MergeStart::Resume(state) => (state, None),
+            MergeStart::Retry { state, output, failed_files } => (state, Some((output, failed_files))),
+            MergeStart::New => {
+                // Create thread-safe application state
+                let app_state = Arc::new(AppState::new(
+                    input_file,
+                    config.output_files
+                        .ok_or_else(|| anyhow::anyhow!("No output file specified"))?,
+                    config.threads.unwrap_or(10) // Default to 10 threads if not specified
+                ).await?);
+
+                // Checkpoint after every file into the named session or the progress file
+                let checkpoint = match &args.session {
+                    Some(name) => Some(session::prepare(name).await?),
+                    None => args.progress_file.clone(),
+                };
+                let checkpoint_root = config.checkpoint_root;
+                match checkpoint {
+                    Some(path) => app_state.checkpoint_to(path, checkpoint_root, recorded.clone()).await?,
+                    None if checkpoint_root.is_some() => {
+                        return Err(ConfigError::InvalidTuning("--checkpoint-root needs --session or --progress-file".to_string()).into());
+                    }
+                    None => {}
+                }
+                (app_state, None)
+            }
+        };
 
         // Fix debug and verbose settings
-        let debug_enabled = args.debug || config.debug; // Enable debug if specified in args or config
-        let verbose_enabled = cli.verbose_count() > 0 || config.verbose; // Enable verbose if specified in CLI or config
+        let debug_enabled = config.debug;
+        let verbose_enabled = config.verbose;
+        let dedup_backend = config.dedup_backend;
+        // Bloom filters keep no lines to write at the end, so their output is always streamed
+        let stream_output = config.stream_output || dedup_backend.streams();
+        if stream_output && matches!(dedup_backend, DedupBackend::ExternalSort | DedupBackend::DiskKv) {
+            return Err(ConfigError::InvalidTuning(format!(
+                "--dedup-backend {} spills to disk, which --stream-output cannot", dedup_backend.label()
+            )).into());
+        }
+        let stages = config.stages.unwrap_or(1) as usize;
+        if stages == 0 {
+            return Err(ConfigError::InvalidTuning("stages must be at least 1".to_string()).into());
+        }
+        if stages > 1 && dedup_backend == DedupBackend::Bloom {
+            return Err(ConfigError::InvalidTuning("--stages cannot be combined with --dedup-backend bloom, which keeps no lines to pass to the next stage".to_string()).into());
+        }
+        if stages > 1 && checkpointed {
+            return Err(ConfigError::InvalidTuning("merges with --stages cannot be resumed; leave out --session and --progress-file".to_string()).into());
+        }
+        let workers = config.workers;
+        let worker_token = config.worker_token;
+        if !workers.is_empty() {
+            if worker_token.is_none() {
+                return Err(ConfigError::InvalidTuning("--workers needs --worker-token, the secret the workers were started with".to_string()).into());
+            }
+            if stages > 1 || dedup_backend == DedupBackend::Bloom || checkpointed {
+                return Err(ConfigError::InvalidTuning(
+                    "--workers cannot be combined with --stages, --dedup-backend bloom, --session or --progress-file".to_string()
+                ).into());
+            }
+        }
+        let strict = config.strict;
+        let tui = config.tui;
+        let notify_webhook = config.notify_webhook.as_deref().map(notify::parse_webhook).transpose().map_err(ConfigError::InvalidWebhook)?;
+        let io_backend = config.io_backend;
+        io_backend.ensure_available()?;
+        let tuning = config.tuning;
+        tuning.validate()?;
+        let rate_limit = |mbps: Option<f64>| mbps.map(RateLimit::new).transpose().map_err(ConfigError::InvalidTuning);
+        let read_limit = rate_limit(config.max_read_mbps)?;
+        let write_limit = rate_limit(config.max_write_mbps)?;
+        if io_backend == IoBackend::Uring && write_limit.is_some() {
+            return Err(ConfigError::InvalidTuning(
+                "the write rate cannot be limited with the uring backend, which writes the output in large blocks at once".to_string()
+            ).into());
+        }
+        let retry_policy = config.retry;
+        let failure_limits = config.failure_limits;
+        failure_limits.validate()?;
+        let append_years = config.append_years.as_deref().map(transform::parse_years).transpose()
+            .map_err(ConfigError::InvalidTemplate)?;
+        let expansion = Expansion::new(append_years, config.append_strings, config.templates)?;
+        let route_walks = config.route_walks;
+        let exclude_walks = config.exclude_walks;
+        if exclude_walks && route_walks.is_some() {
+            return Err(ConfigError::InvalidOutputFormat("--exclude-walks and --route-walks cannot be combined".to_string()).into());
+        }
+        let mut transforms = config.transforms;
+        if config.emit_reversed && !transforms.contains(&Transform::Reverse) {
+            transforms.push(Transform::Reverse);
+        }
+        let strength = StrengthRange {
+            min: config.min_strength.unwrap_or(0),
+            max: config.max_strength.unwrap_or(strength::MAX_SCORE),
+        };
+        strength.validate()?;
+        let standard = Pipeline::standard(
+            strength,
+            exclude_walks,
+            config.script.map(|path| LineScript::load(&path).map(Arc::new)).transpose()?,
+            PluginSet::load(&config.plugins)?,
+            transforms,
+            expansion,
+        );
+        // A config pipeline with filter or transform stages replaces the individual options
+        let line_pipeline = if !config.pipeline.is_empty() {
+            if !standard.is_empty() {
+                return Err(ConfigError::InvalidPipeline(
+                    "the config pipeline lists the filter and transform stages; drop the filter, script, plugin and transform options".to_string()
+                ).into());
+            }
+            pipeline::build_line_stages(&config.pipeline)?
+        } else {
+            standard
+        };
+        let line_options = LineOptions {
+            hex_invalid: config.hex_invalid,
+            binary_mode: config.binary_mode,
+            strip_control: config.strip_control,
+            normalize_homoglyphs: config.normalize_homoglyphs,
+            format: config.input_format,
+            json_field: config.json_field.map(Into::into),
+            dedup_key: config.dedup_key,
+            pipeline: Arc::new(line_pipeline),
+            ..LineOptions::default() // Per-entry settings are filled in by `for_entry`
+        };
+        line_options.validate()?;
+        let output_format = config.output_format;
+        let output_columns = OutputColumns {
+            frequency: config.output_columns.frequency || output_format == OutputFormat::Counted, // Counted output is the frequency
+            provenance: config.output_columns.provenance,
+        };
+        output_columns.validate(output_format, stream_output)?;
+        if output_columns.provenance && (stages > 1 || !workers.is_empty()) {
+            return Err(ConfigError::InvalidOutputFormat(
+                "--with-provenance cannot be combined with --stages or --workers, whose final merge only sees intermediates".to_string()
+            ).into());
+        }
+        if output_columns.provenance && checkpointed {
+            return Err(ConfigError::InvalidOutputFormat(
+                "--with-provenance cannot be combined with --session or --progress-file, as a resume reads the finished inputs back from one file".to_string()
+            ).into());
+        }
+        let language_split = config.split_by_language.then(|| LanguageSplit {
+            languages: config.languages,
+            min_confidence: config.language_confidence.unwrap_or(language::DEFAULT_MIN_CONFIDENCE),
+        });
+        if let Some(split) = &language_split {
+            split.detector()?;
+            if output_format != OutputFormat::Text || stream_output {
+                return Err(ConfigError::InvalidOutputFormat("--split-by-language needs text output without --stream-output".to_string()).into());
+            }
+        }
+        let numeric_lengths = config.numeric_lengths.iter().map(|range| routing::parse_digit_lengths(range)).collect::<Result<Vec<_>, _>>()
+            .map_err(ConfigError::InvalidOutputFormat)?;
+        // Numbers are routed first, so a numeric sequence such as 123456 lands with the numbers
+        let mut routes: Vec<Route> = Vec::new();
+        if let Some(path) = config.route_numeric {
+            routes.push(Route { matcher: RouteMatch::Numeric(numeric_lengths), path });
+        }
+        if let Some(path) = route_walks {
+            routes.push(Route { matcher: RouteMatch::Walks, path });
+        }
+        if !routes.is_empty() && stream_output {
+            return Err(ConfigError::InvalidOutputFormat("routed outputs cannot be combined with --stream-output".to_string()).into());
+        }
+        let tees: Vec<TeeOutput> = config.tee.iter().map(|output| tee::parse_tee(output)).collect::<Result<_, _>>()
+            .map_err(ConfigError::InvalidOutputFormat)?;
+        if !tees.is_empty() && stream_output {
+            return Err(ConfigError::InvalidOutputFormat("--tee cannot be combined with --stream-output".to_string()).into());
+        }
+        let base_words = config.base_words;
+        if base_words.is_some() && stream_output {
+            return Err(ConfigError::InvalidOutputFormat("--base-words cannot be combined with --stream-output".to_string()).into());
+        }
+        let target_encoding = config.output_encoding.as_deref().map(encoding::parse_encoding).transpose()
+            .map_err(ConfigError::InvalidOutputFormat)?
+            .unwrap_or(encoding_rs::UTF_8);
+        let output_encoding = OutputEncoding::new(target_encoding, config.bom).map_err(ConfigError::InvalidOutputFormat)?;
+        if output_encoding.is_some() && (output_format != OutputFormat::Text || stream_output || language_split.is_some()) {
+            return Err(ConfigError::InvalidOutputFormat(
+                "--output-encoding and --bom need text output without --stream-output or --split-by-language".to_string()
+            ).into());
+        }
+        let target_lines = config.target_lines;
+        let max_output_lines = config.max_output_lines;
+        let max_output_bytes = config.max_output_bytes;
+        if target_lines == Some(0) || max_output_lines == Some(0) || max_output_bytes == Some(0) {
+            return Err(ConfigError::InvalidOutputFormat("--target-lines, --max-output-lines and --max-output-bytes must be at least 1".to_string()).into());
+        }
+        let output_cap = match target_lines {
+            Some(_) if max_output_lines.is_some() || max_output_bytes.is_some() => {
+                return Err(ConfigError::InvalidOutputFormat(
+                    "--target-lines cannot be combined with --max-output-lines or --max-output-bytes; use --cap-order frequency".to_string()
+                ).into());
+            }
+            // --target-lines is a line cap that keeps the most frequent lines
+            Some(target) => Some(OutputCap { lines: Some(target as usize), bytes: None, order: CapOrder::Frequency }),
+            None => (max_output_lines.is_some() || max_output_bytes.is_some()).then(|| OutputCap {
+                lines: max_output_lines.map(|lines| lines as usize),
+                bytes: max_output_bytes,
+                order: config.cap_order.unwrap_or_default(),
+            }),
+        };
+        if output_cap.is_some() && stream_output {
+            return Err(ConfigError::InvalidOutputFormat(
+                "--target-lines, --max-output-lines and --max-output-bytes cannot be combined with --stream-output".to_string()
+            ).into());
+        }
+        let temp_dir = scratch::prepare(config.temp_dir)?; // Fail before any work if unusable
+        let download_dir = config.download_dir.or_else(DownloadCache::default_dir);
+
+        // Reclaim space from spill runs of merges that crashed or were killed
+        let (removed, freed) = scratch::cleanup_orphans(&temp_dir).await?;
+        if removed > 0 {
+            info!("Removed {} orphaned spill directories from previous runs, freeing {}", removed, format_bytes(freed));
+        }
+
+        // Sample the inputs for the backend choice and the preflight checks
+        let estimate = ResourceEstimator::estimate_with(&app_state.input_file, list_options.clone(), skip_larger_than).await?;
+
+        // Pick the dedup backend, explaining an automatic choice
+        let backend = dedup_backend.resolve(&estimate, stream_output);
+        if dedup_backend == DedupBackend::Auto {
+            println!("Dedup backend: {} ({})", backend.backend.label(), backend.reason);
+        } else {
+            info!("Dedup backend: {}", backend.backend.label());
+        }
+        if backend.backend == DedupBackend::Bloom {
+            warn!("The bloom backend may drop about 0.1% of unique lines it mistakes for duplicates");
+        }
+
+        // Refuse to start a merge that would run out of disk partway through
+        let preflight = DiskPreflight::check(&estimate, &app_state.output_file, &temp_dir, backend.backend, !stream_output)?;
+        if preflight.shortfalls().next().is_some() {
+            let shortfalls = preflight.describe_shortfalls();
+            if !args.force {
+                return Err(MergerError::DiskSpace(format!("{} (use --force to start anyway)", shortfalls)).into());
+            }
+            warn!("Starting despite insufficient disk space: {}", shortfalls);
+        }
+
+        // Predict the peak memory; spilling covers a set that outgrows it, streamed output cannot
+        let memory = MemoryPreflight::check(&estimate, &tuning, backend.backend);
+        println!("Predicted peak memory: {}", format_bytes(memory.predicted_peak()));
+        if memory.shortfall() > 0 {
+            if stream_output && !args.force {
+                return Err(MergerError::Memory(format!(
+                    "{}; --stream-output cannot spill to disk (use --force to start anyway)",
+                    memory.describe()
+                )).into());
+            }
+            warn!("{}; {}", memory.describe(), if stream_output {
+                "starting anyway, the merge may be killed for running out of memory"
+            } else {
+                "the deduplication set will spill to disk"
+            });
+        }
 
         // Set up signal handler
         let signal_handler = SignalHandler::new(app_state.clone())?;
@@ -57,14 +487,98 @@ impl CommandHandler {
             app_state.clone(),
             debug_enabled,
             verbose_enabled
-        ).await?;
+        ).await?
+            .with_stream_output(stream_output)
+            .with_io_backend(io_backend)
+            .with_tuning(tuning)
+            .with_temp_dir(temp_dir)
+            .with_dedup_backend(backend.backend, estimate.estimated_lines)
+            .with_stages(stages)
+            .with_workers(workers, worker_token.unwrap_or_default())
+            .with_retry_policy(retry_policy)
+            .with_strict(strict)
+            .with_failure_limits(failure_limits)
+            .with_file_stats(args.file_stats)
+            .with_list_options(list_options)
+            .with_skip_larger_than(skip_larger_than)
+            .with_content_sniff(!config.no_content_sniff)
+            .with_download_cache(download_dir.map(|dir| DownloadCache::new(dir, args.refresh)))
+            .with_line_options(line_options)
+            .with_output_format(output_format, output_columns)
+            .with_output_cap(output_cap)
+            .with_language_split(language_split)
+            .with_routes(routes)
+            .with_tees(tees)
+            .with_base_words(base_words)
+            .with_output_encoding(output_encoding)
+            .with_rate_limits(read_limit, write_limit)
+            .with_progress_bars(!tui)
+            .with_run_config(recorded);
+        if let Some((output, failed_files)) = &retry {
+            // The earlier output goes first and reads back as written
+            if output.exists() {
+                let format = if output_format == OutputFormat::Counted { InputFormat::Counted } else { InputFormat::Plain };
+                core = core.with_seed(output.clone(), format);
+            }
+            core = core.with_failed_files_path(failed_files.clone());
+        }
+        // A retry merges into a temporary file that replaces the earlier output afterwards
+        let output = retry.as_ref().map_or_else(|| app_state.output_file.clone(), |(output, _)| output.clone());
+        let report = |core: &ProcessingCore| RunReport { output: output.clone(), ..core.report() };
+
+        // The status page reads the figures the core updates while it runs
+        let web_ui = match config.web_ui {
+            Some(addr) => {
+                let (web_ui, local) = WebUi::start(addr, core.status()).await?;
+                info!("Status page at http://{}/", local);
+                if !local.ip().is_loopback() {
+                    warn!("The status page is reachable from other machines and shows input paths; it has no authentication");
+                }
+                Some(web_ui)
+            }
+            None => None,
+        };
+        let dashboard = if tui { Some(Dashboard::start(core.status(), app_state.clone())?) } else { None };
+        let processed = core.process().await;
+        if let Some(dashboard) = dashboard {
+            dashboard.stop()?;
+        }
+        if let Some(web_ui) = web_ui {
+            web_ui.stop();
+        }
+        if let Err(e) = processed {
+            if let Some(url) = &notify_webhook {
+                notify::send(url, &Notification::failed(e.to_string(), report(&core))).await;
+            }
+            return Err(e.into());
+        }
+        if let Some(report_path) = &args.report {
+            report(&core).save(report_path).await?;
+            info!("Report written to: {:?}", report_path);
+        }
+        if !core.failed_files().is_empty() {
+            println!(
+                "After fixing the cause, rerun them with `rustmerger retry-failed {} --append-to {}`",
+                retry.as_ref().map_or(Path::new(DEFAULT_FAILED_FILES_PATH), |(_, failed_files)| failed_files).display(),
+                output.display()
+            );
+        }
 
-        if let Err(e) = core.process().await {
-            warn!("Error during processing: {}", e);
+        let outcome = core.outcome().await;
+        if outcome != RunOutcome::Interrupted {
+            app_state.finish_progress().await?;
+        } else if let Some(name) = &args.session {
+            println!("Continue with `rustmerger resume --session {}`", name);
+        }
+        if let Some(url) = &notify_webhook {
+            notify::send(url, &Notification::finished(outcome, report(&core))).await;
+        }
+        if !config.no_history {
+            history::record(config.history_file.as_deref(), command, outcome, &report(&core), started.elapsed()).await;
         }
 
         info!("Merge operation completed");
-        Ok(())
+        Ok(outcome)
     }
 
     // Handle configuration file generation
@@ -72,14 +586,14 @@ impl CommandHandler {
         info!("Generating configuration file");
 
         // Create default template config
-        let config = if args.template {
-            Config::template()
-        } else {
-            Config::template()
-        };
+        let config = Config::template();
 
-        // Save configuration to specified path
-        config.save(&args.output).await?;
+        // Save configuration to specified path, explaining every key if asked
+        if args.commented {
+            tokio::fs::write(&args.output, config_template::commented(&config)?).await?;
+        } else {
+            config.save(&args.output).await?;
+        }
 
         info!("Configuration file generated at: {:?}", args.output);
         Ok(())
@@ -89,39 +603,473 @@ impl CommandHandler {
     pub async fn handle_guided_setup(args: GuidedSetupArgs) -> Result<()> {
         info!("Starting guided setup");
 
-        // Run interactive configuration
-        let config = Config::guided_setup().await?;
+        // Run interactive configuration, or take the answers from a file
+        let config = Config::guided_setup(args.answers.as_ref()).await?;
         config.save(&args.output).await?;
 
         info!("Configuration saved to: {:?}", args.output);
         Ok(())
     }
 
-    // Handle resuming from a previous state
-    #[allow(dead_code)]
-    pub async fn handle_resume(args: ResumeArgs) -> Result<()> {
-        info!("Resuming from progress file: {:?}", args.progress_file);
+    // Handle man page and markdown reference generation
+    pub async fn handle_mangen(args: MangenArgs) -> Result<()> {
+        info!("Generating documentation in: {:?}", args.output_dir);
 
-        // Create application state with default values
-        let app_state = Arc::new(AppState::new(
-            args.progress_file.clone(),
-            PathBuf::from("/tmp/output.txt"), // Default output path
-            10 // Default threads
-        ).await?);
+        // Render man pages for the binary and every subcommand
+        let written = DocsGenerator::write_man_pages(&args.output_dir).await?;
 
-        // Initialize processing core with minimal logging
-        let mut core = ProcessingCore::new(
-            app_state.clone(),
-            false, // Debug disabled
-            false  // Verbose disabled
-        ).await?;
+        // Optionally render the markdown reference alongside them
+        let markdown = if args.markdown {
+            Some(DocsGenerator::write_markdown(&args.output_dir).await?)
+        } else {
+            None
+        };
+
+        for path in written.iter().chain(markdown.iter()) {
+            info!("Wrote {:?}", path);
+        }
+        Ok(())
+    }
+
+    // Handle printing the processing schedule without merging
+    pub async fn handle_plan(args: PlanArgs) -> Result<()> {
+        // Load existing config or create default template
+        let config = if let Some(config_path) = args.config {
+            Config::load(&config_path).await?
+        } else {
+            Config::default()
+        };
+
+        let input_file = args.wordlists_file
+            .or(config.input_files)
+            .ok_or_else(|| anyhow::anyhow!("No wordlists file specified"))?;
+
+        info!("Building processing plan for: {:?}", input_file);
+
+        config.tuning.validate()?;
+        let plan = ProcessingPlan::build(&input_file, config.tuning.parallel_files).await?;
+        plan.print_report();
+        Ok(())
+    }
+
+    // Handle the worker command - serves partitions of distributed merges
+    pub async fn handle_worker(args: WorkerArgs) -> Result<()> {
+        if args.token.is_empty() {
+            return Err(anyhow::anyhow!("--token must not be empty"));
+        }
+        let temp_dir = scratch::prepare(args.temp_dir)?;
+        let threads = args.threads
+            .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+        if !args.listen.ip().is_loopback() {
+            warn!("Anyone holding the token can have this worker read files this user can; keep the port off untrusted networks");
+        }
+        distributed::serve(args.listen, args.token, temp_dir, threads).await?;
+        Ok(())
+    }
+
+    // Handle the fetch command - downloads well-known lists into the cache and prints their paths
+    //
+    // Only the paths go to stdout, so `rustmerger fetch ... > lists.txt` gives a wordlists file.
+    pub async fn handle_fetch(args: FetchArgs) -> Result<()> {
+        if args.list {
+            for list in fetch::KNOWN_LISTS {
+                println!("{:<36} {}", list.name, list.about);
+            }
+            return Ok(());
+        }
+
+        // Check every name before downloading anything
+        let lists = args.names.iter()
+            .map(|name| KnownList::find(name).ok_or_else(|| anyhow::anyhow!(
+                "unknown list '{}'; `rustmerger fetch --list` shows the known ones", name
+            )))
+            .collect::<Result<Vec<_>>>()?;
+        let cache = args.cache_dir.or_else(fetch::cache_dir)
+            .ok_or_else(|| anyhow::anyhow!("No cache directory found; set one with --cache-dir"))?;
+
+        let mut paths = String::new();
+        for list in lists {
+            let fetched = fetch::fetch(list, &cache, args.refresh).await?;
+            let how = if fetched.downloaded { "downloaded" } else { "cached" };
+            eprintln!("{}: {} (sha256 {})", list.name, how, fetched.sha256);
+            println!("{}", fetched.path.display());
+            paths.push_str(&format!("{}\n", fetched.path.display()));
+        }
+        if let Some(output) = &args.output {
+            tokio::fs::write(output, paths).await?;
+            eprintln!("Wrote the paths to {}; merge them with --wordlists-file {}", output.display(), output.display());
+        }
+        Ok(())
+    }
+
+    // Handle the estimate command - samples inputs for capacity planning
+    pub async fn handle_estimate(args: EstimateArgs) -> Result<()> {
+        // Load existing config or create default template
+        let config = if let Some(config_path) = &args.config {
+            Config::load(config_path).await?
+        } else {
+            Config::default()
+        };
+
+        let input_file = args.wordlists_file
+            .or(config.input_files)
+            .ok_or_else(|| anyhow::anyhow!("No wordlists file specified"))?;
+
+        info!("Estimating resources for: {:?}", input_file);
+
+        config.tuning.validate()?;
+        // Walk directories and skip large files as a merge with this config would
+        let list_options = ListOptions {
+            follow_symlinks: config.follow_symlinks,
+            recursive: !config.no_recurse,
+            extensions: input_list::normalize_extensions(&config.extensions),
+            remaps: Vec::new(),
+        };
+        let estimate = ResourceEstimator::estimate_with(&input_file, list_options, config.skip_larger_than).await?;
+        println!("Estimate from sampled inputs; nothing is written");
+        estimate.print_report();
+        estimate.print_backends(&config.tuning);
+        Ok(())
+    }
+
+    // Handle running the built-in self-test suite
+    pub async fn handle_selftest(args: SelftestArgs) -> Result<()> {
+        let suite = SelfTest::new()?;
+        info!("Running self-test in: {:?}", suite.path());
+
+        let results = suite.run().await?;
+        let failed = selftest::print_report(&results);
+
+        if args.keep {
+            let dir = suite.keep();
+            println!("  Inputs and outputs kept in {}", dir.display());
+        }
+
+        if failed > 0 {
+            return Err(anyhow::anyhow!("{} of {} self-tests failed", failed, results.len()));
+        }
+        Ok(())
+    }
+
+    // Handle writing a checksummed manifest of the inputs
+    pub async fn handle_manifest(args: ManifestArgs) -> Result<()> {
+        info!("Hashing inputs listed in: {:?}", args.wordlists_file);
+        let manifest = Manifest::build(&args.wordlists_file).await?;
+        manifest.save(&args.output).await?;
+
+        let total: u64 = manifest.files.iter().map(|entry| entry.size).sum();
+        println!(
+            "Recorded {} inputs ({}) in {}",
+            manifest.files.len(),
+            format_bytes(total),
+            args.output.display()
+        );
+        Ok(())
+    }
+
+    // Handle verifying a merged file
+    pub async fn handle_check(args: CheckArgs) -> Result<()> {
+        let options = CheckOptions {
+            expect_sorted: args.expect_sorted,
+            expect_unique: args.expect_unique || !args.expect_sorted, // Uniqueness unless only sorting was asked for
+            max_offenders: args.max_offenders,
+        };
+        info!("Checking {:?}", args.file);
+        let report = tokio::task::spawn_blocking({
+            let file = args.file.clone();
+            move || check::check_file(&file, &options)
+        }).await??;
+
+        for offence in &report.offenders {
+            let kind = match offence.kind {
+                OffenceKind::Duplicate => "duplicate",
+                OffenceKind::OutOfOrder => "out of order",
+            };
+            println!("  line {}: {}: {}", offence.line_number, kind, offence.line);
+        }
+        if report.passed() {
+            println!("{}: {} lines, OK", args.file.display(), report.lines);
+            return Ok(());
+        }
+
+        let shown = report.offenders.len() as u64;
+        let total = report.duplicates + report.out_of_order;
+        if total > shown {
+            println!("  ... and {} more", total - shown);
+        }
+        Err(anyhow::anyhow!(
+            "{}: {} lines, {} duplicates, {} out of order",
+            args.file.display(), report.lines, report.duplicates, report.out_of_order
+        ))
+    }
+
+    // Handle converting one file, or every listed file, to another encoding
+    pub async fn handle_convert_encoding(args: ConvertEncodingArgs) -> Result<()> {
+        // Pair every input with its destination
+        let jobs: Vec<(PathBuf, PathBuf)> = match (args.input, args.wordlists_file) {
+            (Some(input), _) => {
+                let output = args.output.ok_or_else(|| anyhow::anyhow!("--output is required"))?;
+                vec![(input, output)]
+            }
+            (None, Some(list)) => {
+                let output_dir = args.output_dir.ok_or_else(|| anyhow::anyhow!("--output-dir is required with --wordlists-file"))?;
+                tokio::fs::create_dir_all(&output_dir).await?;
+                let mut jobs = Vec::new();
+                for input in InputList::load(&list).await?.files {
+                    let name = input.file_name()
+                        .ok_or_else(|| anyhow::anyhow!("{} has no file name", input.display()))?;
+                    let output = output_dir.join(name);
+                    if jobs.iter().any(|(_, taken): &(PathBuf, PathBuf)| *taken == output) {
+                        return Err(anyhow::anyhow!("more than one input is named {:?}", name));
+                    }
+                    jobs.push((input, output));
+                }
+                jobs
+            }
+            (None, None) => return Err(anyhow::anyhow!("Specify a file or --wordlists-file")),
+        };
+
+        let mut total = EncodingStats::default();
+        for (input, output) in jobs {
+            let (source, stats) = tokio::task::spawn_blocking({
+                let (input, output, from, to) = (input.clone(), output.clone(), args.from, args.to);
+                move || encoding::convert_file(&input, &output, from, to)
+            }).await??;
+            info!(
+                "{} ({}) -> {} ({}): {} malformed, {} unmappable",
+                input.display(), source.name(), output.display(), args.to.name(), stats.malformed, stats.unmappable
+            );
+            total.merge(&stats);
+        }
+
+        if total.files > 1 {
+            info!(
+                "Converted {} files, {} -> {}: {} malformed sequences replaced, {} unmappable characters written as `?`",
+                total.files, format_bytes(total.bytes_in), format_bytes(total.bytes_out), total.malformed, total.unmappable
+            );
+        }
+        Ok(())
+    }
 
-        // Resume processing and handle errors
-        if let Err(e) = core.process().await {
-            warn!("Error during processing: {}", e);
+    // Handle converting a file to or from hashcat $HEX[...] notation
+    pub async fn handle_hex(args: HexArgs) -> Result<()> {
+        let (converted, lines_kind) = tokio::task::spawn_blocking(move || -> std::io::Result<(u64, &str)> {
+            let reader = std::io::BufReader::new(std::fs::File::open(&args.input)?);
+            let writer: Box<dyn std::io::Write> = match &args.output {
+                Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+                None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+            };
+            match args.mode {
+                HexMode::Encode => Ok((hex_format::encode_lines(reader, writer, args.all)?, "encoded")),
+                HexMode::Decode => Ok((hex_format::decode_lines(reader, writer)?, "decoded")),
+            }
+        }).await??;
+        info!("{} lines {}", converted, lines_kind);
+        Ok(())
+    }
+
+    // Handle ranking the inputs by what each adds that no other input has
+    pub async fn handle_redundancy(args: RedundancyArgs) -> Result<()> {
+        if !(0.0..=100.0).contains(&args.threshold) {
+            return Err(anyhow::anyhow!("--threshold must be between 0 and 100, got {}", args.threshold));
+        }
+        let inputs = InputList::load(&args.wordlists_file).await?.files;
+        info!("Comparing {} inputs listed in: {:?}", inputs.len(), args.wordlists_file);
+        let threshold = args.threshold / 100.0;
+        let report = tokio::task::spawn_blocking(move || RedundancyReport::build(&inputs, threshold)).await??;
+
+        report.print_table();
+        if let Some(path) = args.report {
+            tokio::fs::write(&path, serde_json::to_vec_pretty(&report)?).await?;
+            println!("Ranking written to {}", path.display());
+        }
+        Ok(())
+    }
+
+    // Handle analyzing the lines of one wordlist
+    pub async fn handle_analyze(args: AnalyzeArgs) -> Result<()> {
+        if args.min_length > args.max_length {
+            return Err(anyhow::anyhow!("--min-length {} is above --max-length {}", args.min_length, args.max_length));
+        }
+        // Near-duplicate clustering is the only analysis so far, so it is also the default
+        if !args.near_dupes {
+            info!("No analysis chosen; defaulting to --near-dupes");
+        }
+        info!("Clustering near-duplicate lines of {:?}", args.file);
+        let (file, min_length, max_length) = (args.file, args.min_length, args.max_length);
+        let report = tokio::task::spawn_blocking(move || NearDupeReport::build(&file, min_length, max_length)).await??;
+
+        report.print_summary(args.show);
+        if let Some(path) = args.report {
+            tokio::fs::write(&path, serde_json::to_vec_pretty(&report)?).await?;
+            println!("Clusters written to {}", path.display());
+        }
+        Ok(())
+    }
+
+    // Handle listing and inspecting past runs
+    pub async fn handle_history(args: HistoryArgs) -> Result<()> {
+        let path = match args.file.or_else(history::default_path) {
+            Some(path) => path,
+            None => return Err(anyhow::anyhow!("No home directory to find the run history in; pass --file")),
+        };
+        let records = history::load(&path)?;
+        match args.action.unwrap_or(HistoryAction::List { limit: 20, json: false }) {
+            HistoryAction::List { limit, json: false } => history::print_table(&records, limit),
+            HistoryAction::List { limit, json: true } => {
+                for record in records.iter().rev().take(limit) {
+                    println!("{}", serde_json::to_string(record)?);
+                }
+            }
+            HistoryAction::Show { id } => {
+                let record = history::find(&records, &id).map_err(|e| anyhow::anyhow!("{} in {}", e, path.display()))?;
+                println!("{}", serde_json::to_string_pretty(record)?);
+            }
         }
+        Ok(())
+    }
 
-        info!("Resume operation completed");
+    // Handle listing, inspecting and removing saved sessions
+    pub async fn handle_sessions(args: SessionsArgs) -> Result<()> {
+        let dir = session::sessions_dir().ok_or_else(|| anyhow::anyhow!("No home directory to find sessions in"))?;
+        match args.action.unwrap_or(SessionsAction::List) {
+            SessionsAction::List => session::print_table(&session::list(&dir).await?),
+            SessionsAction::Show { name } => {
+                let session = session::load(&name).await?;
+                let progress = &session.progress;
+                println!("Session:       {}", session.name);
+                println!("State:         {}", session.state());
+                println!("Saved:         {}", session::format_saved(&session));
+                println!("Checkpoint:    {}", session.path.display());
+                println!("Wordlists:     {}", progress.input_file.display());
+                println!("Output:        {}", progress.output_file.display());
+                println!("Threads:       {}", progress.threads);
+                println!("Files done:    {}", progress.processed_files.len());
+                println!("Lines done:    {}", progress.current_position);
+                if !progress.finished {
+                    println!("Continue with `rustmerger resume --session {}`", session.name);
+                }
+            }
+            SessionsAction::Clean { names, all } => {
+                let doomed: Vec<session::Session> = if names.is_empty() {
+                    session::list(&dir).await?.into_iter()
+                        .filter(|session| all || session.progress.finished)
+                        .collect()
+                } else {
+                    let mut sessions = Vec::with_capacity(names.len());
+                    for name in &names {
+                        sessions.push(session::load(name).await?);
+                    }
+                    sessions
+                };
+                for session in &doomed {
+                    tokio::fs::remove_file(&session.path).await?;
+                    // The lines an interrupted merge saved for its resume go with it
+                    if let Some(snapshot) = &session.progress.snapshot {
+                        let _ = tokio::fs::remove_file(snapshot).await;
+                    }
+                }
+                println!("Removed {} sessions", doomed.len());
+            }
+        }
         Ok(())
     }
+
+    // Handle reprocessing the inputs of a failed-files list into an existing output
+    pub async fn handle_retry_failed(args: RetryFailedArgs) -> Result<RunOutcome> {
+        let failed = FailedFiles::load(&args.failed_files).await?;
+        if failed.is_empty() {
+            println!("{} lists no failed files", args.failed_files.display());
+            return Ok(RunOutcome::Success);
+        }
+        // The failed files record the options of their merge, which the retry runs with again
+        let Some(mut config) = failed.config.clone() else {
+            return Err(MergerError::InputValidation(format!(
+                "{} does not record the options of its merge; merge the files again instead",
+                args.failed_files.display()
+            )).into());
+        };
+        // The output is read back as written, and holds none of the lines written elsewhere
+        let other_outputs = config.split_by_language || config.route_numeric.is_some() || config.route_walks.is_some()
+            || !config.tee.is_empty() || config.base_words.is_some() || config.output_encoding.is_some() || config.bom.is_some();
+        if !matches!(config.output_format, OutputFormat::Text | OutputFormat::Counted) || other_outputs {
+            return Err(ConfigError::InvalidOutputFormat(
+                "retry-failed can only add to text or counted output written without --split-by-language, routed outputs, --tee, --base-words or --output-encoding; merge the files again instead".to_string()
+            ).into());
+        }
+        info!("Retrying {} failed files into {:?}", failed.len(), args.append_to);
+
+        // Merging the existing output together with the recovered files keeps it free of
+        // duplicates; the result replaces the output only once the merge has finished
+        let dir = match args.append_to.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut listing = String::new();
+        for file in &failed.files {
+            listing.push_str(&format!("{}\n", file.path.display()));
+        }
+        let list = tempfile::Builder::new().prefix(".rustmerger-retry-").suffix(".list").tempfile_in(&dir)?;
+        tokio::fs::write(list.path(), listing).await?;
+        let merged = tempfile::Builder::new().prefix(".rustmerger-retry-").tempfile_in(&dir)?.into_temp_path();
+
+        // The few failed inputs are merged here at once, without a checkpoint
+        config.input_files = Some(list.path().to_path_buf());
+        config.output_files = Some(merged.to_path_buf());
+        config.stages = None;
+        config.workers = Vec::new();
+        config.checkpoint_root = None;
+        let state = Arc::new(AppState::new(
+            list.path().to_path_buf(),
+            merged.to_path_buf(),
+            config.threads.unwrap_or(10) // Default to 10 threads if not specified
+        ).await?);
+        let start = MergeStart::Retry { state, output: args.append_to.clone(), failed_files: args.failed_files.clone() };
+        let outcome = Self::run_merge(MergeArgs::try_parse_from(["merge"])?, config, start).await?;
+
+        if outcome == RunOutcome::Interrupted {
+            warn!("Interrupted; {} left unchanged", args.append_to.display());
+            return Ok(outcome);
+        }
+        // Inputs failing again were written back to the skip list
+        let remaining = if outcome == RunOutcome::SkippedFiles { FailedFiles::load(&args.failed_files).await? } else { FailedFiles::default() };
+        // Never replace the output with a merge that could not read it
+        if remaining.files.iter().any(|file| file.path == args.append_to) {
+            return Err(anyhow::anyhow!("Could not read {}; it was left unchanged", args.append_to.display()));
+        }
+        merged.persist(&args.append_to)?;
+
+        if remaining.is_empty() {
+            tokio::fs::remove_file(&args.failed_files).await?;
+            println!("All {} failed files recovered into {}", failed.len(), args.append_to.display());
+        } else {
+            println!(
+                "Recovered {} of {} failed files into {}; {} still listed in {}",
+                failed.len() - remaining.len(),
+                failed.len(),
+                args.append_to.display(),
+                remaining.len(),
+                args.failed_files.display()
+            );
+        }
+        Ok(outcome)
+    }
+
+    // Handle resuming an interrupted merge with the options it was started with
+    pub async fn handle_resume(args: ResumeArgs) -> Result<RunOutcome> {
+        let progress_file = session::resume_path(args.progress_file.as_deref(), args.session.as_deref())?;
+        info!("Resuming from progress file: {:?}", progress_file);
+        let state = AppState::from_resume(progress_file.clone(), args.checkpoint_root, &args.remap).await?;
+
+        // The checkpoint records the options the merge was started with
+        let Some(config) = state.progress.read().await.config.clone() else {
+            return Err(MergerError::InputValidation(format!(
+                "{} does not record the options of its merge; run the merge again instead of resuming it",
+                progress_file.display()
+            )).into());
+        };
+        // Run-only options, such as --report, keep their defaults
+        let args = MergeArgs::try_parse_from(["merge"])?;
+        Self::run_merge(args, config, MergeStart::Resume(Arc::new(state))).await
+    }
 }
\ No newline at end of file
@@ -1,31 +1,245 @@
 // Import required dependencies
 use serde::{Serialize, Deserialize};  // For JSON serialization/deserialization
 use std::path::PathBuf;       // For file path handling
+use std::net::SocketAddr;     // For the status page address
+use std::collections::HashMap; // For guided-setup answers by prompt key
 use anyhow::Result;                   // For error handling
 use tokio::fs;                        // For async file operations
-use dialoguer::{Input, Confirm};      // For interactive CLI prompts
-use anyhow::Context;
-use std::io::{BufReader, BufWriter, BufRead, Write};
+use dialoguer::{Input, Confirm, Select}; // For interactive CLI prompts
+use clap::ValueEnum;                  // For listing the choices of option enums
 use crate::errors::{MergerError, MergerResult, ConfigError};
+use crate::io_backend::IoBackend;
+use crate::dedup_backend::DedupBackend;
+use crate::tuning::{self, Tuning};
+use crate::line_options::{BinaryMode, StripControl};
+use crate::retry::RetryPolicy;
+use crate::rules::RuleComments;
+use crate::failed_files::FailureLimits;
+use crate::input_format::InputFormat;
+use crate::output_format::{CapOrder, OutputColumns, OutputFormat};
+use crate::dedup_key::DedupKey;
+use crate::transform::Transform;
+use crate::pipeline::StageConfig;
+use crate::encoding;
+use crate::config_template;
+use crate::strength;
 
 // Configuration structure that can be serialized to/from JSON
-#[derive(Debug, Serialize, Deserialize)]
+//
+// Unknown keys are rejected, so a misspelled option fails loudly instead of being ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub input_files: Option<PathBuf>,     // Path to file containing list of input files
+    #[serde(default)]
+    pub follow_symlinks: bool,    // Follow symlinks inside directories of the input list
+    #[serde(default)]
+    pub no_recurse: bool,         // Take only the files directly inside directories of the input list
+    #[serde(default)]
+    pub extensions: Vec<String>,  // Extensions files in directories of the input list need; empty for any
+    #[serde(default)]
+    pub skip_larger_than: Option<u64>, // Skip inputs larger than this many bytes
+    #[serde(default)]
+    pub no_content_sniff: bool,   // Read inputs whose magic bytes mark them as not text
+    #[serde(default)]
+    pub download_dir: Option<PathBuf>, // Directory URL inputs are downloaded to and reused from
     pub output_files: Option<PathBuf>,    // Path where merged output will be written
     pub threads: Option<usize>,           // Number of parallel processing threads
     pub verbose: bool,            // Enable detailed logging
     pub debug: bool,              // Enable debug mode
+    #[serde(default)]
+    pub stream_output: bool,      // Write unique lines as they are found instead of at the end
+    #[serde(default)]
+    pub io_backend: IoBackend,    // File I/O strategy (std or uring)
+    #[serde(default)]
+    pub dedup_backend: DedupBackend, // How the set of lines seen is kept, or auto
+    #[serde(default)]
+    pub tuning: Tuning,           // Buffer, chunk and batch sizes
+    #[serde(default)]
+    pub max_read_mbps: Option<f64>, // Most MB per second read from the inputs
+    #[serde(default)]
+    pub max_write_mbps: Option<f64>, // Most MB per second written to the output
+    #[serde(default)]
+    pub nice: Option<i32>,        // Niceness the merge runs at
+    #[serde(default)]
+    pub ionice: Option<String>,   // Linux I/O scheduling class, such as "idle" or "best-effort:7"
+    #[serde(default)]
+    pub temp_dir: Option<PathBuf>, // Directory for spill runs and other temporary files
+    #[serde(default)]
+    pub stages: Option<u64>,      // Stages of a hierarchical merge; null merges every input at once
+    #[serde(default)]
+    pub workers: Vec<String>,     // Worker addresses the inputs are partitioned across
+    #[serde(default)]
+    pub worker_token: Option<String>, // Shared secret the workers were started with
+    #[serde(default)]
+    pub retry: RetryPolicy,       // Retries for transient read errors
+    #[serde(default)]
+    pub rule_comments: RuleComments, // Handling of comment lines in rule files
+    #[serde(default)]
+    pub max_rule_ops: Option<u64>, // Drop rules with more functions than this
+    #[serde(default)]
+    pub reject_rule_functions: Vec<String>, // Function characters merged rules may not use
+    #[serde(default)]
+    pub rule_ignore_whitespace: bool, // Deduplicate rules ignoring the spaces between their functions
+    #[serde(default)]
+    pub strict: bool,             // Abort when any input cannot be fully processed
+    #[serde(default)]
+    pub failure_limits: FailureLimits, // Abort once too many inputs failed
+    #[serde(default)]
+    pub hex_invalid: bool,        // Keep invalid UTF-8 lines as `$HEX[...]` instead of dropping them
+    #[serde(default)]
+    pub binary_mode: BinaryMode,  // Keep lines with NUL bytes as read or as `$HEX[...]`
+    #[serde(default)]
+    pub strip_control: Option<StripControl>, // Control characters removed before deduplication
+    #[serde(default)]
+    pub normalize_homoglyphs: bool, // Fold fullwidth forms and look-alike letters to ASCII
+    #[serde(default)]
+    pub input_format: InputFormat, // Layout of input lines, detected per file by default
+    #[serde(default)]
+    pub json_field: Option<String>, // Dotted path of the candidate field in JSON Lines inputs
+    #[serde(default)]
+    pub dedup_key: DedupKey,      // Part of combo lines that identifies duplicates
+    #[serde(default)]
+    pub min_strength: Option<u8>, // Weakest strength score kept, 0-4
+    #[serde(default)]
+    pub max_strength: Option<u8>, // Strongest strength score kept, 0-4
+    #[serde(default)]
+    pub transforms: Vec<Transform>, // Rewrites adding variants of every word
+    #[serde(default)]
+    pub emit_reversed: bool,      // Also emit every word reversed
+    #[serde(default)]
+    pub append_years: Option<String>, // Range of years appended to every word, such as `2015-2025`
+    #[serde(default)]
+    pub append_strings: Vec<String>, // Strings appended to every word
+    #[serde(default)]
+    pub templates: Vec<String>,   // Templates building hybrid candidates from every word
+    #[serde(default)]
+    pub exclude_walks: bool,      // Drop keyboard walks and sequences
+    #[serde(default)]
+    pub route_walks: Option<PathBuf>, // File receiving keyboard walks and sequences instead of the output
+    #[serde(default)]
+    pub route_numeric: Option<PathBuf>, // File receiving all-digit candidates instead of the output
+    #[serde(default)]
+    pub numeric_lengths: Vec<String>, // Digit count ranges routed, such as "4-8"; empty for any
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,    // Shared-library filter and transform plugins, applied in order
+    #[serde(default)]
+    pub script: Option<PathBuf>,  // Rhai script keeping, dropping or rewriting every candidate
+    #[serde(default)]
+    pub pipeline: Vec<StageConfig>, // Ordered decode, filter, transform, dedup and output stages
+    #[serde(default)]
+    pub tee: Vec<String>,         // Extra filtered outputs, as "FILE=FILTER,..."
+    #[serde(default)]
+    pub base_words: Option<PathBuf>, // List of unique lines without trailing digits and punctuation
+    #[serde(default)]
+    pub target_lines: Option<u64>, // Keep only this many of the most frequent unique lines
+    #[serde(default)]
+    pub max_output_lines: Option<u64>, // Stop writing after this many unique lines
+    #[serde(default)]
+    pub max_output_bytes: Option<u64>, // Stop writing before the output exceeds this many bytes
+    #[serde(default)]
+    pub cap_order: Option<CapOrder>, // Which lines make the cut under a cap; stable by default
+    #[serde(default)]
+    pub web_ui: Option<SocketAddr>, // Address of the live status page
+    #[serde(default)]
+    pub tui: bool,                // Show the full-screen dashboard instead of progress bars
+    #[serde(default)]
+    pub notify_webhook: Option<String>, // URL POSTed the final report when the merge ends
+    #[serde(default)]
+    pub no_history: bool,         // Do not record runs in the run history
+    #[serde(default)]
+    pub history_file: Option<PathBuf>, // Run history to append to instead of the default location
+    #[serde(default)]
+    pub checkpoint_root: Option<PathBuf>, // Directory checkpoint paths are saved relative to
+    #[serde(default)]
+    pub split_by_language: bool,  // Write lines detected as a language to their own files
+    #[serde(default)]
+    pub languages: Vec<String>,   // Languages considered when splitting; empty for all
+    #[serde(default)]
+    pub language_confidence: Option<f64>, // Detection confidence needed to tag a line
+    #[serde(default)]
+    pub output_encoding: Option<String>, // Encoding of text output, such as "windows-1252"; UTF-8 when unset
+    #[serde(default)]
+    pub bom: Option<bool>,        // Start text output with a byte order mark; by default only UTF-16 does
+    #[serde(default)]
+    pub output_format: OutputFormat, // Shape of the merged output
+    #[serde(default)]
+    pub output_columns: OutputColumns, // Extra columns in SQLite and Parquet output
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             input_files: None,
+            follow_symlinks: false,
+            no_recurse: false,
+            extensions: Vec::new(),
+            skip_larger_than: None,
+            no_content_sniff: false,
+            download_dir: None,
             output_files: None,
             threads: Some(10),
             verbose: true,
             debug: true,
+            stream_output: false,
+            io_backend: IoBackend::default(),
+            dedup_backend: DedupBackend::default(),
+            tuning: Tuning::default(),
+            temp_dir: None,
+            stages: None,
+            workers: Vec::new(),
+            worker_token: None,
+            retry: RetryPolicy::default(),
+            rule_comments: RuleComments::default(),
+            max_rule_ops: None,
+            reject_rule_functions: Vec::new(),
+            rule_ignore_whitespace: false,
+            strict: false,
+            failure_limits: FailureLimits::default(),
+            hex_invalid: false,
+            binary_mode: BinaryMode::default(),
+            strip_control: None,
+            normalize_homoglyphs: false,
+            input_format: InputFormat::default(),
+            json_field: None,
+            dedup_key: DedupKey::default(),
+            min_strength: None,
+            max_strength: None,
+            transforms: Vec::new(),
+            emit_reversed: false,
+            append_years: None,
+            append_strings: Vec::new(),
+            templates: Vec::new(),
+            exclude_walks: false,
+            route_walks: None,
+            route_numeric: None,
+            numeric_lengths: Vec::new(),
+            plugins: Vec::new(),
+            script: None,
+            pipeline: Vec::new(),
+            tee: Vec::new(),
+            base_words: None,
+            target_lines: None,
+            max_output_lines: None,
+            max_output_bytes: None,
+            cap_order: None,
+            web_ui: None,
+            tui: false,
+            notify_webhook: None,
+            no_history: false,
+            history_file: None,
+            checkpoint_root: None,
+            max_read_mbps: None,
+            max_write_mbps: None,
+            nice: None,
+            ionice: None,
+            split_by_language: false,
+            languages: Vec::new(),
+            language_confidence: None,
+            output_encoding: None,
+            bom: None,
+            output_format: OutputFormat::default(),
+            output_columns: OutputColumns::default(),
         }
     }
 }
@@ -35,8 +249,9 @@ impl Config {
     pub async fn load(path: &PathBuf) -> MergerResult<Self> {
         let content = fs::read_to_string(path).await
             .map_err(MergerError::Io)?;
-        serde_json::from_str(&content)
-            .map_err(|e| MergerError::Config(ConfigError::InvalidFormat(e.to_string())))
+        // Commented configs from `generate-config --commented` load like plain JSON
+        serde_json::from_str(&config_template::strip_comments(&content))
+            .map_err(|e| MergerError::Config(ConfigError::InvalidFormat(with_key_hint(e.to_string()))))
     }
 
     // Save configuration to a JSON file
@@ -47,48 +262,58 @@ impl Config {
             .map_err(MergerError::Io)
     }
 
+    // Apply `--set` overrides on top of the loaded settings, checking them like keys in the file
+    pub fn with_overrides(self, overrides: &[ConfigOverride]) -> MergerResult<Self> {
+        if overrides.is_empty() {
+            return Ok(self);
+        }
+        let mut value = serde_json::to_value(&self)
+            .map_err(|e| MergerError::Config(ConfigError::SerializationError(e.to_string())))?;
+        for entry in overrides {
+            let (last, parents) = entry.path.split_last().expect("parse_override yields at least one key");
+            let mut target = &mut value;
+            for key in parents {
+                let object = target.as_object_mut().ok_or_else(|| MergerError::Config(ConfigError::InvalidFormat(
+                    format!("--set {}: `{}` is not an object", entry.path.join("."), key)
+                )))?;
+                target = object.entry(key.clone()).or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+            }
+            let object = target.as_object_mut().ok_or_else(|| MergerError::Config(ConfigError::InvalidFormat(
+                format!("--set {}: the parent of `{}` is not an object", entry.path.join("."), last)
+            )))?;
+            object.insert(last.clone(), entry.value.clone());
+        }
+        serde_json::from_value(value)
+            .map_err(|e| MergerError::Config(ConfigError::InvalidFormat(format!("--set: {}", with_key_hint(e.to_string())))))
+    }
+
     // Create a default configuration template
     pub fn template() -> Self {
-        Self {
-            input_files: None,
-            output_files: None,
-            threads: Some(10),
-            verbose: true,
-            debug: true,
-        }
+        Self::default()
     }
 
-    // Interactive configuration setup using command-line prompts
-    pub async fn guided_setup() -> MergerResult<Self> {
+    // Configuration setup using command-line prompts, or the answers in `answers` when given
+    pub async fn guided_setup(answers: Option<&PathBuf>) -> MergerResult<Self> {
+        let mut prompts = match answers {
+            Some(path) => Prompts::from_file(path).await?,
+            None => Prompts::interactive(),
+        };
+        let mut config = Self::default();
+
         // Prompt for input files path with default value
-        let input_files: String = Input::new()
-            .with_prompt("Enter path to input files list")
-            .default("/tmp/wordlists_to_merge.txt".into())
-            .interact()?;
+        let input_files = prompts.text("input_files", "Enter path to input files list", "/tmp/wordlists_to_merge.txt")?;
 
         // Prompt for output file path with default value
-        let output_files: String = Input::new()
-            .with_prompt("Enter path for output file")
-            .default("/tmp/merged_wordlist.txt".into())
-            .interact()?;
+        let output_files = prompts.text("output_files", "Enter path for output file", "/tmp/merged_wordlist.txt")?;
 
         // Prompt for number of processing threads
-        let threads: String = Input::new()
-            .with_prompt("Enter number of threads")
-            .default("50".into())
-            .interact()?;
+        let threads = prompts.text("threads", "Enter number of threads", "50")?;
 
         // Confirm whether to enable verbose logging
-        let verbose = Confirm::new()
-            .with_prompt("Enable verbose logging?")
-            .default(true)
-            .interact()?;
+        let verbose = prompts.confirm("verbose", "Enable verbose logging?", true)?;
 
         // Confirm whether to enable debug mode
-        let debug = Confirm::new()
-            .with_prompt("Enable debug logging?")
-            .default(false)
-            .interact()?;
+        let debug = prompts.confirm("debug", "Enable debug logging?", false)?;
 
         // Parse threads with proper error handling
         let threads = threads.parse::<usize>()
@@ -98,62 +323,294 @@ impl Config {
             return Err(MergerError::Config(ConfigError::InvalidThreadCount(threads)));
         }
 
-        // Create and return configuration with user-provided values
-        Ok(Self {
-            input_files: Some(PathBuf::from(input_files)),
-            output_files: Some(PathBuf::from(output_files)),
-            threads: Some(threads),
-            verbose,
-            debug,
-        })
-    }
-
-    // Replace the existing validate method with this implementation
-    pub fn validate(&self) -> Result<(), ConfigError> {
-        // Validate thread count
-        if let Some(threads) = self.threads {
-            if threads == 0 || threads > 100 {
-                return Err(ConfigError::InvalidThreadCount(threads));
-            }
+        config.input_files = Some(PathBuf::from(input_files));
+        config.output_files = Some(PathBuf::from(output_files));
+        config.threads = Some(threads);
+        config.verbose = verbose;
+        config.debug = debug;
+
+        // Encoding: what happens to lines that are not plain text, and how the output is written
+        config.hex_invalid = prompts.confirm("hex_invalid", "Keep lines that are not valid UTF-8 as $HEX[...] instead of dropping them?", false)?;
+        config.binary_mode = prompts.choose("binary_mode", "How should lines with NUL bytes be merged?", config.binary_mode)?;
+        config.strip_control = prompts.choose_optional("strip_control", "Remove control characters before deduplication?", config.strip_control)?;
+        let output_encoding = prompts.text("output_encoding", "Output encoding (any WHATWG label)", "utf-8")?;
+        let encoding = encoding::parse_encoding(&output_encoding)
+            .map_err(|e| MergerError::Config(ConfigError::InvalidOutputFormat(e)))?;
+        config.output_encoding = (encoding != encoding_rs::UTF_8).then_some(output_encoding);
+
+        // Deduplication
+        config.dedup_key = prompts.choose("dedup_key", "Which part of each line identifies duplicates?", config.dedup_key)?;
+        config.normalize_homoglyphs = prompts.confirm(
+            "normalize_homoglyphs", "Fold fullwidth and look-alike letters to ASCII before deduplication?", false
+        )?;
+        config.io_backend = prompts.choose("io_backend", "File I/O backend", config.io_backend)?;
+
+        // Memory: how much is buffered and where the set spills once it outgrows memory
+        config.stream_output = prompts.confirm(
+            "stream_output", "Stream unique lines to the output as they are found (lowest memory, no end-of-run output formats)?", false
+        )?;
+        if !config.stream_output {
+            config.dedup_backend = prompts.choose("dedup_backend", "How should the set of lines seen be kept (auto picks from the inputs)?", config.dedup_backend)?;
+        }
+        let chunk_size = prompts.text("chunk_size", "Bytes each reader buffers before sending (K, M or G suffix)", "10M")?;
+        config.tuning.chunk_size = tuning::parse_size(&chunk_size)
+            .map_err(|e| MergerError::Config(ConfigError::InvalidTuning(e)))?;
+        config.tuning.validate()?;
+        config.temp_dir = prompts.optional_text("temp_dir", "Directory for spill files (empty for the system temp directory)")?
+            .map(PathBuf::from);
+
+        // Filters
+        let min_strength = prompts.optional_text("min_strength", "Weakest strength score kept, 0-4 (empty to keep every line)")?;
+        config.min_strength = min_strength.map(|score| match score.parse::<u8>() {
+            Ok(score) if score <= strength::MAX_SCORE => Ok(score),
+            _ => Err(ConfigError::InvalidStrength(format!("'{}' is not a score from 0 to {}", score, strength::MAX_SCORE))),
+        }).transpose()?;
+        config.exclude_walks = prompts.confirm("exclude_walks", "Drop keyboard walks and sequences such as qwerty and 123456?", false)?;
+
+        // Output
+        if !config.stream_output {
+            config.output_format = prompts.choose("output_format", "Output format", config.output_format)?;
+        }
+        let max_output_lines = prompts.optional_text("max_output_lines", "Most unique lines written (empty for no limit)")?;
+        config.max_output_lines = max_output_lines.map(|lines| lines.parse::<u64>().ok().filter(|lines| *lines > 0)
+            .ok_or_else(|| ConfigError::InvalidOutputFormat(format!("'{}' is not a positive line count", lines))))
+            .transpose()?;
+
+        Ok(config)
+    }
+}
+
+// One `--set` override: a dotted key path and the value it takes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOverride {
+    pub path: Vec<String>,        // Keys from the top level down, such as ["tuning", "chunk_size"]
+    pub value: serde_json::Value, // New value
+}
+
+// Parse an override such as `threads=16`, `tuning.chunk_size=4194304` or `transforms=["lower"]`
+//
+// The value is read as JSON when it is valid JSON and taken as a string otherwise.
+pub fn parse_override(value: &str) -> Result<ConfigOverride, String> {
+    let (path, raw) = value.split_once('=')
+        .ok_or_else(|| format!("'{}' is not KEY=VALUE, such as threads=16 or tuning.chunk_size=4194304", value))?;
+    let path: Vec<String> = path.trim().split('.').map(|key| key.trim().to_string()).collect();
+    if path.iter().any(String::is_empty) {
+        return Err(format!("'{}' has an empty key; use dotted paths such as tuning.chunk_size", value));
+    }
+    let value = serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+    Ok(ConfigOverride { path, value })
+}
+
+// `message` with a suggestion appended when it reports an unknown key close to a valid one
+//
+// serde reports "unknown field `thraeds`, expected one of `input_files`, ..." at any depth.
+fn with_key_hint(message: String) -> String {
+    let Some(rest) = message.strip_prefix("unknown field `") else { return message };
+    let Some((unknown, expected)) = rest.split_once('`') else { return message };
+    let closest = expected.split('`').skip(1).step_by(2)
+        .map(|key| (edit_distance(unknown, key), key))
+        .min();
+    match closest {
+        Some((distance, key)) if distance <= (unknown.chars().count() / 3).max(2) => {
+            format!("{} (did you mean `{}`?)", message, key)
+        }
+        _ => message,
+    }
+}
+
+// Levenshtein distance between `a` and `b`, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
         }
+        previous = current;
+    }
+    previous[b.len()]
+}
 
-        // Validate input files path exists
-        let input_path = self.input_files
-            .as_ref()
-            .ok_or(ConfigError::MissingInputFiles)?;
+// Keys of an answers file, one per guided-setup prompt
+const ANSWER_KEYS: &[&str] = &[
+    "input_files", "output_files", "threads", "verbose", "debug", "hex_invalid", "binary_mode", "strip_control",
+    "output_encoding", "dedup_key", "normalize_homoglyphs", "io_backend", "stream_output", "dedup_backend", "chunk_size",
+    "temp_dir", "min_strength", "exclude_walks", "output_format", "max_output_lines",
+];
 
-        if !input_path.exists() {
-            return Err(ConfigError::InputFileNotFound(input_path.clone()));
+// Source of the guided-setup answers: the terminal, or a file of predetermined answers
+//
+// Answers from a file go through the same parsing and checks as typed ones; a prompt
+// without an answer takes its default, as pressing enter would.
+struct Prompts {
+    answers: Option<HashMap<String, serde_json::Value>>, // Answers by prompt key; None to ask on the terminal
+}
+
+impl Prompts {
+    // Ask every question on the terminal
+    fn interactive() -> Self {
+        Self { answers: None }
+    }
+
+    // Take every answer from the JSON object in `path`, rejecting keys no prompt has
+    async fn from_file(path: &PathBuf) -> MergerResult<Self> {
+        let content = fs::read_to_string(path).await.map_err(MergerError::Io)?;
+        let answers: HashMap<String, serde_json::Value> = serde_json::from_str(&content)
+            .map_err(|e| MergerError::Config(ConfigError::InvalidFormat(format!("answers file {}: {}", path.display(), e))))?;
+        if let Some(key) = answers.keys().find(|key| !ANSWER_KEYS.contains(&key.as_str())) {
+            return Err(MergerError::Config(ConfigError::InvalidFormat(format!(
+                "answers file {} has an unknown key '{}'; expected one of {}", path.display(), key, ANSWER_KEYS.join(", ")
+            ))));
         }
+        Ok(Self { answers: Some(answers) })
+    }
+
+    // The answer to `key` as typed text: None when the file has none, or when asking on the terminal
+    fn answer(&mut self, key: &str) -> MergerResult<Option<Option<String>>> {
+        let Some(answers) = self.answers.as_mut() else { return Ok(None) };
+        let value = match answers.remove(key) {
+            None | Some(serde_json::Value::Null) => None,
+            Some(serde_json::Value::String(text)) => Some(text),
+            Some(serde_json::Value::Bool(flag)) => Some(flag.to_string()),
+            Some(serde_json::Value::Number(number)) => Some(number.to_string()),
+            Some(other) => return Err(MergerError::Config(ConfigError::InvalidFormat(format!(
+                "answer '{}' must be a string, number or boolean, got {}", key, other
+            )))),
+        };
+        Ok(Some(value))
+    }
 
-        // Validate output files path
-        let output_path = self.output_files
-            .as_ref()
-            .ok_or(ConfigError::MissingOutputFiles)?;
+    // Ask for text, offering `default`
+    fn text(&mut self, key: &str, prompt: &str, default: &str) -> MergerResult<String> {
+        if let Some(answer) = self.answer(key)? {
+            return Ok(answer.unwrap_or_else(|| default.to_string()));
+        }
+        Ok(Input::new().with_prompt(prompt).default(default.to_string()).interact()?)
+    }
 
-        // Check if input and output paths are the same
-        if input_path == output_path {
-            return Err(ConfigError::InputOutputPathsEqual);
+    // Ask a yes or no question, offering `default`
+    fn confirm(&mut self, key: &str, prompt: &str, default: bool) -> MergerResult<bool> {
+        match self.answer(key)? {
+            Some(None) => Ok(default),
+            Some(Some(answer)) => match answer.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" => Ok(true),
+                "false" | "no" | "n" => Ok(false),
+                _ => Err(MergerError::Config(ConfigError::InvalidFormat(format!("answer '{}' must be yes or no, got '{}'", key, answer)))),
+            },
+            None => Ok(Confirm::new().with_prompt(prompt).default(default).interact()?),
         }
+    }
 
-        // Validate output directory exists and is writable
-        if let Some(parent) = output_path.parent() {
-            if !parent.exists() {
-                return Err(ConfigError::OutputDirectoryNotWritable(parent.to_path_buf()));
+    // Ask for one of the values of `T`, starting on `default`
+    fn choose<T: ValueEnum + PartialEq>(&mut self, key: &str, prompt: &str, default: T) -> MergerResult<T> {
+        match self.answer(key)? {
+            Some(None) => Ok(default),
+            Some(Some(answer)) => parse_choice(key, &answer),
+            None => {
+                let variants = T::value_variants();
+                let selected = variants.iter().position(|variant| *variant == default).unwrap_or(0);
+                let index = Select::new().with_prompt(prompt).items(&choice_names::<T>()).default(selected).interact()?;
+                Ok(variants[index].clone())
             }
-            
-            // Check if directory is writable by attempting to create a temporary file
-            if let Ok(temp_path) = tempfile::Builder::new()
-                .prefix(".test-write-")
-                .tempfile_in(parent)
-            {
-                // Clean up temporary file
-                let _ = temp_path.close();
-            } else {
-                return Err(ConfigError::OutputDirectoryNotWritable(parent.to_path_buf()));
+        }
+    }
+
+    // Ask for one of the values of `T` or none, starting on `default`
+    fn choose_optional<T: ValueEnum + PartialEq>(&mut self, key: &str, prompt: &str, default: Option<T>) -> MergerResult<Option<T>> {
+        match self.answer(key)? {
+            Some(None) => Ok(default),
+            Some(Some(answer)) if answer.trim().eq_ignore_ascii_case("none") => Ok(None),
+            Some(Some(answer)) => parse_choice(key, &answer).map(Some),
+            None => {
+                let variants = T::value_variants();
+                let mut names = vec!["none".to_string()];
+                names.extend(choice_names::<T>());
+                let selected = default.and_then(|default| variants.iter().position(|variant| *variant == default)).map_or(0, |index| index + 1);
+                let index = Select::new().with_prompt(prompt).items(&names).default(selected).interact()?;
+                Ok(index.checked_sub(1).map(|index| variants[index].clone()))
             }
         }
+    }
 
-        Ok(())
+    // Ask for a value that may be left empty, giving None when it is
+    fn optional_text(&mut self, key: &str, prompt: &str) -> MergerResult<Option<String>> {
+        let value = match self.answer(key)? {
+            Some(answer) => answer.unwrap_or_default(),
+            None => Input::<String>::new().with_prompt(prompt).allow_empty(true).interact()?,
+        };
+        let value = value.trim();
+        Ok((!value.is_empty()).then(|| value.to_string()))
     }
-}
\ No newline at end of file
+}
+
+// Names of the values of `T`, as typed on the command line
+fn choice_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants().iter()
+        .map(|variant| variant.to_possible_value().map_or_else(String::new, |value| value.get_name().to_string()))
+        .collect()
+}
+
+// The value of `T` named by the answer to `key`
+fn parse_choice<T: ValueEnum>(key: &str, answer: &str) -> MergerResult<T> {
+    T::from_str(answer.trim(), true).map_err(|_| MergerError::Config(ConfigError::InvalidFormat(format!(
+        "answer '{}' must be one of {}, got '{}'", key, choice_names::<T>().join(", "), answer
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, parse_override, Config};
+
+    // Load a default config with `key` set to `value`, returning the error message when it fails
+    async fn load_with(key: &str, value: serde_json::Value) -> Result<Config, String> {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.json");
+        let mut config = serde_json::to_value(Config::default()).unwrap();
+        config.as_object_mut().unwrap().insert(key.to_string(), value);
+        std::fs::write(&path, config.to_string()).unwrap();
+        Config::load(&path).await.map_err(|e| e.to_string())
+    }
+
+    #[tokio::test]
+    async fn unknown_keys_are_refused_with_the_closest_key() {
+        let error = load_with("thraeds", 4.into()).await.unwrap_err();
+        assert!(error.contains("unknown field `thraeds`") && error.contains("did you mean `threads`?"), "{}", error);
+
+        let error = load_with("colour_output", true.into()).await.unwrap_err();
+        assert!(!error.contains("did you mean"), "{}", error);
+        assert_eq!(load_with("threads", 4.into()).await.unwrap().threads, Some(4));
+    }
+
+    #[test]
+    fn edit_distance_counts_characters() {
+        assert_eq!(edit_distance("thraeds", "threads"), 2);
+        assert_eq!(edit_distance("café", "cafe"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn overrides_set_nested_keys_from_json_or_text() {
+        let overrides = ["threads=16", "tuning.chunk_size=4194304", "temp_dir=/scratch", "transforms=[\"lower\"]"]
+            .map(|value| parse_override(value).unwrap());
+        assert_eq!(overrides[1].path, ["tuning", "chunk_size"]);
+        assert_eq!(overrides[2].value, serde_json::Value::String("/scratch".to_string()));
+
+        let config = Config::default().with_overrides(&overrides).unwrap();
+        assert_eq!(config.threads, Some(16));
+        assert_eq!(config.tuning.chunk_size, 4194304);
+        assert_eq!(config.temp_dir.as_deref(), Some(std::path::Path::new("/scratch")));
+        assert_eq!(config.transforms.len(), 1);
+    }
+
+    #[test]
+    fn overrides_are_checked_like_the_file() {
+        assert!(parse_override("threads").is_err());
+        assert!(parse_override("tuning..chunk_size=1").is_err());
+        let error = Config::default().with_overrides(&[parse_override("thraeds=4").unwrap()]).unwrap_err().to_string();
+        assert!(error.contains("did you mean `threads`?"), "{}", error);
+        let error = Config::default().with_overrides(&[parse_override("threads.count=4").unwrap()]).unwrap_err().to_string();
+        assert!(error.contains("is not an object"), "{}", error);
+    }
+}
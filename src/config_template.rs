@@ -0,0 +1,179 @@
+use crate::config::Config; // Import Config, the settings being documented
+use crate::errors::ConfigError; // Import ConfigError for serialization failures
+
+// Explanation written above each config key, in the order the keys are written
+const FIELD_DOCS: &[(&str, &str)] = &[
+    ("input_files", "Text file listing one wordlist path per line; the same as --wordlists-file"),
+    ("follow_symlinks", "Follow symlinks inside directories of the input list; each file and directory is still read once, and links back to an ancestor are skipped"),
+    ("no_recurse", "Take only the files directly inside directories of the input list, not those in their subdirectories"),
+    ("extensions", "Extensions files inside directories of the input list need, such as [\"txt\", \"lst\", \"dic\"]; empty takes every file. Files listed by name are always taken"),
+    ("skip_larger_than", "Skip inputs larger than this many bytes, such as a disk image listed by mistake, reporting each one; null reads every input"),
+    ("no_content_sniff", "Read inputs even when their first bytes mark them as ELF executables, images, PDFs, SQLite databases or compressed archives; by default they are skipped and reported"),
+    ("download_dir", "Directory http:// and https:// entries of the input list are downloaded to, each copy kept under its URL and ETag and reused by later merges until --refresh finds it changed; null for the per-user cache directory"),
+    ("output_files", "Where the merged wordlist is written; the same as --output-wordlist"),
+    ("threads", "Worker threads, 1-100. More threads help with many inputs on fast storage; on one spinning disk a few are enough"),
+    ("verbose", "Log detailed progress"),
+    ("debug", "Log debugging detail; noisy on large merges"),
+    ("stream_output", "Write unique lines as they are found instead of at the end. Lowest memory, but output formats, caps, --tee and routing need it off"),
+    ("io_backend", "\"std\" or \"uring\" (Linux io_uring, streamed reads and block writes; writes cannot be rate limited)"),
+    ("dedup_backend", "How the set of lines seen is kept: \"auto\" picks from the inputs and says why, \"memory\" (one set, small merges), \"sharded\" (one set per worker), \"external-sort\" (spills sorted runs near the memory limit), \"disk-kv\" (256 MB in memory, the rest on disk) or \"bloom\" (about 1.8 bytes per line, streamed output, drops about 0.1% of unique lines)"),
+    ("tuning", "chunk_size and buffer_size in bytes (at least 1; larger chunks mean fewer hand-offs but more memory per reader), channel_size chunks queued per shard, parallel_files per batch, read_concurrency inputs read at once (1 suits spinning disks, 4-16 SSDs and NVMe) and cpu_workers shards (null follows threads)"),
+    ("max_read_mbps", "Most MB/s read from the inputs, shared by all readers; null for no limit"),
+    ("max_write_mbps", "Most MB/s written to the output; null for no limit. Spill runs are never held back"),
+    ("nice", "Niceness the merge runs at, -20 to 19; null leaves it unchanged"),
+    ("ionice", "Linux I/O class: \"idle\", \"best-effort:0-7\" or \"realtime:0-7\"; null leaves it unchanged"),
+    ("temp_dir", "Directory for spill runs once the set outgrows memory; null for the system temp directory. Put it on fast storage with room for the set"),
+    ("stages", "Merge the inputs in groups over this many stages, at least 1, through intermediates in temp_dir; each merge holds only one group's set, at the cost of writing every line once more per stage. null merges every input at once"),
+    ("workers", "Machines running `rustmerger worker`, such as [\"rig1:7879\", \"rig2:7879\"], merging one size-balanced share of the inputs each; they read the inputs at the same paths, such as on shared storage"),
+    ("worker_token", "Shared secret the workers were started with; anyone holding it can have a worker read its files"),
+    ("retry", "Retries for transient read errors: max_retries per file, initial_backoff_ms doubled per retry up to max_backoff_ms"),
+    ("rule_comments", "Comment lines in rule files: \"preserve\", \"strip\" or \"separate\""),
+    ("max_rule_ops", "Drop merged rules with more functions than this, at least 1; null for no limit"),
+    ("reject_rule_functions", "Function characters merged rules may not use, such as [\"X\", \"M\"]"),
+    ("rule_ignore_whitespace", "Treat rules differing only in the spaces between their functions as duplicates"),
+    ("strict", "Stop at the first input that is skipped or only partly merged"),
+    ("failure_limits", "Stop once more than max_files inputs, or more than max_percent (0-100) of them, failed; null for no limit"),
+    ("hex_invalid", "Keep lines that are not valid UTF-8 as $HEX[...] instead of dropping them"),
+    ("binary_mode", "Lines with NUL bytes: \"keep\" as read or write them as \"hex\""),
+    ("strip_control", "Remove \"nul\" bytes, \"all-control\" C0 characters or \"all-control-except-tab\" before deduplication; null keeps them"),
+    ("normalize_homoglyphs", "Fold fullwidth forms and look-alike Cyrillic and Greek letters to ASCII before deduplication"),
+    ("input_format", "Layout of input lines, \"auto\" (by extension, per file), \"plain\", \"dic\", \"frequency\", \"jsonl\" or \"counted\""),
+    ("json_field", "Dotted path of the candidate field in JSON Lines inputs, such as \"user.password\""),
+    ("dedup_key", "What identifies duplicate combo lines: \"pair\", \"password\" or \"username\""),
+    ("min_strength", "Weakest strength score kept, 0-4; null keeps every line. Scoring costs CPU on every line"),
+    ("max_strength", "Strongest strength score kept, 0-4; null keeps every line"),
+    ("transforms", "Rewrites adding variants of every word, such as [\"capitalize\", \"leet-basic\"]. Each one can multiply the output"),
+    ("emit_reversed", "Also emit every word reversed"),
+    ("append_years", "Years appended to every word, such as \"2015-2025\"; each year adds a copy of the list"),
+    ("append_strings", "Strings appended to every word, such as [\"!\", \"123\"]"),
+    ("templates", "Templates building hybrid candidates, such as [\"{suffix}{word}\"]"),
+    ("exclude_walks", "Drop keyboard walks and sequences such as qwerty and 123456"),
+    ("route_walks", "File receiving keyboard walks and sequences instead of the output"),
+    ("route_numeric", "File receiving all-digit candidates instead of the output"),
+    ("numeric_lengths", "Digit counts routed to route_numeric, such as [\"4-8\"]; empty for any"),
+    ("plugins", "Shared-library filter and transform plugins, applied in order"),
+    ("script", "Rhai script keeping, dropping or rewriting every candidate; runs once per line, so it slows large merges"),
+    ("pipeline", "Ordered decode, filter, transform, dedup and output stages; replaces the matching individual keys"),
+    ("tee", "Extra filtered outputs, such as [\"8to16.txt=length:8-16,ascii\"]"),
+    ("base_words", "File receiving each unique line without its trailing digits and punctuation"),
+    ("target_lines", "Keep only this many of the most frequent unique lines, at least 1"),
+    ("max_output_lines", "Stop writing after this many unique lines, at least 1"),
+    ("max_output_bytes", "Stop writing before the output exceeds this many bytes"),
+    ("cap_order", "Which lines make the cut under a cap: \"stable\" or \"frequency\""),
+    ("web_ui", "Address of the live status page, such as \"127.0.0.1:8080\"; it has no authentication"),
+    ("tui", "Show the full-screen dashboard instead of progress bars"),
+    ("notify_webhook", "URL POSTed the final report when the merge ends"),
+    ("no_history", "Do not record runs in the run history"),
+    ("history_file", "Run history to append to; null for the default in the data directory"),
+    ("checkpoint_root", "Directory the paths in --session and --progress-file checkpoints are saved relative to, so the checkpoint can be resumed after the data is copied to another machine or container; null saves them as given"),
+    ("split_by_language", "Write lines detected as a language to their own files; detection costs CPU on every line"),
+    ("languages", "Languages considered when splitting, such as [\"en\", \"de\"]; empty for all"),
+    ("language_confidence", "Detection confidence needed to tag a line, 0.0-1.0"),
+    ("output_encoding", "Encoding of text output, any WHATWG label such as \"windows-1252\"; null for UTF-8"),
+    ("bom", "Start text output with a byte order mark; null writes one for UTF-16 only"),
+    ("output_format", "Shape of the output: \"text\", \"sqlite\", \"parquet\" or \"counted\""),
+    ("output_columns", "Extra SQLite and Parquet columns: frequency and provenance, 12 bytes per unique line each while merging"),
+];
+
+// `config` as JSON with a `//` comment above every key explaining it
+//
+// `Config::load` strips the comments again, so the file can be edited and used as is.
+pub fn commented(config: &Config) -> Result<String, ConfigError> {
+    let value = serde_json::to_value(config).map_err(|e| ConfigError::SerializationError(e.to_string()))?;
+    let fields = value.as_object().ok_or_else(|| ConfigError::SerializationError("config is not an object".to_string()))?;
+
+    // Keys without an explanation still go at the end, so nothing is lost if one is missed
+    let mut keys: Vec<(&str, Option<&str>)> = FIELD_DOCS.iter()
+        .filter(|(key, _)| fields.contains_key(*key))
+        .map(|(key, doc)| (*key, Some(*doc)))
+        .collect();
+    keys.extend(fields.keys().filter(|key| !FIELD_DOCS.iter().any(|(known, _)| *known == key.as_str())).map(|key| (key.as_str(), None)));
+
+    let mut text = String::from("// rustmerger configuration; load it with `rustmerger merge --config FILE`\n{\n");
+    for (position, (key, doc)) in keys.iter().enumerate() {
+        if let Some(doc) = doc {
+            text.push_str(&format!("  // {}\n", doc));
+        }
+        let value = serde_json::to_string_pretty(&fields[*key]).map_err(|e| ConfigError::SerializationError(e.to_string()))?;
+        let separator = if position + 1 < keys.len() { "," } else { "" };
+        text.push_str(&format!("  \"{}\": {}{}\n", key, value.replace('\n', "\n  "), separator));
+    }
+    text.push_str("}\n");
+    Ok(text)
+}
+
+// `text` without `//` line comments and `/* */` block comments outside strings
+pub fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(c);
+            }
+            ('/', Some('/')) => {
+                // Keep the newline, so parse errors still point at the right line
+                while chars.peek().is_some_and(|next| *next != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        stripped.push('\n');
+                    }
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commented, strip_comments, FIELD_DOCS};
+    use crate::config::Config;
+
+    #[test]
+    fn comments_are_stripped_outside_strings_only() {
+        let text = "{ // note\n  \"url\": \"http://host/*x*/\", /* one\n two */ \"quote\": \"a\\\"//b\"\n}";
+        let stripped = strip_comments(text);
+        assert_eq!(stripped, "{ \n  \"url\": \"http://host/*x*/\", \n \"quote\": \"a\\\"//b\"\n}");
+        assert_eq!(stripped.lines().count(), text.lines().count());
+    }
+
+    #[test]
+    fn the_template_parses_back_to_the_same_config() {
+        let config = Config::default();
+        let template = commented(&config).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&strip_comments(&template)).unwrap();
+        assert_eq!(parsed, serde_json::to_value(&config).unwrap());
+    }
+
+    #[test]
+    fn every_config_key_is_explained() {
+        let value = serde_json::to_value(Config::default()).unwrap();
+        let missing: Vec<_> = value.as_object().unwrap().keys()
+            .filter(|key| !FIELD_DOCS.iter().any(|(known, _)| known == key))
+            .collect();
+        assert!(missing.is_empty(), "keys without an explanation: {:?}", missing);
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::Path; // Import Path for the inputs being sniffed
+use tokio::io::AsyncReadExt; // Import AsyncReadExt for the bounded header read
+
+const SNIFF_BYTES: u64 = 16; // Longest signature checked, so only the start of each input is read
+
+// Kind of non-text file recognized from the magic bytes it starts with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Elf,      // Linux executable or shared library
+    Png,      // PNG image
+    Jpeg,     // JPEG image
+    Pdf,      // PDF document
+    Sqlite,   // SQLite database
+    Gzip,     // gzip-compressed data
+    Bzip2,    // bzip2-compressed data
+    Xz,       // xz-compressed data
+    Zstd,     // Zstandard-compressed data
+    Zip,      // ZIP archive
+    SevenZip, // 7-Zip archive
+}
+
+// Signatures in the order they are tried; each must be at the very start of the file
+const SIGNATURES: &[(&[u8], ContentType)] = &[
+    (b"\x7fELF", ContentType::Elf),
+    (b"\x89PNG\r\n\x1a\n", ContentType::Png),
+    (b"\xff\xd8\xff", ContentType::Jpeg),
+    (b"%PDF-", ContentType::Pdf),
+    (b"SQLite format 3\0", ContentType::Sqlite),
+    (b"\x1f\x8b\x08", ContentType::Gzip),
+    (b"\xfd7zXZ\0", ContentType::Xz),
+    (b"\x28\xb5\x2f\xfd", ContentType::Zstd),
+    (b"PK\x03\x04", ContentType::Zip),
+    (b"PK\x05\x06", ContentType::Zip),
+    (b"7z\xbc\xaf\x27\x1c", ContentType::SevenZip),
+];
+
+impl ContentType {
+    // Name used in messages and the report
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContentType::Elf => "ELF executable",
+            ContentType::Png => "PNG image",
+            ContentType::Jpeg => "JPEG image",
+            ContentType::Pdf => "PDF document",
+            ContentType::Sqlite => "SQLite database",
+            ContentType::Gzip => "gzip archive",
+            ContentType::Bzip2 => "bzip2 archive",
+            ContentType::Xz => "xz archive",
+            ContentType::Zstd => "zstd archive",
+            ContentType::Zip => "ZIP archive",
+            ContentType::SevenZip => "7-Zip archive",
+        }
+    }
+
+    // What to do instead of listing the file as it is
+    pub fn advice(&self) -> &'static str {
+        match self {
+            ContentType::Sqlite => "list it as sqlite:FILE?query=SELECT ... to merge a column of it",
+            ContentType::Gzip | ContentType::Bzip2 | ContentType::Xz | ContentType::Zstd
+            | ContentType::Zip | ContentType::SevenZip => "extract it and list the extracted files",
+            _ => "remove it from the input list",
+        }
+    }
+}
+
+// Type of the data starting with `header`, or None when it may be text
+pub fn sniff(header: &[u8]) -> Option<ContentType> {
+    if let Some((_, kind)) = SIGNATURES.iter().find(|(magic, _)| header.starts_with(magic)) {
+        return Some(*kind);
+    }
+    // "BZh" alone starts ordinary words, so the block magic after the level has to follow
+    match header {
+        [b'B', b'Z', b'h', b'1'..=b'9', 0x31, 0x41, 0x59, 0x26, 0x53, 0x59, ..] => Some(ContentType::Bzip2),
+        _ => None,
+    }
+}
+
+// Type of the file at `path` from its first bytes; None when it may be text or cannot be read,
+// leaving unreadable files for validation to report
+pub async fn sniff_file(path: &Path) -> Option<ContentType> {
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let mut header = Vec::with_capacity(SNIFF_BYTES as usize);
+    file.take(SNIFF_BYTES).read_to_end(&mut header).await.ok()?;
+    sniff(&header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sniff, sniff_file, ContentType};
+
+    #[test]
+    fn signatures_are_recognized_at_the_start() {
+        assert_eq!(sniff(b"\x1f\x8b\x08\x00rest"), Some(ContentType::Gzip));
+        assert_eq!(sniff(b"SQLite format 3\0"), Some(ContentType::Sqlite));
+        assert_eq!(sniff(b"BZh91AY&SY"), Some(ContentType::Bzip2));
+        assert_eq!(sniff(b"PK\x03\x04"), Some(ContentType::Zip));
+    }
+
+    #[test]
+    fn text_is_left_alone() {
+        assert_eq!(sniff(b"BZh9 is a word\n"), None);
+        assert_eq!(sniff(b"password\n"), None);
+        assert_eq!(sniff(b"x\x1f\x8b\x08"), None); // Signatures only count at the very start
+        assert_eq!(sniff(b""), None);
+    }
+
+    #[tokio::test]
+    async fn files_are_sniffed_from_their_first_bytes() {
+        let temp = tempfile::tempdir().unwrap();
+        let pdf = temp.path().join("leak.txt");
+        std::fs::write(&pdf, b"%PDF-1.7\nnot a wordlist").unwrap();
+        assert_eq!(sniff_file(&pdf).await, Some(ContentType::Pdf));
+        assert_eq!(sniff_file(&temp.path().join("missing.txt")).await, None);
+    }
+}
@@ -0,0 +1,238 @@
+use serde::Serialize; // Import Serialize to include the statistics in the JSON report
+use std::cmp::Reverse; // Import Reverse to list the largest tags first
+use std::collections::BTreeMap; // Import BTreeMap to group inputs by tag in a stable order
+use std::path::PathBuf; // Import PathBuf for input paths
+use std::sync::atomic::{AtomicUsize, Ordering}; // Import atomics for counts shared with the shards
+use crate::estimate::format_bytes; // Reuse the byte formatter for the printed table
+
+// Unique lines each input added to the deduplication set, counted by the shards
+//
+// Inputs are identified by their position in the processing order. Once a shard has
+// spilled to disk, lines seen before the spill can be counted again for a later input, so
+// the counts are then recounted from the tallies while the runs are merged, or kept as an
+// upper bound when lines carry no tallies.
+pub struct ContributionCounter {
+    unique: Vec<AtomicUsize>, // New lines per input
+}
+
+impl ContributionCounter {
+    // Create zeroed counters for `inputs` files
+    pub fn new(inputs: usize) -> Self {
+        Self { unique: (0..inputs).map(|_| AtomicUsize::new(0)).collect() }
+    }
+
+    // Credit `lines` newly-seen lines to input `source`
+    pub fn add(&self, source: usize, lines: usize) {
+        if let Some(count) = self.unique.get(source) {
+            count.fetch_add(lines, Ordering::Relaxed);
+        }
+    }
+
+    // Forget every credit, before recounting them from merged runs
+    pub fn reset(&self) {
+        self.unique.iter().for_each(|count| count.store(0, Ordering::Relaxed));
+    }
+
+    // Unique lines credited to input `source` so far
+    pub fn unique(&self, source: usize) -> usize {
+        self.unique.get(source).map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+}
+
+// What one input contributed to the merged output
+#[derive(Debug, Clone, Serialize)]
+pub struct FileContribution {
+    pub path: PathBuf,         // Input file
+    pub bytes: u64,            // File size
+    pub encoding: String,      // Detected encoding label
+    pub lines_read: usize,     // Non-empty lines in the file
+    pub lines_passed: usize,   // Lines accepted for deduplication
+    pub unique_lines: usize,   // Lines not seen in any earlier input
+    pub unique_upper_bound: bool, // Whether `unique_lines` may overcount, after a spill without tallies
+    pub duplicate_rate: f64,   // Share of accepted lines that were already known, 0.0-1.0
+    pub stripped_lines: usize, // Lines that had control characters removed
+    pub elapsed_ms: u64,       // Time spent reading the file
+    pub tags: Vec<String>,     // Tags given to the input in the input list
+}
+
+// What the inputs sharing one tag contributed together
+#[derive(Debug, Clone, Serialize)]
+pub struct TagContribution {
+    pub tag: String,           // Tag from the input list, or `untagged`
+    pub files: usize,          // Inputs carrying the tag
+    pub bytes: u64,            // Their combined size
+    pub lines_read: usize,     // Non-empty lines in them
+    pub lines_passed: usize,   // Lines accepted for deduplication
+    pub unique_lines: usize,   // Lines they added that no earlier input held
+    pub duplicate_rate: f64,   // Share of accepted lines that were already known, 0.0-1.0
+    pub unique_share: f64,     // Share of all unique lines they added, 0.0-1.0
+}
+
+// Contributions summed per tag, most unique lines first, or none when no input is tagged
+//
+// An input with several tags counts toward each, and inputs without one are grouped as `untagged`.
+pub fn by_tag(files: &[FileContribution]) -> Vec<TagContribution> {
+    if files.iter().all(|file| file.tags.is_empty()) {
+        return Vec::new();
+    }
+    let untagged = vec!["untagged".to_string()];
+    let mut groups: BTreeMap<&str, TagContribution> = BTreeMap::new();
+    for file in files {
+        let tags = if file.tags.is_empty() { &untagged } else { &file.tags };
+        for tag in tags {
+            let group = groups.entry(tag.as_str()).or_insert_with(|| TagContribution {
+                tag: tag.clone(),
+                files: 0,
+                bytes: 0,
+                lines_read: 0,
+                lines_passed: 0,
+                unique_lines: 0,
+                duplicate_rate: 0.0,
+                unique_share: 0.0,
+            });
+            group.files += 1;
+            group.bytes += file.bytes;
+            group.lines_read += file.lines_read;
+            group.lines_passed += file.lines_passed;
+            group.unique_lines += file.unique_lines;
+        }
+    }
+
+    let total_unique: usize = files.iter().map(|file| file.unique_lines).sum();
+    let mut tags: Vec<TagContribution> = groups.into_values()
+        .map(|mut group| {
+            group.duplicate_rate = FileContribution::duplicate_rate(group.lines_passed, group.unique_lines);
+            group.unique_share = if total_unique == 0 { 0.0 } else { group.unique_lines as f64 / total_unique as f64 };
+            group
+        })
+        .collect();
+    tags.sort_by_key(|tag| Reverse(tag.unique_lines));
+    tags
+}
+
+impl FileContribution {
+    // Share of accepted lines that were duplicates
+    pub fn duplicate_rate(lines_passed: usize, unique_lines: usize) -> f64 {
+        if lines_passed == 0 {
+            0.0
+        } else {
+            lines_passed.saturating_sub(unique_lines) as f64 / lines_passed as f64
+        }
+    }
+}
+
+// Print one row per input, in processing order
+pub fn print_table(files: &[FileContribution]) {
+    if files.is_empty() {
+        return;
+    }
+
+    println!("\nPer-file contribution:");
+    println!(
+        "  {:>10} {:>12} {:>12} {:>12} {:>6} {:>8} {:<12} File",
+        "Size", "Read", "Passed", "Unique", "Dup%", "Time", "Encoding"
+    );
+    for file in files {
+        println!(
+            "  {:>10} {:>12} {:>12} {:>12} {:>5.1}% {:>7.1}s {:<12} {}",
+            format_bytes(file.bytes),
+            file.lines_read,
+            file.lines_passed,
+            file.unique_lines,
+            file.duplicate_rate * 100.0,
+            file.elapsed_ms as f64 / 1000.0,
+            file.encoding,
+            file.path.display()
+        );
+    }
+    if files.iter().any(|file| file.unique_upper_bound) {
+        println!("  Unique counts are upper bounds: the set spilled to disk, and lines seen before a spill may be credited again");
+    }
+}
+
+// Print one row per tag, most unique lines first
+pub fn print_tag_table(tags: &[TagContribution]) {
+    if tags.is_empty() {
+        return;
+    }
+
+    println!("\nContribution per tag:");
+    println!(
+        "  {:>6} {:>10} {:>12} {:>12} {:>12} {:>6} {:>7} Tag",
+        "Files", "Size", "Read", "Passed", "Unique", "Dup%", "Share"
+    );
+    for tag in tags {
+        println!(
+            "  {:>6} {:>10} {:>12} {:>12} {:>12} {:>5.1}% {:>6.1}% {}",
+            tag.files,
+            format_bytes(tag.bytes),
+            tag.lines_read,
+            tag.lines_passed,
+            tag.unique_lines,
+            tag.duplicate_rate * 100.0,
+            tag.unique_share * 100.0,
+            tag.tag
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{by_tag, ContributionCounter, FileContribution};
+    use std::path::PathBuf;
+
+    // Contribution of an input with the given tags, accepted and unique lines
+    fn file(tags: &[&str], lines_passed: usize, unique_lines: usize) -> FileContribution {
+        FileContribution {
+            path: PathBuf::from("input.txt"),
+            bytes: 100,
+            encoding: "utf-8".to_string(),
+            lines_read: lines_passed,
+            lines_passed,
+            unique_lines,
+            unique_upper_bound: false,
+            duplicate_rate: FileContribution::duplicate_rate(lines_passed, unique_lines),
+            stripped_lines: 0,
+            elapsed_ms: 0,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn counters_credit_known_inputs_until_reset() {
+        let counter = ContributionCounter::new(2);
+        counter.add(0, 3);
+        counter.add(1, 2);
+        counter.add(0, 1);
+        counter.add(5, 9);
+        assert_eq!((counter.unique(0), counter.unique(1), counter.unique(5)), (4, 2, 0));
+        counter.reset();
+        assert_eq!((counter.unique(0), counter.unique(1)), (0, 0));
+    }
+
+    #[test]
+    fn duplicate_rates_are_shares_of_the_accepted_lines() {
+        assert_eq!(FileContribution::duplicate_rate(0, 0), 0.0);
+        assert_eq!(FileContribution::duplicate_rate(4, 1), 0.75);
+        assert_eq!(FileContribution::duplicate_rate(2, 5), 0.0);
+    }
+
+    #[test]
+    fn untagged_inputs_give_no_tag_totals() {
+        assert!(by_tag(&[file(&[], 10, 5), file(&[], 4, 4)]).is_empty());
+    }
+
+    #[test]
+    fn tags_sum_their_inputs_largest_first() {
+        let files = [file(&["leaks"], 10, 2), file(&["leaks", "rockyou"], 10, 6), file(&[], 4, 2)];
+        let tags = by_tag(&files);
+        let names: Vec<_> = tags.iter().map(|tag| tag.tag.as_str()).collect();
+        assert_eq!(names, ["leaks", "rockyou", "untagged"]);
+
+        let leaks = &tags[0];
+        assert_eq!((leaks.files, leaks.bytes, leaks.lines_passed, leaks.unique_lines), (2, 200, 20, 8));
+        assert_eq!(leaks.duplicate_rate, 0.6);
+        assert_eq!(leaks.unique_share, 0.8);
+        assert_eq!(tags[2].unique_share, 0.2);
+    }
+}
@@ -1,29 +1,80 @@
 use anyhow::Result; // Import Result type from anyhow crate for error handling
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle}; // Import progress bar utilities from indicatif crate
 use std::path::{Path, PathBuf}; // Import Path and PathBuf for file path handling
 use std::sync::Arc; // Import Arc for thread-safe reference counting
+use std::borrow::Cow;
 use tokio::io::{AsyncWriteExt, AsyncSeekExt, BufWriter, BufReader, AsyncBufReadExt}; // Remove duplicate AsyncWriteExt
 use tokio::fs::File;
 use futures::StreamExt;
-use std::collections::HashSet;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Instant;
+use std::hash::BuildHasher;
 use tokio::sync::mpsc;
-use futures::stream::{self, FuturesUnordered};
+use futures::stream::FuturesUnordered;
 use crate::app_state::AppState;
+use crate::config::Config;
 use serde::{Serialize, Deserialize};
 use tokio::fs::OpenOptions;
 use tokio::io::SeekFrom;
-use sys_info;
 use crate::progress::ProgressTracker;
+use crate::mmap_reader::MmapReader;
+use crate::line_store::{LineChunk, LineStore, Tally};
+use crate::shards::{ShardRouter, ShardedChunk};
+use crate::memory_monitor::{self, MemoryMonitor};
+use crate::cardinality::{HyperLogLog, UniqueForecast};
+use crate::spill::{self, SpillWatchdog};
+use crate::dedup_backend::{BloomFilter, DedupBackend};
+use crate::stages::{self, StageDir};
+use crate::distributed::{self, WorkerJob, WorkerOptions};
+use crate::input_format::InputFormat;
+use crate::tuning::Tuning;
+use crate::fd_limit;
+use crate::retry::{self, RetryPolicy, RetryStats};
+use crate::input_list::{EntryOptions, InputList, ListOptions};
+use crate::failed_files::{FailedFiles, FailureLimits, DEFAULT_FAILED_FILES_PATH};
+use crate::report::{self, FileIssue, FileReadStats, IssueKind, RunReport};
+use crate::exit_code::RunOutcome;
+use crate::contribution::{self, ContributionCounter, FileContribution};
+use crate::line_options::{BinaryMode, LineOptions};
+use crate::hex_format;
+use crate::homoglyph;
+use crate::pipeline::CandidateBatch;
+use crate::sqlite_source::{self, SqliteSource};
+use crate::content_type::{self, ContentType};
+use crate::download_cache::{self, DownloadCache};
+use crate::output_format::{self, CapOrder, OutputCap, OutputColumns, OutputFormat, RecordSink};
+use crate::language::{LanguageSplit, LanguageSplitSink};
+use crate::routing::{Route, RouteSink};
+use crate::tee::{TeeOutput, TeeSink};
+use crate::base_words::BaseWordSink;
+use crate::encoding::{self, EncodedTextSink, EncodingSample, EncodingSummary, OutputEncoding, DETECT_SAMPLE_BYTES};
+use crate::encoding_cache::EncodingCache;
+use crate::pause::PauseGate;
+use crate::throttle::{Meter, RateLimit};
+use crate::estimate::format_bytes;
+use crate::status::{Phase, RunStatus};
+use crate::io_backend::{self, IoBackend};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use crate::errors::{MergerError, MergerResult};
+use crate::errors::{ConfigError, MergerError, MergerResult};
+use crate::dedup_key::COMBO_SEPARATOR;
 
-const CHUNK_SIZE: usize = 1024 * 1024 * 10; // 10MB chunks
-const BUFFER_SIZE: usize = 1024 * 1024 * 32; // 32MB buffer
-const CHANNEL_SIZE: usize = 1000; // Number of chunks to keep in memory
-const PARALLEL_FILES: usize = 4; // Number of files to process in parallel
 const LINE_BUFFER_CAPACITY: usize = 1024 * 64; // 64KB initial line buffer
-const OUTPUT_CHUNKS: usize = 8;
 const OUTPUT_BUFFER_SIZE: usize = 1024 * 1024 * 16; // 16MB output buffer
+const PACED_WRITE_SIZE: usize = 1024 * 1024; // Largest single write to the output
+
+// What every reader of a merge shares to read its inputs
+#[derive(Clone)]
+struct ReadContext {
+    router: ShardRouter,        // Routes candidates to their shards
+    limits: Arc<MemoryMonitor>, // Chunk limits, tightened as memory runs low
+    hasher: RandomState,        // Hasher shared with the shards
+    io_backend: IoBackend,      // How inputs are read
+    buffer_size: usize,         // Read buffer for inputs that cannot be memory-mapped
+    retry_policy: RetryPolicy,  // Retries of transient read errors
+    line_options: LineOptions,  // How raw lines become candidates
+    entries: Arc<HashMap<PathBuf, EntryOptions>>, // Options the input list gives per input
+    seed: Option<(PathBuf, LineOptions)>, // Lines of an earlier run, and how they read back
+}
 
 // Define a struct to manage the core processing logic
 #[allow(dead_code)]
@@ -32,6 +83,46 @@ pub struct ProcessingCore {
     tracker: ProgressTracker, // Replace progress: MultiProgress with tracker
     verbose: bool, // Flag to enable verbose logging
     debug: bool, // Flag to enable debug mode
+    stream_output: bool, // Flag to write unique lines as soon as they are seen
+    io_backend: IoBackend, // File I/O strategy for reading inputs and writing the output
+    tuning: Tuning, // Buffer, chunk and batch sizes
+    temp_dir: PathBuf, // Directory for spill runs
+    dedup_backend: DedupBackend, // How the set of lines seen so far is kept
+    expected_lines: u64, // Lines the inputs are estimated to hold, sizing Bloom filters
+    stages: usize, // Stages of a hierarchical merge; 1 merges every input at once
+    stage_dir: Option<StageDir>, // Intermediates of a hierarchical merge, kept until the final stage is written
+    workers: Vec<String>, // Worker addresses the inputs are partitioned across; empty merges locally
+    worker_token: String, // Shared secret the workers were started with
+    retry_policy: RetryPolicy, // Retries for transient read errors
+    retry_stats: RetryStats, // Retry outcomes for the final report
+    failed_files: FailedFiles, // Inputs skipped because of errors
+    failed_files_path: PathBuf, // Where the skip list is written when something failed
+    issues: Vec<FileIssue>, // Files skipped or degraded, for the end-of-run summary
+    strict: bool, // Abort on the first file that cannot be fully processed
+    failure_limits: FailureLimits, // Abort once too many inputs failed
+    abort_reason: Option<String>, // Why strict mode or a failure limit stopped the run
+    contributions: Vec<FileContribution>, // What each input added, in processing order
+    file_stats: bool, // Print the per-file contribution table at the end
+    line_options: LineOptions, // How raw lines become candidates
+    entry_options: HashMap<PathBuf, EntryOptions>, // Options the input list gives per input, by path
+    list_options: ListOptions, // How directories in the input list are walked
+    skip_larger_than: Option<u64>, // Inputs over this many bytes are skipped instead of read
+    content_sniff: bool, // Skip inputs whose magic bytes show they are not text
+    download_cache: Option<DownloadCache>, // Where URL inputs are downloaded to; None when there is nowhere
+    output_format: OutputFormat, // Shape of the merged output
+    output_columns: OutputColumns, // Extra columns in analytical outputs
+    output_cap: Option<OutputCap>, // Most lines and bytes written, and which lines make the cut
+    language_split: Option<LanguageSplit>, // Write lines to per-language files
+    routes: Vec<Route>, // Kinds of lines written to their own files instead of the output
+    tees: Vec<TeeOutput>, // Extra outputs receiving the lines that pass their filters
+    base_words: Option<PathBuf>, // List receiving the base word of each unique line
+    output_encoding: Option<OutputEncoding>, // Encoding and BOM of text output other than plain UTF-8
+    encodings: EncodingSummary, // Encodings of the inputs read, for the end-of-run summary
+    status: Arc<RunStatus>, // Live figures for status displays
+    read_limit: Option<Arc<RateLimit>>, // Most bytes per second read from the inputs
+    write_limit: Option<Arc<RateLimit>>, // Most bytes per second written to the output
+    peak_memory: Option<u64>, // Largest resident set size sampled while merging
+    seed: Option<(PathBuf, InputFormat)>, // Lines of an earlier run read back first, as written, and their layout
 }
 
 // Implement methods for ProcessingCore
@@ -39,9 +130,7 @@ impl ProcessingCore {
     // Asynchronous constructor for ProcessingCore
     pub async fn new(app_state: Arc<AppState>, verbose: bool, debug: bool) -> MergerResult<Self> {
         // Estimate total files and lines
-        let input_file = &app_state.input_file;
-        let content = tokio::fs::read_to_string(input_file).await?;
-        let total_files = content.lines().count();
+        let total_files = InputList::load(&app_state.input_file).await?.files.len();
         
         // Rough estimation of lines (can be adjusted based on your needs)
         let estimated_lines = total_files * 1000; // Assuming average 1000 lines per file
@@ -51,9 +140,301 @@ impl ProcessingCore {
             tracker: ProgressTracker::new(total_files, estimated_lines),
             verbose,
             debug,
+            stream_output: false,
+            io_backend: IoBackend::default(),
+            tuning: Tuning::default(),
+            temp_dir: std::env::temp_dir(),
+            dedup_backend: DedupBackend::ExternalSort,
+            expected_lines: 0,
+            stages: 1,
+            stage_dir: None,
+            workers: Vec::new(),
+            worker_token: String::new(),
+            retry_policy: RetryPolicy::default(),
+            retry_stats: RetryStats::default(),
+            failed_files: FailedFiles::default(),
+            failed_files_path: PathBuf::from(DEFAULT_FAILED_FILES_PATH),
+            issues: Vec::new(),
+            strict: false,
+            failure_limits: FailureLimits::default(),
+            abort_reason: None,
+            contributions: Vec::new(),
+            file_stats: false,
+            line_options: LineOptions::default(),
+            entry_options: HashMap::new(),
+            list_options: ListOptions::default(),
+            skip_larger_than: None,
+            content_sniff: true,
+            download_cache: None,
+            output_format: OutputFormat::default(),
+            output_columns: OutputColumns::default(),
+            output_cap: None,
+            language_split: None,
+            routes: Vec::new(),
+            tees: Vec::new(),
+            base_words: None,
+            output_encoding: None,
+            encodings: EncodingSummary::default(),
+            status: Arc::new(RunStatus::new()),
+            read_limit: None,
+            write_limit: None,
+            peak_memory: None,
+            seed: None,
         })
     }
 
+    // Write newly-seen unique lines to the output immediately instead of at the end
+    pub fn with_stream_output(mut self, enabled: bool) -> Self {
+        self.stream_output = enabled;
+        self
+    }
+
+    // Draw the progress bars, or leave the terminal to a dashboard
+    pub fn with_progress_bars(self, enabled: bool) -> Self {
+        if !enabled {
+            self.tracker.hide();
+        }
+        self
+    }
+
+    // Select the file I/O strategy for reading inputs and writing the output
+    pub fn with_io_backend(mut self, backend: IoBackend) -> Self {
+        self.io_backend = backend;
+        self
+    }
+
+    // Override the buffer, chunk and batch sizes
+    pub fn with_tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    // Place spill runs under `dir` instead of the OS temp dir
+    pub fn with_temp_dir(mut self, dir: PathBuf) -> Self {
+        self.temp_dir = dir;
+        self
+    }
+
+    // Keep the set of lines seen with `backend`; Bloom filters are sized for `expected_lines`
+    pub fn with_dedup_backend(mut self, backend: DedupBackend, expected_lines: u64) -> Self {
+        self.dedup_backend = backend;
+        self.expected_lines = expected_lines;
+        self
+    }
+
+    // Merge the inputs in groups over `stages` stages, so each merge holds one group's set
+    pub fn with_stages(mut self, stages: usize) -> Self {
+        self.stages = stages.max(1);
+        self
+    }
+
+    // Partition the inputs across the `workers`, authenticating with `token`, and merge what they send back
+    pub fn with_workers(mut self, workers: Vec<String>, token: String) -> Self {
+        self.workers = workers;
+        self.worker_token = token;
+        self
+    }
+
+    // Retry transient read errors according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    // Abort after checkpointing when any input is skipped or degraded
+    pub fn with_strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    // Abort once more inputs failed than the limits allow
+    pub fn with_failure_limits(mut self, limits: FailureLimits) -> Self {
+        self.failure_limits = limits;
+        self
+    }
+
+    // Print what each input contributed once the merge is done
+    pub fn with_file_stats(mut self, enabled: bool) -> Self {
+        self.file_stats = enabled;
+        self
+    }
+
+    // Write the output as `format`, with `columns` next to each line in analytical formats
+    pub fn with_output_format(mut self, format: OutputFormat, columns: OutputColumns) -> Self {
+        self.output_format = format;
+        self.output_columns = columns;
+        self
+    }
+
+    // Stop writing unique lines once `cap` is reached; frequency order counts occurrences across all inputs
+    pub fn with_output_cap(mut self, cap: Option<OutputCap>) -> Self {
+        self.output_cap = cap;
+        self
+    }
+
+    // Pace reading the inputs and writing the output; spill runs are never slowed down
+    pub fn with_rate_limits(mut self, read: Option<RateLimit>, write: Option<RateLimit>) -> Self {
+        self.read_limit = read.map(Arc::new);
+        self.write_limit = write.map(Arc::new);
+        self
+    }
+
+    // Write each line whose language is detected to its own file next to the output
+    pub fn with_language_split(mut self, split: Option<LanguageSplit>) -> Self {
+        self.language_split = split;
+        self
+    }
+
+    // Divert lines matching each route to its own file instead of the output
+    pub fn with_routes(mut self, routes: Vec<Route>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    // Also write the lines passing each output's filters to that output
+    pub fn with_tees(mut self, tees: Vec<TeeOutput>) -> Self {
+        self.tees = tees;
+        self
+    }
+
+    // Also write each unique line's base word, without trailing digits and punctuation, to `path`
+    pub fn with_base_words(mut self, path: Option<PathBuf>) -> Self {
+        self.base_words = path;
+        self
+    }
+
+    // Write text output in `encoding` instead of plain UTF-8
+    pub fn with_output_encoding(mut self, encoding: Option<OutputEncoding>) -> Self {
+        self.output_encoding = encoding;
+        self
+    }
+
+    // Set how raw input lines become candidates
+    pub fn with_line_options(mut self, options: LineOptions) -> Self {
+        self.line_options = options;
+        self
+    }
+
+    // Walk directories in the input list with `options`: following symlinks or not, into
+    // subdirectories or not, and keeping only some extensions
+    pub fn with_list_options(mut self, options: ListOptions) -> Self {
+        self.list_options = options;
+        self
+    }
+
+    // Skip inputs over `bytes` bytes, such as disk images listed by mistake, reporting each one
+    pub fn with_skip_larger_than(mut self, bytes: Option<u64>) -> Self {
+        self.skip_larger_than = bytes;
+        self
+    }
+
+    // Whether inputs starting with the magic bytes of an executable, image, document, database
+    // or archive are skipped instead of read
+    pub fn with_content_sniff(mut self, enabled: bool) -> Self {
+        self.content_sniff = enabled;
+        self
+    }
+
+    // Download URL inputs into `cache`, reusing the copies already there
+    pub fn with_download_cache(mut self, cache: Option<DownloadCache>) -> Self {
+        self.download_cache = cache;
+        self
+    }
+
+    // Read the inputs in `entries` with their own options, on top of any the input list gives
+    pub fn with_entry_options(mut self, entries: HashMap<PathBuf, EntryOptions>) -> Self {
+        self.entry_options = entries;
+        self
+    }
+
+    // Read the lines of an earlier output back first, as written in `format`, such as the
+    // output a retry adds to; the decode settings apply, the filters and transforms do not
+    pub fn with_seed(mut self, path: PathBuf, format: InputFormat) -> Self {
+        self.seed = Some((path, format));
+        self
+    }
+
+    // Save `config` with the skip list, so a retry of the failed inputs runs with the same options
+    pub fn with_run_config(mut self, config: Config) -> Self {
+        self.failed_files.config = Some(config);
+        self
+    }
+
+    // Write the skip list of failed inputs to `path` instead of failed_files.json
+    pub fn with_failed_files_path(mut self, path: PathBuf) -> Self {
+        self.failed_files_path = path;
+        self
+    }
+
+    // Inputs skipped because of errors so far
+    pub fn failed_files(&self) -> &FailedFiles {
+        &self.failed_files
+    }
+
+    // Summary of the run for `--report`
+    pub fn report(&self) -> RunReport {
+        RunReport {
+            input_list: self.app_state.input_file.clone(),
+            output: self.app_state.output_file.clone(),
+            lines_processed: self.app_state.lines_processed.load(Ordering::Relaxed),
+            unique_lines: self.app_state.unique_lines.load(Ordering::Relaxed),
+            duplicate_percent: report::duplicate_percent(
+                self.app_state.lines_processed.load(Ordering::Relaxed),
+                self.app_state.unique_lines.load(Ordering::Relaxed),
+            ),
+            retries: self.retry_stats,
+            issues: self.issues.clone(),
+            files: self.contributions.clone(),
+            tags: contribution::by_tag(&self.contributions),
+            encodings: self.encodings.clone(),
+            peak_memory: self.peak_memory,
+            generated_at: chrono::Local::now(),
+        }
+    }
+
+    // How the run ended, for the process exit code
+    pub async fn outcome(&self) -> RunOutcome {
+        RunOutcome::of_merge(self.app_state.should_shutdown().await, self.failed_files.len())
+    }
+
+    // Why the run must stop after the latest issue, if strict mode or a failure limit says so
+    fn abort_check(&self, total_inputs: usize) -> Option<String> {
+        if self.strict {
+            // Inputs skipped for their size or content were left out on purpose, so they do not stop the run
+            if let Some(issue) = self.issues.last().filter(|issue| !matches!(issue.kind, IssueKind::Oversized | IssueKind::NotText)) {
+                return Some(format!(
+                    "strict mode: {} could not be fully processed ({})",
+                    issue.path.display(),
+                    issue.detail
+                ));
+            }
+        }
+        self.failure_limits.exceeded(self.failed_files.len(), total_inputs)
+    }
+
+    // Live figures of the merge, updated as it runs
+    pub fn status(&self) -> Arc<RunStatus> {
+        self.status.clone()
+    }
+
+    // Stop the merge like an interruption so the output is left untouched
+    async fn abort(&mut self, reason: String) {
+        self.abort_reason = Some(reason);
+        self.app_state.request_shutdown().await;
+    }
+
+    // Record a file that could not be merged at all
+    fn record_failure(&mut self, path: &Path, error: &MergerError) {
+        self.failed_files.record(path, error);
+        self.status.push_error(path, error.to_string());
+        self.issues.push(FileIssue {
+            path: path.to_path_buf(),
+            kind: IssueKind::of_error(error),
+            skipped: true,
+            detail: error.to_string(),
+        });
+    }
+
     // Main processing function
     pub async fn process(&mut self) -> MergerResult<()> {
         if self.verbose {
@@ -61,131 +442,955 @@ impl ProcessingCore {
         }
         
         let input_path = self.app_state.input_file.clone();
-        let files = match Self::read_input_files(&input_path).await {
-            Ok(f) => f,
+        // Entries of a list whose data moved since the checkpoint follow the resume's --remap
+        let list_options = ListOptions {
+            remaps: self.app_state.progress.read().await.remaps.clone(),
+            ..self.list_options.clone()
+        };
+        let mut list = match Self::read_input_files(&input_path, list_options).await {
+            Ok(list) => list,
             Err(e) => {
                 self.log_error(&format!("Failed to read input files: {}", e)).await?;
-                return Ok(());
+                return Err(e.into());
             }
         };
 
-        let mut files_processed = 0;
-        let app_state = Arc::clone(&self.app_state);
-        
-        for file in files {
-            if app_state.should_shutdown().await {
-                self.tracker.finish();
-                return Ok(());
-            }
-            
-            let file_path = file.clone();
-            let result = self.process_single_file(file_path.clone(), &app_state).await;
-            if let Err(e) = result {
-                let error_msg = format!("Error processing file {:?}: {}", file_path, e);
-                self.log_error(&error_msg).await?;
-                continue;
-            }
-            
-            files_processed += 1;
-            self.tracker.update_overall_progress(files_processed);
+        let files = self.resolve_urls(std::mem::take(&mut list.files), &mut list.options).await;
+        let files = self.skip_oversized(files).await;
+        let files = self.skip_non_text(files).await;
+        let files = self.start_from_seed(files).await;
+        self.entry_options.extend(list.options);
+        let jsonl_without_field = self.entry_options.values()
+            .any(|entry| entry.format == Some(InputFormat::Jsonl) && entry.json_field.is_none() && self.line_options.json_field.is_none());
+        if jsonl_without_field {
+            return Err(ConfigError::MissingJsonField.into());
         }
 
+        // Earlier stages leave intermediates for the final merge below
+        let (files, stage_failures) = if !self.workers.is_empty() {
+            self.run_workers(files).await?
+        } else if self.stages > 1 {
+            self.run_stages(files).await?
+        } else {
+            (files, FailedFiles::default())
+        };
+
         println!("Starting merge and deduplication process...");
-        self.merge_and_deduplicate().await?;
-        
+        self.merge_and_deduplicate(&files).await?;
+        // Added only now, so the failure limits of the final merge count its own inputs
+        self.failed_files.files.extend(stage_failures.files);
+
         self.tracker.finish();
+        let (lines, unique) = (self.app_state.lines_processed.load(Ordering::Relaxed), self.app_state.unique_lines.load(Ordering::Relaxed));
+        if lines > 0 {
+            println!("Duplicates: {} of {} lines ({:.1}%)", lines.saturating_sub(unique), lines, report::duplicate_percent(lines, unique));
+        }
+        if self.file_stats {
+            contribution::print_table(&self.contributions);
+            contribution::print_tag_table(&contribution::by_tag(&self.contributions));
+        }
+        self.encodings.log();
+        report::print_issue_summary(&self.issues);
+        if !self.failed_files.is_empty() {
+            self.failed_files.save(&self.failed_files_path).await?;
+            println!(
+                "{} files failed and were skipped; see {}",
+                self.failed_files.len(),
+                self.failed_files_path.display()
+            );
+        }
+        let stats = self.retry_stats;
+        if stats.retries > 0 {
+            println!(
+                "Retried {} transient read errors: {} files recovered, {} files failed after retries",
+                stats.retries, stats.recovered, stats.exhausted
+            );
+        }
+        if let Some(peak) = self.peak_memory {
+            println!("Peak memory: {}", format_bytes(peak));
+        }
+        if let Some(reason) = &self.abort_reason {
+            self.status.set_phase(Phase::Interrupted);
+            self.app_state.save_progress().await?;
+            return Err(MergerError::Processing(format!(
+                "aborted: {}; progress saved, output left unchanged",
+                reason
+            )));
+        }
+        if self.app_state.should_shutdown().await {
+            self.status.set_phase(Phase::Interrupted);
+            println!("Processing interrupted, progress saved");
+            return Ok(());
+        }
+        self.status.set_phase(Phase::Done);
         println!("Processing completed successfully");
         
         Ok(())
     }
 
-    // Function to merge files and remove duplicates
-    async fn merge_and_deduplicate(&mut self) -> MergerResult<()> {
-        let files = self.validate_and_collect_metadata(&self.app_state.progress.read().await.processed_files).await?;
-        let optimized_files = optimize_processing_order(files).await;
+    // Merge `files` in groups through every stage but the last, returning what the last stage
+    // merges and the inputs that failed on the way
+    //
+    // Each group is merged by a core of its own into an intermediate under the temp dir, so no
+    // merge holds more than one group's set. A stage's intermediates are deleted once the next
+    // stage has read them. When the output needs counts, intermediates carry them as counted text.
+    async fn run_stages(&mut self, files: Vec<PathBuf>) -> MergerResult<(Vec<PathBuf>, FailedFiles)> {
+        let fan_in = stages::fan_in(self.stages, files.len());
+        let stage_dir = StageDir::create(&self.temp_dir)?;
+        let tallied = self.output_columns.needs_tally() || self.output_cap.is_some_and(|cap| cap.order == CapOrder::Frequency);
+        let (output_format, input_format) = if tallied {
+            (OutputFormat::Counted, InputFormat::Counted)
+        } else {
+            (OutputFormat::Text, InputFormat::Plain)
+        };
+        let columns = OutputColumns { frequency: tallied, provenance: false };
+        let first_options = self.line_options.clone();
+
+        let mut inputs = files;
+        let mut failures = FailedFiles::default();
+        let mut merged_stages = 0;
+        'stages: for stage in 1..self.stages {
+            // A stage with no more inputs than one group would only copy them
+            if inputs.len() <= fan_in {
+                break;
+            }
+            let groups = inputs.len().div_ceil(fan_in);
+            let options = if stage == 1 { first_options.clone() } else { first_options.for_intermediates(input_format) };
+            let mut outputs = Vec::with_capacity(groups);
+            for (group, members) in inputs.chunks(fan_in).enumerate() {
+                // The final merge sees the shutdown too and leaves the output untouched
+                if self.app_state.should_shutdown().await {
+                    break 'stages;
+                }
+                let (list, output) = stage_dir.group(stage, group, members)?;
+                println!("Stage {} of {}: merging group {} of {} ({} inputs)", stage, self.stages, group + 1, groups, members.len());
+
+                let mut state = AppState::new(list, output.clone(), self.app_state.threads).await
+                    .map_err(|e| MergerError::Processing(e.to_string()))?;
+                state.shutdown_requested = self.app_state.shutdown_requested.clone();
+                state.pause = self.app_state.pause.clone();
+                let mut core = ProcessingCore::new(Arc::new(state), self.verbose, self.debug).await?
+                    .with_io_backend(self.io_backend)
+                    .with_tuning(self.tuning)
+                    .with_temp_dir(self.temp_dir.clone())
+                    .with_dedup_backend(self.dedup_backend, self.expected_lines / groups as u64)
+                    .with_retry_policy(self.retry_policy)
+                    .with_strict(self.strict)
+                    .with_failure_limits(self.failure_limits)
+                    .with_line_options(options.clone())
+                    .with_output_format(output_format, columns);
+                if stage == 1 {
+                    core = core.with_entry_options(self.entry_options.clone());
+                }
+                core.read_limit = self.read_limit.clone();
+                // Each group runs a full merge of its own, so the recursive future is boxed
+                Box::pin(core.process()).await?;
+
+                // Inputs that failed in a group are reported with the final merge
+                failures.files.extend(core.failed_files.files);
+                self.issues.extend(core.issues);
+                outputs.push(output);
+            }
+            if stage > 1 {
+                inputs.iter().for_each(|intermediate| {
+                    let _ = std::fs::remove_file(intermediate);
+                });
+            }
+            inputs = outputs;
+            merged_stages = stage;
+        }
+
+        if merged_stages > 0 {
+            self.line_options = first_options.for_intermediates(input_format);
+            println!("Final stage: merging {} intermediates", inputs.len());
+        }
+        self.stage_dir = Some(stage_dir);
+        Ok((inputs, failures))
+    }
+
+    // Merge `files` on the workers, one partition of about equal size each, returning the
+    // intermediates they send back and the inputs they skipped
+    //
+    // Workers decode and deduplicate their partition; the filters and transforms run here, in the
+    // final merge of the intermediates.
+    async fn run_workers(&mut self, files: Vec<PathBuf>) -> MergerResult<(Vec<PathBuf>, FailedFiles)> {
+        let stage_dir = StageDir::create(&self.temp_dir)?;
+        let counted = self.output_columns.needs_tally() || self.output_cap.is_some_and(|cap| cap.order == CapOrder::Frequency);
+        let mut sized = Vec::with_capacity(files.len());
+        for file in files {
+            // Inputs only on the workers' storage weigh nothing here
+            let size = tokio::fs::metadata(&file).await.map(|meta| meta.len()).unwrap_or(0);
+            sized.push((file, size));
+        }
+
+        let options = WorkerOptions::from_line_options(&self.line_options);
+        let mut jobs = Vec::with_capacity(self.workers.len());
+        for (part, (worker, inputs)) in self.workers.iter().zip(distributed::partition(&sized, self.workers.len())).enumerate() {
+            println!("Sending {} inputs to worker {}", inputs.len(), worker);
+            let entries = inputs.iter()
+                .filter_map(|input| self.entry_options.get(input).map(|entry| (input.clone(), entry.to_string())))
+                .collect();
+            let job = WorkerJob { token: self.worker_token.clone(), inputs, options: options.clone(), counted, entries };
+            let output = stage_dir.intermediate(1, part);
+            jobs.push(async move {
+                let failed = distributed::dispatch(worker, &job, &output).await?;
+                Ok::<_, MergerError>((output, failed))
+            });
+        }
+        let mut intermediates = Vec::with_capacity(jobs.len());
+        let mut failures = FailedFiles::default();
+        for result in futures::future::join_all(jobs).await {
+            let (output, failed) = result?;
+            intermediates.push(output);
+            failures.files.extend(failed);
+        }
+
+        // The intermediates are decoded already but not yet filtered or transformed
+        let format = if counted { InputFormat::Counted } else { InputFormat::Plain };
+        self.line_options = LineOptions {
+            pipeline: self.line_options.pipeline.clone(),
+            ..self.line_options.for_intermediates(format)
+        };
+        println!("Merging {} intermediates from the workers", intermediates.len());
+        self.stage_dir = Some(stage_dir);
+        Ok((intermediates, failures))
+    }
+
+    // Replace URL inputs with their copies in the download cache, downloading the missing ones
+    //
+    // A URL that cannot be downloaded is skipped like an unreadable file. Options the input list
+    // gives a URL move to its copy.
+    async fn resolve_urls(&mut self, files: Vec<PathBuf>, options: &mut HashMap<PathBuf, EntryOptions>) -> Vec<PathBuf> {
+        if !files.iter().any(|path| download_cache::is_url(path)) {
+            return files;
+        }
+        let mut resolved = Vec::with_capacity(files.len());
+        for path in files {
+            if !download_cache::is_url(&path) {
+                resolved.push(path);
+                continue;
+            }
+            let url = path.to_string_lossy().into_owned();
+            let copy = match &self.download_cache {
+                Some(cache) => cache.get(&url).await,
+                None => Err(MergerError::Processing("no download cache directory; set one with --download-dir".to_string())),
+            };
+            match copy {
+                Ok(copy) => {
+                    if let Some(entry) = options.remove(&path) {
+                        options.insert(copy.clone(), entry);
+                    }
+                    resolved.push(copy);
+                }
+                Err(e) => {
+                    log::warn!("Error downloading {}: {}", url, e);
+                    self.record_failure(&path, &e);
+                }
+            }
+        }
+        resolved
+    }
+
+    // Leave out the inputs over --skip-larger-than, reporting each one as skipped
+    //
+    // They are not added to the skip list: nothing failed, so they neither count towards the
+    // failure limits nor change the exit code. Unreadable inputs are kept for validation to report.
+    async fn skip_oversized(&mut self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let Some(max) = self.skip_larger_than else { return files };
+        let mut kept = Vec::with_capacity(files.len());
+        for path in files {
+            match sqlite_source::input_size(&path).await {
+                Ok(size) if size > max => {
+                    let detail = format!("{} is over the --skip-larger-than limit of {}", format_bytes(size), format_bytes(max));
+                    log::warn!("Skipping {:?}: {}", path, detail);
+                    self.status.push_error(&path, detail.clone());
+                    self.issues.push(FileIssue { path, kind: IssueKind::Oversized, skipped: true, detail });
+                }
+                _ => kept.push(path),
+            }
+        }
+        kept
+    }
+
+    // Leave out the inputs whose first bytes show they are not text, reporting the type found
+    //
+    // Like oversized inputs they are not failures. Database queries are never sniffed.
+    async fn skip_non_text(&mut self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        if !self.content_sniff {
+            return files;
+        }
+        // Headers are read 50 at a time, keeping the list order
+        let sniffed: Vec<(PathBuf, Option<ContentType>)> = futures::stream::iter(files)
+            .map(|path| async move {
+                let kind = if sqlite_source::is_sqlite(&path) { None } else { content_type::sniff_file(&path).await };
+                (path, kind)
+            })
+            .buffered(50)
+            .collect()
+            .await;
+
+        let mut kept = Vec::with_capacity(sniffed.len());
+        for (path, kind) in sniffed {
+            let Some(kind) = kind else {
+                kept.push(path);
+                continue;
+            };
+            let detail = format!("{} (by its magic bytes); {}", kind.label(), kind.advice());
+            log::warn!("Skipping {:?}: {}", path, detail);
+            self.status.push_error(&path, detail.clone());
+            self.issues.push(FileIssue { path, kind: IssueKind::NotText, skipped: true, detail });
+        }
+        kept
+    }
+
+    // Function to read, deduplicate and record every input file in a single pass
+    async fn merge_and_deduplicate(&mut self, input_files: &[PathBuf]) -> MergerResult<()> {
+        let files = self.validate_and_collect_metadata(input_files).await?;
+        if let Some(reason) = self.abort_check(input_files.len()) {
+            self.abort(reason).await;
+            return Ok(());
+        }
+        let sizes: HashMap<PathBuf, u64> = files.iter().cloned().collect();
+        let mut optimized_files = optimize_processing_order(files).await;
+        // Inputs given a higher priority in the input list go first; the sort keeps size order among equals
+        optimized_files.sort_by_key(|path| std::cmp::Reverse(self.entry_options.get(path).map_or(0, |entry| entry.priority)));
+        // Lines of an earlier run were seen before any input left, so they go first
+        if let Some(position) = self.seed.as_ref().and_then(|(seed, _)| optimized_files.iter().position(|path| path == seed)) {
+            let seed = optimized_files.remove(position);
+            optimized_files.insert(0, seed);
+        }
+        self.status.set_inputs(optimized_files.iter().map(|path| (path.clone(), sizes.get(path).copied().unwrap_or(0))));
+
+        // Never schedule more open inputs than the descriptor limit allows
+        let max_open = fd_limit::max_concurrent_opens();
+        let read_concurrency = self.tuning.read_concurrency.min(max_open);
+        if read_concurrency < self.tuning.read_concurrency {
+            log::warn!(
+                "Open file limit allows {} concurrent inputs; read concurrency reduced from {} (raise `ulimit -n` to avoid this)",
+                max_open,
+                self.tuning.read_concurrency
+            );
+        }
         
         // Calculate optimal batch size based on available system memory
         let mem_info = sys_info::mem_info()?;
         let available_memory = (mem_info.avail as usize * 1024) / 2;
-        let batch_size = (available_memory / std::mem::size_of::<String>()).min(CHUNK_SIZE);
+        let batch_size = (available_memory / std::mem::size_of::<String>()).min(self.tuning.chunk_size);
+
+        // Reader chunk limits start from the batch size and shrink as memory tightens
+        let limits = Arc::new(MemoryMonitor::new(self.tuning.chunk_size, batch_size));
+        let monitor_task = limits.spawn();
         
-        let (tx, mut rx) = mpsc::channel::<HashSet<String>>(CHANNEL_SIZE);
+        // Readers and the aggregators share one hasher so hashes computed while reading are reused
+        let hasher = RandomState::new();
         let unique_count = Arc::new(AtomicUsize::new(0));
-        
-        // Spawn writer task with optimized batching
-        let writer_task = tokio::spawn({
-            let unique_count = unique_count.clone();
-            async move {
-                let mut final_set = HashSet::with_capacity(batch_size);
-                
-                while let Some(mut chunk_set) = rx.recv().await {
-                    final_set.extend(chunk_set.drain());
-                    unique_count.store(final_set.len(), Ordering::Relaxed);
-                }
-                final_set
-            }
-        });
+        let contributions = Arc::new(ContributionCounter::new(optimized_files.len()));
+        let sketch = Arc::new(HyperLogLog::new()); // Distinct lines seen, including ones spilled to disk
+
+        // Lines are partitioned by hash into independent shards, one aggregator task each
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let shard_count = self.dedup_backend.shards(self.tuning.cpu_workers.unwrap_or_else(|| self.app_state.threads.clamp(1, cores)));
+        let (router, receivers) = ShardRouter::new(shard_count, self.tuning.channel_size);
+        let router = router.with_pause(self.app_state.pause.clone()).with_read_limit(self.read_limit.clone());
+
+        // Shards spill sorted runs to disk when their sets near the backend's high-water mark
+        let high_water = self.dedup_backend.high_water(SpillWatchdog::default_high_water());
+        let watchdog = Arc::new(
+            SpillWatchdog::new(shard_count, high_water, !self.stream_output, self.temp_dir.clone())
+                .with_memory(limits.clone()),
+        );
+        let watchdog_task = watchdog.spawn();
+
+        // In streaming mode shards hand newly-seen lines to a single output writer
+        let (stream_tx, stream_task) = if self.stream_output {
+            let (stream_tx, stream_rx) = mpsc::channel::<Vec<u8>>(self.tuning.channel_size);
+            let output_file = self.app_state.output_file.clone();
+            (Some(stream_tx), Some(tokio::spawn(Self::stream_writer(output_file, stream_rx, self.app_state.pause.clone(), self.write_limit.clone()))))
+        } else {
+            (None, None)
+        };
+
+        let shard_tasks: Vec<_> = receivers.into_iter()
+            .enumerate()
+            .map(|(shard, rx)| {
+                // Each shard sees its share of the lines, so its filter is sized for that share
+                let bloom = (self.dedup_backend == DedupBackend::Bloom)
+                    .then(|| BloomFilter::for_lines(self.expected_lines / shard_count as u64));
+                let capacity = if bloom.is_some() { 0 } else { batch_size / shard_count };
+                let store = LineStore::with_capacity(capacity)
+                    .with_tally(self.output_columns.needs_tally() || self.output_cap.is_some_and(|cap| cap.order == CapOrder::Frequency))
+                    .with_key(self.line_options.dedup_key);
+                tokio::spawn(Self::aggregate_shard(shard, rx, store, bloom, unique_count.clone(), contributions.clone(), sketch.clone(), stream_tx.clone(), watchdog.clone()))
+            })
+            .collect();
+        drop(stream_tx);
 
         // Process files in parallel with optimized ordering
         let mut total_lines_processed = 0;
-        
-        // Process files in chunks
-        for chunk in optimized_files.chunks(PARALLEL_FILES) {
-            let tx = tx.clone();
-            let chunk_files = chunk.to_vec();
-            
-            for file in chunk_files {
-                if let Ok(lines_count) = Self::process_large_file(&file, tx.clone(), batch_size).await {
-                    total_lines_processed += lines_count;
-                    let current_unique = unique_count.load(Ordering::Relaxed);
-                    self.tracker.update_dedup_progress(current_unique, total_lines_processed);
+        let mut files_processed = 0;
+        let mut aborted = false;
+        let mut seed_read = false; // The earlier run's lines are in the set
+        let mut read_files = Vec::new();
+        let bytes_total: u64 = sizes.values().sum();
+        let mut bytes_read = 0;
+        let mut forecast = UniqueForecast::default();
+
+        // Up to `read_concurrency` inputs are read at once; their results are handled in input order.
+        // Readers check for a shutdown before they start, so an abort only waits for the reads in flight.
+        let context = ReadContext {
+            router: router.clone(),
+            limits: limits.clone(),
+            hasher: hasher.clone(),
+            io_backend: self.io_backend,
+            buffer_size: self.tuning.buffer_size,
+            retry_policy: self.retry_policy,
+            line_options: self.line_options.clone(),
+            entries: Arc::new(self.entry_options.clone()),
+            seed: self.seed.clone().map(|(seed, format)| (seed, self.line_options.for_intermediates(format))),
+        };
+        let (app_state, status) = (self.app_state.clone(), self.status.clone());
+        let mut reads = futures::stream::iter(optimized_files.iter().cloned().enumerate())
+            .map(|(source, file)| {
+                let (context, app_state, status) = (context.clone(), app_state.clone(), status.clone());
+                async move {
+                    app_state.pause.wait().await;
+                    if app_state.should_shutdown().await {
+                        return None;
+                    }
+                    // Each file is read here, and read again only after a transient error
+                    let started = Instant::now();
+                    status.start_file(source);
+                    let (result, retries) = Self::read_with_retries(&context, &file, source).await;
+                    Some((source, file, result, retries, started.elapsed()))
+                }
+            })
+            .buffered(read_concurrency);
+
+        while let Some(read) = reads.next().await {
+            // Inputs that finish after an abort are not recorded, so a resume reads them again
+            let Some((source, file, result, retries, elapsed)) = read.filter(|_| !aborted) else { continue };
+            self.retry_stats.add(retries);
+            let read_stats = match result {
+                Ok(stats) => stats,
+                Err(e) => {
+                    self.log_error(&format!("Error processing file {:?}: {}", file, e)).await?;
+                    self.record_failure(&file, &e);
+                    self.status.finish_file(source, 0, true);
+                    if let Some(reason) = self.abort_check(input_files.len()) {
+                        self.abort(reason).await;
+                        aborted = true;
+                    }
+                    continue;
+                }
+            };
+            if let Some(issue) = read_stats.issue(&file) {
+                self.status.push_error(&file, issue.detail.clone());
+                self.issues.push(issue);
+                if let Some(reason) = self.abort_check(input_files.len()) {
+                    // The file is not recorded as processed, so a resume reads it again
+                    self.abort(reason).await;
+                    aborted = true;
+                    continue;
                 }
             }
+            if read_stats.lines == 0 && read_stats.skipped_lines > 0 {
+                log::warn!("No candidates in {:?}: none of its {} lines held one", file, read_stats.skipped_lines);
+            }
+            let lines_count = read_stats.lines;
+            read_files.push((source, file.clone(), read_stats, elapsed));
+
+            if self.seed.as_ref().is_some_and(|(seed, _)| *seed == file) {
+                seed_read = true;
+            } else {
+                self.record_processed_file(&file, lines_count).await?;
+            }
+            files_processed += 1;
+            if let Some(rss) = limits.resident() {
+                self.tracker.set_memory_usage(rss);
+            }
+            self.tracker.update_overall_progress(files_processed);
+
+            total_lines_processed += lines_count;
+            let current_unique = unique_count.load(Ordering::Relaxed);
+            bytes_read += sizes.get(&file).copied().unwrap_or(0);
+            self.tracker.set_unique_forecast(forecast.update(sketch.estimate(), bytes_read, bytes_total));
+            self.tracker.update_dedup_progress(current_unique, total_lines_processed);
+            self.app_state.lines_processed.store(total_lines_processed, Ordering::Relaxed);
+            self.app_state.unique_lines.store(current_unique, Ordering::Relaxed);
+            self.status.finish_file(source, lines_count, false);
+            self.status.set_counts(total_lines_processed, current_unique);
         }
+        drop(reads);
+        drop(context);
+        self.status.set_phase(Phase::Writing);
 
-        drop(tx); // Close the channel
+        drop(router); // Close the shard channels
+        monitor_task.abort();
 
-        // Get the final set and write results
-        let unique_lines = writer_task.await?;
-        let file = File::create(&self.app_state.output_file).await?;
-        let mut writer = BufWriter::with_capacity(BUFFER_SIZE, file);
-        let total_unique = unique_lines.len();
-        
-        println!("Writing {} unique lines to output file", total_unique);
-        
-        let mut buffer = String::with_capacity(CHUNK_SIZE);
-        for line in unique_lines {
-            buffer.push_str(&line);
-            buffer.push('\n');
-            
-            if buffer.len() >= CHUNK_SIZE {
-                writer.write_all(buffer.as_bytes()).await?;
-                buffer.clear();
+        // Collect every shard's set and spill runs, and finish the streamed output
+        let mut shards = Vec::with_capacity(shard_tasks.len());
+        for task in shard_tasks {
+            shards.push(task.await??);
+        }
+        watchdog_task.abort();
+        if let Some(task) = stream_task {
+            task.await??;
+        }
+        // The sets are at their largest once every shard is done
+        if let Some(rss) = memory_monitor::resident_set_size() {
+            limits.record_resident(rss);
+        }
+        self.peak_memory = limits.peak_resident();
+        let mut total_unique: usize = shards.iter().map(|(store, _)| store.len()).sum();
+
+        let stripped: usize = read_files.iter().map(|(_, _, stats, _)| stats.stripped_lines).sum();
+        if stripped > 0 {
+            println!("Removed control characters from {} lines", stripped);
+        }
+        let normalized: usize = read_files.iter().map(|(_, _, stats, _)| stats.normalized_lines).sum();
+        if normalized > 0 {
+            println!("Folded fullwidth and look-alike characters to ASCII in {} lines", normalized);
+        }
+        let binary_hex: usize = read_files.iter().map(|(_, _, stats, _)| stats.binary_hex).sum();
+        if binary_hex > 0 {
+            println!("Kept {} lines with NUL bytes as $HEX[...]", binary_hex);
+        }
+        let transformed: usize = read_files.iter().map(|(_, _, stats, _)| stats.transformed_lines).sum();
+        if transformed > 0 {
+            println!("Generated {} extra candidates with transforms and templates before deduplication", transformed);
+        }
+        let filtered: usize = read_files.iter().map(|(_, _, stats, _)| stats.filtered_lines).sum();
+        if filtered > 0 {
+            println!("Dropped {} lines with a strength score outside the kept range", filtered);
+        }
+        let walks: usize = read_files.iter().map(|(_, _, stats, _)| stats.walk_lines).sum();
+        if walks > 0 {
+            println!("Dropped {} keyboard walks and sequences", walks);
+        }
+        let scripted: usize = read_files.iter().map(|(_, _, stats, _)| stats.script_lines).sum();
+        if scripted > 0 {
+            println!("The line script dropped {} lines", scripted);
+        }
+        let plugin_dropped: usize = read_files.iter().map(|(_, _, stats, _)| stats.plugin_lines).sum();
+        if plugin_dropped > 0 {
+            println!("Plugins dropped {} lines", plugin_dropped);
+        }
+        let entry_filtered: usize = read_files.iter().map(|(_, _, stats, _)| stats.entry_filtered_lines).sum();
+        if entry_filtered > 0 {
+            println!("Dropped {} lines failing the filters of their input list entry", entry_filtered);
+        }
+
+        // Inputs by position, naming the source column of analytical outputs
+        let source_names: Vec<String> = optimized_files.iter().map(|path| path.display().to_string()).collect();
+
+        // Every shard has finished, so the per-input unique counts are final unless a shard
+        // spilled; those are recounted from the tallies as the runs merge, or stay upper bounds.
+        // Encodings were detected while reading and are remembered for later dry runs
+        let spilled = watchdog.run_count() > 0;
+        let recount = (spilled && shards.iter().all(|(store, _)| store.is_tallied())).then(|| contributions.clone());
+        let mut contribution_sources = Vec::with_capacity(read_files.len());
+        let mut encoding_cache = EncodingCache::load().await;
+        for (source, path, stats, elapsed) in read_files {
+            contribution_sources.push(source);
+            let unique_lines = contributions.unique(source);
+            let encoding = stats.encoding.unwrap_or("unknown").to_string();
+            encoding_cache.record(&path, &encoding).await;
+            self.encodings.add(&encoding, stats.invalid_lines, stats.hex_encoded);
+            self.contributions.push(FileContribution {
+                bytes: sizes.get(&path).copied().unwrap_or(0),
+                encoding,
+                lines_read: stats.read_lines(),
+                lines_passed: stats.lines,
+                unique_lines,
+                unique_upper_bound: spilled,
+                duplicate_rate: FileContribution::duplicate_rate(stats.lines, unique_lines),
+                stripped_lines: stats.stripped_lines,
+                elapsed_ms: elapsed.as_millis() as u64,
+                tags: self.entry_options.get(&path).map(|entry| entry.tags.clone()).unwrap_or_default(),
+                path,
+            });
+        }
+        encoding_cache.save().await;
+
+        // An interrupted merge saves its set, so a resume reads only the inputs it had not finished.
+        // Without the previous snapshot's lines the set would not hold every processed file, so
+        // that snapshot is kept instead.
+        let interrupted = self.app_state.should_shutdown().await;
+        if interrupted && self.dedup_backend != DedupBackend::Bloom && (self.seed.is_none() || seed_read) {
+            self.save_snapshot(std::mem::take(&mut shards), &watchdog, max_open).await?;
+        }
+
+        // Streamed lines are already on disk, including a partial result when interrupted
+        if self.stream_output {
+            // Bloom filters keep no lines, so the count comes from the shards' tallies
+            total_unique = unique_count.load(Ordering::Relaxed);
+            println!("Streamed {} unique lines to output file", total_unique);
+            self.tracker.update_dedup_progress(total_unique, total_lines_processed);
+            self.app_state.lines_processed.store(total_lines_processed, Ordering::Relaxed);
+            self.app_state.unique_lines.store(total_unique, Ordering::Relaxed);
+            self.status.set_counts(total_lines_processed, total_unique);
+            return Ok(());
+        }
+
+        // Leave the output untouched when interrupted; the progress file allows a resume
+        if interrupted {
+            return Ok(());
+        }
+
+        if self.language_split.is_some() || self.output_format != OutputFormat::Text || !self.routes.is_empty() || !self.tees.is_empty()
+            || self.base_words.is_some() || self.output_encoding.is_some()
+        {
+            // Sinks take lines one at a time, from memory or merged spill runs, tagging or routing each
+            let detector = self.language_split.as_ref().map(LanguageSplit::detector).transpose()?;
+            if detector.is_some() {
+                println!("Writing unique lines split by language");
+            } else {
+                println!("Writing unique lines to {} output", self.output_format.label());
+            }
+            let output_file = self.app_state.output_file.clone();
+            let (format, columns, cap) = (self.output_format, self.output_columns, self.output_cap);
+            let (routes, tees, encoding) = (self.routes.clone(), self.tees.clone(), self.output_encoding);
+            let (base_words, limit, credits) = (self.base_words.clone(), self.write_limit.clone(), recount.clone());
+            let temp_dir = self.temp_dir.clone();
+            total_unique = tokio::task::spawn_blocking(move || {
+                let sink: Box<dyn RecordSink + Send> = match (detector, encoding) {
+                    (Some(detector), _) => Box::new(LanguageSplitSink::create(&output_file, detector)?),
+                    (None, Some(encoding)) => Box::new(EncodedTextSink::create(&output_file, encoding)?),
+                    (None, None) => output_format::create_sink(format, &output_file, columns, source_names)?,
+                };
+                // Extra outputs see every line, including the ones routed away from the output
+                let sink = TeeSink::wrap(RouteSink::wrap(sink, &routes)?, &tees)?;
+                let mut sink = BaseWordSink::wrap(sink, base_words.as_deref(), &temp_dir, max_open)?;
+                let mut meter = Meter::new(limit.as_deref());
+                let unique = for_each_record(shards, &watchdog, max_open, cap, credits.as_deref(), |line, tally| {
+                    meter.add(line.len() + 1);
+                    sink.write(line, tally)
+                })?;
+                sink.finish()?;
+                Ok::<_, std::io::Error>(unique)
+            }).await??;
+        } else if watchdog.run_count() > 0 || self.output_cap.is_some() {
+            // Spilled shards are merged from their sorted runs, so the output size is only known afterwards
+            println!("Merging spilled runs into output file");
+            let output_file = self.app_state.output_file.clone();
+            let (cap, limit, credits) = (self.output_cap, self.write_limit.clone(), recount.clone());
+            total_unique = tokio::task::spawn_blocking(move || {
+                write_spilled(&output_file, shards, &watchdog, max_open, cap, limit.as_deref(), credits.as_deref())
+            }).await??;
+        } else if self.io_backend == IoBackend::Uring {
+            println!("Writing {} unique lines to output file", total_unique);
+            let stores: Vec<LineStore> = shards.into_iter().map(|(store, _)| store).collect();
+            let output_file = self.app_state.output_file.clone();
+            tokio::task::spawn_blocking(move || {
+                io_backend::uring_write_file(&output_file, stores.iter().flat_map(output_chunks))
+            }).await??;
+        } else {
+            // Each shard writes its own region of the output concurrently
+            println!("Writing {} unique lines to output file", total_unique);
+            let stores: Vec<LineStore> = shards.into_iter().map(|(store, _)| store).collect();
+            let output_file = self.app_state.output_file.clone();
+            let total_bytes: u64 = stores.iter().map(LineStore::output_bytes).sum();
+            File::create(&output_file).await?.set_len(total_bytes).await?;
+
+            let mut offset = 0;
+            let mut writers = Vec::with_capacity(stores.len());
+            for store in stores {
+                let start = offset;
+                offset += store.output_bytes();
+                writers.push(tokio::spawn(write_region(output_file.clone(), start, store, self.write_limit.clone())));
+            }
+            for writer in writers {
+                writer.await??;
             }
         }
-        
-        if !buffer.is_empty() {
-            writer.write_all(buffer.as_bytes()).await?;
+
+        // Spilled shards credited each line to its first input while merging
+        if let Some(credits) = recount {
+            for (contribution, source) in self.contributions.iter_mut().zip(contribution_sources) {
+                contribution.unique_lines = credits.unique(source);
+                contribution.unique_upper_bound = false;
+                contribution.duplicate_rate = FileContribution::duplicate_rate(contribution.lines_passed, contribution.unique_lines);
+            }
         }
 
-        writer.flush().await?;
         self.tracker.update_dedup_progress(total_unique, total_lines_processed);
+        self.app_state.lines_processed.store(total_lines_processed, Ordering::Relaxed);
+        self.app_state.unique_lines.store(total_unique, Ordering::Relaxed);
+        self.status.set_counts(total_lines_processed, total_unique);
 
         Ok(())
     }
 
+    // Aggregate one shard: insert its chunks with their precomputed hashes, spilling when asked
+    //
+    // With a Bloom filter the store stays empty and only the filter decides what is new.
+    #[allow(clippy::too_many_arguments)]
+    async fn aggregate_shard(
+        shard: usize,
+        mut rx: mpsc::Receiver<LineChunk>,
+        mut store: LineStore,
+        mut bloom: Option<BloomFilter>,
+        unique_count: Arc<AtomicUsize>,
+        contributions: Arc<ContributionCounter>,
+        sketch: Arc<HyperLogLog>,
+        stream_tx: Option<mpsc::Sender<Vec<u8>>>,
+        watchdog: Arc<SpillWatchdog>,
+    ) -> MergerResult<(LineStore, Vec<PathBuf>)> {
+        let mut runs = Vec::new();
+        while let Some(mut chunk) = rx.recv().await {
+            let source = chunk.source;
+            let mut added = 0;
+            let mut fresh = Vec::new();
+            for (hash, line, count) in chunk.drain() {
+                sketch.insert(hash);
+                let inserted = match bloom.as_mut() {
+                    Some(bloom) => bloom.insert(hash),
+                    None => store.insert_tallied(hash, line.as_bytes(), Tally { count, source }),
+                };
+                if !inserted {
+                    continue;
+                }
+                added += 1;
+                if stream_tx.is_some() {
+                    fresh.extend_from_slice(line.as_bytes());
+                    fresh.push(b'\n');
+                }
+            }
+            unique_count.fetch_add(added, Ordering::Relaxed);
+            contributions.add(source, added);
+
+            if let Some(stream_tx) = stream_tx.as_ref().filter(|_| !fresh.is_empty()) {
+                stream_tx.send(fresh).await?;
+            }
+
+            watchdog.report(shard, store.footprint());
+            if watchdog.take_request(shard) {
+                let watchdog = watchdog.clone();
+                let (spilled, run) = tokio::task::spawn_blocking(move || {
+                    let run = watchdog.spill(shard, &mut store)?;
+                    Ok::<_, std::io::Error>((store, run))
+                }).await??;
+                store = spilled;
+                runs.push(run);
+            }
+        }
+        Ok((store, runs))
+    }
+
+    // Write streamed batches of newly-seen lines to the output as they arrive
+    //
+    // Buffered lines are flushed when the merge is paused, so the output is current while it waits.
+    async fn stream_writer(
+        output_file: PathBuf,
+        mut rx: mpsc::Receiver<Vec<u8>>,
+        gate: PauseGate,
+        limit: Option<Arc<RateLimit>>,
+    ) -> MergerResult<()> {
+        let mut writer = BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, File::create(&output_file).await?);
+        let mut pauses = gate.subscribe();
+        loop {
+            tokio::select! {
+                batch = rx.recv() => match batch {
+                    Some(batch) => {
+                        if let Some(limit) = &limit {
+                            limit.pace(batch.len() as u64).await;
+                        }
+                        writer.write_all(&batch).await?;
+                        // Lines queued before the pause keep arriving for a while
+                        if gate.is_paused() && rx.is_empty() {
+                            writer.flush().await?;
+                        }
+                    }
+                    None => break,
+                },
+                Ok(()) = pauses.changed() => {
+                    if *pauses.borrow_and_update() {
+                        writer.flush().await?;
+                    }
+                }
+            }
+        }
+        writer.flush().await?;
+        Ok(())
+    }
+
+    // How the lines of `file` become candidates: the lines of an earlier run read back as
+    // written, every other input with the options its list entry gives
+    fn options_for(context: &ReadContext, file: &Path) -> LineOptions {
+        match &context.seed {
+            Some((seed, options)) if seed == file => options.clone(),
+            _ => context.line_options.for_entry(file, context.entries.get(file)),
+        }
+    }
+
+    // How the snapshot of an interrupted run is written and read: with counts when the output needs them
+    fn snapshot_format(&self) -> (OutputFormat, InputFormat) {
+        if self.output_columns.needs_tally() || self.output_cap.is_some_and(|cap| cap.order == CapOrder::Frequency) {
+            (OutputFormat::Counted, InputFormat::Counted)
+        } else {
+            (OutputFormat::Text, InputFormat::Plain)
+        }
+    }
+
+    // `files` with the lines of an earlier run read first
+    //
+    // On resume the checkpoint's snapshot holds those lines, and the processed files it was
+    // saved with are left out. Files finished after that, by a run that was then killed, are
+    // read again.
+    async fn start_from_seed(&mut self, mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let format = self.snapshot_format().1;
+        let progress = self.app_state.progress.read().await;
+        if let Some(snapshot) = progress.snapshot.clone().filter(|snapshot| snapshot.exists()) {
+            let done: HashSet<&PathBuf> = progress.processed_files.iter().take(progress.snapshot_files).collect();
+            files.retain(|file| !done.contains(file));
+            println!("Resuming: {} inputs already merged are read back from {}", done.len(), snapshot.display());
+            self.seed = Some((snapshot, format));
+        }
+        drop(progress);
+        if let Some((seed, _)) = &self.seed {
+            files.insert(0, seed.clone());
+        }
+        files
+    }
+
+    // Save the set of an interrupted merge next to its checkpoint, for the resume to start from
+    //
+    // Every processed file recorded so far is in the set. The lines are written to a temporary
+    // file that replaces the previous snapshot only once complete.
+    async fn save_snapshot(&self, shards: Vec<(LineStore, Vec<PathBuf>)>, watchdog: &Arc<SpillWatchdog>, max_open: usize) -> MergerResult<()> {
+        let (save_path, snapshot_files) = {
+            let progress = self.app_state.progress.read().await;
+            (progress.save_path.clone(), progress.processed_files.len())
+        };
+        let Some(save_path) = save_path else { return Ok(()) };
+        let path = save_path.with_extension("lines");
+        let partial = save_path.with_extension("lines.partial");
+        let (format, watchdog) = (self.snapshot_format().0, watchdog.clone());
+        let (target, columns) = (partial.clone(), OutputColumns { frequency: format == OutputFormat::Counted, provenance: false });
+        let lines = tokio::task::spawn_blocking(move || {
+            let mut sink = output_format::create_sink(format, &target, columns, Vec::new())?;
+            let lines = for_each_record(shards, &watchdog, max_open, None, None, |line, tally| sink.write(line, tally))?;
+            sink.finish()?;
+            Ok::<_, std::io::Error>(lines)
+        }).await??;
+        tokio::fs::rename(&partial, &path).await?;
+
+        let mut progress = self.app_state.progress.write().await;
+        progress.snapshot = Some(path.clone());
+        progress.snapshot_files = snapshot_files;
+        progress.save().await?;
+        println!("Saved {} unique lines to {} for the resume", lines, path.display());
+        Ok(())
+    }
+
+    // Record a fully read file in the progress state and persist it
+    async fn record_processed_file(&self, file: &Path, lines_count: usize) -> Result<()> {
+        let mut progress = self.app_state.progress.write().await; // Acquire a write lock on the progress state
+        if !progress.processed_files.iter().any(|p| p == file) {
+            progress.processed_files.push(file.to_path_buf()); // Add the file to the list of processed files
+        }
+        progress.current_position += lines_count; // Update the current position
+        progress.save().await?; // Save the progress state
+        
+        if self.verbose {
+            log::debug!("Processed file: {}", file.display()); // Log the processed file if verbose is enabled
+        }
+        
+        Ok(())
+    }
+
+    // Read one file, retrying transient I/O errors with exponential backoff, and count the retries
+    //
+    // A retried file is read from the start; lines already sent are deduplicated away.
+    async fn read_with_retries(context: &ReadContext, file: &PathBuf, source: usize) -> (MergerResult<FileReadStats>, RetryStats) {
+        let mut attempt = 0;
+        let mut retries = RetryStats::default();
+        loop {
+            let result = Self::process_large_file(
+                file, context.router.clone(), context.limits.clone(), context.io_backend, context.buffer_size,
+                &context.hasher, source, Self::options_for(context, file),
+            ).await;
+            match result {
+                Err(e) if retry::is_transient(&e) && attempt < context.retry_policy.max_retries => {
+                    attempt += 1;
+                    retries.retries += 1;
+                    let delay = context.retry_policy.backoff(attempt);
+                    if let Err(log_error) = Self::append_error_log(&format!(
+                        "Transient error reading {:?}, retry {} of {} in {:?}: {}",
+                        file, attempt, context.retry_policy.max_retries, delay, e
+                    )).await {
+                        return (Err(log_error.into()), retries);
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    if attempt > 0 {
+                        retries.exhausted += 1;
+                    }
+                    return (Err(e), retries);
+                }
+                Ok(stats) => {
+                    if attempt > 0 {
+                        retries.recovered += 1;
+                    }
+                    return (Ok(stats), retries);
+                }
+            }
+        }
+    }
+
     // Move process_large_file into the impl block and make it an associated function
+    #[allow(clippy::too_many_arguments)]
     async fn process_large_file(
         path: &PathBuf,
-        tx: mpsc::Sender<HashSet<String>>,
-        chunk_size: usize,
-    ) -> MergerResult<usize> {
+        router: ShardRouter,
+        limits: Arc<MemoryMonitor>,
+        io_backend: IoBackend,
+        buffer_size: usize,
+        hasher: &RandomState,
+        source: usize,
+        options: LineOptions,
+    ) -> MergerResult<FileReadStats> {
+        // Database queries are streamed row by row on a blocking thread
+        if sqlite_source::is_sqlite(path) {
+            let database = SqliteSource::parse(path)?;
+            return tokio::task::spawn_blocking({
+                let hasher = hasher.clone();
+                move || Self::dedup_rows(&database, router, &limits, &hasher, source, &options)
+            }).await?;
+        }
+
+        // With io_uring the file is streamed through the ring's buffers on a blocking thread
+        if io_backend == IoBackend::Uring {
+            return tokio::task::spawn_blocking({
+                let path = path.clone();
+                let hasher = hasher.clone();
+                move || Self::dedup_uring(&path, router, &limits, &hasher, source, &options)
+            }).await?;
+        }
+
+        // Local regular files are memory-mapped and scanned on a blocking thread
+        let mapped = tokio::task::spawn_blocking({
+            let path = path.clone();
+            let router = router.clone();
+            let hasher = hasher.clone();
+            let limits = limits.clone();
+            let options = options.clone();
+            move || Self::process_mapped_file(&path, router, &limits, &hasher, source, &options)
+        }).await??;
+        if let Some(stats) = mapped {
+            return Ok(stats);
+        }
+
         let file = File::open(path).await?;
-        let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+        let mut reader = BufReader::with_capacity(buffer_size, file);
         let mut buffer = Vec::with_capacity(LINE_BUFFER_CAPACITY);
-        let mut current_set = HashSet::with_capacity(chunk_size);
+        let mut current_set = ShardedChunk::new(&router, limits.chunk_lines(), source);
         let mut bytes_processed = 0;
-        let mut total_lines = 0;
+        let mut stats = FileReadStats::default();
+        let mut batch = CandidateBatch::default();
+        let mut meter = Meter::new(router.read_limit());
+        let mut sample = EncodingSample::default();
         
         loop {
             buffer.clear();
@@ -193,67 +1398,314 @@ impl ProcessingCore {
                 0 => break,
                 n => {
                     bytes_processed += n;
+                    meter.add_async(n).await;
+                    if !sample.is_full() {
+                        sample.push(&buffer);
+                    }
                     if !buffer.is_empty() {
-                        if let Ok(line) = String::from_utf8(buffer[..n-1].to_vec()) {
-                            if !line.is_empty() {
-                                current_set.insert(line);
-                                total_lines += 1;
-                            }
+                        // Only strip the newline when there is one; the last line may lack it
+                        let end = if buffer[n - 1] == b'\n' { n - 1 } else { n };
+                        if end > 0 {
+                            Self::insert_line(&mut current_set, &router, hasher, &buffer[..end], &options, &mut stats, &mut batch)?;
                         }
                     }
                 }
             }
             
-            if bytes_processed >= CHUNK_SIZE || current_set.len() >= chunk_size {
-                tx.send(current_set).await?;
-                current_set = HashSet::with_capacity(chunk_size);
+            if bytes_processed >= limits.chunk_bytes() || current_set.len() >= limits.chunk_lines() {
+                current_set.flush(&router).await?;
                 bytes_processed = 0;
             }
         }
         
-        if !current_set.is_empty() {
-            tx.send(current_set).await?;
-        }
+        Self::run_pipeline(&mut current_set, &router, hasher, &options, &mut stats, &mut batch)?;
+        current_set.flush(&router).await?;
+        stats.encoding = Some(sample.detect());
         
-        Ok(total_lines)
+        Ok(stats)
     }
 
-    // Function to read input files from the provided path
-    async fn read_input_files(input_file: &Path) -> Result<Vec<PathBuf>> {
-        let content = tokio::fs::read_to_string(input_file).await?;
-        Ok(content.lines()
-            .map(PathBuf::from)
-            .collect())
+    // Deduplicate a memory-mapped file; returns None when the file cannot be mapped
+    fn process_mapped_file(
+        path: &Path,
+        router: ShardRouter,
+        limits: &MemoryMonitor,
+        hasher: &RandomState,
+        source: usize,
+        options: &LineOptions,
+    ) -> MergerResult<Option<FileReadStats>> {
+        let mmap = match MmapReader::open(path) {
+            Ok(Some(mmap)) => mmap,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                log::debug!("Falling back to buffered reads for {}: {}", path.display(), e);
+                return Ok(None);
+            }
+        };
+
+        Self::dedup_bytes(&mmap, router, limits, hasher, source, options).map(Some)
     }
 
-    // Function to process a single file
-    async fn process_single_file(&mut self, file: PathBuf, app_state: &Arc<AppState>) -> Result<()> {
-        if app_state.should_shutdown().await {
-            return Err(anyhow::anyhow!("Processing interrupted by shutdown signal")); // Return an error if shutdown is requested
+    // Deduplicate the lines of an in-memory file, sending a set every chunk
+    fn dedup_bytes(
+        data: &[u8],
+        router: ShardRouter,
+        limits: &MemoryMonitor,
+        hasher: &RandomState,
+        source: usize,
+        options: &LineOptions,
+    ) -> MergerResult<FileReadStats> {
+        let mut current_set = ShardedChunk::new(&router, limits.chunk_lines(), source);
+        let mut bytes_processed = 0;
+        let mut stats = FileReadStats::default();
+        let mut batch = CandidateBatch::default();
+
+        let mut meter = Meter::new(router.read_limit());
+        for line in MmapReader::lines(data) {
+            bytes_processed += line.len() + 1;
+            meter.add(line.len() + 1);
+            if !line.is_empty() {
+                Self::insert_line(&mut current_set, &router, hasher, line, options, &mut stats, &mut batch)?;
+            }
+
+            // Limits are re-read on every line so a tightening takes effect mid-file
+            if bytes_processed >= limits.chunk_bytes() || current_set.len() >= limits.chunk_lines() {
+                current_set.flush_blocking(&router)?;
+                bytes_processed = 0;
+            }
         }
 
-        let content = match tokio::fs::read_to_string(&file).await {
-            Ok(content) => content,
-            Err(e) => {
-                self.log_error(&format!("Error reading {}: {}", file.display(), e)).await?;
-                return Ok(());
+        Self::run_pipeline(&mut current_set, &router, hasher, options, &mut stats, &mut batch)?;
+        current_set.flush_blocking(&router)?;
+        stats.encoding = Some(encoding::detect(&data[..data.len().min(DETECT_SAMPLE_BYTES)]).name());
+
+        Ok(stats)
+    }
+
+    // Deduplicate the lines streamed through io_uring, sending a set every chunk
+    fn dedup_uring(
+        path: &Path,
+        router: ShardRouter,
+        limits: &MemoryMonitor,
+        hasher: &RandomState,
+        source: usize,
+        options: &LineOptions,
+    ) -> MergerResult<FileReadStats> {
+        let mut current_set = ShardedChunk::new(&router, limits.chunk_lines(), source);
+        let mut bytes_processed = 0;
+        let mut stats = FileReadStats::default();
+        let mut batch = CandidateBatch::default();
+
+        let mut meter = Meter::new(router.read_limit());
+        let mut sample = EncodingSample::default();
+        io_backend::uring_read_lines(path, |line: &[u8]| -> MergerResult<()> {
+            bytes_processed += line.len() + 1;
+            meter.add(line.len() + 1);
+            if !sample.is_full() {
+                sample.push(line);
+                sample.push(b"\n");
+            }
+            if !line.is_empty() {
+                Self::insert_line(&mut current_set, &router, hasher, line, options, &mut stats, &mut batch)?;
+            }
+
+            // Limits are re-read on every line so a tightening takes effect mid-file
+            if bytes_processed >= limits.chunk_bytes() || current_set.len() >= limits.chunk_lines() {
+                current_set.flush_blocking(&router)?;
+                bytes_processed = 0;
+            }
+            Ok(())
+        })?;
+
+        Self::run_pipeline(&mut current_set, &router, hasher, options, &mut stats, &mut batch)?;
+        current_set.flush_blocking(&router)?;
+        stats.encoding = Some(sample.detect());
+
+        Ok(stats)
+    }
+
+    // Deduplicate the first column of a database query, sending a set every chunk
+    fn dedup_rows(
+        database: &SqliteSource,
+        router: ShardRouter,
+        limits: &MemoryMonitor,
+        hasher: &RandomState,
+        source: usize,
+        options: &LineOptions,
+    ) -> MergerResult<FileReadStats> {
+        let mut current_set = ShardedChunk::new(&router, limits.chunk_lines(), source);
+        let mut bytes_processed = 0;
+        let mut stats = FileReadStats::default();
+        let mut batch = CandidateBatch::default();
+
+        let mut meter = Meter::new(router.read_limit());
+        database.for_each_row(|value| {
+            meter.add(value.map_or(0, <[u8]>::len) + 1);
+            match value {
+                // A value spanning lines would not come back out of the output as one candidate
+                Some(value) if !value.is_empty() && memchr::memchr2(b'\n', b'\r', value).is_none() => {
+                    bytes_processed += value.len() + 1;
+                    Self::insert_line(&mut current_set, &router, hasher, value, options, &mut stats, &mut batch)?;
+                }
+                _ => stats.skipped_lines += 1,
             }
+
+            if bytes_processed >= limits.chunk_bytes() || current_set.len() >= limits.chunk_lines() {
+                current_set.flush_blocking(&router)?;
+                bytes_processed = 0;
+            }
+            Ok(())
+        })?;
+
+        Self::run_pipeline(&mut current_set, &router, hasher, options, &mut stats, &mut batch)?;
+        current_set.flush_blocking(&router)?;
+        stats.encoding = Some("sqlite");
+
+        Ok(stats)
+    }
+
+    // Add the candidate of one non-empty line to the chunk, counting lines that are dropped or contain NULs
+    //
+    // With pipeline stages the candidate waits in `batch` until enough have gathered to run them.
+    fn insert_line(
+        current_set: &mut ShardedChunk,
+        router: &ShardRouter,
+        hasher: &RandomState,
+        line: &[u8],
+        options: &LineOptions,
+        stats: &mut FileReadStats,
+        batch: &mut CandidateBatch,
+    ) -> MergerResult<()> {
+        let first = stats.read_lines() + batch.len() == 0;
+        // Inputs listed in a legacy encoding become UTF-8 before anything looks at them
+        let decoded;
+        let line = match options.encoding {
+            Some(encoding) => {
+                decoded = encoding.decode_without_bom_handling(line).0;
+                decoded.as_bytes()
+            }
+            None => line,
+        };
+        let Some((mut line, weight)) = options.extract(line, first) else {
+            stats.skipped_lines += 1;
+            return Ok(());
         };
+        if let Some(split) = options.separator.and_then(|separator| memchr::memchr(separator, &line)) {
+            let mut combo = line.into_owned();
+            combo[split] = COMBO_SEPARATOR;
+            line = Cow::Owned(combo);
+        }
+        if let Some(stripped) = options.strip_control.and_then(|mode| mode.strip(&line)) {
+            stats.stripped_lines += 1;
+            if stripped.is_empty() {
+                stats.skipped_lines += 1; // Nothing but control characters
+                return Ok(());
+            }
+            line = Cow::Owned(stripped);
+        }
+        if options.normalize_homoglyphs {
+            if let Some(folded) = std::str::from_utf8(&line).ok().and_then(homoglyph::fold) {
+                stats.normalized_lines += 1;
+                line = Cow::Owned(folded.into_bytes());
+            }
+        }
+        if !options.filters.iter().all(|filter| filter.admits(&line)) {
+            stats.entry_filtered_lines += 1;
+            return Ok(());
+        }
+        if options.pipeline.is_empty() {
+            Self::insert_candidate(current_set, router, hasher, &line, weight, false, options, stats);
+            return Ok(());
+        }
+        batch.push(&line, weight, false);
+        if batch.is_full() {
+            Self::run_pipeline(current_set, router, hasher, options, stats, batch)?;
+        }
+        Ok(())
+    }
 
-        // Process the content here
-        let mut progress = app_state.progress.write().await; // Acquire a write lock on the progress state
-        progress.processed_files.push(file.clone()); // Add the file to the list of processed files
-        progress.current_position += content.lines().count(); // Update the current position
-        progress.save().await?; // Save the progress state
-        
-        if self.verbose {
-            log::debug!("Processed file: {}", file.display()); // Log the processed file if verbose is enabled
+    // Run the waiting candidates through the pipeline stages and add the ones that come out
+    fn run_pipeline(
+        current_set: &mut ShardedChunk,
+        router: &ShardRouter,
+        hasher: &RandomState,
+        options: &LineOptions,
+        stats: &mut FileReadStats,
+        batch: &mut CandidateBatch,
+    ) -> MergerResult<()> {
+        if batch.is_empty() {
+            return Ok(());
         }
-        
+        options.pipeline.run(batch, &options.source, stats)?;
+        for (candidate, weight, generated) in batch.iter() {
+            Self::insert_candidate(current_set, router, hasher, candidate, weight, generated, options, stats);
+        }
+        batch.clear();
         Ok(())
     }
 
+    // Add a candidate that came out of the pipeline; `generated` marks transform output,
+    // which is counted apart from the input lines
+    #[allow(clippy::too_many_arguments)]
+    fn insert_candidate(
+        current_set: &mut ShardedChunk,
+        router: &ShardRouter,
+        hasher: &RandomState,
+        line: &[u8],
+        weight: u64,
+        generated: bool,
+        options: &LineOptions,
+        stats: &mut FileReadStats,
+    ) {
+        if !generated && memchr::memchr(0, line).is_some() {
+            if options.binary_mode == BinaryMode::Hex {
+                let encoded = hex_format::encode(line);
+                current_set.insert(router, hasher.hash_one(options.dedup_key.key(encoded.as_bytes())), &encoded, weight, options.dedup_key);
+                stats.binary_hex += 1;
+                stats.lines += 1;
+                return;
+            }
+            stats.nul_lines += 1;
+        }
+        match std::str::from_utf8(line) {
+            Ok(text) => {
+                // Hash once here; the aggregator inserts with this hash as-is
+                current_set.insert(router, hasher.hash_one(options.dedup_key.key(line)), text, weight, options.dedup_key);
+            }
+            Err(_) if options.hex_invalid => {
+                let encoded = hex_format::encode(line);
+                current_set.insert(router, hasher.hash_one(options.dedup_key.key(encoded.as_bytes())), &encoded, weight, options.dedup_key);
+                if !generated {
+                    stats.hex_encoded += 1;
+                }
+            }
+            Err(_) if generated => return,
+            Err(_) => {
+                stats.invalid_lines += 1;
+                return;
+            }
+        }
+        if generated {
+            stats.transformed_lines += 1;
+        } else {
+            stats.lines += 1;
+        }
+    }
+
+    // Function to read and normalize the input files listed at the provided path
+    async fn read_input_files(input_file: &Path, options: ListOptions) -> Result<InputList> {
+        let list = InputList::load_with(input_file, options).await?;
+        if let Some(summary) = list.normalization.describe() {
+            println!("{}", summary);
+        }
+        if !list.options.is_empty() {
+            println!("{} inputs have their own options in the input list", list.options.len());
+        }
+        Ok(list)
+    }
+
     // Function to validate the input files
+    #[allow(dead_code)]
     async fn validate_files(&mut self, files: &[PathBuf]) -> Result<()> {
         for (i, file) in files.iter().enumerate() {
             if !file.exists() {
@@ -267,6 +1719,11 @@ impl ProcessingCore {
 
     // Function to log errors to a file
     async fn log_error(&self, message: &str) -> Result<()> {
+        Self::append_error_log(message).await
+    }
+
+    // Append a timestamped message to error.log
+    async fn append_error_log(message: &str) -> Result<()> {
         let mut file = tokio::fs::OpenOptions::new()
             .create(true)
             .append(true)
@@ -283,30 +1740,29 @@ impl ProcessingCore {
         Ok(())
     } 
 
-    async fn validate_and_collect_metadata(&self, files: &[PathBuf]) -> Result<Vec<(PathBuf, u64)>> {
+    async fn validate_and_collect_metadata(&mut self, files: &[PathBuf]) -> Result<Vec<(PathBuf, u64)>> {
         let mut valid_files = Vec::with_capacity(files.len());
         
         // Process files in parallel batches
         let batch_size = 50; // Validate 50 files at a time
         for chunk in files.chunks(batch_size) {
             let futures: FuturesUnordered<_> = chunk.iter().map(|path| async move {
-                match tokio::fs::metadata(path).await {
-                    Ok(meta) => Some((path.clone(), meta.len())),
-                    Err(e) => {
-                        eprintln!("Error accessing file {}: {}", path.display(), e);
-                        None
-                    }
-                }
+                (path, sqlite_source::input_size(path).await)
             }).collect();
             
             // Collect results from this batch
-            let batch_results: Vec<_> = futures
-                .filter_map(|result| async move { result })
-                .collect()
-                .await;
+            let batch_results: Vec<_> = futures.collect().await;
             
-            // Extend valid_files with batch results
-            valid_files.extend(batch_results);
+            // Keep accessible files and record the rest in the skip list
+            for (path, result) in batch_results {
+                match result {
+                    Ok(size) => valid_files.push((path.clone(), size)),
+                    Err(e) => {
+                        eprintln!("Error accessing file {}: {}", path.display(), e);
+                        self.record_failure(path, &e);
+                    }
+                }
+            }
         }
         
         Ok(valid_files)
@@ -314,6 +1770,7 @@ impl ProcessingCore {
 }
 
 // Enum to represent different processing stages
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessingStage {
     Initializing, // Initializing stage
@@ -324,6 +1781,7 @@ pub enum ProcessingStage {
     Failed, // Failed stage
 }
 
+#[allow(dead_code)]
 async fn write_chunk(
     lines: Vec<String>,
     file: &Path,
@@ -332,6 +1790,7 @@ async fn write_chunk(
     let mut file = OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(false)
         .open(file)
         .await?;
     file.seek(SeekFrom::Start(offset)).await?;
@@ -345,10 +1804,197 @@ async fn write_chunk(
     Ok(())
 }
 
-async fn optimize_processing_order(files: Vec<(PathBuf, u64)>) -> Vec<PathBuf> {
+// Write one shard's lines into its region of the pre-sized output file, within `limit` if set
+async fn write_region(path: PathBuf, offset: u64, store: LineStore, limit: Option<Arc<RateLimit>>) -> MergerResult<()> {
+    let mut file = OpenOptions::new().write(true).open(&path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    for chunk in output_chunks(&store) {
+        // Paced writes go out in steps so the output grows steadily instead of in bursts
+        for piece in chunk.chunks(PACED_WRITE_SIZE) {
+            if let Some(limit) = &limit {
+                limit.pace(piece.len() as u64).await;
+            }
+            file.write_all(piece).await?;
+        }
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+// Write every shard to the output in order, merging the runs of shards that spilled
+fn write_spilled(
+    path: &Path,
+    shards: Vec<(LineStore, Vec<PathBuf>)>,
+    watchdog: &SpillWatchdog,
+    max_open: usize,
+    cap: Option<OutputCap>,
+    limit: Option<&RateLimit>,
+    credits: Option<&ContributionCounter>,
+) -> std::io::Result<usize> {
+    use std::io::Write as _;
+
+    let mut writer = std::io::BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, std::fs::File::create(path)?);
+    let mut meter = Meter::new(limit);
+    let unique = for_each_record(shards, watchdog, max_open, cap, credits, |line, _| {
+        meter.add(line.len() + 1);
+        writer.write_all(line)?;
+        writer.write_all(b"\n")
+    })?;
+    writer.flush()?;
+    Ok(unique)
+}
+
+// Pass every unique line of every shard with its tally to `emit`; returns the number passed
+//
+// Shards that spilled are merged from their runs, so their lines come out sorted. With a
+// `cap`, lines stop being passed once it is reached. In stable order those are the first
+// lines in output order. In frequency order a first pass over the tallies finds the lowest
+// count that still fits and only the most frequent lines are passed on; ties at that count
+// go to the earliest in output order. With `credits`, every line is credited to the first
+// input holding it, from its tally, replacing the counts made as lines were inserted.
+fn for_each_record<F>(
+    mut shards: Vec<(LineStore, Vec<PathBuf>)>,
+    watchdog: &SpillWatchdog,
+    max_open: usize,
+    cap: Option<OutputCap>,
+    credits: Option<&ContributionCounter>,
+    mut emit: F,
+) -> std::io::Result<usize>
+where
+    F: FnMut(&[u8], Tally) -> std::io::Result<()>,
+{
+    // The remaining in-memory lines of spilled shards become one more run so each shard merges in a single pass
+    for (shard, (store, runs)) in shards.iter_mut().enumerate() {
+        if runs.is_empty() {
+            continue;
+        }
+        if !store.is_empty() {
+            runs.push(watchdog.spill(shard, store)?);
+        }
+        watchdog.compact(shard, runs, max_open, store.is_tallied(), store.key())?;
+    }
+    if let Some(credits) = credits {
+        credits.reset();
+    }
+
+    let passed = match cap {
+        None => visit_records(&shards, credits, &mut emit)?,
+        Some(cap) if cap.order == CapOrder::Stable => {
+            let (mut passed, mut bytes, mut full) = (0, 0, false);
+            let unique = visit_records(&shards, credits, |line, tally| {
+                let size = line.len() as u64 + 1;
+                full = full || !cap.fits(passed + 1, bytes + size);
+                if full {
+                    return Ok(());
+                }
+                passed += 1;
+                bytes += size;
+                emit(line, tally)
+            })?;
+            println!("Kept the first {} of {} unique lines ({})", passed, unique, format_bytes(bytes));
+            passed
+        }
+        Some(cap) => {
+            // Lines and bytes per count, to find the cutoff without holding every tally
+            let mut counts: BTreeMap<u64, (usize, u64)> = BTreeMap::new();
+            let unique = visit_records(&shards, credits, |line, tally| {
+                let (lines, bytes) = counts.entry(tally.count).or_default();
+                *lines += 1;
+                *bytes += line.len() as u64 + 1;
+                Ok(())
+            })?;
+            let (mut above_lines, mut above_bytes, mut cutoff) = (0, 0, 0); // No count is cut when everything fits
+            for (&count, &(lines, bytes)) in counts.iter().rev() {
+                if !cap.fits(above_lines + lines, above_bytes + bytes) {
+                    cutoff = count;
+                    break;
+                }
+                above_lines += lines;
+                above_bytes += bytes;
+            }
+
+            // Lines at the cutoff fill what the lines above it leave, in output order
+            let (mut passed, mut bytes, mut at_cutoff, mut full) = (0, 0, (0, 0), false);
+            let mut lowest = u64::MAX;
+            visit_records(&shards, None, |line, tally| {
+                let size = line.len() as u64 + 1;
+                if tally.count == cutoff {
+                    full = full || !cap.fits(above_lines + at_cutoff.0 + 1, above_bytes + at_cutoff.1 + size);
+                    if full {
+                        return Ok(());
+                    }
+                    at_cutoff = (at_cutoff.0 + 1, at_cutoff.1 + size);
+                } else if tally.count < cutoff {
+                    return Ok(());
+                }
+                passed += 1;
+                bytes += size;
+                lowest = lowest.min(tally.count);
+                emit(line, tally)
+            })?;
+            println!(
+                "Kept the {} most frequent of {} unique lines ({}, occurring at least {} times)",
+                passed, unique, format_bytes(bytes), if passed == 0 { cutoff } else { lowest }
+            );
+            passed
+        }
+    };
+
+    for (_, runs) in &shards {
+        runs.iter().for_each(|run| spill::remove_run(run));
+    }
+    Ok(passed)
+}
+
+// Pass every unique line of every shard with its tally to `emit`, leaving the runs in place,
+// and credit it to its first input in `credits`; returns the unique count
+fn visit_records<F>(
+    shards: &[(LineStore, Vec<PathBuf>)],
+    credits: Option<&ContributionCounter>,
+    mut emit: F,
+) -> std::io::Result<usize>
+where
+    F: FnMut(&[u8], Tally) -> std::io::Result<()>,
+{
+    let mut emit = |line: &[u8], tally: Tally| {
+        if let Some(credits) = credits {
+            credits.add(tally.source, 1);
+        }
+        emit(line, tally)
+    };
+    let mut unique = 0;
+    for (store, runs) in shards {
+        if runs.is_empty() {
+            for (line, tally) in store.records() {
+                emit(line, tally)?;
+            }
+            unique += store.len();
+        } else {
+            unique += spill::merge_runs(runs, store.is_tallied(), store.key(), &mut emit)?;
+        }
+    }
+    Ok(unique)
+}
+
+// Pack lines into newline-terminated buffers of roughly OUTPUT_BUFFER_SIZE bytes for writing
+fn output_chunks(store: &LineStore) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let mut lines = store.iter().peekable();
+    std::iter::from_fn(move || {
+        lines.peek()?;
+        let mut buffer = Vec::with_capacity(OUTPUT_BUFFER_SIZE);
+        while buffer.len() < OUTPUT_BUFFER_SIZE {
+            let Some(line) = lines.next() else { break };
+            buffer.extend_from_slice(line);
+            buffer.push(b'\n');
+        }
+        Some(buffer)
+    })
+}
+
+pub async fn optimize_processing_order(files: Vec<(PathBuf, u64)>) -> Vec<PathBuf> {
     // Sort files by size in descending order for better memory utilization
     let mut sorted_files = files;
-    sorted_files.sort_by(|a, b| b.1.cmp(&a.1));
+    sorted_files.sort_by_key(|f| std::cmp::Reverse(f.1));
     
     // Group files by size ranges to process similar-sized files together
     let mut optimized = Vec::with_capacity(sorted_files.len());
@@ -369,4 +2015,141 @@ async fn optimize_processing_order(files: Vec<(PathBuf, u64)>) -> Vec<PathBuf> {
     optimized.extend(medium);
     optimized.extend(small);
     optimized
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{for_each_record, ProcessingCore};
+    use crate::app_state::AppState;
+    use crate::contribution::ContributionCounter;
+    use crate::input_format::InputFormat;
+    use crate::line_options::LineOptions;
+    use crate::line_store::{LineStore, Tally};
+    use crate::output_format::{OutputColumns, OutputFormat};
+    use crate::progress::Progress;
+    use crate::spill::SpillWatchdog;
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    // Checkpoint in `dir` of a merge of first.txt and second.txt interrupted once first.txt was
+    // done, whose snapshot holds `zero` instead of first.txt's line, so the test can tell which was read
+    fn interrupted_checkpoint(dir: &Path) -> Progress {
+        let (first, second) = (dir.join("first.txt"), dir.join("second.txt"));
+        std::fs::write(&first, "one\n").unwrap();
+        std::fs::write(&second, "two\n").unwrap();
+        std::fs::write(dir.join("list.txt"), format!("{}\n{}\n", first.display(), second.display())).unwrap();
+        std::fs::write(dir.join("progress.lines"), "zero\n").unwrap();
+        Progress {
+            input_file: dir.join("list.txt"),
+            output_file: dir.join("output.txt"),
+            threads: 1,
+            processed_files: vec![first],
+            save_path: Some(dir.join("progress.json")),
+            snapshot: Some(dir.join("progress.lines")),
+            snapshot_files: 1,
+            ..Progress::default()
+        }
+    }
+
+    async fn resume(dir: &Path, interrupt: bool) -> Progress {
+        interrupted_checkpoint(dir).save().await.unwrap();
+        let state = Arc::new(AppState::from_resume(dir.join("progress.json"), None, &[]).await.unwrap());
+        if interrupt {
+            state.request_shutdown().await;
+        }
+        let mut core = ProcessingCore::new(state, false, false).await.unwrap().with_temp_dir(dir.to_path_buf());
+        core.process().await.unwrap();
+        Progress::load(&dir.join("progress.json")).await.unwrap()
+    }
+
+    #[test]
+    fn spilled_lines_are_credited_once_to_their_first_input() {
+        let temp = tempfile::tempdir().unwrap();
+        let watchdog = SpillWatchdog::new(1, u64::MAX, true, temp.path().to_path_buf());
+        let hasher = RandomState::new();
+        let credits = ContributionCounter::new(2);
+        let mut store = LineStore::with_capacity(0).with_tally(true);
+        let insert = |store: &mut LineStore, line: &str, source: usize| {
+            if store.insert_tallied(hasher.hash_one(line.as_bytes()), line.as_bytes(), Tally::once(source)) {
+                credits.add(source, 1);
+            }
+        };
+
+        // Input 0 adds two lines, the shard spills, then input 1 repeats one and adds one
+        insert(&mut store, "alpha", 0);
+        insert(&mut store, "beta", 0);
+        let runs = vec![watchdog.spill(0, &mut store).unwrap()];
+        insert(&mut store, "alpha", 1);
+        insert(&mut store, "gamma", 1);
+        assert_eq!(credits.unique(1), 2); // Counted as inserted, "alpha" looks new again
+
+        let mut lines = Vec::new();
+        let unique = for_each_record(vec![(store, runs)], &watchdog, 16, None, Some(&credits), |line, _| {
+            lines.push(line.to_vec());
+            Ok(())
+        }).unwrap();
+        assert_eq!(unique, 3);
+        assert_eq!(lines, vec![b"alpha".to_vec(), b"beta".to_vec(), b"gamma".to_vec()]);
+        assert_eq!((credits.unique(0), credits.unique(1)), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn resume_reads_the_snapshot_instead_of_the_files_it_holds() {
+        let temp = tempfile::tempdir().unwrap();
+        resume(temp.path(), false).await;
+        let mut lines: Vec<String> = std::fs::read_to_string(temp.path().join("output.txt")).unwrap().lines().map(str::to_string).collect();
+        lines.sort();
+        assert_eq!(lines, ["two", "zero"]);
+    }
+
+    #[tokio::test]
+    async fn interrupted_resume_keeps_a_snapshot_it_did_not_read() {
+        let temp = tempfile::tempdir().unwrap();
+        let progress = resume(temp.path(), true).await;
+        assert_eq!(progress.snapshot_files, 1);
+        assert_eq!(std::fs::read_to_string(temp.path().join("progress.lines")).unwrap(), "zero\n");
+    }
+
+    #[tokio::test]
+    async fn seed_counts_add_to_the_inputs() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("output.txt"), "2\talpha\n1\tbeta\n").unwrap();
+        std::fs::write(dir.join("recovered.txt"), "alpha\ngamma\n").unwrap();
+        std::fs::write(dir.join("list.txt"), format!("{}\n", dir.join("recovered.txt").display())).unwrap();
+
+        let state = Arc::new(AppState::new(dir.join("list.txt"), dir.join("merged.txt"), 1).await.unwrap());
+        let columns = OutputColumns { frequency: true, provenance: false };
+        let mut core = ProcessingCore::new(state, false, false).await.unwrap()
+            .with_temp_dir(dir.to_path_buf())
+            .with_output_format(OutputFormat::Counted, columns)
+            .with_seed(dir.join("output.txt"), InputFormat::Counted);
+        core.process().await.unwrap();
+
+        let mut lines: Vec<String> = std::fs::read_to_string(dir.join("merged.txt")).unwrap().lines().map(str::to_string).collect();
+        lines.sort();
+        assert_eq!(lines, ["1\tbeta", "1\tgamma", "3\talpha"]);
+    }
+
+    #[tokio::test]
+    async fn counted_inputs_sum_their_counts() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("first.txt"), "  5 alpha\n  2 beta\n").unwrap();
+        std::fs::write(dir.join("second.txt"), "3\talpha\ngamma\n").unwrap();
+        std::fs::write(dir.join("list.txt"), format!("{}\n{}\n", dir.join("first.txt").display(), dir.join("second.txt").display())).unwrap();
+
+        let state = Arc::new(AppState::new(dir.join("list.txt"), dir.join("merged.txt"), 1).await.unwrap());
+        let columns = OutputColumns { frequency: true, provenance: false };
+        let mut core = ProcessingCore::new(state, false, false).await.unwrap()
+            .with_temp_dir(dir.to_path_buf())
+            .with_line_options(LineOptions { format: InputFormat::Counted, ..LineOptions::default() })
+            .with_output_format(OutputFormat::Counted, columns);
+        core.process().await.unwrap();
+
+        let mut lines: Vec<String> = std::fs::read_to_string(dir.join("merged.txt")).unwrap().lines().map(str::to_string).collect();
+        lines.sort();
+        assert_eq!(lines, ["1\tgamma", "2\tbeta", "8\talpha"]);
+    }
+}
@@ -0,0 +1,220 @@
+use clap::ValueEnum; // Import ValueEnum so the backend can be chosen with --dedup-backend
+use serde::{Deserialize, Serialize}; // Import serde traits so the backend can be set in the config
+use crate::estimate::{format_bytes, ResourceEstimate}; // Import the estimate the automatic choice is based on
+use crate::spill::SpillWatchdog; // Import SpillWatchdog for the default high-water mark
+
+const SMALL_SET_BYTES: u64 = 64 * 1024 * 1024; // Sets below this gain nothing from sharding
+const HEADROOM_FRACTION: f64 = 0.5; // Share of available memory an in-memory set may be predicted to use
+const DISK_KV_MEMORY_BYTES: u64 = 256 * 1024 * 1024; // Memory the disk-kv backend keeps before spilling
+const BLOOM_BITS_PER_LINE: f64 = 14.4; // Bits per line for a 0.1% false-positive rate
+const BLOOM_HASHES: u32 = 10; // Bit positions per line at that rate
+const BLOOM_MIN_BITS: u64 = 1 << 20; // Smallest filter, so tiny estimates still dedup well
+
+// How the set of lines seen so far is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DedupBackend {
+    #[default]
+    Auto,         // Pick one of the exact backends from the sampled inputs and available memory
+    Memory,       // One in-memory set; fastest for small merges, spills only when the system runs out of memory
+    Sharded,      // One in-memory set per worker, partitioned by hash; spills only when the system runs out of memory
+    ExternalSort, // Sharded sets that spill sorted runs to disk near the memory high-water mark
+    DiskKv,       // Sharded sets holding a small fixed budget in memory, the rest in sorted runs on disk
+    Bloom,        // Bloom filters instead of sets; streamed output, may drop about 0.1% of unique lines
+}
+
+// Backend a merge runs with, and why it was chosen
+#[derive(Debug, Clone)]
+pub struct BackendChoice {
+    pub backend: DedupBackend, // Never Auto
+    pub reason: String,        // Explanation printed when the backend was picked automatically
+}
+
+impl DedupBackend {
+    // Name used on the command line and in messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            DedupBackend::Auto => "auto",
+            DedupBackend::Memory => "memory",
+            DedupBackend::Sharded => "sharded",
+            DedupBackend::ExternalSort => "external-sort",
+            DedupBackend::DiskKv => "disk-kv",
+            DedupBackend::Bloom => "bloom",
+        }
+    }
+
+    // Whether the output has to be streamed, because the backend keeps no lines to write at the end
+    pub fn streams(&self) -> bool {
+        *self == DedupBackend::Bloom
+    }
+
+    // Shards to partition lines into, given the `workers` the merge would otherwise use
+    pub fn shards(&self, workers: usize) -> usize {
+        match self {
+            DedupBackend::Memory => 1,
+            _ => workers,
+        }
+    }
+
+    // Combined set footprint that triggers a spill, given the `default` derived from available memory
+    //
+    // In-memory backends have none, so only nearly exhausted system memory makes them spill.
+    pub fn high_water(&self, default: u64) -> u64 {
+        match self {
+            DedupBackend::Memory | DedupBackend::Sharded => u64::MAX,
+            DedupBackend::DiskKv => default.min(DISK_KV_MEMORY_BYTES),
+            _ => default,
+        }
+    }
+
+    // Bytes the set takes in memory at its largest, for the lines in `estimate`
+    pub fn set_bytes(&self, estimate: &ResourceEstimate) -> u64 {
+        match self {
+            DedupBackend::Bloom => BloomFilter::memory_for(estimate.estimated_lines),
+            DedupBackend::DiskKv => estimate.memory_bytes.min(DISK_KV_MEMORY_BYTES),
+            _ => estimate.memory_bytes,
+        }
+    }
+
+    // Bytes of spill runs written for the lines in `estimate`; runs hold at most the unique lines
+    pub fn spill_bytes(&self, estimate: &ResourceEstimate) -> u64 {
+        let outgrows = estimate.memory_bytes > self.high_water(SpillWatchdog::default_high_water());
+        if *self != DedupBackend::Bloom && outgrows {
+            estimate.disk_bytes
+        } else {
+            0
+        }
+    }
+
+    // Resolve Auto from `estimate`; any other backend is kept as requested
+    //
+    // Bloom and disk-kv are never picked automatically: one can lose lines and the other is
+    // slower than external-sort whenever memory is not deliberately held back.
+    pub fn resolve(self, estimate: &ResourceEstimate, stream_output: bool) -> BackendChoice {
+        if self != DedupBackend::Auto {
+            return BackendChoice { backend: self, reason: "chosen with --dedup-backend".to_string() };
+        }
+        let set = format_bytes(estimate.memory_bytes);
+        let (backend, reason) = match estimate.available_memory {
+            _ if stream_output => (
+                DedupBackend::Sharded,
+                "streamed output writes lines as they are found, so the set has to stay in memory".to_string(),
+            ),
+            None => (
+                DedupBackend::ExternalSort,
+                format!("available memory is unknown, so the predicted {} set may spill to disk", set),
+            ),
+            Some(available) if estimate.memory_bytes as f64 > available as f64 * HEADROOM_FRACTION => (
+                DedupBackend::ExternalSort,
+                format!("the predicted {} set could outgrow the {} available, so it spills to disk as needed", set, format_bytes(available)),
+            ),
+            Some(_) if estimate.memory_bytes < SMALL_SET_BYTES => (
+                DedupBackend::Memory,
+                format!("the predicted {} set is small enough for one set without sharding", set),
+            ),
+            Some(available) => (
+                DedupBackend::Sharded,
+                format!("the predicted {} set fits comfortably in the {} available", set, format_bytes(available)),
+            ),
+        };
+        BackendChoice { backend, reason }
+    }
+}
+
+// Bit array answering "seen before?" with no false negatives and rare false positives
+//
+// Uses double hashing: the two halves of the line's 64-bit hash give every bit position,
+// so no line is hashed twice.
+pub struct BloomFilter {
+    bits: Vec<u64>, // Bit array, 64 bits per word
+    len: u64,       // Number of bits
+}
+
+impl BloomFilter {
+    // Filter for about `lines` lines at a 0.1% false-positive rate
+    pub fn for_lines(lines: u64) -> Self {
+        let len = ((lines as f64 * BLOOM_BITS_PER_LINE) as u64).max(BLOOM_MIN_BITS);
+        Self { bits: vec![0; len.div_ceil(64) as usize], len }
+    }
+
+    // Bytes the filter for `lines` lines takes
+    pub fn memory_for(lines: u64) -> u64 {
+        ((lines as f64 * BLOOM_BITS_PER_LINE) as u64).max(BLOOM_MIN_BITS) / 8
+    }
+
+    // Record `hash`; true when it was not seen before
+    pub fn insert(&mut self, hash: u64) -> bool {
+        let (h1, h2) = (hash & 0xFFFF_FFFF, (hash >> 32) | 1);
+        let mut fresh = false;
+        for i in 0..BLOOM_HASHES as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.len;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                self.bits[word] |= mask;
+                fresh = true;
+            }
+        }
+        fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BloomFilter, DedupBackend, SMALL_SET_BYTES};
+    use crate::estimate::ResourceEstimate;
+    use std::time::Duration;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    // Estimate predicting a set of `memory_bytes` with `available_memory` free
+    fn estimate(memory_bytes: u64, available_memory: Option<u64>) -> ResourceEstimate {
+        ResourceEstimate {
+            files_total: 1,
+            files_missing: Vec::new(),
+            files_sampled: 1,
+            total_bytes: memory_bytes,
+            estimated_lines: 1000,
+            estimated_unique: 1000,
+            avg_line_bytes: 8.0,
+            memory_bytes,
+            disk_bytes: memory_bytes / 2,
+            temp_bytes: 0,
+            estimated_runtime: Duration::from_secs(1),
+            available_memory,
+        }
+    }
+
+    #[test]
+    fn auto_picks_from_the_predicted_set_and_free_memory() {
+        let pick = |memory, available, stream| DedupBackend::Auto.resolve(&estimate(memory, available), stream).backend;
+        assert_eq!(pick(SMALL_SET_BYTES / 2, Some(8 * GIB), false), DedupBackend::Memory);
+        assert_eq!(pick(GIB, Some(8 * GIB), false), DedupBackend::Sharded);
+        assert_eq!(pick(6 * GIB, Some(8 * GIB), false), DedupBackend::ExternalSort);
+        assert_eq!(pick(GIB, None, false), DedupBackend::ExternalSort);
+        assert_eq!(pick(6 * GIB, Some(8 * GIB), true), DedupBackend::Sharded);
+    }
+
+    #[test]
+    fn requested_backends_are_kept() {
+        let choice = DedupBackend::Bloom.resolve(&estimate(GIB, Some(8 * GIB)), false);
+        assert_eq!(choice.backend, DedupBackend::Bloom);
+        assert!(choice.reason.contains("--dedup-backend"));
+    }
+
+    #[test]
+    fn in_memory_backends_never_spill() {
+        assert_eq!(DedupBackend::Sharded.high_water(GIB), u64::MAX);
+        assert_eq!(DedupBackend::DiskKv.high_water(8 * GIB), 256 * 1024 * 1024);
+        assert_eq!(DedupBackend::ExternalSort.high_water(GIB), GIB);
+        assert_eq!((DedupBackend::Memory.shards(8), DedupBackend::Sharded.shards(8)), (1, 8));
+    }
+
+    #[test]
+    fn bloom_filters_report_repeats() {
+        let mut filter = BloomFilter::for_lines(100);
+        assert!(filter.insert(0x1234_5678_9abc_def0));
+        assert!(filter.insert(42));
+        assert!(!filter.insert(0x1234_5678_9abc_def0));
+        assert!(!filter.insert(42));
+    }
+}
@@ -0,0 +1,51 @@
+use clap::ValueEnum; // Import ValueEnum to choose a key on the command line
+use serde::{Deserialize, Serialize}; // Import serde traits for the config file
+
+pub const COMBO_SEPARATOR: u8 = b':'; // Separates the username from the password in combo lines
+
+// Part of a line that decides whether two lines are duplicates
+//
+// Combo lines are split at their first `:`, so passwords may contain colons.
+// Lines without one are keyed on the whole line in every mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupKey {
+    #[default]
+    Pair,     // The whole line: `alice:hunter2` and `bob:hunter2` are both kept
+    Password, // The part after the separator: only the first line with a given password is kept
+    Username, // The part before the separator: only the first line for a given username is kept
+}
+
+impl DedupKey {
+    // The bytes of `line` that identify it
+    pub fn key(self, line: &[u8]) -> &[u8] {
+        if self == DedupKey::Pair {
+            return line;
+        }
+        match memchr::memchr(COMBO_SEPARATOR, line) {
+            Some(split) if self == DedupKey::Username => &line[..split],
+            Some(split) => &line[split + 1..],
+            None => line,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DedupKey;
+
+    #[test]
+    fn combo_lines_split_at_the_first_separator() {
+        let line = b"alice:hunter2:extra";
+        assert_eq!(DedupKey::Pair.key(line), line);
+        assert_eq!(DedupKey::Username.key(line), b"alice");
+        assert_eq!(DedupKey::Password.key(line), b"hunter2:extra");
+    }
+
+    #[test]
+    fn lines_without_a_separator_key_on_themselves() {
+        assert_eq!(DedupKey::Username.key(b"hunter2"), b"hunter2");
+        assert_eq!(DedupKey::Password.key(b"hunter2"), b"hunter2");
+        assert_eq!(DedupKey::Password.key(b"alice:"), b"");
+    }
+}
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize}; // Import serde traits for the job and reply lines
+use sha2::{Digest, Sha256}; // Import SHA-256 to compare tokens in constant time
+use std::cmp::Reverse; // Import Reverse to place the largest inputs first
+use std::collections::HashMap; // Import HashMap for the input list options of a job
+use std::net::SocketAddr; // Import SocketAddr for the worker's listening address
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for inputs and intermediates
+use std::sync::Arc; // Import Arc to hand the worker's state to its core and share settings across jobs
+use std::time::Duration; // Import Duration for the job read timeout
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader}; // Import async I/O for the exchange
+use tokio::net::{TcpListener, TcpStream}; // Import TCP connections between coordinator and workers
+use crate::app_state::AppState; // Import AppState for the worker's merge
+use crate::core::ProcessingCore; // Import ProcessingCore, which merges a partition
+use crate::dedup_key::DedupKey; // Import DedupKey so workers deduplicate like the coordinator
+use crate::errors::{MergerError, MergerResult}; // Import error types for failed exchanges
+use crate::failed_files::FailedFile; // Import FailedFile to report skipped inputs back
+use crate::input_format::InputFormat; // Import InputFormat for the layout of the inputs
+use crate::input_list::EntryOptions; // Import EntryOptions for inputs with options of their own
+use crate::line_options::{BinaryMode, LineOptions, StripControl}; // Import the decode settings sent with a job
+use crate::output_format::{OutputColumns, OutputFormat}; // Import the formats of intermediates
+use crate::stages::StageDir; // Import StageDir for the worker's list and intermediate
+
+const MAX_LINE_BYTES: u64 = 64 * 1024 * 1024; // Longest job or reply line read, so a stray peer cannot exhaust memory
+const JOB_READ_TIMEOUT: Duration = Duration::from_secs(30); // Time a connection has to send its job line, so an idle peer cannot hold a task
+
+// Decode settings a worker applies while reading its inputs
+//
+// Filters, transforms, scripts and plugins are not sent: they work line by line, so the
+// coordinator runs them on the deduplicated intermediates with the same result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerOptions {
+    pub hex_invalid: bool,                   // Keep invalid UTF-8 as $HEX[...]
+    pub binary_mode: BinaryMode,             // Lines with NUL bytes kept as read or as $HEX[...]
+    pub strip_control: Option<StripControl>, // Control characters removed before deduplication
+    pub normalize_homoglyphs: bool,          // Fold look-alike characters to ASCII
+    pub format: InputFormat,                 // Layout of the input lines
+    pub json_field: Option<String>,          // Candidate field of JSON Lines inputs
+    pub dedup_key: DedupKey,                 // Part of each line that identifies it
+}
+
+impl WorkerOptions {
+    // The decode settings of `options`
+    pub fn from_line_options(options: &LineOptions) -> Self {
+        Self {
+            hex_invalid: options.hex_invalid,
+            binary_mode: options.binary_mode,
+            strip_control: options.strip_control,
+            normalize_homoglyphs: options.normalize_homoglyphs,
+            format: options.format,
+            json_field: options.json_field.as_deref().map(str::to_string),
+            dedup_key: options.dedup_key,
+        }
+    }
+
+    // Line options reading with these settings and nothing else
+    fn line_options(&self) -> LineOptions {
+        LineOptions {
+            hex_invalid: self.hex_invalid,
+            binary_mode: self.binary_mode,
+            strip_control: self.strip_control,
+            normalize_homoglyphs: self.normalize_homoglyphs,
+            format: self.format,
+            json_field: self.json_field.as_deref().map(Arc::from),
+            dedup_key: self.dedup_key,
+            ..LineOptions::default()
+        }
+    }
+}
+
+// One partition of a distributed merge, sent by the coordinator as a JSON line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerJob {
+    pub token: String,          // Shared secret the worker was started with
+    pub inputs: Vec<PathBuf>,   // Inputs to merge, as paths on the worker
+    pub options: WorkerOptions, // How the inputs are decoded
+    pub counted: bool,          // Send counted text, so occurrence counts survive
+    #[serde(default)]
+    pub entries: HashMap<PathBuf, String>, // Input list options of the inputs that have any, as written in the list
+}
+
+// Header of a worker's answer, sent as a JSON line; on success the intermediate follows
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkerReply {
+    pub error: Option<String>,   // Why the job failed; nothing follows
+    pub bytes: u64,              // Length of the intermediate that follows
+    pub failed: Vec<FailedFile>, // Inputs the worker skipped
+}
+
+// Split `files` into at most `parts` groups of about equal total size, placing the largest first
+pub fn partition(files: &[(PathBuf, u64)], parts: usize) -> Vec<Vec<PathBuf>> {
+    let mut sorted: Vec<&(PathBuf, u64)> = files.iter().collect();
+    sorted.sort_by_key(|(_, size)| Reverse(*size));
+    let mut groups: Vec<(u64, Vec<PathBuf>)> = vec![(0, Vec::new()); parts.max(1)];
+    for (path, size) in sorted {
+        let lightest = groups.iter_mut().min_by_key(|(total, _)| *total).expect("at least one group");
+        lightest.0 += size;
+        lightest.1.push(path.clone());
+    }
+    groups.into_iter().map(|(_, files)| files).filter(|files| !files.is_empty()).collect()
+}
+
+// Send `job` to the worker at `addr` and write the intermediate it returns to `output`,
+// returning the inputs the worker skipped
+pub async fn dispatch(addr: &str, job: &WorkerJob, output: &Path) -> MergerResult<Vec<FailedFile>> {
+    let failed = |detail: String| MergerError::Processing(format!("worker {}: {}", addr, detail));
+    let stream = TcpStream::connect(addr).await.map_err(|e| failed(e.to_string()))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut line = serde_json::to_vec(job).map_err(|e| failed(e.to_string()))?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    let mut reader = BufReader::new(reader);
+    let mut header = String::new();
+    (&mut reader).take(MAX_LINE_BYTES).read_line(&mut header).await?;
+    let reply: WorkerReply = serde_json::from_str(&header).map_err(|e| failed(format!("unreadable reply: {}", e)))?;
+    if let Some(error) = reply.error {
+        return Err(failed(error));
+    }
+
+    let mut file = tokio::fs::File::create(output).await?;
+    let copied = tokio::io::copy(&mut (&mut reader).take(reply.bytes), &mut file).await?;
+    file.flush().await?;
+    if copied != reply.bytes {
+        return Err(failed(format!("connection closed after {} of {} bytes", copied, reply.bytes)));
+    }
+    Ok(reply.failed)
+}
+
+// Serve merge jobs on `addr` until the process is stopped, each connection in its own task
+//
+// Inputs are read from the paths the coordinator sends, so every worker needs the corpus at
+// the same paths, such as on shared storage. Jobs without `token` are refused. Jobs and the
+// token travel as plaintext JSON, so the port belongs on a trusted network or a tunnel.
+pub async fn serve(addr: SocketAddr, token: String, temp_dir: PathBuf, threads: usize) -> MergerResult<()> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("Worker listening on {}", listener.local_addr()?);
+    let token: Arc<str> = token.into();
+    let temp_dir = Arc::new(temp_dir);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let (token, temp_dir) = (token.clone(), temp_dir.clone());
+        tokio::spawn(async move {
+            match handle_job(stream, &token, &temp_dir, threads).await {
+                Ok(inputs) => println!("Sent the merge of {} inputs to {}", inputs, peer),
+                Err(e) => log::warn!("Job from {} failed: {}", peer, e),
+            }
+        });
+    }
+}
+
+// Whether `given` equals `expected`, taking the same time wherever they differ
+//
+// Both are hashed first so neither the length nor the position of the first difference shows.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    let (given, expected) = (Sha256::digest(given.as_bytes()), Sha256::digest(expected.as_bytes()));
+    given.iter().zip(expected.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// Read one job from `stream`, merge it and send back the intermediate, returning the input count
+async fn handle_job(stream: TcpStream, token: &str, temp_dir: &Path, threads: usize) -> MergerResult<usize> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    let mut reader = BufReader::new(reader).take(MAX_LINE_BYTES);
+    match tokio::time::timeout(JOB_READ_TIMEOUT, reader.read_line(&mut line)).await {
+        Ok(read) => read?,
+        Err(_) => return refuse(&mut writer, format!("no job within {} seconds", JOB_READ_TIMEOUT.as_secs())).await,
+    };
+    let job: WorkerJob = match serde_json::from_str(&line) {
+        Ok(job) => job,
+        Err(e) => return refuse(&mut writer, format!("unreadable job: {}", e)).await,
+    };
+    if !tokens_match(&job.token, token) {
+        return refuse(&mut writer, "wrong token".to_string()).await;
+    }
+
+    let mut entries = HashMap::with_capacity(job.entries.len());
+    for (input, fields) in &job.entries {
+        match EntryOptions::parse(fields.split('\t')) {
+            Ok(options) => entries.insert(input.clone(), options),
+            Err(e) => return refuse(&mut writer, format!("options of {}: {}", input.display(), e)).await,
+        };
+    }
+
+    println!("Merging {} inputs", job.inputs.len());
+    let (dir, failed) = match merge_job(&job, entries, temp_dir, threads).await {
+        Ok(merged) => merged,
+        Err(e) => return refuse(&mut writer, e.to_string()).await,
+    };
+    let output = dir.intermediate(0, 0);
+    let reply = WorkerReply { error: None, bytes: tokio::fs::metadata(&output).await?.len(), failed };
+    send_reply(&mut writer, &reply).await?;
+    tokio::io::copy(&mut tokio::fs::File::open(&output).await?, &mut writer).await?;
+    writer.shutdown().await?;
+    Ok(job.inputs.len())
+}
+
+// Merge the inputs of `job` into an intermediate in a new directory under `temp_dir`
+async fn merge_job(
+    job: &WorkerJob,
+    entries: HashMap<PathBuf, EntryOptions>,
+    temp_dir: &Path,
+    threads: usize,
+) -> MergerResult<(StageDir, Vec<FailedFile>)> {
+    let dir = StageDir::create(temp_dir)?;
+    let (list, output) = dir.group(0, 0, &job.inputs)?;
+    let state = AppState::new(list, output, threads).await.map_err(|e| MergerError::Processing(e.to_string()))?;
+    let format = if job.counted { OutputFormat::Counted } else { OutputFormat::Text };
+    let mut core = ProcessingCore::new(Arc::new(state), false, false).await?
+        .with_temp_dir(temp_dir.to_path_buf())
+        .with_line_options(job.options.line_options())
+        .with_entry_options(entries)
+        .with_output_format(format, OutputColumns { frequency: job.counted, provenance: false });
+    core.process().await?;
+    let failed = core.failed_files().files.clone();
+    Ok((dir, failed))
+}
+
+// Answer with `error` instead of an intermediate
+async fn refuse<T>(writer: &mut tokio::net::tcp::OwnedWriteHalf, error: String) -> MergerResult<T> {
+    send_reply(writer, &WorkerReply { error: Some(error.clone()), ..WorkerReply::default() }).await?;
+    Err(MergerError::Processing(error))
+}
+
+async fn send_reply(writer: &mut tokio::net::tcp::OwnedWriteHalf, reply: &WorkerReply) -> MergerResult<()> {
+    let mut line = serde_json::to_vec(reply)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dispatch, handle_job, tokens_match, WorkerJob, WorkerOptions};
+    use crate::line_options::LineOptions;
+    use std::path::{Path, PathBuf};
+    use tokio::net::TcpListener;
+
+    // Serve one connection with `token` and dispatch a job for `inputs` carrying `sent`
+    async fn exchange(token: &'static str, sent: &str, inputs: Vec<PathBuf>, temp: &Path) -> crate::errors::MergerResult<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let temp_dir = temp.to_path_buf();
+        let worker = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = handle_job(stream, token, &temp_dir, 1).await;
+        });
+        let job = WorkerJob {
+            token: sent.to_string(),
+            inputs,
+            options: WorkerOptions::from_line_options(&LineOptions::default()),
+            counted: false,
+            entries: Default::default(),
+        };
+        let output = temp.join("intermediate.txt");
+        let result = dispatch(&addr, &job, &output).await;
+        worker.await.unwrap();
+        result.map(|_| std::fs::read_to_string(&output).unwrap())
+    }
+
+    #[test]
+    fn tokens_match_only_when_equal() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret-and-more", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn jobs_with_the_token_are_merged() {
+        let temp = tempfile::tempdir().unwrap();
+        let words = temp.path().join("words.txt");
+        std::fs::write(&words, b"alpha\nbravo\nalpha\n").unwrap();
+
+        let merged = exchange("secret", "secret", vec![words], temp.path()).await.unwrap();
+        let mut lines: Vec<&str> = merged.lines().collect();
+        lines.sort_unstable();
+        assert_eq!(lines, ["alpha", "bravo"]);
+    }
+
+    #[tokio::test]
+    async fn jobs_with_another_token_are_refused() {
+        let temp = tempfile::tempdir().unwrap();
+        let error = exchange("secret", "guess", Vec::new(), temp.path()).await.unwrap_err();
+        assert!(error.to_string().contains("wrong token"), "{}", error);
+    }
+}
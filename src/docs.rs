@@ -0,0 +1,101 @@
+use anyhow::Result; // Import Result type from anyhow for error handling
+use clap::{Command, CommandFactory}; // Import clap types to introspect the CLI definition
+use clap_mangen::Man; // Import the man page renderer
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for file path handling
+use crate::cli::Cli; // Import the Cli struct so docs always match the binary
+
+// Name of the binary used as the prefix for generated pages
+const BIN_NAME: &str = "rustmerger";
+
+// Struct to generate documentation from the clap definitions
+pub struct DocsGenerator;
+
+impl DocsGenerator {
+    // Write a man page for the binary and one for each subcommand
+    pub async fn write_man_pages(output_dir: &Path) -> Result<Vec<PathBuf>> {
+        tokio::fs::create_dir_all(output_dir).await?;
+
+        let mut cmd = Self::command();
+        let mut written = Vec::new();
+
+        // Render the top-level page first (rustmerger.1)
+        written.push(Self::render_man(&cmd, BIN_NAME, output_dir).await?);
+
+        // Render one page per subcommand (rustmerger-merge.1, ...)
+        for sub in cmd.get_subcommands_mut().filter(|s| s.get_name() != "help") {
+            let name = format!("{}-{}", BIN_NAME, sub.get_name());
+            written.push(Self::render_man(sub, &name, output_dir).await?);
+        }
+
+        Ok(written)
+    }
+
+    // Write a markdown CLI reference containing the help text of every command
+    pub async fn write_markdown(output_dir: &Path) -> Result<PathBuf> {
+        tokio::fs::create_dir_all(output_dir).await?;
+
+        let mut cmd = Self::command();
+        let mut markdown = String::new();
+
+        markdown.push_str(&format!("# {} Command Reference\n\n", BIN_NAME));
+        if let Some(about) = cmd.get_about() {
+            markdown.push_str(&format!("{}\n\n", about));
+        }
+        markdown.push_str(&format!("```\n{}\n```\n", cmd.render_long_help()));
+
+        // Append a section per subcommand, mirroring the README layout
+        for sub in cmd.get_subcommands_mut().filter(|s| s.get_name() != "help") {
+            markdown.push_str(&format!("\n## {}\n\n", sub.get_name()));
+            if let Some(about) = sub.get_about() {
+                markdown.push_str(&format!("{}\n\n", about));
+            }
+            markdown.push_str(&format!("```\n{}\n```\n", sub.render_long_help()));
+        }
+
+        let path = output_dir.join(format!("{}.md", BIN_NAME));
+        tokio::fs::write(&path, markdown).await?;
+        Ok(path)
+    }
+
+    // Build the fully-resolved clap command (global args propagated to subcommands)
+    fn command() -> Command {
+        let mut cmd = Cli::command().bin_name(BIN_NAME);
+        cmd.build();
+        cmd
+    }
+
+    // Render a single command to <output_dir>/<name>.1
+    async fn render_man(cmd: &Command, name: &str, output_dir: &Path) -> Result<PathBuf> {
+        let page = cmd.clone().display_name(name.to_string());
+        let mut buffer: Vec<u8> = Vec::new();
+        Man::new(page).render(&mut buffer)?;
+
+        let path = output_dir.join(format!("{}.1", name));
+        tokio::fs::write(&path, buffer).await?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DocsGenerator;
+
+    #[tokio::test]
+    async fn man_pages_cover_every_subcommand() {
+        let temp = tempfile::tempdir().unwrap();
+        let written = DocsGenerator::write_man_pages(temp.path()).await.unwrap();
+        let names: Vec<_> = written.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names[0], "rustmerger.1");
+        assert!(names.contains(&"rustmerger-merge.1".to_string()));
+        assert!(!names.contains(&"rustmerger-help.1".to_string()));
+        assert_eq!(names.len(), DocsGenerator::command().get_subcommands().filter(|sub| sub.get_name() != "help").count() + 1);
+    }
+
+    #[tokio::test]
+    async fn the_markdown_reference_has_a_section_per_subcommand() {
+        let temp = tempfile::tempdir().unwrap();
+        let markdown = std::fs::read_to_string(DocsGenerator::write_markdown(temp.path()).await.unwrap()).unwrap();
+        assert!(markdown.starts_with("# rustmerger Command Reference"));
+        assert!(markdown.contains("\n## merge\n") && markdown.contains("--wordlists-file"));
+    }
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Local}; // Import chrono to record when a copy was downloaded
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED}; // Import the revalidation headers
+use reqwest::StatusCode; // Import StatusCode to recognize an unchanged resource
+use serde::{Deserialize, Serialize}; // Import serde traits for the cache index
+use sha2::{Digest, Sha256}; // Import SHA-256 for cache keys
+use std::io::Write; // Import Write to fill the temporary index record
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for list entries and cached copies
+use url::Url; // Import Url to name cached copies after the file they came from
+use crate::errors::{MergerError, MergerResult}; // Import the error types for failed downloads
+use crate::fetch; // Import fetch for the HTTP client and the per-user cache directory
+use crate::manifest; // Import manifest to hash cached copies when revalidating them
+
+const DOWNLOADS_DIR: &str = "downloads"; // Directory under the per-user cache directory
+const INDEX_DIR: &str = "urls"; // Directory of the index records inside the cache
+
+// Whether a list entry names a file to download rather than a local path
+pub fn is_url(entry: &Path) -> bool {
+    entry.to_str().is_some_and(|entry| entry.starts_with("http://") || entry.starts_with("https://"))
+}
+
+// What is known about the cached copy of one URL, kept as JSON in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheRecord {
+    url: String,                   // Address the copy was downloaded from
+    etag: Option<String>,          // ETag the server sent with it
+    last_modified: Option<String>, // Last-Modified the server sent with it
+    path: PathBuf,                 // Copy, relative to the cache directory
+    bytes: u64,                    // Its length
+    sha256: String,                // Hex-encoded SHA-256 of its contents
+    fetched_at: DateTime<Local>,   // When it was downloaded
+}
+
+// Content-addressed cache of downloaded inputs
+//
+// Each copy is stored under the SHA-256 of its URL and the ETag (or Last-Modified) the server
+// sent, so a changed resource gets a new entry instead of being overwritten halfway through a
+// merge. An index record per URL points at the current copy.
+#[derive(Debug, Clone)]
+pub struct DownloadCache {
+    dir: PathBuf,  // Root of the cache
+    refresh: bool, // Ask the server whether cached copies changed instead of trusting them
+}
+
+impl DownloadCache {
+    // Cache in `dir`; with `refresh`, cached copies are revalidated with their servers
+    pub fn new(dir: PathBuf, refresh: bool) -> Self {
+        Self { dir, refresh }
+    }
+
+    // Default cache directory: `downloads` in the per-user cache directory
+    pub fn default_dir() -> Option<PathBuf> {
+        fetch::user_cache_dir().map(|dir| dir.join(DOWNLOADS_DIR))
+    }
+
+    // Local copy of `url`, downloading it unless a complete copy is cached
+    //
+    // With `refresh` the server is asked whether the cached copy changed, with If-None-Match and
+    // If-Modified-Since. An unchanged copy is checked against its recorded SHA-256 and downloaded
+    // again only if it no longer matches.
+    pub async fn get(&self, url: &str) -> MergerResult<PathBuf> {
+        let record = self.record(url).await;
+        let cached = match &record {
+            Some(record) => self.intact(record).await,
+            None => None,
+        };
+        if let (Some(path), false) = (&cached, self.refresh) {
+            return Ok(path.clone());
+        }
+
+        let client = fetch::client()?;
+        let send = |conditional: Option<&CacheRecord>| {
+            let mut request = client.get(url);
+            if let Some(record) = conditional {
+                if let Some(etag) = &record.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(modified) = &record.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, modified);
+                }
+            }
+            async move {
+                request.send().await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|e| fetch::download_error(url, e))
+            }
+        };
+
+        let mut response = send(record.as_ref().filter(|_| cached.is_some())).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let (Some(record), Some(path)) = (&record, cached) {
+                let actual = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || manifest::sha256_file(&path)
+                }).await??;
+                if actual == record.sha256 {
+                    log::info!("{} is unchanged since {}", url, record.fetched_at.format("%Y-%m-%d %H:%M"));
+                    return Ok(path);
+                }
+                log::warn!("Cached copy of {} does not match its checksum; downloading it again", url);
+            }
+            response = send(None).await?;
+        }
+        self.store(url, response, record).await
+    }
+
+    // Save the body of `response` as the copy of `url`, replacing the `previous` copy
+    async fn store(&self, url: &str, response: reqwest::Response, previous: Option<CacheRecord>) -> MergerResult<PathBuf> {
+        let header = |name: HeaderName| response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let key = content_key(url, etag.as_deref().or(last_modified.as_deref()));
+        let dir = self.dir.join(&key);
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let (temp, sha256) = fetch::save_body(response, &dir).await?;
+        let name = file_name(url);
+        let path = dir.join(&name);
+        temp.persist(&path).map_err(|e| e.error)?;
+        let record = CacheRecord {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            path: Path::new(&key).join(&name),
+            bytes: tokio::fs::metadata(&path).await?.len(),
+            sha256,
+            fetched_at: Local::now(),
+        };
+        let index = self.index_path(url);
+        if let Some(parent) = index.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let json = serde_json::to_vec_pretty(&record).map_err(|e| MergerError::Processing(e.to_string()))?;
+        tokio::task::spawn_blocking(move || write_atomic(&index, &json)).await??;
+
+        // The copy the server replaced is never read again
+        if let Some(previous) = previous.filter(|previous| previous.path.parent() != record.path.parent()) {
+            if let Some(old) = previous.path.parent() {
+                let _ = tokio::fs::remove_dir_all(self.dir.join(old)).await;
+            }
+        }
+        Ok(path)
+    }
+
+    // Index record of `url`, if it was downloaded before and the record can be read
+    async fn record(&self, url: &str) -> Option<CacheRecord> {
+        let content = tokio::fs::read(self.index_path(url)).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    // The copy `record` points at, if it is there at its recorded length
+    async fn intact(&self, record: &CacheRecord) -> Option<PathBuf> {
+        let path = self.dir.join(&record.path);
+        let metadata = tokio::fs::metadata(&path).await.ok()?;
+        (metadata.len() == record.bytes).then_some(path)
+    }
+
+    fn index_path(&self, url: &str) -> PathBuf {
+        self.dir.join(INDEX_DIR).join(format!("{}.json", hex::encode(Sha256::digest(url.as_bytes()))))
+    }
+}
+
+// Replace `path` with `contents` through a temporary file beside it, so readers never see a partial record
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(contents)?;
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+// Cache key of the copy of `url` the server identified with `validator`
+fn content_key(url: &str, validator: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    hasher.update([0]);
+    hasher.update(validator.unwrap_or_default().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// Name for the copy of `url`: the last segment of its path, so the extension still picks the input format
+fn file_name(url: &str) -> String {
+    Url::parse(url).ok()
+        .and_then(|url| url.path_segments().and_then(|mut segments| segments.next_back().map(str::to_string)))
+        .filter(|name| !name.is_empty() && name != "." && name != "..")
+        .unwrap_or_else(|| "download".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_key, file_name, write_atomic};
+
+    #[test]
+    fn index_records_are_replaced_whole() {
+        let temp = tempfile::tempdir().unwrap();
+        let index = temp.path().join("record.json");
+        write_atomic(&index, b"{\"first\": true, \"padding\": \"longer than the second\"}").unwrap();
+        write_atomic(&index, b"{\"second\": true}").unwrap();
+        assert_eq!(std::fs::read(&index).unwrap(), b"{\"second\": true}");
+        // No temporary file is left beside the record
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn a_new_validator_gets_a_new_key() {
+        let url = "https://example.com/lists/rockyou.txt";
+        assert_eq!(content_key(url, Some("\"v1\"")), content_key(url, Some("\"v1\"")));
+        assert_ne!(content_key(url, Some("\"v1\"")), content_key(url, Some("\"v2\"")));
+    }
+
+    #[test]
+    fn copies_are_named_after_the_last_path_segment() {
+        assert_eq!(file_name("https://example.com/lists/rockyou.txt.gz?raw=1"), "rockyou.txt.gz");
+        assert_eq!(file_name("https://example.com/"), "download");
+    }
+}
@@ -0,0 +1,167 @@
+use crate::cli::MergeArgs; // Import MergeArgs for the command-line options of the run
+use crate::config::Config; // Import Config, which the options are folded into
+
+// Fold the command-line options of a merge into `config`, preferring them over its keys
+//
+// The merge reads its options from the result, which is also the config a later
+// `merge --config` needs to repeat the run without the options. Options without a config
+// key, such as --session or --report, are left out.
+pub fn apply_args(config: &mut Config, args: &MergeArgs, verbose: bool) {
+    config.input_files = args.wordlists_file.clone().or(config.input_files.take());
+    config.follow_symlinks |= args.follow_symlinks;
+    config.no_recurse |= args.no_recurse;
+    if !args.extensions.is_empty() {
+        config.extensions = args.extensions.clone();
+    }
+    config.skip_larger_than = args.skip_larger_than.map(|bytes| bytes as u64).or(config.skip_larger_than);
+    config.no_content_sniff |= args.no_content_sniff;
+    config.download_dir = args.download_dir.clone().or(config.download_dir.take());
+    config.output_files = args.output_wordlist.clone().or(config.output_files.take());
+    config.verbose |= verbose;
+    config.debug |= args.debug;
+    config.stream_output |= args.stream_output;
+    config.io_backend = args.io_backend.unwrap_or(config.io_backend);
+    config.dedup_backend = args.dedup_backend.unwrap_or(config.dedup_backend);
+
+    let tuning = &mut config.tuning;
+    tuning.chunk_size = args.chunk_size.unwrap_or(tuning.chunk_size);
+    tuning.buffer_size = args.buffer_size.unwrap_or(tuning.buffer_size);
+    tuning.channel_size = args.channel_size.unwrap_or(tuning.channel_size);
+    tuning.parallel_files = args.parallel_files.unwrap_or(tuning.parallel_files);
+    tuning.read_concurrency = args.read_concurrency.unwrap_or(tuning.read_concurrency);
+    tuning.cpu_workers = args.cpu_workers.or(tuning.cpu_workers);
+    config.max_read_mbps = args.max_read_mbps.or(config.max_read_mbps);
+    config.max_write_mbps = args.max_write_mbps.or(config.max_write_mbps);
+    config.nice = args.nice.or(config.nice);
+    config.ionice = args.ionice.map(|ionice| ionice.to_string()).or(config.ionice.take());
+    config.temp_dir = args.temp_dir.clone().or(config.temp_dir.take());
+    config.stages = args.stages.or(config.stages);
+    if !args.workers.is_empty() {
+        config.workers = args.workers.clone();
+    }
+    config.worker_token = args.worker_token.clone().or(config.worker_token.take());
+
+    config.retry.max_retries = args.max_retries.unwrap_or(config.retry.max_retries);
+    config.retry.initial_backoff_ms = args.retry_backoff_ms.unwrap_or(config.retry.initial_backoff_ms);
+    config.strict |= args.strict;
+    config.failure_limits.max_files = args.max_failed_files.or(config.failure_limits.max_files);
+    config.failure_limits.max_percent = args.max_failed_percent.or(config.failure_limits.max_percent);
+
+    config.rule_comments = args.rule_comments.unwrap_or(config.rule_comments);
+    config.max_rule_ops = args.max_rule_ops.or(config.max_rule_ops);
+    if !args.reject_rule_functions.is_empty() {
+        config.reject_rule_functions = args.reject_rule_functions.iter().map(|function| char::from(*function).to_string()).collect();
+    }
+    config.rule_ignore_whitespace |= args.rule_ignore_whitespace;
+
+    config.web_ui = args.web_ui.or(config.web_ui);
+    config.tui |= args.tui;
+    config.notify_webhook = args.notify_webhook.as_ref().map(|url| url.to_string()).or(config.notify_webhook.take());
+    config.no_history |= args.no_history;
+    config.checkpoint_root = args.checkpoint_root.clone().or(config.checkpoint_root.take());
+
+    config.hex_invalid |= args.hex_invalid;
+    config.binary_mode = args.binary_mode.unwrap_or(config.binary_mode);
+    config.strip_control = args.strip_control.or(config.strip_control);
+    config.normalize_homoglyphs |= args.normalize_homoglyphs;
+    config.input_format = args.input_format.unwrap_or(config.input_format);
+    config.json_field = args.json_field.clone().or(config.json_field.take());
+    config.dedup_key = args.dedup_key.unwrap_or(config.dedup_key);
+
+    config.min_strength = args.min_strength.or(config.min_strength);
+    config.max_strength = args.max_strength.or(config.max_strength);
+    if !args.transforms.is_empty() {
+        config.transforms = args.transforms.clone();
+    }
+    config.emit_reversed |= args.emit_reversed;
+    config.append_years = args.append_years.map(|years| format!("{}-{}", years.first, years.last)).or(config.append_years.take());
+    if !args.append_strings.is_empty() {
+        config.append_strings = args.append_strings.clone();
+    }
+    if !args.templates.is_empty() {
+        config.templates = args.templates.clone();
+    }
+    config.exclude_walks |= args.exclude_walks;
+    config.route_walks = args.route_walks.clone().or(config.route_walks.take());
+    config.route_numeric = args.route_numeric.clone().or(config.route_numeric.take());
+    if !args.numeric_lengths.is_empty() {
+        config.numeric_lengths = args.numeric_lengths.iter().map(|lengths| format!("{}-{}", lengths.min, lengths.max)).collect();
+    }
+    if !args.plugins.is_empty() {
+        config.plugins = args.plugins.clone();
+    }
+    config.script = args.script.clone().or(config.script.take());
+
+    if !args.tee.is_empty() {
+        config.tee = args.tee.iter().map(ToString::to_string).collect();
+    }
+    config.base_words = args.base_words.clone().or(config.base_words.take());
+    config.target_lines = args.target_lines.or(config.target_lines);
+    config.max_output_lines = args.max_output_lines.or(config.max_output_lines);
+    config.max_output_bytes = args.max_output_bytes.map(|bytes| bytes as u64).or(config.max_output_bytes);
+    config.cap_order = args.cap_order.or(config.cap_order);
+    config.split_by_language |= args.split_by_language;
+    if !args.languages.is_empty() {
+        config.languages = args.languages.clone();
+    }
+    config.language_confidence = args.language_confidence.or(config.language_confidence);
+    config.output_encoding = args.output_encoding.map(|encoding| encoding.name().to_string()).or(config.output_encoding.take());
+    config.bom = if args.bom { Some(true) } else if args.no_bom { Some(false) } else { config.bom };
+    config.output_format = args.output_format.unwrap_or(config.output_format);
+    config.output_columns.frequency |= args.with_frequency;
+    config.output_columns.provenance |= args.with_provenance;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_args;
+    use crate::cli::MergeArgs;
+    use crate::config::Config;
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    // Config as loaded from a file that sets a few keys
+    fn loaded() -> Config {
+        let mut config = Config {
+            input_files: Some(PathBuf::from("from-config.txt")),
+            extensions: vec!["lst".to_string()],
+            verbose: false,
+            bom: Some(true),
+            ..Config::default()
+        };
+        config.tuning.chunk_size = 4096;
+        config.retry.max_retries = 7;
+        config
+    }
+
+    // Fold the merge options `args` into the loaded config
+    fn resolve(args: &[&str], verbose: bool) -> Config {
+        let mut config = loaded();
+        let args = MergeArgs::try_parse_from(std::iter::once("merge").chain(args.iter().copied())).unwrap();
+        apply_args(&mut config, &args, verbose);
+        config
+    }
+
+    #[test]
+    fn options_not_given_keep_the_config_keys() {
+        let config = resolve(&[], false);
+        assert_eq!(config.input_files, Some(PathBuf::from("from-config.txt")));
+        assert_eq!(config.extensions, ["lst"]);
+        assert_eq!((config.tuning.chunk_size, config.retry.max_retries), (4096, 7));
+        assert_eq!(config.bom, Some(true));
+        assert!(!config.verbose && !config.strict);
+    }
+
+    #[test]
+    fn options_given_replace_the_config_keys() {
+        let config = resolve(&[
+            "--wordlists-file", "from-args.txt", "--extensions", "txt,dic", "--chunk-size", "8192",
+            "--max-retries", "2", "--no-bom", "--strict",
+        ], true);
+        assert_eq!(config.input_files, Some(PathBuf::from("from-args.txt")));
+        assert_eq!(config.extensions, ["txt", "dic"]);
+        assert_eq!((config.tuning.chunk_size, config.retry.max_retries), (8192, 2));
+        assert_eq!(config.bom, Some(false));
+        assert!(config.verbose && config.strict);
+    }
+}
@@ -0,0 +1,379 @@
+use encoding_rs::{DecoderResult, Encoding, EncoderResult, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252}; // Import encoding_rs for decoding and encoding
+use serde::Serialize; // Import Serialize to include the run's encodings in the report
+use std::collections::BTreeMap; // Import BTreeMap to count inputs per encoding in a stable order
+use std::io::{BufWriter, Read, Write}; // Import I/O traits for streaming conversion
+use std::path::Path; // Import Path for input and output files
+use crate::line_store::Tally; // Import Tally, passed along with each output line
+use crate::output_format::RecordSink; // Import RecordSink, implemented by the encoded output
+
+pub const DETECT_SAMPLE_BYTES: usize = 64 * 1024; // Bytes sampled to detect an encoding
+const CONVERT_BUFFER_SIZE: usize = 1024 * 1024; // Bytes read per conversion step
+const UNMAPPABLE_REPLACEMENT: &str = "?"; // Written for characters the target encoding lacks
+const UNMAPPABLE_EXAMPLES: usize = 5; // Left-out output lines named in the summary
+
+// Parse an encoding label such as `utf-8`, `windows-1252`, `latin1` or `utf-16le`
+pub fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("unknown encoding `{}`", label))
+}
+
+// Encoding to read input as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceEncoding {
+    Auto,                     // Detect per file from a sample
+    Fixed(&'static Encoding), // Always use this encoding
+}
+
+// Parse `auto` or an encoding label
+pub fn parse_source_encoding(label: &str) -> Result<SourceEncoding, String> {
+    if label.eq_ignore_ascii_case("auto") {
+        Ok(SourceEncoding::Auto)
+    } else {
+        parse_encoding(label).map(SourceEncoding::Fixed)
+    }
+}
+
+// Guess the encoding of a sample from the start of a file
+//
+// A BOM wins; otherwise a NUL in most odd or even byte positions is BOM-less UTF-16,
+// valid UTF-8 is UTF-8, and anything else is taken to be Windows-1252, the usual legacy
+// encoding of Western wordlists.
+pub fn detect(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+
+    // NULs are valid UTF-8, so BOM-less UTF-16 has to be recognized first
+    let pairs = sample.len() / 2;
+    if pairs > 0 {
+        let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+        let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+        if odd_nuls * 2 > pairs && even_nuls * 10 < pairs {
+            return UTF_16LE;
+        }
+        if even_nuls * 2 > pairs && odd_nuls * 10 < pairs {
+            return UTF_16BE;
+        }
+    }
+
+    // A sample cut mid-character is still UTF-8 as long as only the tail is incomplete
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+// First DETECT_SAMPLE_BYTES of an input, gathered as its lines stream past
+//
+// Lets a reader label the input's encoding from the one read pass it already makes.
+#[derive(Debug, Default)]
+pub struct EncodingSample {
+    bytes: Vec<u8>, // Sampled bytes, at most DETECT_SAMPLE_BYTES
+}
+
+impl EncodingSample {
+    // Append `bytes` as read, until the sample is full
+    pub fn push(&mut self, bytes: &[u8]) {
+        let room = DETECT_SAMPLE_BYTES - self.bytes.len();
+        self.bytes.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    // Whether enough has been sampled to detect the encoding
+    pub fn is_full(&self) -> bool {
+        self.bytes.len() >= DETECT_SAMPLE_BYTES
+    }
+
+    // Name of the encoding detected from the sample
+    pub fn detect(&self) -> &'static str {
+        detect(&self.bytes).name()
+    }
+}
+
+// Character-level outcome of converting text
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodingStats {
+    pub files: usize,       // Files converted
+    pub bytes_in: u64,      // Bytes read
+    pub bytes_out: u64,     // Bytes written
+    pub malformed: u64,     // Invalid input sequences replaced with U+FFFD
+    pub unmappable: u64,    // Characters the target encoding cannot represent, written as `?`
+}
+
+impl EncodingStats {
+    // Add the counts of another conversion
+    pub fn merge(&mut self, other: &EncodingStats) {
+        self.files += other.files;
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+        self.malformed += other.malformed;
+        self.unmappable += other.unmappable;
+    }
+}
+
+// Encodings of a merge's inputs and the lines that were not valid UTF-8, reported once at the end
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EncodingSummary {
+    pub files: BTreeMap<String, usize>, // Inputs per detected encoding
+    pub invalid_lines: u64,             // Lines dropped because they are not valid UTF-8
+    pub invalid_files: usize,           // Inputs that had such lines dropped
+    pub hex_encoded: u64,               // Lines that are not valid UTF-8, kept as `$HEX[...]`
+}
+
+impl EncodingSummary {
+    // Count one input detected as `encoding`, with its dropped and hex-encoded lines
+    pub fn add(&mut self, encoding: &str, invalid_lines: usize, hex_encoded: usize) {
+        *self.files.entry(encoding.to_string()).or_default() += 1;
+        self.invalid_lines += invalid_lines as u64;
+        self.invalid_files += usize::from(invalid_lines > 0);
+        self.hex_encoded += hex_encoded as u64;
+    }
+
+    // Log one summary, unless every input was clean UTF-8
+    pub fn log(&self) {
+        let all_utf8 = self.files.keys().all(|encoding| encoding == UTF_8.name());
+        if all_utf8 && self.invalid_lines == 0 && self.hex_encoded == 0 {
+            return;
+        }
+        let mut counts: Vec<(&String, &usize)> = self.files.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1));
+        let counts: Vec<String> = counts.iter().map(|(encoding, files)| format!("{} {}", files, encoding)).collect();
+        log::info!("Input encodings: {}", counts.join(", "));
+        if self.invalid_lines > 0 {
+            log::warn!("{} lines that are not valid UTF-8 were dropped from {} files", self.invalid_lines, self.invalid_files);
+        }
+        if self.hex_encoded > 0 {
+            log::info!("{} lines that are not valid UTF-8 were kept as $HEX[...]", self.hex_encoded);
+        }
+    }
+}
+
+// Streaming encoder for a target encoding
+//
+// encoding_rs only encodes UTF-8 and legacy encodings, so UTF-16 output is produced here.
+pub struct TextEncoder {
+    target: &'static Encoding,                    // Encoding being written
+    encoder: Option<encoding_rs::Encoder>,        // encoding_rs encoder for non-UTF-16 targets
+    pub unmappable: u64,                          // Characters replaced so far
+}
+
+impl TextEncoder {
+    // Create an encoder writing `target`
+    pub fn new(target: &'static Encoding) -> Self {
+        let encoder = (target != UTF_16LE && target != UTF_16BE).then(|| target.new_encoder());
+        Self { target, encoder, unmappable: 0 }
+    }
+
+    // Encode `text` and append the bytes to `out`
+    pub fn encode(&mut self, text: &str, out: &mut Vec<u8>) {
+        if self.target == UTF_8 {
+            out.extend_from_slice(text.as_bytes());
+            return;
+        }
+        if self.target == UTF_16LE || self.target == UTF_16BE {
+            for unit in text.encode_utf16() {
+                let bytes = if self.target == UTF_16LE { unit.to_le_bytes() } else { unit.to_be_bytes() };
+                out.extend_from_slice(&bytes);
+            }
+            return;
+        }
+
+        let encoder = self.encoder.as_mut().expect("legacy targets always have an encoder");
+        let mut remaining = text;
+        let mut buffer = vec![0u8; text.len() + 16];
+        loop {
+            let (result, read, written) = encoder.encode_from_utf8_without_replacement(remaining, &mut buffer, false);
+            out.extend_from_slice(&buffer[..written]);
+            remaining = &remaining[read..];
+            match result {
+                EncoderResult::InputEmpty => break,
+                EncoderResult::OutputFull => buffer.resize(buffer.len() * 2, 0),
+                EncoderResult::Unmappable(_) => {
+                    self.unmappable += 1;
+                    out.extend_from_slice(UNMAPPABLE_REPLACEMENT.as_bytes());
+                }
+            }
+        }
+    }
+}
+
+// Convert the file at `input` from `from` into `to` at `output`
+//
+// Returns the source encoding used and the conversion counts. A source BOM is dropped.
+pub fn convert_file(
+    input: &Path,
+    output: &Path,
+    from: SourceEncoding,
+    to: &'static Encoding,
+) -> std::io::Result<(&'static Encoding, EncodingStats)> {
+    let mut reader = std::fs::File::open(input)?;
+    let mut buffer = vec![0u8; CONVERT_BUFFER_SIZE];
+    let mut filled = 0;
+    while filled < DETECT_SAMPLE_BYTES {
+        let read = reader.read(&mut buffer[filled..DETECT_SAMPLE_BYTES])?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    let source = match from {
+        SourceEncoding::Auto => detect(&buffer[..filled]),
+        SourceEncoding::Fixed(encoding) => encoding,
+    };
+
+    let mut decoder = source.new_decoder_with_bom_removal();
+    let mut encoder = TextEncoder::new(to);
+    let mut writer = BufWriter::with_capacity(CONVERT_BUFFER_SIZE, std::fs::File::create(output)?);
+    let mut stats = EncodingStats { files: 1, ..EncodingStats::default() };
+    let mut text = String::with_capacity(CONVERT_BUFFER_SIZE * 3);
+    let mut encoded = Vec::with_capacity(CONVERT_BUFFER_SIZE * 2);
+
+    loop {
+        let last = filled == 0;
+        stats.bytes_in += filled as u64;
+
+        let mut input = &buffer[..filled];
+        loop {
+            text.clear();
+            text.reserve(decoder.max_utf8_buffer_length_without_replacement(input.len()).unwrap_or(input.len() * 3));
+            let (result, read) = decoder.decode_to_string_without_replacement(input, &mut text, last);
+            input = &input[read..];
+            if let DecoderResult::Malformed(_, _) = result {
+                stats.malformed += 1;
+                text.push('\u{FFFD}');
+            }
+
+            encoded.clear();
+            encoder.encode(&text, &mut encoded);
+            writer.write_all(&encoded)?;
+            stats.bytes_out += encoded.len() as u64;
+
+            if matches!(result, DecoderResult::InputEmpty) {
+                break;
+            }
+        }
+
+        if last {
+            break;
+        }
+        filled = reader.read(&mut buffer)?;
+    }
+
+    writer.flush()?;
+    stats.unmappable = encoder.unmappable;
+    Ok((source, stats))
+}
+
+// Encoding and byte order mark of the merged text output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputEncoding {
+    pub encoding: &'static Encoding, // Encoding the lines are written in
+    pub bom: bool,                   // Whether the file starts with a byte order mark
+}
+
+impl OutputEncoding {
+    // `encoding` with a BOM as requested, or by default only for UTF-16; None for plain UTF-8
+    //
+    // A BOM exists only in UTF-8 and UTF-16, so requesting one for another encoding fails.
+    pub fn new(encoding: &'static Encoding, bom: Option<bool>) -> Result<Option<Self>, String> {
+        let unicode = encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE;
+        if bom == Some(true) && !unicode {
+            return Err(format!("{} has no byte order mark; --bom needs UTF-8 or UTF-16 output", encoding.name()));
+        }
+        let bom = bom.unwrap_or(encoding != UTF_8) && unicode;
+        Ok((encoding != UTF_8 || bom).then_some(Self { encoding, bom }))
+    }
+}
+
+// Merged output written in a legacy or UTF-16 encoding, or as UTF-8 with a BOM
+//
+// A candidate the target cannot represent is left out rather than written with `?`,
+// which would turn it into a different password.
+pub struct EncodedTextSink {
+    writer: BufWriter<std::fs::File>, // Output being written
+    encoder: TextEncoder,             // Encoder for the target encoding
+    encoded: Vec<u8>,                 // Bytes of the line being written
+    dropped: u64,                     // Lines left out because of unmappable characters
+    examples: Vec<String>,            // First few lines left out, for the summary
+}
+
+impl EncodedTextSink {
+    // Create or truncate `path`, to be written as `output` asks, starting with the BOM if any
+    pub fn create(path: &Path, output: OutputEncoding) -> std::io::Result<Self> {
+        let mut writer = BufWriter::with_capacity(CONVERT_BUFFER_SIZE, std::fs::File::create(path)?);
+        let mut encoder = TextEncoder::new(output.encoding);
+        let mut encoded = Vec::new();
+        if output.bom {
+            encoder.encode("\u{FEFF}", &mut encoded);
+            writer.write_all(&encoded)?;
+        }
+        Ok(Self { writer, encoder, encoded, dropped: 0, examples: Vec::new() })
+    }
+}
+
+impl RecordSink for EncodedTextSink {
+    fn write(&mut self, candidate: &[u8], _tally: Tally) -> std::io::Result<()> {
+        self.encoded.clear();
+        match std::str::from_utf8(candidate) {
+            Ok(text) => {
+                let unmappable = self.encoder.unmappable;
+                self.encoder.encode(text, &mut self.encoded);
+                if self.encoder.unmappable > unmappable {
+                    self.dropped += 1;
+                    if self.examples.len() < UNMAPPABLE_EXAMPLES {
+                        self.examples.push(text.to_string());
+                    }
+                    return Ok(());
+                }
+            }
+            // Lines kept as raw bytes are written as they are
+            Err(_) => self.encoded.extend_from_slice(candidate),
+        }
+        self.encoder.encode("\n", &mut self.encoded);
+        self.writer.write_all(&self.encoded)
+    }
+
+    fn finish(mut self: Box<Self>) -> std::io::Result<()> {
+        self.writer.flush()?;
+        if self.dropped > 0 {
+            log::warn!(
+                "Left out {} lines with characters {} cannot represent, such as: {}",
+                self.dropped, self.encoder.target.name(), self.examples.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, EncodingSample, DETECT_SAMPLE_BYTES};
+
+    #[test]
+    fn detects_utf8_windows_1252_and_utf16() {
+        assert_eq!(detect("pässword\n".as_bytes()).name(), "UTF-8");
+        assert_eq!(detect(b"p\xe4ssword\n").name(), "windows-1252");
+        assert_eq!(detect(b"p\0a\0s\0s\0\n\0").name(), "UTF-16LE");
+        assert_eq!(detect(b"\0p\0a\0s\0s\0\n").name(), "UTF-16BE");
+    }
+
+    #[test]
+    fn sample_stops_at_the_detection_size() {
+        let mut sample = EncodingSample::default();
+        let line = vec![b'a'; 1000];
+        while !sample.is_full() {
+            sample.push(&line);
+        }
+        sample.push(b"\xe4"); // Past the sample, so it does not count
+        assert_eq!(sample.bytes.len(), DETECT_SAMPLE_BYTES);
+        assert_eq!(sample.detect(), "UTF-8");
+    }
+
+    #[test]
+    fn sample_detects_from_streamed_lines() {
+        let mut sample = EncodingSample::default();
+        sample.push(b"caf\xe9");
+        sample.push(b"\n");
+        assert_eq!(sample.detect(), "windows-1252");
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::Result; // Import Result for detection errors
+use serde::{Deserialize, Serialize}; // Import serde traits for the JSON cache file
+use std::collections::HashMap; // Import HashMap to look entries up by path
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the cache and its inputs
+use crate::history; // Import the history module for the per-user data directory
+use crate::plan; // Import plan for the sampling detector the cache stands in for
+
+const CACHE_FILE: &str = "encodings.json"; // Cache file name in the data directory
+
+// Encoding detected for one input as it was when sampled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedEncoding {
+    path: PathBuf,      // Input as listed
+    size: u64,          // Size when it was sampled
+    modified_ns: u128,  // Modification time when it was sampled, in nanoseconds since the epoch
+    encoding: String,   // Detected encoding label
+}
+
+// Encodings detected by earlier runs, keyed by path and reused while size and modification time match
+//
+// Merges record what they detect while reading, so dry runs of a stable corpus skip the sample.
+#[derive(Debug, Default)]
+pub struct EncodingCache {
+    path: Option<PathBuf>,                     // Cache file, None when there is no data directory
+    entries: HashMap<PathBuf, CachedEncoding>, // Known encodings by input
+    changed: bool,                             // Whether entries were added or replaced since loading
+}
+
+impl EncodingCache {
+    // Load the cache in the data directory; a missing or unreadable cache starts empty
+    pub async fn load() -> Self {
+        let Some(path) = history::data_dir().map(|dir| dir.join(CACHE_FILE)) else {
+            return Self::default();
+        };
+        let entries = match tokio::fs::read(&path).await {
+            Ok(content) => serde_json::from_slice::<Vec<CachedEncoding>>(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring the encoding cache {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        let entries = entries.into_iter().map(|entry| (entry.path.clone(), entry)).collect();
+        Self { path: Some(path), entries, changed: false }
+    }
+
+    // Encoding label of `path`, from the cache while the file is unchanged, else sampled
+    pub async fn detect(&mut self, path: &Path) -> Result<String> {
+        let stamp = stamp(path).await;
+        if let (Some((size, modified_ns)), Some(entry)) = (stamp, self.entries.get(path)) {
+            if entry.size == size && entry.modified_ns == modified_ns {
+                return Ok(entry.encoding.clone());
+            }
+        }
+
+        let encoding = plan::detect_encoding(path).await?;
+        if let Some((size, modified_ns)) = stamp {
+            let entry = CachedEncoding { path: path.to_path_buf(), size, modified_ns, encoding: encoding.clone() };
+            self.entries.insert(path.to_path_buf(), entry);
+            self.changed = true;
+        }
+        Ok(encoding)
+    }
+
+    // Remember `encoding` as detected for `path` while reading it, so later dry runs skip the sample
+    pub async fn record(&mut self, path: &Path, encoding: &str) {
+        let Some((size, modified_ns)) = stamp(path).await else { return };
+        if let Some(entry) = self.entries.get(path) {
+            if entry.size == size && entry.modified_ns == modified_ns && entry.encoding == encoding {
+                return;
+            }
+        }
+        let entry = CachedEncoding { path: path.to_path_buf(), size, modified_ns, encoding: encoding.to_string() };
+        self.entries.insert(path.to_path_buf(), entry);
+        self.changed = true;
+    }
+
+    // Write the cache back if it changed, dropping inputs that no longer exist; failing only warns
+    pub async fn save(&mut self) {
+        let Some(path) = self.path.as_ref().filter(|_| self.changed) else { return };
+        self.entries.retain(|input, _| input.exists());
+        let mut entries: Vec<&CachedEncoding> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let result = async {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            let content = serde_json::to_vec(&entries).map_err(std::io::Error::other)?;
+            tokio::fs::write(path, content).await
+        }.await;
+        match result {
+            Ok(()) => self.changed = false,
+            Err(e) => log::warn!("Could not save the encoding cache {}: {}", path.display(), e),
+        }
+    }
+}
+
+// Size and modification time of `path`, or None if either cannot be read
+async fn stamp(path: &Path) -> Option<(u64, u128)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((metadata.len(), modified.as_nanos()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EncodingCache;
+    use crate::plan;
+
+    #[tokio::test]
+    async fn recorded_encodings_stand_until_the_file_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let input = temp.path().join("words.txt");
+        std::fs::write(&input, "alpha\n").unwrap();
+        let mut cache = EncodingCache::default();
+        cache.record(&input, "windows-1252").await;
+        assert_eq!(cache.detect(&input).await.unwrap(), "windows-1252");
+
+        // A different size is enough to tell the file changed
+        std::fs::write(&input, "alpha\nbeta\n").unwrap();
+        let sampled = plan::detect_encoding(&input).await.unwrap();
+        assert_eq!(cache.detect(&input).await.unwrap(), sampled);
+    }
+
+    #[tokio::test]
+    async fn saving_drops_inputs_that_are_gone() {
+        let temp = tempfile::tempdir().unwrap();
+        let (kept, removed) = (temp.path().join("kept.txt"), temp.path().join("removed.txt"));
+        std::fs::write(&kept, "alpha\n").unwrap();
+        std::fs::write(&removed, "beta\n").unwrap();
+        let file = temp.path().join("data").join("encodings.json");
+        let mut cache = EncodingCache { path: Some(file.clone()), ..EncodingCache::default() };
+        cache.record(&kept, "utf-8").await;
+        cache.record(&removed, "utf-8").await;
+        std::fs::remove_file(&removed).unwrap();
+        cache.save().await;
+
+        let saved = std::fs::read_to_string(&file).unwrap();
+        assert!(saved.contains("kept.txt") && !saved.contains("removed.txt"));
+        assert!(!cache.changed);
+    }
+}
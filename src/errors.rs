@@ -1,15 +1,13 @@
 use thiserror::Error;
 use std::path::PathBuf;
-use dialoguer;
 use tokio::task::JoinError;
 use tokio::sync::mpsc::error::SendError;
-use std::collections::HashSet;
-use ctrlc;
 
 /// Type alias for Result with MergerError as the error type
 pub type MergerResult<T> = Result<T, MergerError>;
 
 /// Custom error types for the file merger application
+#[allow(dead_code)]
 #[derive(Error, Debug)]
 pub enum MergerError {
     /// Standard IO errors
@@ -51,10 +49,26 @@ pub enum MergerError {
         source: ResumeError,
     },
 
+    /// Not enough free disk space for the merge
+    #[error("Insufficient disk space: {0}")]
+    DiskSpace(String),
+
+    /// Not enough memory for the merge, and no way to spill
+    #[error("Insufficient memory: {0}")]
+    Memory(String),
+
     /// Deduplication errors
     #[error("Deduplication error: {0}")]
     Deduplication(String),
 
+    /// Plugin loading and call errors
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+
+    /// Line script compile and run errors
+    #[error("Script error: {0}")]
+    Script(String),
+
     /// UTF-8 encoding errors
     #[error("Invalid UTF-8 in file {path}: {message}")]
     InvalidUtf8 {
@@ -64,6 +78,7 @@ pub enum MergerError {
 }
 
 /// Specific errors related to resume functionality
+#[allow(dead_code)]
 #[derive(Error, Debug)]
 pub enum ResumeError {
     #[error("Progress file not found: {0}")]
@@ -80,6 +95,7 @@ pub enum ResumeError {
 }
 
 /// Specific errors related to configuration
+#[allow(dead_code)]
 #[derive(Error, Debug)]
 pub enum ConfigError {
     #[error("Invalid thread count: {0}. Must be between 1 and 100")]
@@ -105,6 +121,42 @@ pub enum ConfigError {
     
     #[error("Serialization error: {0}")]
     SerializationError(String),
+
+    #[error("Temporary directory is not writable: {0}")]
+    TempDirectoryNotWritable(PathBuf),
+
+    #[error("Invalid tuning value: {0}")]
+    InvalidTuning(String),
+
+    #[error("Invalid failure limit: {0}")]
+    InvalidFailureLimit(String),
+
+    #[error("JSON Lines input needs --json-field to name the candidate field")]
+    MissingJsonField,
+
+    #[error("Incompatible output options: {0}")]
+    InvalidOutputFormat(String),
+
+    #[error("Invalid session name: {0}")]
+    InvalidSession(String),
+
+    #[error("Invalid process priority: {0}")]
+    InvalidPriority(String),
+
+    #[error("Invalid strength filter: {0}")]
+    InvalidStrength(String),
+
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Invalid language option: {0}")]
+    InvalidLanguage(String),
+
+    #[error("Invalid pipeline: {0}")]
+    InvalidPipeline(String),
+
+    #[error("Invalid notification webhook: {0}")]
+    InvalidWebhook(String),
 }
 
 impl From<dialoguer::Error> for MergerError {
@@ -137,6 +189,12 @@ impl<T> From<std::sync::mpsc::SendError<T>> for MergerError {
     }
 }
 
+impl From<rusqlite::Error> for MergerError {
+    fn from(err: rusqlite::Error) -> Self {
+        MergerError::Processing(format!("SQLite error: {}", err))
+    }
+}
+
 impl From<ctrlc::Error> for MergerError {
     fn from(err: ctrlc::Error) -> Self {
         MergerError::Processing(format!("Ctrl+C handler error: {}", err))
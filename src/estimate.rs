@@ -0,0 +1,305 @@
+use anyhow::Result; // Import Result type from anyhow for error handling
+use std::collections::HashSet; // Import HashSet to measure duplication within the sample
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for file path handling
+use std::time::{Duration, Instant}; // Import Duration and Instant to time the sample pass
+use tokio::fs::File; // Import async File for reading samples
+use tokio::io::AsyncReadExt; // Import AsyncReadExt for bounded reads
+use crate::input_list::{InputList, ListOptions}; // Import InputList and ListOptions to read the normalized listing
+use crate::sqlite_source; // Import sqlite_source to leave database queries out of the sample
+use crate::download_cache; // Import download_cache to leave URL inputs out of the sample
+use crate::dedup_backend::DedupBackend; // Import DedupBackend to estimate each backend's needs
+use crate::preflight::MemoryPreflight; // Import MemoryPreflight for the set and read buffer sizes
+use crate::spill::SpillWatchdog; // Import SpillWatchdog for the default high-water mark
+use crate::tuning::Tuning; // Import Tuning for the read buffers held in memory
+use clap::ValueEnum; // Import ValueEnum to list every backend
+
+const SAMPLE_BYTES_PER_FILE: u64 = 1024 * 1024; // Read at most 1MB from each sampled file
+const MAX_SAMPLED_FILES: usize = 256; // Upper bound on files sampled for large lists
+const ARENA_PREFIX_BYTES: usize = 4; // Length prefix stored with each line in the arena
+const INDEX_SLOT_BYTES: usize = 24 + 1; // 24-byte span with its hash plus one control byte per index slot
+const HASH_LOAD_FACTOR: f64 = 0.875; // hashbrown's maximum load factor
+
+// Resource estimate produced by sampling the input files
+#[derive(Debug, Clone)]
+pub struct ResourceEstimate {
+    pub files_total: usize,               // Number of entries in the input list
+    pub files_missing: Vec<PathBuf>,      // Entries that do not exist or are not regular files
+    pub files_sampled: usize,             // Number of files actually sampled
+    pub total_bytes: u64,                 // Combined size of all readable input files
+    pub estimated_lines: u64,             // Extrapolated number of non-empty lines
+    pub estimated_unique: u64,            // Extrapolated number of unique lines
+    pub avg_line_bytes: f64,              // Average line length in the sample (without newline)
+    pub memory_bytes: u64,                // Estimated RAM needed for the deduplication set
+    pub disk_bytes: u64,                  // Estimated disk needed for the merged output
+    pub temp_bytes: u64,                  // Estimated spill runs in the temp directory, 0 when the set fits in memory
+    pub estimated_runtime: Duration,      // Extrapolated wall-clock time for the run
+    pub available_memory: Option<u64>,    // Currently available system memory, if known
+}
+
+// Memory and temporary disk one dedup backend would need for the estimated inputs
+#[derive(Debug, Clone)]
+pub struct BackendEstimate {
+    pub backend: DedupBackend, // Backend the figures are for
+    pub peak_memory: u64,      // Set held in memory plus read buffers
+    pub temp_disk: u64,        // Spill runs written to the temp directory
+}
+
+// Struct that samples inputs to compute resource estimates
+pub struct ResourceEstimator;
+
+impl ResourceEstimator {
+    // Read the input list as a merge with `options` does, leaving out files over `max_file_size`
+    // bytes the merge would skip
+    pub async fn estimate_with(input_file: &Path, options: ListOptions, max_file_size: Option<u64>) -> Result<ResourceEstimate> {
+        let files = InputList::load_with(input_file, options).await?.files;
+        Self::estimate_files(&files, max_file_size).await
+    }
+
+    // Validate and sample an already-resolved list of files, leaving out files over `max_file_size` bytes
+    pub async fn estimate_files(files: &[PathBuf], max_file_size: Option<u64>) -> Result<ResourceEstimate> {
+        let mut readable = Vec::with_capacity(files.len());
+        let mut files_missing = Vec::new();
+        let mut total_bytes = 0u64;
+
+        // Validate every entry up front so the report lists all problems at once
+        for path in files {
+            // Database queries have no lines to sample and URLs are only downloaded by the merge,
+            // so both are left out of the estimate
+            if sqlite_source::is_sqlite(path) || download_cache::is_url(path) {
+                continue;
+            }
+            match tokio::fs::metadata(path).await {
+                Ok(meta) if meta.is_file() && max_file_size.is_some_and(|max| meta.len() > max) => {}
+                Ok(meta) if meta.is_file() => {
+                    total_bytes += meta.len();
+                    readable.push((path.clone(), meta.len()));
+                }
+                _ => files_missing.push(path.clone()),
+            }
+        }
+
+        // Spread the samples evenly across the list when it is very long
+        let stride = readable.len().div_ceil(MAX_SAMPLED_FILES).max(1);
+        let sampled: Vec<&(PathBuf, u64)> = readable.iter().step_by(stride).collect();
+
+        let started = Instant::now();
+        let mut seen: HashSet<Vec<u8>> = HashSet::new();
+        let mut sample_lines = 0u64;
+        let mut sample_line_bytes = 0u64;
+        let mut sample_bytes = 0u64;
+
+        for (path, size) in &sampled {
+            let file = File::open(path).await?;
+            let mut buffer = Vec::with_capacity((*size).min(SAMPLE_BYTES_PER_FILE) as usize);
+            file.take(SAMPLE_BYTES_PER_FILE).read_to_end(&mut buffer).await?;
+            sample_bytes += buffer.len() as u64;
+
+            // Drop a trailing partial line when the sample was cut short
+            let complete = if (buffer.len() as u64) < *size {
+                match buffer.iter().rposition(|&b| b == b'\n') {
+                    Some(pos) => &buffer[..=pos],
+                    None => &buffer[..0],
+                }
+            } else {
+                &buffer[..]
+            };
+
+            for line in complete.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+                sample_lines += 1;
+                sample_line_bytes += line.len() as u64;
+                seen.insert(line.to_vec());
+            }
+        }
+        let sample_elapsed = started.elapsed();
+
+        // Extrapolate from the sample to the full input size
+        let bytes_per_line = if sample_lines > 0 {
+            sample_bytes as f64 / sample_lines as f64
+        } else {
+            0.0
+        };
+        let avg_line_bytes = if sample_lines > 0 {
+            sample_line_bytes as f64 / sample_lines as f64
+        } else {
+            0.0
+        };
+        let estimated_lines = if bytes_per_line > 0.0 {
+            (total_bytes as f64 / bytes_per_line) as u64
+        } else {
+            0
+        };
+        let unique_ratio = if sample_lines > 0 {
+            seen.len() as f64 / sample_lines as f64
+        } else {
+            0.0
+        };
+        let estimated_unique = (estimated_lines as f64 * unique_ratio) as u64;
+
+        let memory_bytes = Self::memory_for(estimated_unique, avg_line_bytes);
+        let disk_bytes = (estimated_unique as f64 * (avg_line_bytes + 1.0)) as u64;
+        // A set that outgrows memory is spilled as sorted runs holding every unique line once more
+        let temp_bytes = if memory_bytes > SpillWatchdog::default_high_water() { disk_bytes } else { 0 };
+        let estimated_runtime = if sample_bytes > 0 {
+            sample_elapsed.mul_f64(total_bytes as f64 / sample_bytes as f64)
+        } else {
+            Duration::ZERO
+        };
+
+        let available_memory = sys_info::mem_info().ok().map(|m| m.avail * 1024);
+
+        Ok(ResourceEstimate {
+            files_total: files.len(),
+            files_missing,
+            files_sampled: sampled.len(),
+            total_bytes,
+            estimated_lines,
+            estimated_unique,
+            avg_line_bytes,
+            memory_bytes,
+            disk_bytes,
+            temp_bytes,
+            estimated_runtime,
+            available_memory,
+        })
+    }
+
+    // Bytes needed to hold `unique` lines of `avg_line_bytes` in the arena-backed LineStore
+    pub fn memory_for(unique: u64, avg_line_bytes: f64) -> u64 {
+        let per_entry = avg_line_bytes + ARENA_PREFIX_BYTES as f64
+            + INDEX_SLOT_BYTES as f64 / HASH_LOAD_FACTOR;
+        (unique as f64 * per_entry) as u64
+    }
+}
+
+impl ResourceEstimate {
+    // Figures for every backend a merge of these inputs could run with, reading with `tuning`
+    pub fn per_backend(&self, tuning: &Tuning) -> Vec<BackendEstimate> {
+        let default_high_water = SpillWatchdog::default_high_water();
+        DedupBackend::value_variants().iter()
+            .filter(|backend| **backend != DedupBackend::Auto)
+            .map(|backend| {
+                let memory = MemoryPreflight::check(self, tuning, *backend);
+                // A set that spills stops growing in memory at the high-water mark
+                let held = memory.set_bytes.min(backend.high_water(default_high_water));
+                BackendEstimate {
+                    backend: *backend,
+                    peak_memory: held + memory.buffer_bytes,
+                    temp_disk: backend.spill_bytes(self),
+                }
+            })
+            .collect()
+    }
+
+    // Print the peak memory and temporary disk of every backend, and the one auto would pick
+    pub fn print_backends(&self, tuning: &Tuning) {
+        println!("  {:<15} {:>12} {:>12}", "Dedup backend", "Peak RAM", "Temp disk");
+        for estimate in self.per_backend(tuning) {
+            let exceeds = self.available_memory.is_some_and(|available| estimate.peak_memory > available);
+            println!(
+                "  {:<15} {:>12} {:>12}{}",
+                estimate.backend.label(),
+                format_bytes(estimate.peak_memory),
+                format_bytes(estimate.temp_disk),
+                if exceeds { "  (exceeds available RAM)" } else { "" }
+            );
+        }
+        let choice = DedupBackend::Auto.resolve(self, false);
+        println!("  Auto would pick {}: {}", choice.backend.label(), choice.reason);
+    }
+
+    // Print a human-readable report to stdout
+    pub fn print_report(&self) {
+        println!("  Input files:        {} ({} missing or unreadable)", self.files_total, self.files_missing.len());
+        for path in &self.files_missing {
+            println!("    missing: {}", path.display());
+        }
+        println!("  Files sampled:      {}", self.files_sampled);
+        println!("  Total input size:   {}", format_bytes(self.total_bytes));
+        println!("  Estimated lines:    {}", self.estimated_lines);
+        println!("  Estimated unique:   {}", self.estimated_unique);
+        println!("  Average line:       {:.1} bytes", self.avg_line_bytes);
+        println!("  Estimated RAM:      {}", format_bytes(self.memory_bytes));
+        println!(
+            "  Estimated disk:     {} ({} output, {} temporary spill runs)",
+            format_bytes(self.disk_bytes + self.temp_bytes),
+            format_bytes(self.disk_bytes),
+            format_bytes(self.temp_bytes)
+        );
+        println!("  Estimated runtime:  {}s", self.estimated_runtime.as_secs());
+
+        if let Some(available) = self.available_memory {
+            println!("  Available RAM:      {}", format_bytes(available));
+            if self.memory_bytes > available {
+                println!("  Warning: estimated RAM exceeds currently available memory");
+            }
+        }
+    }
+}
+
+// Format a byte count using binary units (KB, MB, GB, TB)
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+// Format a number of seconds as `1h 02m 05s`, `2m 05s` or `5s`
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourceEstimator;
+    use crate::input_list::ListOptions;
+
+    #[tokio::test]
+    async fn listing_comments_and_blanks_are_not_inputs() {
+        let temp = tempfile::tempdir().unwrap();
+        let words = temp.path().join("words.txt");
+        std::fs::write(&words, b"alpha\nbravo\nalpha\ndelta\n").unwrap();
+        let list = temp.path().join("list.txt");
+        std::fs::write(&list, format!("# corpus\n\n{}\n  \n", words.display())).unwrap();
+
+        let estimate = ResourceEstimator::estimate_with(&list, ListOptions::default(), None).await.unwrap();
+        assert_eq!(estimate.files_total, 1);
+        assert!(estimate.files_missing.is_empty());
+        assert_eq!((estimate.estimated_lines, estimate.estimated_unique), (4, 3));
+        assert_eq!(estimate.disk_bytes, 3 * 6); // Three unique 5-byte lines, each with a newline
+        assert_eq!(estimate.temp_bytes, 0); // A set this small never spills
+    }
+
+    #[tokio::test]
+    async fn directories_and_sizes_follow_the_merge_options() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("lists");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("small.txt"), b"alpha\n").unwrap();
+        std::fs::write(dir.join("large.txt"), b"alpha\nbravo\ncharlie\ndelta\n").unwrap();
+        std::fs::write(dir.join("notes.md"), b"echo\n").unwrap();
+        let list = temp.path().join("list.txt");
+        std::fs::write(&list, format!("{}\n", dir.display())).unwrap();
+
+        let options = ListOptions { extensions: vec!["txt".to_string()], ..ListOptions::default() };
+        let estimate = ResourceEstimator::estimate_with(&list, options, Some(16)).await.unwrap();
+        assert_eq!(estimate.files_total, 2); // notes.md has the wrong extension
+        assert_eq!(estimate.files_sampled, 1); // large.txt is over 16 bytes
+        assert_eq!((estimate.total_bytes, estimate.estimated_lines), (6, 1));
+    }
+}
@@ -0,0 +1,56 @@
+use std::process::ExitCode; // Import ExitCode to return the status from main
+
+pub const EXIT_SUCCESS: u8 = 0; // Every input was merged
+pub const EXIT_FATAL: u8 = 1; // The command failed; the output must not be trusted
+pub const EXIT_SKIPPED_FILES: u8 = 3; // The merge finished, but some inputs were skipped (see failed_files.json)
+pub const EXIT_INTERRUPTED: u8 = 130; // Stopped by a signal after saving the checkpoint (128 + SIGINT)
+
+// How a command that did not fail outright ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Success,      // Completed with every input merged
+    SkippedFiles, // Completed, but some inputs could not be merged
+    Interrupted,  // Aborted by a signal with the checkpoint saved
+}
+
+impl RunOutcome {
+    // Outcome of a merge from whether it was interrupted and how many inputs were skipped
+    pub fn of_merge(interrupted: bool, skipped_files: usize) -> Self {
+        if interrupted {
+            RunOutcome::Interrupted
+        } else if skipped_files > 0 {
+            RunOutcome::SkippedFiles
+        } else {
+            RunOutcome::Success
+        }
+    }
+
+    // Process exit code documented for this outcome
+    pub fn exit_code(self) -> ExitCode {
+        ExitCode::from(match self {
+            RunOutcome::Success => EXIT_SUCCESS,
+            RunOutcome::SkippedFiles => EXIT_SKIPPED_FILES,
+            RunOutcome::Interrupted => EXIT_INTERRUPTED,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RunOutcome, EXIT_INTERRUPTED, EXIT_SKIPPED_FILES, EXIT_SUCCESS};
+    use std::process::ExitCode;
+
+    #[test]
+    fn interruptions_outrank_skipped_files() {
+        assert_eq!(RunOutcome::of_merge(false, 0), RunOutcome::Success);
+        assert_eq!(RunOutcome::of_merge(false, 2), RunOutcome::SkippedFiles);
+        assert_eq!(RunOutcome::of_merge(true, 2), RunOutcome::Interrupted);
+    }
+
+    #[test]
+    fn outcomes_map_to_the_documented_codes() {
+        assert_eq!(RunOutcome::Success.exit_code(), ExitCode::from(EXIT_SUCCESS));
+        assert_eq!(RunOutcome::SkippedFiles.exit_code(), ExitCode::from(EXIT_SKIPPED_FILES));
+        assert_eq!(RunOutcome::Interrupted.exit_code(), ExitCode::from(EXIT_INTERRUPTED));
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Local}; // Import chrono to timestamp failures
+use serde::{Deserialize, Serialize}; // Import serde traits for the JSON skip list
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for file paths
+use crate::config::Config; // Import Config, the options a retry runs with
+use crate::errors::{ConfigError, MergerResult}; // Import MergerResult for load/save errors
+
+pub const DEFAULT_FAILED_FILES_PATH: &str = "failed_files.json"; // Written next to error.log
+
+// Thresholds at which so many inputs are failing that the merge is aborted
+//
+// A wrong mount point or a stale list makes every file fail; stopping early beats
+// grinding through the whole list to produce an empty output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailureLimits {
+    pub max_files: Option<usize>, // Abort once more than this many inputs failed
+    pub max_percent: Option<f64>, // Abort once more than this share of all inputs failed
+}
+
+impl FailureLimits {
+    // Reject percentages outside 0-100
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self.max_percent {
+            Some(percent) if !(0.0..=100.0).contains(&percent) => Err(ConfigError::InvalidFailureLimit(
+                format!("max failed percent must be between 0 and 100, got {}", percent),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Why the merge must stop with `failed` of `total` inputs failed, if a limit is exceeded
+    pub fn exceeded(&self, failed: usize, total: usize) -> Option<String> {
+        if let Some(max) = self.max_files {
+            if failed > max {
+                return Some(format!("{} inputs failed, more than the limit of {}", failed, max));
+            }
+        }
+        if let Some(max) = self.max_percent {
+            let percent = failed as f64 * 100.0 / total.max(1) as f64;
+            if percent > max {
+                return Some(format!("{} of {} inputs ({:.1}%) failed, more than the limit of {}%", failed, total, percent, max));
+            }
+        }
+        None
+    }
+}
+
+// One input that could not be merged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedFile {
+    pub path: PathBuf,             // Input file that failed
+    pub reason: String,            // Error that caused it to be skipped
+    pub failed_at: DateTime<Local>, // When the failure happened
+}
+
+// Machine-readable list of inputs skipped during a merge
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FailedFiles {
+    pub files: Vec<FailedFile>, // Failures in the order they happened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Config>, // Options of the merge, which retry-failed runs with again
+}
+
+impl FailedFiles {
+    // Record that `path` was skipped because of `reason`
+    pub fn record(&mut self, path: &Path, reason: impl ToString) {
+        self.files.push(FailedFile {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+            failed_at: Local::now(),
+        });
+    }
+
+    // Number of recorded failures
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    // Whether nothing failed
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    // Load a skip list written by an earlier merge
+    pub async fn load(path: &Path) -> MergerResult<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+        serde_json::from_str(&content)
+            .map_err(|e| crate::errors::MergerError::InputValidation(format!("{}: {}", path.display(), e)))
+    }
+
+    // Write the skip list as pretty-printed JSON
+    pub async fn save(&self, path: &Path) -> MergerResult<()> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| crate::errors::MergerError::Processing(e.to_string()))?;
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FailureLimits;
+
+    #[test]
+    fn limits_stop_a_merge_only_past_their_threshold() {
+        let limits = FailureLimits { max_files: Some(2), max_percent: Some(50.0) };
+        assert_eq!(limits.exceeded(2, 10), None);
+        assert!(limits.exceeded(3, 10).unwrap().contains("more than the limit of 2"));
+        assert_eq!(limits.exceeded(1, 2), None); // Exactly 50% is allowed
+        assert!(limits.exceeded(2, 3).unwrap().contains("(66.7%)"));
+        assert_eq!(FailureLimits::default().exceeded(100, 100), None);
+    }
+
+    #[test]
+    fn percentages_must_lie_within_0_to_100() {
+        assert!(FailureLimits { max_files: None, max_percent: Some(100.0) }.validate().is_ok());
+        assert!(FailureLimits { max_files: None, max_percent: Some(150.0) }.validate().is_err());
+        assert!(FailureLimits { max_files: None, max_percent: Some(-1.0) }.validate().is_err());
+    }
+}
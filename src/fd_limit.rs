@@ -0,0 +1,51 @@
+const RESERVED_DESCRIPTORS: u64 = 64; // Kept free for the output, logs, progress file, sockets and the runtime
+const UNKNOWN_LIMIT_BUDGET: usize = 1024; // Concurrent opens assumed safe when the limit cannot be read
+
+// Soft limit on open file descriptors, raised to the hard limit first where allowed
+pub fn open_file_limit() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // Safety: getrlimit/setrlimit only read and write the struct passed in
+        unsafe {
+            if libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) != 0 {
+                return None;
+            }
+            if limit.rlim_cur < limit.rlim_max {
+                let raised = libc::rlimit { rlim_cur: limit.rlim_max, rlim_max: limit.rlim_max };
+                if libc::setrlimit(libc::RLIMIT_NOFILE, &raised) == 0 {
+                    log::debug!("Raised open file limit from {} to {}", limit.rlim_cur, raised.rlim_cur);
+                    limit = raised;
+                }
+            }
+        }
+        #[allow(clippy::unnecessary_cast)] // rlim_t is not u64 on every platform
+        Some(limit.rlim_cur as u64)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+// Files that may be open at once without risking EMFILE
+pub fn max_concurrent_opens() -> usize {
+    match open_file_limit() {
+        Some(limit) => usize::try_from(limit.saturating_sub(RESERVED_DESCRIPTORS).max(1)).unwrap_or(usize::MAX),
+        None => UNKNOWN_LIMIT_BUDGET,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{max_concurrent_opens, open_file_limit, RESERVED_DESCRIPTORS};
+
+    #[test]
+    fn opens_leave_descriptors_in_reserve() {
+        let opens = max_concurrent_opens() as u64;
+        assert!(opens >= 1);
+        if let Some(limit) = open_file_limit() {
+            assert!(opens == 1 || opens + RESERVED_DESCRIPTORS == limit);
+        }
+    }
+}
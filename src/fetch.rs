@@ -0,0 +1,269 @@
+use futures::StreamExt; // Import StreamExt to read downloads chunk by chunk
+use sha2::{Digest, Sha256}; // Import SHA-256 for the checksum of each download
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the cache layout
+use std::time::Duration; // Import Duration for the connection timeout
+use tokio::io::AsyncWriteExt; // Import AsyncWriteExt to write downloads to disk
+use crate::errors::{MergerError, MergerResult}; // Import the error types for failed downloads
+use crate::manifest; // Import manifest to hash cached copies like manifests hash inputs
+
+const CACHE_DIR: &str = "rustmerger"; // Directory under the per-user cache directory
+const LISTS_DIR: &str = "lists"; // Directory of the fetched lists inside it
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30); // Longest wait for a server to accept the connection
+
+// How a known list is packed where it is published
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packing {
+    Plain,               // The list itself
+    TarGz(&'static str), // A gzip-compressed tar archive holding the list under this file name
+}
+
+// A well-known public list that can be fetched by name
+#[derive(Debug, Clone, Copy)]
+pub struct KnownList {
+    pub name: &'static str,  // Shortcut such as "seclists/passwords/rockyou", also its directory in the cache
+    pub url: &'static str,   // Where it is downloaded from
+    pub packing: Packing,    // Whether it has to be unpacked after downloading
+    pub about: &'static str, // One-line description for `fetch --list`
+}
+
+// Lists `fetch` knows, by name
+pub const KNOWN_LISTS: &[KnownList] = &[
+    KnownList {
+        name: "seclists/passwords/rockyou",
+        url: "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Passwords/Leaked-Databases/rockyou.txt.tar.gz",
+        packing: Packing::TarGz("rockyou.txt"),
+        about: "RockYou leak, about 14 million passwords (133 MB unpacked)",
+    },
+    KnownList {
+        name: "seclists/passwords/rockyou-75",
+        url: "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Passwords/Leaked-Databases/rockyou-75.txt",
+        packing: Packing::Plain,
+        about: "RockYou passwords used at least 75 times",
+    },
+    KnownList {
+        name: "seclists/passwords/xato-1m",
+        url: "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Passwords/xato-net-10-million-passwords-1000000.txt",
+        packing: Packing::Plain,
+        about: "Top million of the xato.net 10 million passwords",
+    },
+    KnownList {
+        name: "seclists/usernames/xato-10m",
+        url: "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Usernames/xato-net-10-million-usernames.txt",
+        packing: Packing::Plain,
+        about: "Usernames of the xato.net 10 million credentials",
+    },
+    KnownList {
+        name: "seclists/usernames/top-shortlist",
+        url: "https://raw.githubusercontent.com/danielmiessler/SecLists/master/Usernames/top-usernames-shortlist.txt",
+        packing: Packing::Plain,
+        about: "A few dozen of the most common usernames",
+    },
+    KnownList {
+        name: "hashcat/rules/best64",
+        url: "https://raw.githubusercontent.com/hashcat/hashcat/master/rules/best64.rule",
+        packing: Packing::Plain,
+        about: "hashcat's best64 rules",
+    },
+    KnownList {
+        name: "hashcat/rules/dive",
+        url: "https://raw.githubusercontent.com/hashcat/hashcat/master/rules/dive.rule",
+        packing: Packing::Plain,
+        about: "hashcat's dive rules, about 100,000 rules",
+    },
+];
+
+impl KnownList {
+    // Look up a list by name, ignoring case and surrounding slashes
+    pub fn find(name: &str) -> Option<&'static KnownList> {
+        let name = name.trim().trim_matches('/');
+        KNOWN_LISTS.iter().find(|list| list.name.eq_ignore_ascii_case(name))
+    }
+
+    // Name of the file the list is cached as
+    pub fn file_name(&self) -> &'static str {
+        match self.packing {
+            Packing::TarGz(member) => member,
+            Packing::Plain => self.url.rsplit('/').next().unwrap_or(self.name),
+        }
+    }
+}
+
+// A list in the cache, ready to be listed in a merge
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub path: PathBuf,    // Cached copy of the list
+    pub sha256: String,   // Its hex-encoded SHA-256, as recorded next to it
+    pub downloaded: bool, // Whether it was downloaded now rather than found in the cache
+}
+
+// Per-user cache directory: `$XDG_CACHE_HOME/rustmerger`, else `~/.cache/rustmerger`
+pub fn user_cache_dir() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache.join(CACHE_DIR))
+}
+
+// Directory fetched lists are kept in: `lists` in the per-user cache directory
+pub fn cache_dir() -> Option<PathBuf> {
+    user_cache_dir().map(|dir| dir.join(LISTS_DIR))
+}
+
+// Put `list` in `cache`, downloading it unless a copy matching its recorded checksum is there
+//
+// The checksum is recorded next to the list in `sha256sum` format when it is downloaded, so a
+// copy truncated or edited since is noticed and downloaded again. `refresh` downloads it anyway.
+pub async fn fetch(list: &KnownList, cache: &Path, refresh: bool) -> MergerResult<Fetched> {
+    let dir = cache.join(list.name);
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(list.file_name());
+    let sums = dir.join(format!("{}.sha256", list.file_name()));
+
+    if !refresh {
+        if let Some(recorded) = recorded_checksum(&sums).await {
+            if tokio::fs::try_exists(&path).await? {
+                let actual = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || manifest::sha256_file(&path)
+                }).await??;
+                if actual == recorded {
+                    return Ok(Fetched { path, sha256: actual, downloaded: false });
+                }
+                log::warn!("Cached {} does not match its recorded checksum; downloading it again", path.display());
+            }
+        }
+    }
+
+    let (download, sha256) = download(list.url, &dir).await?;
+    let (file, sha256) = match list.packing {
+        Packing::Plain => (download, sha256),
+        Packing::TarGz(member) => {
+            let unpacked = unpack_tar_gz(download, member, &dir).await?;
+            let sha256 = tokio::task::spawn_blocking({
+                let unpacked = unpacked.path().to_path_buf();
+                move || manifest::sha256_file(&unpacked)
+            }).await??;
+            (unpacked, sha256)
+        }
+    };
+    file.persist(&path).map_err(|e| e.error)?;
+    tokio::fs::write(&sums, format!("{}  {}\n", sha256, list.file_name())).await?;
+    Ok(Fetched { path, sha256, downloaded: true })
+}
+
+// Checksum recorded in the `sha256sum`-format file at `sums`, if it exists and holds one
+async fn recorded_checksum(sums: &Path) -> Option<String> {
+    let content = tokio::fs::read_to_string(sums).await.ok()?;
+    let recorded = content.split_whitespace().next()?;
+    (recorded.len() == 64 && recorded.bytes().all(|b| b.is_ascii_hexdigit())).then(|| recorded.to_ascii_lowercase())
+}
+
+// HTTP client for downloads, which may take as long as they need once connected
+pub fn client() -> MergerResult<reqwest::Client> {
+    reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build()
+        .map_err(|e| MergerError::Processing(format!("cannot set up downloads: {}", e)))
+}
+
+// Error for a failed download of `url`
+pub fn download_error(url: &str, error: reqwest::Error) -> MergerError {
+    MergerError::Processing(format!("download of {} failed: {}", url, error.without_url()))
+}
+
+// Download `url` into a temporary file in `dir`, returning it and the SHA-256 of what was received
+async fn download(url: &str, dir: &Path) -> MergerResult<(tempfile::NamedTempFile, String)> {
+    let response = client()?.get(url).send().await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| download_error(url, e))?;
+    save_body(response, dir).await
+}
+
+// Write the body of `response` to a temporary file in `dir`, returning it and the SHA-256 of the body
+//
+// The file is removed again if anything fails, so a cache never holds a partial download.
+pub async fn save_body(response: reqwest::Response, dir: &Path) -> MergerResult<(tempfile::NamedTempFile, String)> {
+    let url = response.url().to_string();
+    if let Some(length) = response.content_length() {
+        log::info!("Downloading {} ({} bytes)", url, length);
+    }
+
+    let temp = tempfile::Builder::new().prefix(".download").tempfile_in(dir)?;
+    let mut file = tokio::fs::File::from_std(temp.reopen()?);
+    let mut hasher = Sha256::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| download_error(&url, e))?;
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok((temp, hex::encode(hasher.finalize())))
+}
+
+// Unpack the file named `member` from the gzip-compressed tar `archive` into a temporary file in `dir`
+async fn unpack_tar_gz(archive: tempfile::NamedTempFile, member: &'static str, dir: &Path) -> MergerResult<tempfile::NamedTempFile> {
+    // Decompress to a plain tar first, since the tar reader is synchronous
+    let tar = tempfile::Builder::new().prefix(".unpack").tempfile_in(dir)?;
+    let compressed = tokio::io::BufReader::new(tokio::fs::File::open(archive.path()).await?);
+    let mut decoder = async_compression::tokio::bufread::GzipDecoder::new(compressed);
+    let mut tar_file = tokio::fs::File::from_std(tar.reopen()?);
+    tokio::io::copy(&mut decoder, &mut tar_file).await?;
+    tar_file.flush().await?;
+    drop(archive);
+
+    let unpacked = tempfile::Builder::new().prefix(".unpack").tempfile_in(dir)?;
+    tokio::task::spawn_blocking(move || -> MergerResult<tempfile::NamedTempFile> {
+        let mut reader = tar::Archive::new(std::fs::File::open(tar.path())?);
+        for entry in reader.entries()? {
+            let mut entry = entry?;
+            if entry.path()?.file_name().is_some_and(|name| name == member) {
+                std::io::copy(&mut entry, &mut unpacked.reopen()?)?;
+                return Ok(unpacked);
+            }
+        }
+        Err(MergerError::Processing(format!("the downloaded archive holds no {}", member)))
+    }).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fetch, recorded_checksum, KnownList};
+    use crate::manifest;
+
+    #[test]
+    fn lists_are_found_by_name_and_cached_under_their_file_name() {
+        let rockyou = KnownList::find(" /SecLists/Passwords/RockYou/ ").unwrap();
+        assert_eq!(rockyou.name, "seclists/passwords/rockyou");
+        assert_eq!(rockyou.file_name(), "rockyou.txt");
+        assert_eq!(KnownList::find("hashcat/rules/best64").unwrap().file_name(), "best64.rule");
+        assert!(KnownList::find("seclists/passwords").is_none());
+    }
+
+    #[tokio::test]
+    async fn checksums_are_read_in_sha256sum_format() {
+        let temp = tempfile::tempdir().unwrap();
+        let sums = temp.path().join("list.txt.sha256");
+        let checksum = "AB".repeat(32);
+        std::fs::write(&sums, format!("{}  list.txt\n", checksum)).unwrap();
+        assert_eq!(recorded_checksum(&sums).await, Some("ab".repeat(32)));
+        std::fs::write(&sums, "abc  list.txt\n").unwrap();
+        assert_eq!(recorded_checksum(&sums).await, None);
+        assert_eq!(recorded_checksum(&temp.path().join("missing")).await, None);
+    }
+
+    #[tokio::test]
+    async fn cached_copies_matching_their_checksum_are_not_downloaded() {
+        let temp = tempfile::tempdir().unwrap();
+        let list = KnownList::find("hashcat/rules/best64").unwrap();
+        let dir = temp.path().join(list.name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(list.file_name());
+        std::fs::write(&path, ":\nl\nu\n").unwrap();
+        let checksum = manifest::sha256_file(&path).unwrap();
+        std::fs::write(dir.join("best64.rule.sha256"), format!("{}  best64.rule\n", checksum)).unwrap();
+
+        let fetched = fetch(list, temp.path(), false).await.unwrap();
+        assert!(!fetched.downloaded);
+        assert_eq!((fetched.path, fetched.sha256), (path, checksum));
+    }
+}
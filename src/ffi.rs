@@ -0,0 +1,315 @@
+// C interface for embedding rustmerger in other tools
+//
+// A job is started with `rustmerger_merge_start`, observed with `rustmerger_poll` and
+// `rustmerger_stats`, optionally stopped with `rustmerger_cancel`, and released with
+// `rustmerger_job_free`. build.rs generates the matching header from this file and plugin.rs.
+
+use std::ffi::{c_char, CStr, CString}; // Import C string types for paths and error messages
+use std::path::PathBuf; // Import PathBuf for the input list and output paths
+use std::sync::atomic::Ordering; // Import Ordering to read the shared counters
+use std::sync::{Arc, Mutex}; // Import Arc and Mutex for state shared with the merge task
+use std::time::Instant; // Import Instant for the elapsed time
+use tokio::runtime::Runtime; // Import Runtime, owned by each job
+use tokio::task::JoinHandle; // Import JoinHandle to collect the merge result
+use crate::app_state::AppState; // Import AppState for the job's progress and cancellation
+use crate::core::ProcessingCore; // Import ProcessingCore, which runs the merge
+use crate::errors::MergerResult; // Import MergerResult, the outcome of the merge task
+use crate::input_list::InputList; // Import InputList to count the listed inputs
+
+// State of a merge job as reported by `rustmerger_poll`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RustmergerStatus {
+    Running = 0,         // The job is still running
+    Completed = 1,       // The job finished and the output file is complete
+    Failed = 2,          // The job stopped with an error; see `rustmerger_error_message`
+    Cancelled = 3,       // The job stopped early after `rustmerger_cancel`
+    InvalidArgument = 4, // A null job or output pointer was passed
+}
+
+// File-level progress of a merge job
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RustmergerProgress {
+    pub files_total: u64,     // Number of inputs in the input list
+    pub files_processed: u64, // Number of input files read so far
+}
+
+// Line-level statistics of a merge job
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RustmergerStats {
+    pub lines_processed: u64, // Number of lines fed into deduplication so far
+    pub unique_lines: u64,    // Number of unique lines found so far
+    pub elapsed_ms: u64,      // Wall-clock time since the job was started, in milliseconds
+}
+
+// Opaque handle to a running or finished merge job
+pub struct RustmergerJob {
+    runtime: Runtime,                                            // Runtime the merge runs on
+    app_state: Arc<AppState>,                                    // State shared with the merge
+    files_total: u64,                                            // Inputs in the input list
+    started: Instant,                                            // When the job was started
+    task: Mutex<Option<JoinHandle<MergerResult<()>>>>,           // Merge task, until its result is collected
+    outcome: Mutex<Option<(RustmergerStatus, Option<CString>)>>, // Final status and error message
+}
+
+impl RustmergerJob {
+    // Start the merge on a dedicated runtime and return the handle
+    fn start(input_file: PathBuf, output_file: PathBuf, threads: usize) -> anyhow::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(threads.max(1))
+            .enable_all()
+            .build()?;
+
+        let files_total = runtime.block_on(InputList::load(&input_file))?.files.len() as u64;
+
+        let app_state = Arc::new(runtime.block_on(AppState::new(input_file, output_file, threads))?);
+        let task = runtime.spawn({
+            let app_state = app_state.clone();
+            async move {
+                let mut core = ProcessingCore::new(app_state, false, false).await?;
+                core.process().await
+            }
+        });
+
+        Ok(Self {
+            runtime,
+            app_state,
+            files_total,
+            started: Instant::now(),
+            task: Mutex::new(Some(task)),
+            outcome: Mutex::new(None),
+        })
+    }
+
+    // Current status, collecting the task result the first time it is seen finished
+    fn status(&self) -> RustmergerStatus {
+        let mut outcome = self.outcome.lock().unwrap();
+        if let Some((status, _)) = outcome.as_ref() {
+            return *status;
+        }
+
+        let mut task = self.task.lock().unwrap();
+        match task.as_ref() {
+            Some(handle) if !handle.is_finished() => return RustmergerStatus::Running,
+            None => return RustmergerStatus::Running,
+            Some(_) => {}
+        }
+
+        let handle = task.take().expect("task checked above");
+        let cancelled = self.runtime.block_on(self.app_state.should_shutdown());
+        let finished = match self.runtime.block_on(handle) {
+            Ok(Ok(())) if cancelled => (RustmergerStatus::Cancelled, None),
+            Ok(Ok(())) => (RustmergerStatus::Completed, None),
+            Ok(Err(e)) => (RustmergerStatus::Failed, CString::new(e.to_string()).ok()),
+            Err(e) => (RustmergerStatus::Failed, CString::new(e.to_string()).ok()),
+        };
+        let status = finished.0;
+        *outcome = Some(finished);
+        status
+    }
+}
+
+// Convert a C string argument into a path
+unsafe fn path_arg(ptr: *const c_char) -> Option<PathBuf> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok().map(PathBuf::from)
+}
+
+/// Start merging the files listed in `input_list` into `output_file`.
+///
+/// Returns a job handle, or null if an argument is invalid or the input
+/// list cannot be read. The handle must be released with
+/// `rustmerger_job_free`.
+///
+/// # Safety
+///
+/// `input_list` and `output_file` must be null or valid NUL-terminated
+/// UTF-8 strings.
+#[no_mangle]
+pub unsafe extern "C" fn rustmerger_merge_start(
+    input_list: *const c_char,
+    output_file: *const c_char,
+    threads: u32,
+) -> *mut RustmergerJob {
+    let (Some(input), Some(output)) = (path_arg(input_list), path_arg(output_file)) else {
+        return std::ptr::null_mut();
+    };
+
+    match RustmergerJob::start(input, output, threads as usize) {
+        Ok(job) => Box::into_raw(Box::new(job)),
+        Err(e) => {
+            log::error!("Failed to start merge: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Report the job status and, if `progress` is not null, its file progress.
+///
+/// # Safety
+///
+/// `job` must be null or a handle returned by `rustmerger_merge_start` that
+/// has not been freed. `progress` must be null or point to writable memory
+/// for a `RustmergerProgress`.
+#[no_mangle]
+pub unsafe extern "C" fn rustmerger_poll(
+    job: *const RustmergerJob,
+    progress: *mut RustmergerProgress,
+) -> RustmergerStatus {
+    let Some(job) = job.as_ref() else {
+        return RustmergerStatus::InvalidArgument;
+    };
+
+    if let Some(progress) = progress.as_mut() {
+        let processed = job.runtime.block_on(job.app_state.progress.read()).processed_files.len();
+        *progress = RustmergerProgress {
+            files_total: job.files_total,
+            files_processed: processed as u64,
+        };
+    }
+
+    job.status()
+}
+
+/// Fill `stats` with the job's line statistics.
+///
+/// Returns `RustmergerStatus::InvalidArgument` if either pointer is null,
+/// otherwise the current job status.
+///
+/// # Safety
+///
+/// `job` must be null or a live handle from `rustmerger_merge_start`.
+/// `stats` must be null or point to writable memory for a `RustmergerStats`.
+#[no_mangle]
+pub unsafe extern "C" fn rustmerger_stats(
+    job: *const RustmergerJob,
+    stats: *mut RustmergerStats,
+) -> RustmergerStatus {
+    let (Some(job), Some(stats)) = (job.as_ref(), stats.as_mut()) else {
+        return RustmergerStatus::InvalidArgument;
+    };
+
+    *stats = RustmergerStats {
+        lines_processed: job.app_state.lines_processed.load(Ordering::Relaxed) as u64,
+        unique_lines: job.app_state.unique_lines.load(Ordering::Relaxed) as u64,
+        elapsed_ms: job.started.elapsed().as_millis() as u64,
+    };
+
+    job.status()
+}
+
+/// Ask the job to stop. Cancellation is checked between input files, so
+/// the job may take a moment to reach `RustmergerStatus::Cancelled`.
+///
+/// # Safety
+///
+/// `job` must be null or a live handle from `rustmerger_merge_start`.
+#[no_mangle]
+pub unsafe extern "C" fn rustmerger_cancel(job: *const RustmergerJob) {
+    if let Some(job) = job.as_ref() {
+        job.runtime.block_on(job.app_state.request_shutdown());
+    }
+}
+
+/// Error message of a failed job, or null if the job has not failed.
+///
+/// The string is owned by the job and stays valid until
+/// `rustmerger_job_free`.
+///
+/// # Safety
+///
+/// `job` must be null or a live handle from `rustmerger_merge_start`.
+#[no_mangle]
+pub unsafe extern "C" fn rustmerger_error_message(job: *const RustmergerJob) -> *const c_char {
+    let Some(job) = job.as_ref() else {
+        return std::ptr::null();
+    };
+
+    job.status();
+    match job.outcome.lock().unwrap().as_ref() {
+        Some((_, Some(message))) => message.as_ptr(),
+        _ => std::ptr::null(),
+    }
+}
+
+/// Release a job handle, cancelling and waiting for the job if it is still
+/// running. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `job` must be null or a handle from `rustmerger_merge_start` that has
+/// not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rustmerger_job_free(job: *mut RustmergerJob) {
+    if job.is_null() {
+        return;
+    }
+
+    let job = Box::from_raw(job);
+    let task = job.task.lock().unwrap().take();
+    if let Some(handle) = task {
+        job.runtime.block_on(job.app_state.request_shutdown());
+        let _ = job.runtime.block_on(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        rustmerger_error_message, rustmerger_job_free, rustmerger_merge_start, rustmerger_poll, rustmerger_stats,
+        RustmergerProgress, RustmergerStats, RustmergerStatus,
+    };
+    use std::ffi::CString;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn null_arguments_are_refused() {
+        let path = CString::new("list.txt").unwrap();
+        unsafe {
+            assert!(rustmerger_merge_start(std::ptr::null(), path.as_ptr(), 1).is_null());
+            assert_eq!(rustmerger_poll(std::ptr::null(), std::ptr::null_mut()), RustmergerStatus::InvalidArgument);
+            assert_eq!(rustmerger_stats(std::ptr::null(), std::ptr::null_mut()), RustmergerStatus::InvalidArgument);
+            assert!(rustmerger_error_message(std::ptr::null()).is_null());
+            rustmerger_job_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn jobs_run_to_completion() {
+        let temp = tempfile::tempdir().unwrap();
+        let (first, second) = (temp.path().join("a.txt"), temp.path().join("b.txt"));
+        std::fs::write(&first, "alpha\nbeta\n").unwrap();
+        std::fs::write(&second, "beta\ngamma\n").unwrap();
+        let list = temp.path().join("list.txt");
+        std::fs::write(&list, format!("{}\n{}\n", first.display(), second.display())).unwrap();
+        let output = temp.path().join("merged.txt");
+        let (list, output_arg) = (CString::new(list.to_str().unwrap()).unwrap(), CString::new(output.to_str().unwrap()).unwrap());
+
+        unsafe {
+            let job = rustmerger_merge_start(list.as_ptr(), output_arg.as_ptr(), 2);
+            assert!(!job.is_null());
+            let mut progress = RustmergerProgress::default();
+            let started = Instant::now();
+            while rustmerger_poll(job, &mut progress) == RustmergerStatus::Running {
+                assert!(started.elapsed() < Duration::from_secs(60), "merge did not finish");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            assert_eq!(rustmerger_poll(job, &mut progress), RustmergerStatus::Completed);
+            assert_eq!(progress.files_total, 2);
+
+            let mut stats = RustmergerStats::default();
+            assert_eq!(rustmerger_stats(job, &mut stats), RustmergerStatus::Completed);
+            assert_eq!((stats.lines_processed, stats.unique_lines), (4, 3));
+            assert!(rustmerger_error_message(job).is_null());
+            rustmerger_job_free(job);
+        }
+
+        let mut merged: Vec<_> = std::fs::read_to_string(&output).unwrap().lines().map(str::to_string).collect();
+        merged.sort();
+        assert_eq!(merged, ["alpha", "beta", "gamma"]);
+    }
+}
@@ -0,0 +1,110 @@
+use std::io::{BufRead, Write}; // Import buffered reading and writing for line conversion
+
+const HEX_PREFIX: &[u8] = b"$HEX["; // Opening of hashcat's hex notation
+const HEX_SUFFIX: &[u8] = b"]"; // Closing of hashcat's hex notation
+
+// Write `line` in hashcat `$HEX[...]` notation
+pub fn encode(line: &[u8]) -> String {
+    format!("$HEX[{}]", hex::encode(line))
+}
+
+// Raw bytes of a `$HEX[...]` line, or None when the line is not in that notation
+pub fn decode(line: &[u8]) -> Option<Vec<u8>> {
+    let digits = line.strip_prefix(HEX_PREFIX)?.strip_suffix(HEX_SUFFIX)?;
+    hex::decode(digits).ok()
+}
+
+// Whether a line cannot be written safely as plain text
+//
+// That is the case for invalid UTF-8, control characters (which break line-based tools)
+// and lines that already look like `$HEX[...]` and would otherwise be decoded later.
+pub fn needs_encoding(line: &[u8]) -> bool {
+    match std::str::from_utf8(line) {
+        Ok(text) => text.chars().any(char::is_control) || decode(line).is_some(),
+        Err(_) => true,
+    }
+}
+
+// Copy lines from `reader` to `writer`, writing them in `$HEX[...]` notation
+//
+// With `all` every line is encoded, otherwise only lines for which `needs_encoding` holds.
+// Returns the number of lines encoded.
+pub fn encode_lines<R: BufRead, W: Write>(mut reader: R, mut writer: W, all: bool) -> std::io::Result<u64> {
+    let mut buffer = Vec::new();
+    let mut encoded = 0;
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+        let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        if all || needs_encoding(line) {
+            writer.write_all(encode(line).as_bytes())?;
+            encoded += 1;
+        } else {
+            writer.write_all(line)?;
+        }
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(encoded)
+}
+
+// Copy lines from `reader` to `writer`, turning `$HEX[...]` lines back into raw bytes
+//
+// Other lines, including malformed `$HEX[...]`, are copied unchanged. Returns the number
+// of lines decoded.
+pub fn decode_lines<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> std::io::Result<u64> {
+    let mut buffer = Vec::new();
+    let mut decoded = 0;
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+        let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        match decode(line) {
+            Some(raw) => {
+                writer.write_all(&raw)?;
+                decoded += 1;
+            }
+            None => writer.write_all(line)?,
+        }
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_lines, encode, encode_lines, needs_encoding};
+
+    #[test]
+    fn encoding_round_trips_raw_bytes() {
+        let raw = b"caf\xe9\x00";
+        assert_eq!(encode(raw), "$HEX[636166e900]");
+        assert_eq!(decode(encode(raw).as_bytes()).as_deref(), Some(&raw[..]));
+        assert_eq!(decode(b"$HEX[zz]"), None);
+        assert_eq!(decode(b"password"), None);
+    }
+
+    #[test]
+    fn only_unsafe_lines_need_encoding() {
+        assert!(!needs_encoding("café".as_bytes()));
+        assert!(needs_encoding(b"caf\xe9"));
+        assert!(needs_encoding(b"tab\there"));
+        assert!(needs_encoding(b"$HEX[41]"));
+    }
+
+    #[test]
+    fn lines_convert_both_ways() {
+        let mut encoded = Vec::new();
+        assert_eq!(encode_lines(&b"plain\ncaf\xe9\n"[..], &mut encoded, false).unwrap(), 1);
+        assert_eq!(encoded, b"plain\n$HEX[636166e9]\n");
+
+        let mut decoded = Vec::new();
+        assert_eq!(decode_lines(&encoded[..], &mut decoded).unwrap(), 1);
+        assert_eq!(decoded, b"plain\ncaf\xe9\n");
+    }
+}
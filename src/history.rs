@@ -0,0 +1,243 @@
+use chrono::{DateTime, Local}; // Import chrono for the finish time of each run
+use serde::{Deserialize, Serialize}; // Import serde traits for the JSON Lines journal
+use sha2::{Digest, Sha256}; // Import SHA-256 for the input fingerprint
+use std::io::Write; // Import Write to append records
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the journal and run paths
+use std::time::Duration; // Import Duration for run times
+use crate::errors::{MergerError, MergerResult}; // Import the error types for unreadable journals
+use crate::estimate::{format_bytes, format_duration}; // Reuse the formatters for the listing
+use crate::exit_code::RunOutcome; // Import RunOutcome to record how each run ended
+use crate::input_list::InputList; // Import InputList to fingerprint the inputs
+use crate::report::RunReport; // Import RunReport, the source of the recorded figures
+
+const DATA_DIR: &str = "rustmerger"; // Directory under the user's data directory
+const HISTORY_FILE: &str = "runs.jsonl"; // Journal file name
+
+// Summary of one finished run, one JSON line in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: String,                 // Random identifier, shown shortened by `history`
+    pub command: String,            // Subcommand that ran: merge, retry-failed or resume
+    pub version: String,            // rustmerger version
+    pub finished_at: DateTime<Local>, // When the run ended
+    pub duration_secs: f64,         // Wall-clock time of the run
+    pub status: String,             // success, skipped_files or interrupted
+    pub input_list: PathBuf,        // Listing of the inputs
+    pub inputs: usize,              // Inputs listed
+    pub inputs_hash: String,        // SHA-256 over every input's path, size and modification time
+    pub output: PathBuf,            // Merged output file
+    pub output_bytes: u64,          // Size of the output when the run ended
+    pub lines_processed: usize,     // Lines fed into deduplication
+    pub unique_lines: usize,        // Unique lines written
+    pub skipped_files: usize,       // Inputs skipped because of errors
+}
+
+impl RunRecord {
+    // Record of a run of `command` that ended with `outcome` after `duration`
+    pub async fn new(command: &str, outcome: RunOutcome, report: &RunReport, duration: Duration) -> Self {
+        let listed = InputList::load(&report.input_list).await.map(|list| list.files).unwrap_or_default();
+        let inputs = listed.len();
+        let inputs_hash = tokio::task::spawn_blocking(move || fingerprint(&listed)).await.unwrap_or_default();
+        let status = match outcome {
+            RunOutcome::Success => "success",
+            RunOutcome::SkippedFiles => "skipped_files",
+            RunOutcome::Interrupted => "interrupted",
+        };
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            command: command.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            finished_at: Local::now(),
+            duration_secs: duration.as_secs_f64(),
+            status: status.to_string(),
+            input_list: report.input_list.clone(),
+            inputs,
+            inputs_hash,
+            output: report.output.clone(),
+            output_bytes: std::fs::metadata(&report.output).map(|meta| meta.len()).unwrap_or(0),
+            lines_processed: report.lines_processed,
+            unique_lines: report.unique_lines,
+            skipped_files: report.issues.iter().filter(|issue| issue.skipped).count(),
+        }
+    }
+}
+
+// SHA-256 over each input's path, size and modification time, in listing order
+//
+// Contents are not read, so this is cheap on any input set; use `manifest` for content checksums.
+fn fingerprint(files: &[PathBuf]) -> String {
+    let mut hasher = Sha256::new();
+    for file in files {
+        let metadata = std::fs::metadata(file).ok();
+        let size = metadata.as_ref().map_or(0, |meta| meta.len());
+        let modified = metadata.and_then(|meta| meta.modified().ok())
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_nanos());
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(size.to_le_bytes());
+        hasher.update(modified.to_le_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+// Per-user data directory: `$XDG_DATA_HOME/rustmerger`, else `~/.local/share/rustmerger`
+pub fn data_dir() -> Option<PathBuf> {
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+    Some(data.join(DATA_DIR))
+}
+
+// Journal location: `runs.jsonl` in the data directory
+pub fn default_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(HISTORY_FILE))
+}
+
+// Append `record` to the journal at `path`, creating it and its directory if needed
+pub fn append(path: &Path, record: &RunRecord) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_vec(record).map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    // One write per record keeps concurrent runs from interleaving their lines
+    std::fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(&line)
+}
+
+// Append a record of the run to `journal`, or the default journal; failing to record only warns
+pub async fn record(journal: Option<&Path>, command: &str, outcome: RunOutcome, report: &RunReport, duration: Duration) {
+    let Some(path) = journal.map(Path::to_path_buf).or_else(default_path) else {
+        log::warn!("No home directory to keep the run history in; this run was not recorded");
+        return;
+    };
+    let record = RunRecord::new(command, outcome, report, duration).await;
+    if let Err(e) = append(&path, &record) {
+        log::warn!("Could not record the run in {}: {}", path.display(), e);
+    }
+}
+
+// Every record in the journal at `path`, oldest first; a missing journal has none
+//
+// Lines that do not parse, such as one cut short by a crash, are skipped with a warning.
+pub fn load(path: &Path) -> MergerResult<Vec<RunRecord>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(MergerError::Io(e)),
+    };
+    let mut records = Vec::new();
+    for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) => log::warn!("Skipping line {} of {}: {}", number + 1, path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+// The record whose id starts with `prefix`, which must match exactly one
+pub fn find<'a>(records: &'a [RunRecord], prefix: &str) -> Result<&'a RunRecord, String> {
+    let mut matches = records.iter().filter(|record| record.id.starts_with(prefix));
+    match (matches.next(), matches.next()) {
+        (Some(record), None) => Ok(record),
+        (None, _) => Err(format!("no run with an id starting with '{}'", prefix)),
+        (Some(_), Some(_)) => Err(format!("several runs have ids starting with '{}'; give more characters", prefix)),
+    }
+}
+
+// Print the latest `limit` records, newest first, one line each
+pub fn print_table(records: &[RunRecord], limit: usize) {
+    if records.is_empty() {
+        println!("No runs recorded yet");
+        return;
+    }
+    println!(
+        "{:<8}  {:<19}  {:>10}  {:<13}  {:>14}  {:>10}  Output",
+        "ID", "Finished", "Duration", "Status", "Unique lines", "Size"
+    );
+    for record in records.iter().rev().take(limit) {
+        println!(
+            "{:<8}  {:<19}  {:>10}  {:<13}  {:>14}  {:>10}  {}",
+            &record.id[..record.id.len().min(8)],
+            record.finished_at.format("%Y-%m-%d %H:%M:%S"),
+            format_duration(record.duration_secs as u64),
+            record.status,
+            record.unique_lines,
+            format_bytes(record.output_bytes),
+            record.output.display()
+        );
+    }
+    if records.len() > limit {
+        println!("{} older runs not shown; use --limit to see more", records.len() - limit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, fingerprint, find, load, RunRecord};
+    use chrono::Local;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    // Record of a successful merge with the given id
+    fn record(id: &str) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            command: "merge".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            finished_at: Local::now(),
+            duration_secs: 1.5,
+            status: "success".to_string(),
+            input_list: PathBuf::from("inputs.txt"),
+            inputs: 2,
+            inputs_hash: String::new(),
+            output: PathBuf::from("merged.txt"),
+            output_bytes: 42,
+            lines_processed: 10,
+            unique_lines: 7,
+            skipped_files: 0,
+        }
+    }
+
+    #[test]
+    fn appended_records_load_back_past_a_cut_line() {
+        let temp = tempfile::tempdir().unwrap();
+        let journal = temp.path().join("data").join("runs.jsonl");
+        append(&journal, &record("abc123")).unwrap();
+        // A run that crashed mid-write leaves a partial line behind
+        std::fs::OpenOptions::new().append(true).open(&journal).unwrap().write_all(b"{\"id\":\"cut\n").unwrap();
+        append(&journal, &record("abd456")).unwrap();
+
+        let records = load(&journal).unwrap();
+        let ids: Vec<_> = records.iter().map(|record| record.id.as_str()).collect();
+        assert_eq!(ids, ["abc123", "abd456"]);
+        assert_eq!(records[0].unique_lines, 7);
+    }
+
+    #[test]
+    fn a_missing_journal_has_no_records() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(load(&temp.path().join("runs.jsonl")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn prefixes_must_match_exactly_one_run() {
+        let records = [record("abc123"), record("abd456")];
+        assert_eq!(find(&records, "abd").unwrap().id, "abd456");
+        assert!(find(&records, "ab").unwrap_err().contains("several runs"));
+        assert!(find(&records, "xyz").unwrap_err().contains("no run"));
+    }
+
+    #[test]
+    fn fingerprints_change_with_the_inputs() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("words.txt");
+        std::fs::write(&file, "alpha\n").unwrap();
+        let before = fingerprint(std::slice::from_ref(&file));
+        assert_eq!(before, fingerprint(std::slice::from_ref(&file)));
+        std::fs::write(&file, "alpha\nbeta\n").unwrap();
+        assert_ne!(before, fingerprint(std::slice::from_ref(&file)));
+    }
+}
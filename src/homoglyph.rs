@@ -0,0 +1,112 @@
+// Fold fullwidth forms and common look-alike letters to their ASCII equivalents
+//
+// Scraped international dumps hold the same password typed on an East Asian IME (fullwidth
+// `ｐａｓｓ`) or with Cyrillic and Greek letters that render like Latin ones (`раss`), which
+// deduplicate as distinct lines. Only characters that are near-universally shown the same as
+// an ASCII one are folded, and the look-alikes only inside tokens that are otherwise Latin, so
+// genuine Russian or Greek words are kept; accented letters and other scripts are left alone.
+
+// Cyrillic and Greek letters drawn like a Latin letter, with that letter
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic lowercase
+    ('а', 'a'), ('е', 'e'), ('о', 'o'), ('р', 'p'), ('с', 'c'), ('у', 'y'), ('х', 'x'),
+    ('і', 'i'), ('ј', 'j'), ('ѕ', 's'), ('ԁ', 'd'), ('ԛ', 'q'), ('ԝ', 'w'), ('һ', 'h'),
+    // Cyrillic uppercase
+    ('А', 'A'), ('В', 'B'), ('Е', 'E'), ('К', 'K'), ('М', 'M'), ('Н', 'H'), ('О', 'O'),
+    ('Р', 'P'), ('С', 'C'), ('Т', 'T'), ('Х', 'X'), ('У', 'Y'), ('І', 'I'), ('Ј', 'J'),
+    ('Ѕ', 'S'), ('Ԁ', 'D'), ('Ԛ', 'Q'), ('Ԝ', 'W'),
+    // Greek
+    ('Α', 'A'), ('Β', 'B'), ('Ε', 'E'), ('Ζ', 'Z'), ('Η', 'H'), ('Ι', 'I'), ('Κ', 'K'),
+    ('Μ', 'M'), ('Ν', 'N'), ('Ο', 'O'), ('Ρ', 'P'), ('Τ', 'T'), ('Υ', 'Y'), ('Χ', 'X'),
+    ('ο', 'o'), ('ι', 'i'), ('κ', 'k'), ('ν', 'v'),
+];
+
+// ASCII equivalent of a fullwidth form, or None when `c` is not one
+fn fold_fullwidth(c: char) -> Option<char> {
+    match c {
+        // Fullwidth ASCII variants sit at a fixed offset from ASCII
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFF01 + 0x21),
+        '\u{3000}' => Some(' '), // Ideographic space
+        _ => None,
+    }
+}
+
+// Latin letter that `c` is drawn like, or None when it is not a confusable
+fn fold_confusable(c: char) -> Option<char> {
+    CONFUSABLES.iter().find(|(from, _)| *from == c).map(|&(_, to)| to)
+}
+
+// Letters of the Latin script, ASCII and the accented Latin-1 and Latin Extended-A/B blocks
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic()
+        || (matches!(c, '\u{00C0}'..='\u{024F}') && c != '\u{00D7}' && c != '\u{00F7}')
+}
+
+// Whether the look-alikes of `token` stand in for Latin letters: it holds at least one Latin
+// letter, and every other letter in it is a confusable. Words written wholly in Cyrillic or
+// Greek, or mixing in letters without a Latin twin, are genuine text and stay as they are.
+fn is_mixed_script(token: &[char]) -> bool {
+    token.iter().any(|&c| is_latin(c))
+        && token
+            .iter()
+            .all(|&c| !c.is_alphabetic() || is_latin(c) || fold_confusable(c).is_some())
+}
+
+// `text` with fullwidth forms folded everywhere and look-alike letters folded inside
+// otherwise-Latin tokens, or None when nothing changes
+pub fn fold(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().map(|c| fold_fullwidth(c).unwrap_or(c)).collect();
+    let mut folded = String::with_capacity(text.len());
+    for token in chars.split_inclusive(|c| c.is_whitespace()) {
+        if is_mixed_script(token) {
+            folded.extend(token.iter().map(|&c| fold_confusable(c).unwrap_or(c)));
+        } else {
+            folded.extend(token.iter());
+        }
+    }
+    (folded != text).then_some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fold;
+
+    #[test]
+    fn ascii_is_untouched() {
+        assert_eq!(fold("password123"), None);
+    }
+
+    #[test]
+    fn fullwidth_folds_everywhere() {
+        assert_eq!(fold("ｐａｓｓ１２３").as_deref(), Some("pass123"));
+        assert_eq!(fold("пароль\u{3000}ｐａｓｓ").as_deref(), Some("пароль pass"));
+    }
+
+    #[test]
+    fn confusables_fold_in_mixed_script_tokens() {
+        assert_eq!(fold("раss").as_deref(), Some("pass")); // Cyrillic р and а
+        assert_eq!(fold("Ηello wоrld").as_deref(), Some("Hello world")); // Greek Η, Cyrillic о
+    }
+
+    #[test]
+    fn genuine_cyrillic_and_greek_words_are_kept() {
+        assert_eq!(fold("пароль"), None);
+        assert_eq!(fold("Καλημέρα"), None);
+        assert_eq!(fold("рор"), None); // Every letter a confusable, but no Latin one
+        assert_eq!(fold("пaроль"), None); // Non-confusable Cyrillic п alongside a Latin a
+    }
+
+    #[test]
+    fn only_the_mixed_token_is_folded() {
+        assert_eq!(fold("пароль раss").as_deref(), Some("пароль pass"));
+    }
+
+    #[test]
+    fn accented_latin_is_kept() {
+        assert_eq!(fold("café"), None);
+        assert_eq!(fold("cafе").as_deref(), Some("cafe")); // Cyrillic е
+    }
+}
@@ -0,0 +1,212 @@
+use clap::ValueEnum; // Import ValueEnum to choose a format on the command line
+use serde::{Deserialize, Serialize}; // Import serde traits for the config file
+use std::borrow::Cow; // Import Cow so unchanged lines are not copied
+use std::path::Path; // Import Path to detect the format from the extension
+
+// Layout of the lines of an input file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    #[default]
+    Auto,      // Detect per file from the extension
+    Plain,     // One candidate per line, taken as-is
+    Dic,       // Hunspell/aspell dictionary: a word count line, then `word/FLAGS` entries
+    Frequency, // Frequency list: a word followed by whitespace and its count
+    Jsonl,     // JSON Lines: one object per line, the candidate taken from a field
+    Counted,   // Pre-counted list: an occurrence count, whitespace, then the word (`uniq -c` style)
+}
+
+impl InputFormat {
+    // The format to read `path` with; `Auto` becomes a concrete format
+    pub fn resolve(self, path: &Path) -> InputFormat {
+        if self != InputFormat::Auto {
+            return self;
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("dic") => InputFormat::Dic,
+            Some(ext) if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") => InputFormat::Jsonl,
+            _ => InputFormat::Plain,
+        }
+    }
+
+    // The candidate held by a non-empty line and the occurrences it stands for,
+    // or None when the line carries no candidate
+    //
+    // `first` is set for the first non-empty line of the file, which holds the word count
+    // in dictionary files. JSON Lines need `json_field`, a dotted path such as `user.password`.
+    // Only counted lists weigh a line as more than one occurrence.
+    pub fn extract<'a>(self, line: &'a [u8], first: bool, json_field: Option<&str>) -> Option<(Cow<'a, [u8]>, u64)> {
+        let word = match self {
+            InputFormat::Auto | InputFormat::Plain => Some(Cow::Borrowed(line)),
+            InputFormat::Jsonl => json_field.and_then(|field| json_value(line, field)),
+            InputFormat::Dic => {
+                if first && line.iter().all(u8::is_ascii_digit) {
+                    return None;
+                }
+                dic_word(line)
+            }
+            InputFormat::Frequency => frequency_word(line),
+            InputFormat::Counted => return counted_word(line),
+        };
+        word.map(|word| (word, 1))
+    }
+}
+
+// The word of a dictionary entry, without affix flags or morphological fields
+//
+// Flags follow the first unescaped `/`; a literal slash in a word is written `\/`.
+// Morphological fields follow a tab, or a space before a `xx:` field tag.
+fn dic_word(line: &[u8]) -> Option<Cow<'_, [u8]>> {
+    let mut end = line.len();
+    let mut escaped = false;
+    let mut i = 0;
+    while i < line.len() {
+        match line[i] {
+            b'\\' if line.get(i + 1) == Some(&b'/') => {
+                escaped = true;
+                i += 1;
+            }
+            b'/' | b'\t' => {
+                end = i;
+                break;
+            }
+            b' ' if is_field_tag(&line[i + 1..]) => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let word = &line[..end];
+    if word.is_empty() {
+        return None;
+    }
+    if !escaped {
+        return Some(Cow::Borrowed(word));
+    }
+    let mut unescaped = Vec::with_capacity(word.len());
+    let mut i = 0;
+    while i < word.len() {
+        if word[i] == b'\\' && word.get(i + 1) == Some(&b'/') {
+            i += 1;
+        }
+        unescaped.push(word[i]);
+        i += 1;
+    }
+    Some(Cow::Owned(unescaped))
+}
+
+// Whether `rest` starts with a morphological field tag such as `po:` or `st:`
+fn is_field_tag(rest: &[u8]) -> bool {
+    rest.len() >= 3 && rest[0].is_ascii_lowercase() && rest[1].is_ascii_lowercase() && rest[2] == b':'
+}
+
+// The word of a frequency list entry, without its trailing count
+//
+// Lines without a trailing count are taken whole, so plain lines mixed in are kept.
+fn frequency_word(line: &[u8]) -> Option<Cow<'_, [u8]>> {
+    let Some(split) = line.iter().rposition(|b| *b == b' ' || *b == b'\t') else {
+        return Some(Cow::Borrowed(line));
+    };
+    let count = &line[split + 1..];
+    if count.is_empty() || !count.iter().all(u8::is_ascii_digit) {
+        return Some(Cow::Borrowed(line));
+    }
+    let word = line[..split].trim_ascii_end();
+    (!word.is_empty()).then_some(Cow::Borrowed(word))
+}
+
+// The word of a counted list entry and its count
+//
+// The count may be padded with leading whitespace, as `uniq -c` does, and is separated
+// from the word by one space or tab so words keep their own leading whitespace.
+// Lines without a leading count are taken whole as one occurrence; a zero count drops the line.
+fn counted_word(line: &[u8]) -> Option<(Cow<'_, [u8]>, u64)> {
+    let rest = line.trim_ascii_start();
+    let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+    let count = std::str::from_utf8(&rest[..digits]).ok().and_then(|count| count.parse::<u64>().ok());
+    match (count, rest.get(digits)) {
+        (Some(count), Some(b' ' | b'\t')) if digits + 1 < rest.len() => {
+            (count > 0).then_some((Cow::Borrowed(&rest[digits + 1..]), count))
+        }
+        _ => Some((Cow::Borrowed(line), 1)),
+    }
+}
+
+// The value at the dotted `field` path of a JSON Lines record, as text
+//
+// Strings are taken as-is, numbers and booleans in their JSON spelling. Missing fields,
+// null, arrays, objects and lines that are not JSON objects carry no candidate.
+fn json_value<'a>(line: &[u8], field: &str) -> Option<Cow<'a, [u8]>> {
+    let record: serde_json::Value = serde_json::from_slice(line).ok()?;
+    let value = field.split('.').try_fold(&record, |value, key| value.get(key))?;
+    let text = match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Number(number) => number.to_string(),
+        serde_json::Value::Bool(flag) => flag.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then(|| Cow::Owned(text.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputFormat;
+    use std::path::Path;
+
+    // The candidate and count `format` takes from `line`, as text
+    fn extract(format: InputFormat, line: &str, first: bool) -> Option<(String, u64)> {
+        format.extract(line.as_bytes(), first, None)
+            .map(|(word, count)| (String::from_utf8(word.into_owned()).unwrap(), count))
+    }
+
+    #[test]
+    fn dictionaries_are_detected_by_extension() {
+        assert_eq!(InputFormat::Auto.resolve(Path::new("en_US.DIC")), InputFormat::Dic);
+        assert_eq!(InputFormat::Auto.resolve(Path::new("words.txt")), InputFormat::Plain);
+        assert_eq!(InputFormat::Frequency.resolve(Path::new("en_US.dic")), InputFormat::Frequency);
+    }
+
+    #[test]
+    fn dictionary_entries_lose_their_flags_and_fields() {
+        assert_eq!(extract(InputFormat::Dic, "62118", true), None);
+        assert_eq!(extract(InputFormat::Dic, "2024", false), Some(("2024".into(), 1)));
+        assert_eq!(extract(InputFormat::Dic, "house/SM", false), Some(("house".into(), 1)));
+        assert_eq!(extract(InputFormat::Dic, "and/or\\/not", false), Some(("and".into(), 1)));
+        assert_eq!(extract(InputFormat::Dic, "km\\/h/S", false), Some(("km/h".into(), 1)));
+        assert_eq!(extract(InputFormat::Dic, "ran po:verb st:run", false), Some(("ran".into(), 1)));
+        assert_eq!(extract(InputFormat::Dic, "ice cream", false), Some(("ice cream".into(), 1)));
+    }
+
+    #[test]
+    fn frequency_entries_lose_their_count_only() {
+        assert_eq!(extract(InputFormat::Frequency, "the 23135851162", false), Some(("the".into(), 1)));
+        assert_eq!(extract(InputFormat::Frequency, "new york\t1200", false), Some(("new york".into(), 1)));
+        assert_eq!(extract(InputFormat::Frequency, "no count here", false), Some(("no count here".into(), 1)));
+    }
+
+    #[test]
+    fn json_lines_give_the_field_at_a_dotted_path() {
+        let field = |line: &str, path: &str| InputFormat::Jsonl.extract(line.as_bytes(), false, Some(path))
+            .map(|(word, _)| String::from_utf8(word.into_owned()).unwrap());
+        let record = r#"{"user":{"password":"hunter2","pin":1234,"admin":false,"tags":["a"]}}"#;
+        assert_eq!(field(record, "user.password").as_deref(), Some("hunter2"));
+        assert_eq!(field(record, "user.pin").as_deref(), Some("1234"));
+        assert_eq!(field(record, "user.admin").as_deref(), Some("false"));
+        assert_eq!(field(record, "user.tags"), None);
+        assert_eq!(field(record, "user.email"), None);
+        assert_eq!(field("not json", "user.password"), None);
+        assert_eq!(InputFormat::Auto.resolve(Path::new("dump.ndjson")), InputFormat::Jsonl);
+    }
+
+    #[test]
+    fn counted_entries_weigh_their_word_by_the_count() {
+        assert_eq!(extract(InputFormat::Counted, "   42 password", false), Some(("password".into(), 42)));
+        assert_eq!(extract(InputFormat::Counted, "3\t two words", false), Some((" two words".into(), 3)));
+        assert_eq!(extract(InputFormat::Counted, "0 dropped", false), None);
+        assert_eq!(extract(InputFormat::Counted, "123456", false), Some(("123456".into(), 1)));
+        assert_eq!(extract(InputFormat::Counted, "letmein", false), Some(("letmein".into(), 1)));
+    }
+}
@@ -0,0 +1,488 @@
+use clap::ValueEnum; // Import ValueEnum to parse and write entry formats by name
+use encoding_rs::Encoding; // Import Encoding for entries in legacy encodings
+use std::collections::{HashMap, HashSet}; // Import HashMap for entry options and HashSet to detect repeated paths
+use std::fmt; // Import fmt to write entry options back in their listing form
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for list entries
+use crate::encoding::parse_encoding; // Import parse_encoding for the encoding field
+use crate::input_format::InputFormat; // Import InputFormat for the format field
+use crate::progress::{self, PathRemap}; // Import path remapping for data moved since a checkpoint
+use crate::tee::{parse_filter, TeeFilter}; // Import the tee filters, reused by the filter field
+
+// What was cleaned up while reading an input list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Normalization {
+    pub blank_lines: usize,     // Empty or whitespace-only lines skipped
+    pub comments: usize,        // `#` comment lines skipped
+    pub trimmed: usize,         // Entries with surrounding whitespace removed
+    pub expanded: usize,        // Entries with a leading `~` expanded to the home directory
+    pub duplicates: usize,      // Entries dropped because the same file was already listed
+    pub directories: usize,     // Directory entries expanded to the files under them
+    pub symlinks_skipped: usize, // Symlinks inside listed directories left out, or broken
+    pub revisited: usize,       // Directories reached again, such as through a symlink to an ancestor
+    pub unreadable: usize,      // Directories inside listed ones that could not be read
+    pub unmatched: usize,       // Files inside listed directories left out by the extension filter
+}
+
+impl Normalization {
+    // Human-readable summary, or None when the list needed no cleanup
+    pub fn describe(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            (self.blank_lines, "blank lines skipped"),
+            (self.comments, "comment lines skipped"),
+            (self.trimmed, "entries trimmed"),
+            (self.expanded, "`~` paths expanded"),
+            (self.duplicates, "duplicate paths removed"),
+            (self.directories, "directories expanded"),
+            (self.symlinks_skipped, "symlinks in directories skipped"),
+            (self.revisited, "directories reached twice skipped"),
+            (self.unreadable, "unreadable directories skipped"),
+            (self.unmatched, "files without a listed extension skipped"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("Input list normalized: {}", parts.join(", ")))
+        }
+    }
+}
+
+// How the entries of an input list are resolved
+#[derive(Debug, Clone)]
+pub struct ListOptions {
+    pub follow_symlinks: bool,   // Follow symlinks met inside listed directories
+    pub recursive: bool,         // Walk the subdirectories of listed directories, not just their own files
+    pub extensions: Vec<String>, // Extensions, lowercase and without the dot, files in listed directories need; empty for any
+    pub remaps: Vec<PathRemap>,  // Prefix rewrites applied to every entry before it is looked at
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self { follow_symlinks: false, recursive: true, extensions: Vec::new(), remaps: Vec::new() }
+    }
+}
+
+// Normalize extensions given as `txt`, `.TXT` or ` lst ` to the form `ListOptions` compares
+pub fn normalize_extensions<S: AsRef<str>>(extensions: &[S]) -> Vec<String> {
+    extensions.iter()
+        .map(|extension| extension.as_ref().trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|extension| !extension.is_empty())
+        .collect()
+}
+
+// Identity of a file or directory, the same however it is reached
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FileId {
+    Inode(u64, u64), // Device and inode, which hard links and symlinks share
+    Path(PathBuf),   // Canonical path, where inodes are not available, or the path as written when missing
+}
+
+// Processing options given for one entry of an input list, after its path and a tab
+//
+// Fields are `key=value` and separated by tabs, such as
+// `/data/leak.txt<TAB>encoding=windows-1252<TAB>priority=10<TAB>tags=leak2024,client`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntryOptions {
+    pub encoding: Option<&'static Encoding>, // Encoding the file is in, decoded to UTF-8 line by line
+    pub priority: i64,                       // Higher priorities are read first; 0 by default
+    pub tags: Vec<String>,                   // Labels for the file, such as where it came from
+    pub format: Option<InputFormat>,         // Layout of the file's lines, instead of the merge's
+    pub json_field: Option<String>,          // Candidate field of a JSON Lines file, instead of the merge's
+    pub separator: Option<u8>,               // Byte the file's combo lines use instead of `:`
+    pub filters: Vec<TeeFilter>,             // Conditions the file's candidates must meet to be merged
+    pub recursive: Option<bool>,             // Whether a listed directory is walked into its subdirectories, instead of the merge's choice
+    pub extensions: Vec<String>,             // Extensions the files of a listed directory need, instead of the merge's
+}
+
+impl EntryOptions {
+    // Parse the tab-separated `key=value` fields following an entry's path
+    pub fn parse<'a>(fields: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut options = Self::default();
+        for field in fields.into_iter().map(str::trim).filter(|field| !field.is_empty()) {
+            let (key, value) = field.split_once('=')
+                .ok_or_else(|| format!("'{}' is not key=value", field))?;
+            let value = value.trim();
+            match key.trim() {
+                "encoding" => {
+                    let encoding = parse_encoding(value)?;
+                    // Lines are split at `\n` bytes before decoding, which UTF-16 does not survive
+                    if !encoding.is_ascii_compatible() {
+                        return Err(format!(
+                            "encoding {} is not read line by line; convert the file with `rustmerger convert-encoding` first",
+                            encoding.name()
+                        ));
+                    }
+                    options.encoding = Some(encoding);
+                }
+                "priority" => {
+                    options.priority = value.parse().map_err(|_| format!("priority '{}' is not a whole number", value))?;
+                }
+                "tags" => {
+                    options.tags = value.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(str::to_string).collect();
+                }
+                "format" => {
+                    options.format = Some(InputFormat::from_str(value, true).map_err(|_| format!(
+                        "unknown format '{}': expected auto, plain, dic, frequency, jsonl or counted", value
+                    ))?);
+                }
+                "json-field" if !value.is_empty() => options.json_field = Some(value.to_string()),
+                "separator" => {
+                    options.separator = Some(match value {
+                        "tab" => b'\t',
+                        "space" => b' ',
+                        _ if value.len() == 1 && value.is_ascii() => value.as_bytes()[0],
+                        _ => return Err(format!("separator '{}' is not one ASCII character, tab or space", value)),
+                    });
+                }
+                "filter" => {
+                    options.filters = value.split(',').filter(|filter| !filter.trim().is_empty()).map(parse_filter).collect::<Result<_, _>>()?;
+                }
+                "recursive" => {
+                    options.recursive = Some(value.parse().map_err(|_| format!("recursive '{}' is not true or false", value))?);
+                }
+                "extensions" => options.extensions = normalize_extensions(&value.split(',').collect::<Vec<_>>()),
+                other => return Err(format!(
+                    "unknown field '{}': expected encoding, priority, tags, format, json-field, separator, filter, recursive or extensions", other
+                )),
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl fmt::Display for EntryOptions {
+    // The fields as they are written after an entry's path, tab-separated
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields = Vec::new();
+        if let Some(encoding) = self.encoding {
+            fields.push(format!("encoding={}", encoding.name()));
+        }
+        if self.priority != 0 {
+            fields.push(format!("priority={}", self.priority));
+        }
+        if !self.tags.is_empty() {
+            fields.push(format!("tags={}", self.tags.join(",")));
+        }
+        if let Some(name) = self.format.and_then(|format| format.to_possible_value()) {
+            fields.push(format!("format={}", name.get_name()));
+        }
+        if let Some(field) = &self.json_field {
+            fields.push(format!("json-field={}", field));
+        }
+        match self.separator {
+            Some(b'\t') => fields.push("separator=tab".to_string()),
+            Some(b' ') => fields.push("separator=space".to_string()),
+            Some(separator) => fields.push(format!("separator={}", separator as char)),
+            None => {}
+        }
+        if !self.filters.is_empty() {
+            let filters: Vec<String> = self.filters.iter().map(TeeFilter::to_string).collect();
+            fields.push(format!("filter={}", filters.join(",")));
+        }
+        if let Some(recursive) = self.recursive {
+            fields.push(format!("recursive={}", recursive));
+        }
+        if !self.extensions.is_empty() {
+            fields.push(format!("extensions={}", self.extensions.join(",")));
+        }
+        write!(f, "{}", fields.join("\t"))
+    }
+}
+
+// Normalized list of input files read from a `--wordlists-file` listing
+#[derive(Debug, Clone, Default)]
+pub struct InputList {
+    pub files: Vec<PathBuf>,          // Entries in listing order, each file at most once
+    pub options: HashMap<PathBuf, EntryOptions>, // Options of the entries that give any, by path
+    pub normalization: Normalization, // What was cleaned up to get there
+}
+
+impl InputList {
+    // Read and normalize the listing at `path`
+    pub async fn load(path: &Path) -> std::io::Result<Self> {
+        Self::load_with(path, ListOptions::default()).await
+    }
+
+    // Read and normalize the listing at `path`, resolving its entries with `options`
+    pub async fn load_with(path: &Path, options: ListOptions) -> std::io::Result<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+        // Directory walks touch the filesystem, so they stay off the async threads
+        tokio::task::spawn_blocking(move || Self::parse_with(&content, &options)).await?
+            .map_err(|e| std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            ))
+    }
+
+    // Normalize listing text: skip blanks and `#` comments, trim, expand `~`, expand directories,
+    // drop repeats and parse the options following a tab after each path
+    pub fn parse_with(content: &str, options: &ListOptions) -> Result<Self, String> {
+        let mut list = Self::default();
+        let mut seen = HashSet::new();
+        let mut visited = HashSet::new(); // Directories walked so far, across all entries
+
+        for (number, raw) in content.lines().enumerate() {
+            let entry = raw.trim();
+            if entry.is_empty() {
+                list.normalization.blank_lines += 1;
+                continue;
+            }
+            if entry.starts_with('#') {
+                list.normalization.comments += 1;
+                continue;
+            }
+            let mut fields = entry.split('\t');
+            let entry = fields.next().unwrap_or_default().trim_end();
+            let mut entry_options = EntryOptions::parse(fields).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            // The walk settings only shape which files a directory entry stands for, so the files do not keep them
+            let recursive = entry_options.recursive.take().unwrap_or(options.recursive);
+            let extensions = match std::mem::take(&mut entry_options.extensions) {
+                own if own.is_empty() => options.extensions.clone(),
+                own => own,
+            };
+            if raw.split('\t').next() != Some(entry) {
+                list.normalization.trimmed += 1;
+            }
+
+            let path = match expand_home(entry) {
+                Some(expanded) => {
+                    list.normalization.expanded += 1;
+                    expanded
+                }
+                None => PathBuf::from(entry),
+            };
+            let path = progress::remap(&path, &options.remaps);
+
+            // A listed directory stands for every file under it with a wanted extension, with the entry's options
+            let found = if path.is_dir() {
+                list.normalization.directories += 1;
+                let walk_options = ListOptions { follow_symlinks: options.follow_symlinks, recursive, extensions, remaps: Vec::new() };
+                walk(&path, &walk_options, &mut visited, &mut list.normalization)
+            } else {
+                vec![path]
+            };
+            for path in found {
+                // Spellings of the same existing file (`./a`, `a`, symlinks, hard links) collapse to one entry
+                let key = file_id(&path).unwrap_or_else(|| FileId::Path(path.clone()));
+                if !seen.insert(key) {
+                    list.normalization.duplicates += 1;
+                    continue;
+                }
+                if entry_options != EntryOptions::default() {
+                    list.options.insert(path.clone(), entry_options.clone());
+                }
+                list.files.push(path);
+            }
+        }
+
+        Ok(list)
+    }
+}
+
+// Files under `root`, depth first in name order, entering every directory at most once
+//
+// Only the files directly inside it unless `options.recursive`, and only those with one of
+// `options.extensions` when any are given. Symlinks inside it are left out unless
+// `options.follow_symlinks`. Followed ones leading back into a directory already in `visited`
+// are skipped, so a link to an ancestor cannot loop.
+fn walk(root: &Path, options: &ListOptions, visited: &mut HashSet<FileId>, normalization: &mut Normalization) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let id = file_id(&dir).unwrap_or_else(|| FileId::Path(dir.clone()));
+        if !visited.insert(id) {
+            normalization.revisited += 1;
+            continue;
+        }
+        let mut entries: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+            Err(e) => {
+                log::warn!("Skipping unreadable directory {}: {}", dir.display(), e);
+                normalization.unreadable += 1;
+                continue;
+            }
+        };
+        entries.sort();
+
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let Ok(metadata) = std::fs::symlink_metadata(&entry) else { continue };
+            let metadata = if metadata.file_type().is_symlink() {
+                match std::fs::metadata(&entry) {
+                    Ok(target) if options.follow_symlinks => target,
+                    _ => {
+                        normalization.symlinks_skipped += 1;
+                        continue;
+                    }
+                }
+            } else {
+                metadata
+            };
+            if metadata.is_dir() {
+                if options.recursive {
+                    subdirs.push(entry);
+                }
+            } else if metadata.is_file() {
+                if has_extension(&entry, &options.extensions) {
+                    files.push(entry);
+                } else {
+                    normalization.unmatched += 1;
+                }
+            }
+        }
+        // Pushed in reverse, so subdirectories are walked in name order
+        pending.extend(subdirs.into_iter().rev());
+    }
+    files
+}
+
+// Whether `path` ends in one of `extensions`, compared without case; any path does when there are none
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    extensions.is_empty() || path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|wanted| extension.eq_ignore_ascii_case(wanted)))
+}
+
+// Identity of the file or directory at `path`, following symlinks; None when it cannot be read
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|metadata| FileId::Inode(metadata.dev(), metadata.ino()))
+}
+
+// Identity of the file or directory at `path`, following symlinks; None when it cannot be read
+#[cfg(not(unix))]
+fn file_id(path: &Path) -> Option<FileId> {
+    std::fs::canonicalize(path).ok().map(FileId::Path)
+}
+
+// Expand a leading `~` or `~/` to the current user's home directory
+fn expand_home(entry: &str) -> Option<PathBuf> {
+    let rest = match entry.strip_prefix('~')? {
+        "" => "",
+        rest if rest.starts_with('/') || rest.starts_with(std::path::MAIN_SEPARATOR) => &rest[1..],
+        _ => return None, // `~user` forms are left alone
+    };
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_extensions, EntryOptions, InputList, ListOptions};
+    use crate::input_format::InputFormat;
+    use crate::tee::TeeFilter;
+    use std::path::Path;
+
+    // Create each of `names` under `dir` holding one line
+    fn touch(dir: &Path, names: &[&str]) {
+        for name in names {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "line\n").unwrap();
+        }
+    }
+
+    #[test]
+    fn blanks_comments_and_repeats_are_dropped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        touch(dir, &["a.txt", "b.txt"]);
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        let content = format!("# corpus\n\n  {}  \n{}\n   \n{}/./a.txt\n", a.display(), b.display(), dir.display());
+
+        let list = InputList::parse_with(&content, &ListOptions::default()).unwrap();
+        assert_eq!(list.files, [a, b]);
+        let normalization = &list.normalization;
+        assert_eq!((normalization.blank_lines, normalization.comments, normalization.trimmed, normalization.duplicates), (2, 1, 1, 1));
+        assert_eq!(
+            normalization.describe().as_deref(),
+            Some("Input list normalized: 2 blank lines skipped, 1 comment lines skipped, 1 entries trimmed, 1 duplicate paths removed")
+        );
+    }
+
+    #[test]
+    fn clean_lists_need_no_summary() {
+        let list = InputList::parse_with("/data/missing.txt\n", &ListOptions::default()).unwrap();
+        assert_eq!(list.files, [Path::new("/data/missing.txt")]);
+        assert_eq!(list.normalization.describe(), None);
+    }
+
+    #[test]
+    fn entry_options_parse_and_write_back() {
+        let fields = "encoding=latin1\tpriority=-2\ttags=leak, client\tformat=counted\tseparator=tab\tfilter=length:8-64";
+        let options = EntryOptions::parse(fields.split('\t')).unwrap();
+        assert_eq!(options.encoding, Some(encoding_rs::WINDOWS_1252));
+        assert_eq!(options.priority, -2);
+        assert_eq!(options.tags, ["leak", "client"]);
+        assert_eq!(options.format, Some(InputFormat::Counted));
+        assert_eq!(options.separator, Some(b'\t'));
+        assert_eq!(options.filters, [TeeFilter::Length(8, 64)]);
+        assert_eq!(
+            options.to_string(),
+            "encoding=windows-1252\tpriority=-2\ttags=leak,client\tformat=counted\tseparator=tab\tfilter=length:8-64"
+        );
+        assert_eq!(EntryOptions::parse(options.to_string().split('\t')), Ok(options));
+    }
+
+    #[test]
+    fn invalid_entry_options_are_refused() {
+        for field in ["priority=high", "encoding=utf-16le", "separator=::", "colour=red", "tags"] {
+            assert!(EntryOptions::parse([field]).is_err(), "{}", field);
+        }
+        let error = InputList::parse_with("a.txt\n/data/b.txt\tpriority=x\n", &ListOptions::default()).unwrap_err();
+        assert!(error.starts_with("line 2:"), "{}", error);
+    }
+
+    #[test]
+    fn listed_options_are_kept_by_path() {
+        let list = InputList::parse_with("/data/a.txt\ttags=leak\n/data/b.txt\n", &ListOptions::default()).unwrap();
+        assert_eq!(list.options.len(), 1);
+        assert_eq!(list.options[Path::new("/data/a.txt")].tags, ["leak"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directories_expand_in_name_order_and_leave_out_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("lists");
+        touch(&root, &["b.txt", "a/z.txt", "a/y.txt"]);
+        std::os::unix::fs::symlink(root.join("b.txt"), root.join("c.txt")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("a/loop")).unwrap();
+        let content = format!("{}\n", root.display());
+
+        let list = InputList::parse_with(&content, &ListOptions::default()).unwrap();
+        assert_eq!(list.files, [root.join("b.txt"), root.join("a/y.txt"), root.join("a/z.txt")]);
+        assert_eq!((list.normalization.directories, list.normalization.symlinks_skipped), (1, 2));
+
+        // Followed, the file link is the same file and the loop leads back to a walked directory
+        let follow = ListOptions { follow_symlinks: true, ..ListOptions::default() };
+        let list = InputList::parse_with(&content, &follow).unwrap();
+        assert_eq!(list.files, [root.join("b.txt"), root.join("a/y.txt"), root.join("a/z.txt")]);
+        assert_eq!((list.normalization.duplicates, list.normalization.revisited), (1, 1));
+    }
+
+    #[test]
+    fn walks_keep_wanted_extensions_and_may_stay_at_the_top() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("lists");
+        touch(&root, &["a.TXT", "b.lst", "notes.md", "sub/c.txt"]);
+        let content = format!("{}\n", root.display());
+        assert_eq!(normalize_extensions(&[" .TXT", "lst", "."]), ["txt", "lst"]);
+
+        let options = ListOptions { recursive: false, extensions: normalize_extensions(&["txt"]), ..ListOptions::default() };
+        let list = InputList::parse_with(&content, &options).unwrap();
+        assert_eq!(list.files, [root.join("a.TXT")]);
+        assert_eq!(list.normalization.unmatched, 2);
+
+        // The entry's own fields take the place of the merge's choices
+        let content = format!("{}\trecursive=true\textensions=txt,lst\n", root.display());
+        let list = InputList::parse_with(&content, &options).unwrap();
+        assert_eq!(list.files, [root.join("a.TXT"), root.join("b.lst"), root.join("sub/c.txt")]);
+        assert!(list.options.is_empty()); // Walk settings do not stay with the files
+    }
+}
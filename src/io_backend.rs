@@ -0,0 +1,73 @@
+use clap::ValueEnum; // Import ValueEnum so the backend can be chosen with --io-backend
+use serde::{Deserialize, Serialize}; // Import serde traits so the backend can be set in the config
+use std::io; // Import io for error types
+use std::path::Path; // Import Path for file path handling
+
+// io_uring reader/writer, only compiled on Linux with the `io-uring` feature
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub mod uring;
+
+// File I/O strategy used for reading inputs and writing the merged output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IoBackend {
+    #[default]
+    Std,   // Memory-mapped reads with buffered fallback and tokio writes
+    Uring, // io_uring reads and writes (Linux, `io-uring` feature)
+}
+
+impl IoBackend {
+    // Fail early when the selected backend is not compiled into this binary
+    pub fn ensure_available(self) -> io::Result<()> {
+        match self {
+            IoBackend::Std => Ok(()),
+            IoBackend::Uring if cfg!(all(target_os = "linux", feature = "io-uring")) => Ok(()),
+            IoBackend::Uring => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "io_uring backend requires Linux and a build with `--features io-uring`",
+            )),
+        }
+    }
+}
+
+// Stream the lines of a file through io_uring, without their newlines
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub fn uring_read_lines<E: From<io::Error>>(path: &Path, on_line: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+    uring::read_lines(path, on_line)
+}
+
+// Stream the lines of a file through io_uring (unavailable in this build)
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+pub fn uring_read_lines<E: From<io::Error>>(_path: &Path, _on_line: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+    IoBackend::Uring.ensure_available().map_err(E::from)
+}
+
+// Write `chunks` to a new file through io_uring, returning the bytes written
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub fn uring_write_file<I: IntoIterator<Item = Vec<u8>>>(path: &Path, chunks: I) -> io::Result<u64> {
+    uring::write_file(path, chunks)
+}
+
+// Write `chunks` to a new file through io_uring (unavailable in this build)
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+pub fn uring_write_file<I: IntoIterator<Item = Vec<u8>>>(_path: &Path, _chunks: I) -> io::Result<u64> {
+    IoBackend::Uring.ensure_available().map(|_| 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IoBackend;
+
+    #[test]
+    fn the_std_backend_is_always_available() {
+        assert!(IoBackend::Std.ensure_available().is_ok());
+        let uring = IoBackend::Uring.ensure_available();
+        assert_eq!(uring.is_ok(), cfg!(all(target_os = "linux", feature = "io-uring")));
+    }
+
+    #[test]
+    fn backends_are_named_in_lowercase_in_the_config() {
+        assert_eq!(serde_json::to_string(&IoBackend::Uring).unwrap(), "\"uring\"");
+        assert_eq!(serde_json::from_str::<IoBackend>("\"std\"").unwrap(), IoBackend::Std);
+    }
+}
@@ -0,0 +1,240 @@
+use io_uring::{opcode, types, IoUring}; // Import the io_uring submission types
+use std::collections::VecDeque; // Import VecDeque to queue partial writes for resubmission
+use std::fs::File; // Import File to open inputs and create the output
+use std::io; // Import io for error types
+use std::os::unix::io::AsRawFd; // Import AsRawFd to hand descriptors to the ring
+use std::path::Path; // Import Path for file path handling
+
+const QUEUE_DEPTH: u32 = 32; // Maximum operations in flight per ring
+const BLOCK_SIZE: usize = 1024 * 1024; // Bytes requested per read operation
+const READ_BUFFERS: usize = 8; // Blocks read ahead of the line scanner
+
+// Stream the lines of a file through a few reusable buffers with their reads in flight
+//
+// Blocks are read ahead into READ_BUFFERS slots of BLOCK_SIZE bytes and handed out in file
+// order, so memory stays bounded however large the input is. A line cut by a block boundary
+// is carried over into the next block; `on_line` gets every line without its newline.
+pub fn read_lines<E: From<io::Error>>(path: &Path, mut on_line: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    // One buffer per slot, reused for every block that slot reads; declared before the ring so
+    // the ring is torn down first when an error returns early
+    let mut slots: Vec<ReadSlot> = (0..READ_BUFFERS).map(|_| ReadSlot::new()).collect();
+    let mut ring = IoUring::new(QUEUE_DEPTH)?;
+    let fd = types::Fd(file.as_raw_fd());
+    let mut next_offset = 0u64; // Start of the next block to submit
+    let mut next_block = 0u64;  // Sequence number of the next block to submit
+    let mut emit_block = 0u64;  // Sequence number of the next block to hand out
+    let mut in_flight = 0;
+    let mut carry = Vec::new(); // Start of a line cut by the previous block boundary
+
+    loop {
+        // Fill idle slots with the next blocks of the file
+        for (index, slot) in slots.iter_mut().enumerate() {
+            if slot.state == SlotState::Idle && next_offset < len {
+                let target = BLOCK_SIZE.min((len - next_offset) as usize);
+                slot.start(next_block, next_offset, target);
+                submit_read(&mut ring, fd, index, slot)?;
+                in_flight += 1;
+                next_offset += target as u64;
+                next_block += 1;
+            }
+        }
+
+        // Hand out completed blocks in file order, freeing their slots for reuse
+        while let Some(slot) = slots.iter_mut().find(|slot| slot.state == SlotState::Full && slot.block == emit_block) {
+            let mut rest = &slot.buffer[..slot.filled];
+            while let Some(end) = memchr::memchr(b'\n', rest) {
+                if carry.is_empty() {
+                    on_line(&rest[..end])?;
+                } else {
+                    carry.extend_from_slice(&rest[..end]);
+                    on_line(&carry)?;
+                    carry.clear();
+                }
+                rest = &rest[end + 1..];
+            }
+            carry.extend_from_slice(rest);
+            slot.state = SlotState::Idle;
+            emit_block += 1;
+        }
+
+        if in_flight == 0 {
+            if next_offset < len {
+                continue;
+            }
+            break;
+        }
+
+        ring.submit_and_wait(1)?;
+        let completions: Vec<(usize, i32)> = ring.completion()
+            .map(|c| (c.user_data() as usize, c.result()))
+            .collect();
+
+        for (index, result) in completions {
+            in_flight -= 1;
+            let slot = &mut slots[index];
+            match result {
+                n if n > 0 => {
+                    slot.filled += n as usize;
+                    if slot.filled < slot.target {
+                        submit_read(&mut ring, fd, index, slot)?; // Short read: ask for the rest
+                        in_flight += 1;
+                    } else {
+                        slot.state = SlotState::Full;
+                    }
+                }
+                0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "file shrank while reading").into()),
+                n => {
+                    let err = io::Error::from_raw_os_error(-n);
+                    if !matches!(err.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock) {
+                        return Err(err.into());
+                    }
+                    submit_read(&mut ring, fd, index, slot)?;
+                    in_flight += 1;
+                }
+            }
+        }
+    }
+
+    // A final line without a trailing newline is still a line
+    if !carry.is_empty() {
+        on_line(&carry)?;
+    }
+    Ok(())
+}
+
+// Whether a read slot is free, waiting on the ring, or holding a block not yet handed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotState {
+    Idle,
+    Reading,
+    Full,
+}
+
+// Reusable buffer for one block read
+struct ReadSlot {
+    buffer: Vec<u8>,  // BLOCK_SIZE bytes, allocated once
+    state: SlotState, // Where the slot is in its read
+    block: u64,       // Sequence number of the block it holds
+    offset: u64,      // File offset of the block
+    target: usize,    // Bytes the block spans
+    filled: usize,    // Bytes read so far
+}
+
+impl ReadSlot {
+    fn new() -> Self {
+        ReadSlot { buffer: vec![0u8; BLOCK_SIZE], state: SlotState::Idle, block: 0, offset: 0, target: 0, filled: 0 }
+    }
+
+    // Point the slot at a new block
+    fn start(&mut self, block: u64, offset: u64, target: usize) {
+        self.state = SlotState::Reading;
+        self.block = block;
+        self.offset = offset;
+        self.target = target;
+        self.filled = 0;
+    }
+}
+
+// Queue the unread remainder of the block in `slot`
+fn submit_read(ring: &mut IoUring, fd: types::Fd, index: usize, slot: &mut ReadSlot) -> io::Result<()> {
+    let remaining = &mut slot.buffer[slot.filled..slot.target];
+    let entry = opcode::Read::new(fd, remaining.as_mut_ptr(), remaining.len() as u32)
+        .offset(slot.offset + slot.filled as u64)
+        .build()
+        .user_data(index as u64);
+    // Safety: the buffer is owned by `slots`, which outlives the ring's use of it, and the slot is
+    // not touched again until its completion is reaped
+    unsafe { ring.submission().push(&entry) }
+        .map_err(|_| io::Error::other("io_uring submission queue full"))
+}
+
+// Write `chunks` sequentially to a new file with up to QUEUE_DEPTH writes in flight
+pub fn write_file<I: IntoIterator<Item = Vec<u8>>>(path: &Path, chunks: I) -> io::Result<u64> {
+    let file = File::create(path)?;
+    let mut ring = IoUring::new(QUEUE_DEPTH)?;
+    let fd = types::Fd(file.as_raw_fd());
+    let mut chunks = chunks.into_iter();
+
+    // Buffers in flight with their file offset and the bytes written so far
+    let mut slots: Vec<Option<(Vec<u8>, u64, usize)>> = (0..QUEUE_DEPTH).map(|_| None).collect();
+    let mut retry: VecDeque<usize> = VecDeque::new();
+    let mut offset = 0u64;
+    let mut in_flight = 0;
+    let mut exhausted = false;
+
+    loop {
+        // Resubmit partial writes first, then fill free slots with new chunks
+        while let Some(slot) = retry.pop_front() {
+            submit_write(&mut ring, fd, slot, &slots)?;
+            in_flight += 1;
+        }
+        while !exhausted && in_flight < QUEUE_DEPTH as usize {
+            match chunks.next() {
+                Some(buffer) if buffer.is_empty() => continue,
+                Some(buffer) => {
+                    let slot = slots.iter().position(Option::is_none).expect("a slot is free below queue depth");
+                    let len = buffer.len() as u64;
+                    slots[slot] = Some((buffer, offset, 0));
+                    offset += len;
+                    submit_write(&mut ring, fd, slot, &slots)?;
+                    in_flight += 1;
+                }
+                None => exhausted = true,
+            }
+        }
+
+        if in_flight == 0 {
+            break;
+        }
+
+        ring.submit_and_wait(1)?;
+        let completions: Vec<(usize, i32)> = ring.completion()
+            .map(|c| (c.user_data() as usize, c.result()))
+            .collect();
+
+        for (slot, result) in completions {
+            in_flight -= 1;
+            let (buffer, _, written) = slots[slot].as_mut().expect("completion for an occupied slot");
+            match result {
+                n if n > 0 => {
+                    *written += n as usize;
+                    if *written < buffer.len() {
+                        retry.push_back(slot);
+                    } else {
+                        slots[slot] = None;
+                    }
+                }
+                0 => return Err(io::Error::new(io::ErrorKind::WriteZero, "io_uring write made no progress")),
+                n => {
+                    let err = io::Error::from_raw_os_error(-n);
+                    if !matches!(err.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock) {
+                        return Err(err);
+                    }
+                    retry.push_back(slot);
+                }
+            }
+        }
+    }
+
+    Ok(offset)
+}
+
+// Queue the unwritten remainder of the buffer in `slot`
+fn submit_write(
+    ring: &mut IoUring,
+    fd: types::Fd,
+    slot: usize,
+    slots: &[Option<(Vec<u8>, u64, usize)>],
+) -> io::Result<()> {
+    let (buffer, offset, written) = slots[slot].as_ref().expect("submitting an occupied slot");
+    let remaining = &buffer[*written..];
+    let entry = opcode::Write::new(fd, remaining.as_ptr(), remaining.len() as u32)
+        .offset(offset + *written as u64)
+        .build()
+        .user_data(slot as u64);
+    // Safety: the buffer is owned by `slots` and is not dropped until its completion is reaped
+    unsafe { ring.submission().push(&entry) }
+        .map_err(|_| io::Error::other("io_uring submission queue full"))
+}
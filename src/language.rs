@@ -0,0 +1,184 @@
+use std::collections::hash_map::{Entry, HashMap}; // Import HashMap for the per-language outputs
+use std::fs::File; // Import File for the output files
+use std::io::{self, BufWriter, Write}; // Import buffered writing for the outputs
+use std::path::{Path, PathBuf}; // Import Path and PathBuf to name the per-language files
+use whatlang::{Detector, Lang}; // Import whatlang for language detection
+use crate::errors::ConfigError; // Import ConfigError for unknown language codes
+use crate::estimate::format_bytes; // Reuse the byte formatter for the summary
+use crate::line_store::Tally; // Import Tally, passed along with each line
+use crate::output_format::RecordSink; // Import RecordSink, implemented by the splitting writer
+
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.2; // Confidence below which a candidate stays untagged
+const MIN_LETTERS: usize = 4; // Fewer letters than this are never tagged
+const OUTPUT_BUFFER_SIZE: usize = 1024 * 1024; // Bytes buffered per output file
+
+// ISO 639-1 codes for the ISO 639-3 codes whatlang reports
+const ISO_639_1: [(&str, &str); 69] = [
+    ("afr", "af"), ("aka", "ak"), ("amh", "am"), ("ara", "ar"), ("aze", "az"), ("bel", "be"),
+    ("ben", "bn"), ("bul", "bg"), ("cat", "ca"), ("ces", "cs"), ("cmn", "zh"), ("dan", "da"),
+    ("deu", "de"), ("ell", "el"), ("eng", "en"), ("epo", "eo"), ("est", "et"), ("fin", "fi"),
+    ("fra", "fr"), ("guj", "gu"), ("heb", "he"), ("hin", "hi"), ("hrv", "hr"), ("hun", "hu"),
+    ("hye", "hy"), ("ind", "id"), ("ita", "it"), ("jav", "jv"), ("jpn", "ja"), ("kan", "kn"),
+    ("kat", "ka"), ("khm", "km"), ("kor", "ko"), ("lat", "la"), ("lav", "lv"), ("lit", "lt"),
+    ("mal", "ml"), ("mar", "mr"), ("mkd", "mk"), ("mya", "my"), ("nep", "ne"), ("nld", "nl"),
+    ("nob", "nb"), ("ori", "or"), ("pan", "pa"), ("pes", "fa"), ("pol", "pl"), ("por", "pt"),
+    ("ron", "ro"), ("rus", "ru"), ("sin", "si"), ("slk", "sk"), ("slv", "sl"), ("sna", "sn"),
+    ("spa", "es"), ("srp", "sr"), ("swe", "sv"), ("tam", "ta"), ("tel", "te"), ("tgl", "tl"),
+    ("tha", "th"), ("tuk", "tk"), ("tur", "tr"), ("ukr", "uk"), ("urd", "ur"), ("uzb", "uz"),
+    ("vie", "vi"), ("yid", "yi"), ("zul", "zu"),
+];
+
+// How candidates are assigned to per-language outputs
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageSplit {
+    pub languages: Vec<String>, // Languages to detect, as ISO 639-1 or 639-3 codes; empty for all
+    pub min_confidence: f64,    // Detection confidence needed to tag a candidate, 0.0-1.0
+}
+
+impl LanguageSplit {
+    // Check the confidence and resolve the language codes
+    pub fn detector(&self) -> Result<LanguageDetector, ConfigError> {
+        if !(0.0..=1.0).contains(&self.min_confidence) {
+            return Err(ConfigError::InvalidLanguage(format!("confidence {} must be between 0 and 1", self.min_confidence)));
+        }
+        let languages = self.languages.iter()
+            .map(|code| lang_from_code(code).ok_or_else(|| ConfigError::InvalidLanguage(format!("unknown language code '{}'", code))))
+            .collect::<Result<Vec<Lang>, _>>()?;
+        let detector = if languages.is_empty() { Detector::new() } else { Detector::with_allowlist(languages) };
+        Ok(LanguageDetector { detector, min_confidence: self.min_confidence })
+    }
+}
+
+// Language detection tuned for short candidates
+pub struct LanguageDetector {
+    detector: Detector,  // whatlang detector, limited to the chosen languages
+    min_confidence: f64, // Confidence needed to tag a candidate
+}
+
+impl LanguageDetector {
+    // ISO 639-1 code of the language `candidate` is in, or None when unsure
+    //
+    // Only letters are looked at, so `sonnenschein123` is detected like `sonnenschein`.
+    pub fn tag(&self, candidate: &[u8]) -> Option<&'static str> {
+        let text = std::str::from_utf8(candidate).ok()?;
+        let letters: String = text.chars().map(|c| if c.is_alphabetic() { c } else { ' ' }).collect();
+        if letters.chars().filter(|c| c.is_alphabetic()).count() < MIN_LETTERS {
+            return None;
+        }
+        let info = self.detector.detect(&letters)?;
+        (info.confidence() >= self.min_confidence).then(|| iso_code(info.lang()))
+    }
+}
+
+// Short code of `lang`, ISO 639-1 where one exists
+fn iso_code(lang: Lang) -> &'static str {
+    let code = lang.code();
+    ISO_639_1.iter().find(|(long, _)| *long == code).map_or(code, |(_, short)| short)
+}
+
+// The language named by an ISO 639-1 or 639-3 code
+fn lang_from_code(code: &str) -> Option<Lang> {
+    let code = code.trim().to_ascii_lowercase();
+    let long = ISO_639_1.iter().find(|(_, short)| *short == code).map_or(code.as_str(), |(long, _)| long);
+    Lang::from_code(long)
+}
+
+// Path of the output for language `code`: `out.txt` becomes `out.en.txt`
+pub fn language_path(output: &Path, code: &str) -> PathBuf {
+    let stem = output.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match output.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, code, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, code),
+    };
+    output.with_file_name(name)
+}
+
+// Text output split by language: tagged lines go to their language's file, the rest to the main output
+pub struct LanguageSplitSink {
+    output: PathBuf,                                        // Main output, also the base of the language files
+    main: (BufWriter<File>, u64),                           // Writer and line count of untagged lines
+    languages: HashMap<&'static str, (BufWriter<File>, u64)>, // Writer and line count per language code
+    detector: LanguageDetector,                             // Tags each line
+}
+
+impl LanguageSplitSink {
+    // Create the main output; language files are created when their first line arrives
+    pub fn create(output: &Path, detector: LanguageDetector) -> io::Result<Self> {
+        let main = BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, File::create(output)?);
+        Ok(Self { output: output.to_path_buf(), main: (main, 0), languages: HashMap::new(), detector })
+    }
+}
+
+impl RecordSink for LanguageSplitSink {
+    fn write(&mut self, candidate: &[u8], _tally: Tally) -> io::Result<()> {
+        let (writer, lines) = match self.detector.tag(candidate) {
+            None => &mut self.main,
+            Some(code) => match self.languages.entry(code) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let file = File::create(language_path(&self.output, code))?;
+                    entry.insert((BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, file), 0))
+                }
+            },
+        };
+        writer.write_all(candidate)?;
+        writer.write_all(b"\n")?;
+        *lines += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.main.0.flush()?;
+        let mut languages: Vec<(&'static str, (BufWriter<File>, u64))> = self.languages.drain().collect();
+        languages.sort_by_key(|(_, (_, lines))| std::cmp::Reverse(*lines));
+        for (code, (mut writer, lines)) in languages {
+            writer.flush()?;
+            let path = language_path(&self.output, code);
+            let size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            println!("  {}: {} lines ({}) in {}", code, lines, format_bytes(size), path.display());
+        }
+        println!("  untagged: {} lines in {}", self.main.1, self.output.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{language_path, lang_from_code, LanguageSplit};
+    use std::path::Path;
+    use whatlang::Lang;
+
+    // Split between `languages` at the default confidence
+    fn split(languages: &[&str]) -> LanguageSplit {
+        LanguageSplit { languages: languages.iter().map(|code| code.to_string()).collect(), min_confidence: super::DEFAULT_MIN_CONFIDENCE }
+    }
+
+    #[test]
+    fn codes_resolve_in_either_iso_form() {
+        assert_eq!(lang_from_code("en"), Some(Lang::Eng));
+        assert_eq!(lang_from_code(" DEU "), Some(Lang::Deu));
+        assert_eq!(lang_from_code("xx"), None);
+    }
+
+    #[test]
+    fn language_files_sit_next_to_the_output() {
+        assert_eq!(language_path(Path::new("out/merged.txt"), "de"), Path::new("out/merged.de.txt"));
+        assert_eq!(language_path(Path::new("merged"), "en"), Path::new("merged.en"));
+    }
+
+    #[test]
+    fn bad_codes_and_confidences_are_refused() {
+        assert!(split(&["en", "xx"]).detector().is_err());
+        let split = LanguageSplit { min_confidence: 1.5, ..split(&[]) };
+        assert!(split.detector().is_err());
+    }
+
+    #[test]
+    fn tags_only_candidates_with_enough_letters() {
+        let detector = split(&["en", "de"]).detector().unwrap();
+        assert_eq!(detector.tag(b"the quick brown fox jumps over the lazy dog 123"), Some("en"));
+        assert_eq!(detector.tag(b"der schnelle braune fuchs springt ueber den faulen hund"), Some("de"));
+        assert_eq!(detector.tag(b"abc123!"), None);
+        assert_eq!(detector.tag(b"\xff\xfeinvalid"), None);
+    }
+}
@@ -13,6 +13,12 @@ pub mod progress;
 // Declare the config module, which handles configuration management
 pub mod config;
 
+// Declare the config_template module, which writes and reads commented configs
+pub mod config_template;
+
+// Declare the preset module, which bundles settings for common merge jobs
+pub mod preset;
+
 // Declare the file_utils module, which provides utility functions for file operations
 pub mod file_utils;
 
@@ -27,3 +33,189 @@ pub mod signal_handler;
 
 // Declare the errors module, which contains custom error types
 pub mod errors;
+
+// Declare the estimate module, which samples inputs to estimate resource requirements
+pub mod estimate;
+
+// Declare the plan module, which previews the processing schedule for an input list
+pub mod plan;
+
+// Declare the selftest module, which runs the built-in suite against the real pipeline
+pub mod selftest;
+
+// Declare the ffi module, which exposes a C interface for embedding the merger
+pub mod ffi;
+
+// Declare the mmap_reader module, which scans memory-mapped input files for line boundaries
+pub mod mmap_reader;
+
+// Declare the io_backend module, which selects between the std and io_uring file I/O paths
+pub mod io_backend;
+
+// Declare the line_store module, which keeps unique lines in a compact arena
+pub mod line_store;
+
+// Declare the shards module, which routes lines by hash to independent aggregators
+pub mod shards;
+
+// Declare the memory_monitor module, which shrinks reader chunks under memory pressure
+pub mod memory_monitor;
+
+// Declare the dedup_backend module, which picks how the set of seen lines is kept
+pub mod dedup_backend;
+
+// Declare the stages module, which lays out the groups of hierarchical merges
+pub mod stages;
+
+// Declare the distributed module, which exchanges merge partitions with worker machines
+pub mod distributed;
+
+// Declare the spill module, which moves deduplication sets to disk before memory runs out
+pub mod spill;
+
+// Declare the tuning module, which holds the configurable buffer and chunk sizes
+pub mod tuning;
+
+// Declare the scratch module, which validates the directory used for temporary files
+pub mod scratch;
+
+// Declare the preflight module, which checks free disk space before a merge
+pub mod preflight;
+
+// Declare the fd_limit module, which keeps concurrent opens under RLIMIT_NOFILE
+pub mod fd_limit;
+
+// Declare the retry module, which classifies and retries transient read errors
+pub mod retry;
+
+// Declare the input_list module, which reads and normalizes wordlist listings
+pub mod input_list;
+
+// Declare the rules module, which merges rule files and handles their comments
+pub mod rules;
+
+// Declare the rule_parser module, which splits hashcat rules into their functions
+pub mod rule_parser;
+
+// Declare the failed_files module, which records inputs skipped because of errors
+pub mod failed_files;
+
+// Declare the report module, which summarizes skipped and degraded files after a run
+pub mod report;
+
+// Declare the exit_code module, which maps run outcomes to documented exit codes
+pub mod exit_code;
+
+// Declare the contribution module, which tracks what each input adds to the output
+pub mod contribution;
+
+// Declare the manifest module, which records and verifies input checksums
+pub mod manifest;
+
+// Declare the check module, which verifies merged files for duplicates and ordering
+pub mod check;
+
+// Declare the encoding module, which detects and converts text encodings
+pub mod encoding;
+
+// Declare the hex_format module, which reads and writes hashcat $HEX[...] notation
+pub mod hex_format;
+
+// Declare the line_options module, which controls how input lines become candidates
+pub mod line_options;
+
+// Declare the input_format module, which extracts candidates from dictionary and other line layouts
+pub mod input_format;
+
+// Declare the sqlite_source module, which streams candidates out of SQLite queries
+pub mod sqlite_source;
+
+// Declare the output_format module, which writes unique lines into SQLite or Parquet stores
+pub mod output_format;
+
+// Declare the dedup_key module, which picks the part of a combo line used for deduplication
+pub mod dedup_key;
+
+// Declare the redundancy module, which ranks inputs by the lines only they provide
+pub mod redundancy;
+
+// Declare the near_dupes module, which clusters lines within one edit of each other
+pub mod near_dupes;
+
+// Declare the strength module, which scores candidates by how hard they are to guess
+pub mod strength;
+
+// Declare the transform module, which generates case and leet variants of words
+pub mod transform;
+
+// Declare the language module, which splits the output into per-language files
+pub mod language;
+
+// Declare the patterns module, which spots keyboard walks and sequences
+pub mod patterns;
+
+// Declare the side_output module, which writes lines to extra files beside the main output
+pub mod side_output;
+
+// Declare the routing module, which diverts kinds of candidates to their own files
+pub mod routing;
+
+// Declare the plugin module, which loads per-line filter and transform plugins
+pub mod plugin;
+
+// Declare the script module, which runs Rhai line scripts
+pub mod script;
+
+// Declare the pipeline module, which runs the ordered filter and transform stages
+pub mod pipeline;
+
+// Declare the tee module, which writes extra filtered outputs
+pub mod tee;
+
+// Declare the base_words module, which writes the base word list of a merge
+pub mod base_words;
+
+// Declare the status module, which holds the live figures of a running merge
+pub mod status;
+
+// Declare the web_ui module, which serves a status page for a running merge
+pub mod web_ui;
+
+// Declare the tui module, which draws a full-screen dashboard for a running merge
+pub mod tui;
+
+// Declare the notify module, which posts webhook notifications when a merge ends
+pub mod notify;
+
+// Declare the history module, which keeps a journal of finished runs
+pub mod history;
+
+// Declare the session module, which keeps named resume checkpoints
+pub mod session;
+
+// Declare the pause module, which holds readers while a merge is paused
+pub mod pause;
+
+// Declare the throttle module, which paces reads and writes
+pub mod throttle;
+
+// Declare the priority module, which sets niceness and the I/O scheduling class
+pub mod priority;
+
+// Declare the cardinality module, which estimates the final unique count with HyperLogLog
+pub mod cardinality;
+
+// Declare the encoding_cache module, which remembers detected encodings across runs
+pub mod encoding_cache;
+
+// Declare the homoglyph module, which folds fullwidth and look-alike characters to ASCII
+pub mod homoglyph;
+
+// Declare the content_type module, which recognizes non-text inputs by their magic bytes
+pub mod content_type;
+
+// Declare the fetch module, which downloads well-known public lists into a checksummed cache
+pub mod fetch;
+
+// Declare the download_cache module, which keeps URL inputs in a cache keyed by URL and ETag
+pub mod download_cache;
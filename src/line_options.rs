@@ -0,0 +1,164 @@
+use clap::ValueEnum; // Import ValueEnum so the binary mode can be chosen on the command line
+use serde::{Deserialize, Serialize}; // Import serde traits so the binary mode can be set in the config
+use std::borrow::Cow; // Import Cow so unchanged lines are not copied
+use std::path::Path; // Import Path to resolve the format of each input
+use std::sync::Arc; // Import Arc to share the JSON field path and pipeline between readers
+use crate::dedup_key::{DedupKey, COMBO_SEPARATOR}; // Import DedupKey and the separator for combo deduplication
+use encoding_rs::Encoding; // Import Encoding for inputs listed in a legacy encoding
+use crate::errors::ConfigError; // Import ConfigError for incomplete options
+use crate::input_format::InputFormat; // Import InputFormat for candidate extraction
+use crate::input_list::EntryOptions; // Import EntryOptions, the per-input options of the input list
+use crate::pipeline::Pipeline; // Import Pipeline for the filter and transform stages
+use crate::tee::TeeFilter; // Import TeeFilter for per-input filters
+
+// What happens to lines holding NUL bytes, the mark of binary content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryMode {
+    #[default]
+    Keep, // Merge them as read, reporting mostly-binary files as degraded
+    Hex,  // Write them as `$HEX[...]`, so the text lines around them merge as usual
+}
+
+// Control characters removed from every candidate before deduplication
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StripControl {
+    Nul,                 // NUL bytes only
+    AllControl,          // Every C0 control character, 0x00 to 0x1F, tabs included
+    AllControlExceptTab, // Every C0 control character but tab, which separates tab-delimited combos
+}
+
+impl StripControl {
+    // `line` without the characters this mode removes, or None when it has none
+    pub fn strip(self, line: &[u8]) -> Option<Vec<u8>> {
+        let removed = |byte: u8| match self {
+            StripControl::Nul => byte == 0,
+            StripControl::AllControl => byte < 0x20,
+            StripControl::AllControlExceptTab => byte < 0x20 && byte != b'\t',
+        };
+        line.iter().any(|&byte| removed(byte))
+            .then(|| line.iter().copied().filter(|&byte| !removed(byte)).collect())
+    }
+}
+
+// How raw input lines become deduplication candidates
+//
+// Cloned into every reader, so it must stay cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct LineOptions {
+    pub hex_invalid: bool,            // Keep lines that are not valid UTF-8 as `$HEX[...]` instead of dropping them
+    pub binary_mode: BinaryMode,      // Lines with NUL bytes kept as read or as `$HEX[...]`
+    pub strip_control: Option<StripControl>, // Control characters removed from candidates
+    pub normalize_homoglyphs: bool,   // Fold fullwidth forms and look-alike letters to ASCII
+    pub format: InputFormat,          // Layout of the input lines; `Auto` until resolved for a file
+    pub json_field: Option<Arc<str>>, // Dotted path of the candidate field in JSON Lines inputs
+    pub dedup_key: DedupKey,          // Part of each candidate that identifies it
+    pub pipeline: Arc<Pipeline>,      // Filters and transforms every candidate goes through before deduplication
+    pub source: Arc<str>,             // Input being read, as passed to the script; set by `for_file`
+    pub encoding: Option<&'static Encoding>, // Encoding lines are decoded from before anything else; set by `for_entry`
+    pub separator: Option<u8>,        // Combo separator rewritten to `:`; set by `for_entry`
+    pub filters: Arc<[TeeFilter]>,    // Conditions candidates must meet before the pipeline; set by `for_entry`
+}
+
+impl LineOptions {
+    // Check that a forced JSON Lines format names its field
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match (&self.format, self.json_field.as_deref()) {
+            (InputFormat::Jsonl, None | Some("")) => Err(ConfigError::MissingJsonField),
+            _ => Ok(()),
+        }
+    }
+
+    // The options for reading `path`, with its format resolved
+    pub fn for_file(&self, path: &Path) -> Self {
+        Self { format: self.format.resolve(path), source: path.display().to_string().into(), ..self.clone() }
+    }
+
+    // The options for reading `path`, with the options its input list entry gives, if any
+    pub fn for_entry(&self, path: &Path, entry: Option<&EntryOptions>) -> Self {
+        let Some(entry) = entry else { return self.for_file(path) };
+        Self {
+            // UTF-8 lines need no decoding
+            encoding: entry.encoding.filter(|encoding| *encoding != encoding_rs::UTF_8),
+            separator: entry.separator.filter(|separator| *separator != COMBO_SEPARATOR),
+            filters: entry.filters.as_slice().into(),
+            format: entry.format.unwrap_or(self.format),
+            json_field: entry.json_field.as_deref().map(Arc::from).or_else(|| self.json_field.clone()),
+            ..self.clone()
+        }.for_file(path)
+    }
+
+    // The options for reading the intermediates of a hierarchical merge, laid out as `format`
+    //
+    // Filters and transforms already ran in the first stage, so running them again would
+    // transform variants a second time. The decode settings stay, as their output reads back unchanged.
+    pub fn for_intermediates(&self, format: InputFormat) -> Self {
+        Self {
+            strip_control: None,
+            normalize_homoglyphs: false,
+            format,
+            json_field: None,
+            pipeline: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    // The candidate held by a non-empty line and the occurrences it stands for, or None when it carries none
+    pub fn extract<'a>(&self, line: &'a [u8], first: bool) -> Option<(Cow<'a, [u8]>, u64)> {
+        self.format.extract(line, first, self.json_field.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineOptions, StripControl};
+    use crate::input_format::InputFormat;
+    use crate::input_list::EntryOptions;
+    use std::path::Path;
+
+    #[test]
+    fn strip_modes_remove_their_control_characters() {
+        let line = b"a\0b\tc\x1bd";
+        assert_eq!(StripControl::Nul.strip(line).unwrap(), b"ab\tc\x1bd");
+        assert_eq!(StripControl::AllControl.strip(line).unwrap(), b"abcd");
+        assert_eq!(StripControl::AllControlExceptTab.strip(line).unwrap(), b"ab\tcd");
+    }
+
+    #[test]
+    fn lines_without_control_characters_are_left_alone() {
+        assert_eq!(StripControl::AllControl.strip(b"plain"), None);
+        assert_eq!(StripControl::AllControlExceptTab.strip(b"user\tpass"), None);
+    }
+
+    #[test]
+    fn entries_without_options_only_resolve_the_format() {
+        let options = LineOptions::default().for_entry(Path::new("words.dic"), None);
+        assert_eq!(options.format, InputFormat::Dic);
+        assert_eq!(&*options.source, "words.dic");
+        assert!(options.encoding.is_none() && options.separator.is_none());
+    }
+
+    #[test]
+    fn entry_options_replace_the_merge_options() {
+        let merge = LineOptions { json_field: Some("password".into()), ..LineOptions::default() };
+        let entry = EntryOptions {
+            encoding: Some(encoding_rs::WINDOWS_1252),
+            separator: Some(b';'),
+            format: Some(InputFormat::Jsonl),
+            ..EntryOptions::default()
+        };
+        let options = merge.for_entry(Path::new("leak.txt"), Some(&entry));
+        assert_eq!(options.encoding, Some(encoding_rs::WINDOWS_1252));
+        assert_eq!((options.separator, options.format), (Some(b';'), InputFormat::Jsonl));
+        assert_eq!(options.json_field.as_deref(), Some("password"));
+    }
+
+    #[test]
+    fn entry_options_matching_the_defaults_are_dropped() {
+        let entry = EntryOptions { encoding: Some(encoding_rs::UTF_8), separator: Some(b':'), ..EntryOptions::default() };
+        let options = LineOptions::default().for_entry(Path::new("leak.txt"), Some(&entry));
+        assert!(options.encoding.is_none() && options.separator.is_none());
+        assert_eq!(options.format, InputFormat::Plain);
+    }
+}
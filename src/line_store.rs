@@ -0,0 +1,345 @@
+use hashbrown::hash_table::Entry; // Import Entry for single-probe insert-if-absent
+use hashbrown::HashTable; // Import HashTable to index lines by caller-supplied hashes
+use crate::dedup_key::DedupKey; // Import DedupKey to compare lines by their key
+
+const SEGMENT_SIZE: usize = 64 * 1024 * 1024; // Bytes per arena segment
+const TALLY_SIZE: usize = 12; // Bytes of the count and source kept ahead of a line when tallying
+
+// How often a line was seen and the first input it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub count: u64,    // Occurrences across every input
+    pub source: usize, // Lowest position of an input containing the line
+}
+
+impl Tally {
+    // One occurrence in input `source`
+    pub fn once(source: usize) -> Self {
+        Self { count: 1, source }
+    }
+
+    // Fold in the occurrences of the same line seen elsewhere
+    pub fn merge(&mut self, other: Tally) {
+        self.count += other.count;
+        self.source = self.source.min(other.source);
+    }
+}
+
+// Location of a stored line inside the arena
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    hash: u64,    // Hash of the line's key, reused when the index grows
+    segment: u32, // Index of the segment holding the line
+    offset: u32,  // Byte offset within the segment
+    len: u32,     // Length of the line in bytes
+}
+
+// Compact set of unique lines stored back to back in large arena segments
+//
+// Each line costs its bytes, a 4-byte length prefix and a 24-byte index span holding its hash,
+// instead of a separate heap allocation and 24-byte `String` header per line.
+// With tallying, another 12 bytes ahead of each line hold its `Tally`.
+pub struct LineStore {
+    segments: Vec<Vec<u8>>,  // Arena segments; never reallocated once full
+    index: HashTable<Span>,  // Hash index over the stored spans
+    bytes: u64,              // Total bytes of stored lines
+    tally: bool,             // Whether a count and source are kept with every line
+    key: DedupKey,           // Part of each line that identifies it
+}
+
+impl LineStore {
+    // Create an empty store with room for `lines` index entries
+    //
+    // Lines arrive with their hashes, so every caller must hash with the same `RandomState`.
+    pub fn with_capacity(lines: usize) -> Self {
+        Self {
+            segments: Vec::new(),
+            index: HashTable::with_capacity(lines),
+            bytes: 0,
+            tally: false,
+            key: DedupKey::default(),
+        }
+    }
+
+    // Treat lines as duplicates when their `key` parts match, keeping the first one
+    //
+    // Callers must hash the key part rather than the whole line.
+    pub fn with_key(mut self, key: DedupKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    // Part of each line that identifies it
+    pub fn key(&self) -> DedupKey {
+        self.key
+    }
+
+    // Keep an occurrence count and first source with every line
+    pub fn with_tally(mut self, enabled: bool) -> Self {
+        self.tally = enabled;
+        self
+    }
+
+    // Whether lines are stored with their `Tally`
+    pub fn is_tallied(&self) -> bool {
+        self.tally
+    }
+
+    // Number of unique lines stored
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    // Whether no lines are stored
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    // Size of the stored lines once written out with a newline each
+    pub fn output_bytes(&self) -> u64 {
+        self.bytes + self.len() as u64
+    }
+
+    // Estimated heap footprint of the arena and index in bytes
+    pub fn footprint(&self) -> u64 {
+        let arena: usize = self.segments.iter().map(Vec::capacity).sum();
+        let index = self.index.capacity() * (std::mem::size_of::<Span>() + 1);
+        (arena + index) as u64
+    }
+
+    // Drop every stored line and release the arena and index memory
+    pub fn clear(&mut self) {
+        self.segments = Vec::new();
+        self.index = HashTable::new();
+        self.bytes = 0;
+    }
+
+    // Stored lines with their tallies in byte order of their keys, as written to a spill run
+    pub fn sorted(&self) -> Vec<(&[u8], Tally)> {
+        let mut records: Vec<(&[u8], Tally)> = self.records().collect();
+        let key = self.key;
+        records.sort_unstable_by(|a, b| key.key(a.0).cmp(key.key(b.0)));
+        records
+    }
+
+    // Store `line` under the precomputed `hash` of its key if it is new; returns true when it was inserted
+    pub fn insert(&mut self, hash: u64, line: &[u8]) -> bool {
+        self.insert_tallied(hash, line, Tally::once(0))
+    }
+
+    // Store `line` like `insert`, adding `tally` to its count and source when tallying
+    pub fn insert_tallied(&mut self, hash: u64, line: &[u8], tally: Tally) -> bool {
+        let key = self.key;
+        let wanted = key.key(line);
+        let found = self.index.find(hash, |span| span.hash == hash && key.key(Self::resolve(&self.segments, *span)) == wanted);
+        if let Some(span) = found.copied() {
+            if self.tally {
+                let mut stored = Self::read_tally(&self.segments, span);
+                stored.merge(tally);
+                Self::write_tally(&mut self.segments, span, stored);
+            }
+            return false;
+        }
+
+        let span = self.push_bytes(hash, line, tally);
+        // Growing the index moves spans by their stored hashes, without reading a line back
+        self.index.insert_unique(hash, span, |span| span.hash);
+        true
+    }
+
+    // Iterate over stored lines in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.records().map(|(line, _)| line)
+    }
+
+    // Iterate over stored lines and their tallies in insertion order
+    //
+    // Without tallying every line reports a single occurrence in input 0.
+    pub fn records(&self) -> impl Iterator<Item = (&[u8], Tally)> {
+        let tally_size = if self.tally { TALLY_SIZE } else { 0 };
+        self.segments.iter().flat_map(move |segment| {
+            // Each segment holds its lines back to back, each prefixed by its u32 length and tally
+            let mut rest = segment.as_slice();
+            std::iter::from_fn(move || {
+                if rest.is_empty() {
+                    return None;
+                }
+                let (len, tail) = rest.split_at(4);
+                let len = u32::from_le_bytes(len.try_into().expect("4-byte length prefix")) as usize;
+                let (tally, tail) = tail.split_at(tally_size);
+                let (line, tail) = tail.split_at(len);
+                rest = tail;
+                let tally = if tally.is_empty() { Tally::once(0) } else { Self::decode_tally(tally) };
+                Some((line, tally))
+            })
+        })
+    }
+
+    // Copy `line` into the arena, opening a new segment when the current one is full
+    fn push_bytes(&mut self, hash: u64, line: &[u8], tally: Tally) -> Span {
+        let tally_size = if self.tally { TALLY_SIZE } else { 0 };
+        let needed = line.len() + 4 + tally_size;
+        let fits = self.segments.last().is_some_and(|s| s.capacity() - s.len() >= needed);
+        if !fits {
+            // Oversized lines get a segment of their own so nothing is ever reallocated
+            self.segments.push(Vec::with_capacity(needed.max(SEGMENT_SIZE)));
+        }
+
+        let segment_index = self.segments.len() - 1;
+        let segment = &mut self.segments[segment_index];
+        segment.extend_from_slice(&(line.len() as u32).to_le_bytes());
+        if self.tally {
+            segment.extend_from_slice(&tally.count.to_le_bytes());
+            segment.extend_from_slice(&(tally.source as u32).to_le_bytes());
+        }
+        let offset = segment.len();
+        segment.extend_from_slice(line);
+        self.bytes += line.len() as u64;
+
+        Span {
+            hash,
+            segment: segment_index as u32,
+            offset: offset as u32,
+            len: line.len() as u32,
+        }
+    }
+
+    // Bytes of the line at `span` within `segments`
+    fn resolve(segments: &[Vec<u8>], span: Span) -> &[u8] {
+        let start = span.offset as usize;
+        &segments[span.segment as usize][start..start + span.len as usize]
+    }
+
+    // Tally stored just ahead of the line at `span`
+    fn read_tally(segments: &[Vec<u8>], span: Span) -> Tally {
+        let start = span.offset as usize - TALLY_SIZE;
+        Self::decode_tally(&segments[span.segment as usize][start..span.offset as usize])
+    }
+
+    // Overwrite the tally stored just ahead of the line at `span`
+    fn write_tally(segments: &mut [Vec<u8>], span: Span, tally: Tally) {
+        let start = span.offset as usize - TALLY_SIZE;
+        let bytes = &mut segments[span.segment as usize][start..span.offset as usize];
+        bytes[..8].copy_from_slice(&tally.count.to_le_bytes());
+        bytes[8..].copy_from_slice(&(tally.source as u32).to_le_bytes());
+    }
+
+    // Tally from its 12 stored bytes: a u64 count then a u32 source
+    fn decode_tally(bytes: &[u8]) -> Tally {
+        let (count, source) = bytes.split_at(8);
+        Tally {
+            count: u64::from_le_bytes(count.try_into().expect("8-byte count")),
+            source: u32::from_le_bytes(source.try_into().expect("4-byte source")) as usize,
+        }
+    }
+}
+
+// Lines deduplicated by a reader task, each kept with its precomputed hash
+//
+// The aggregator drains these straight into a `LineStore` without hashing
+// any line a second time.
+pub struct LineChunk {
+    table: HashTable<(u64, String, u64)>, // Unique lines of the chunk with their hashes and occurrence counts
+    pub source: usize,                    // Position of the input the lines came from
+}
+
+impl LineChunk {
+    // Create an empty chunk for input `source` with room for `lines` entries
+    pub fn with_capacity(lines: usize, source: usize) -> Self {
+        Self { table: HashTable::with_capacity(lines), source }
+    }
+
+    // Add `weight` occurrences of `line` under the precomputed `hash` of its `key`;
+    // returns true when it was new to this chunk
+    pub fn insert(&mut self, hash: u64, line: &str, weight: u64, key: DedupKey) -> bool {
+        let wanted = key.key(line.as_bytes());
+        let entry = self.table.entry(
+            hash,
+            |(h, l, _)| *h == hash && key.key(l.as_bytes()) == wanted,
+            |(h, _, _)| *h,
+        );
+        match entry {
+            Entry::Occupied(mut slot) => {
+                slot.get_mut().2 += weight;
+                false
+            }
+            Entry::Vacant(slot) => {
+                slot.insert((hash, line.to_owned(), weight));
+                true
+            }
+        }
+    }
+
+    // Whether the chunk holds no lines
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    // Remove every line together with its hash and occurrence count
+    pub fn drain(&mut self) -> impl Iterator<Item = (u64, String, u64)> + '_ {
+        self.table.drain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineStore, Tally};
+    use crate::dedup_key::DedupKey;
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+
+    #[test]
+    fn keeps_the_first_copy_of_each_line() {
+        let hasher = RandomState::new();
+        let mut store = LineStore::with_capacity(0);
+        for line in [&b"alpha"[..], b"beta", b"alpha", b"gamma", b"beta"] {
+            store.insert(hasher.hash_one(line), line);
+        }
+        assert_eq!(store.iter().collect::<Vec<_>>(), vec![&b"alpha"[..], b"beta", b"gamma"]);
+        assert_eq!(store.output_bytes(), 5 + 4 + 5 + 3);
+    }
+
+    #[test]
+    fn lines_stay_findable_as_the_index_grows() {
+        let hasher = RandomState::new();
+        let mut store = LineStore::with_capacity(0);
+        let lines: Vec<Vec<u8>> = (0..10_000).map(|n| format!("line{}", n).into_bytes()).collect();
+        for line in &lines {
+            assert!(store.insert(hasher.hash_one(line), line));
+        }
+        for line in &lines {
+            assert!(!store.insert(hasher.hash_one(line), line), "{:?} was lost on resize", line);
+        }
+        assert_eq!(store.len(), lines.len());
+    }
+
+    #[test]
+    fn equal_hashes_still_compare_the_bytes() {
+        let mut store = LineStore::with_capacity(0);
+        assert!(store.insert(7, b"one"));
+        assert!(store.insert(7, b"two"));
+        assert!(!store.insert(7, b"one"));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn tallies_count_copies_and_keep_the_first_source() {
+        let hasher = RandomState::new();
+        let mut store = LineStore::with_capacity(0).with_tally(true);
+        store.insert_tallied(hasher.hash_one(b"word"), b"word", Tally::once(3));
+        store.insert_tallied(hasher.hash_one(b"word"), b"word", Tally { count: 2, source: 1 });
+        assert_eq!(store.records().collect::<Vec<_>>(), vec![(&b"word"[..], Tally { count: 3, source: 1 })]);
+    }
+
+    #[test]
+    fn password_key_keeps_one_line_per_password() {
+        let hasher = RandomState::new();
+        let key = DedupKey::Password;
+        let mut store = LineStore::with_capacity(0).with_key(key);
+        for line in [&b"alice:hunter2"[..], b"bob:hunter2", b"carol:letmein"] {
+            store.insert(hasher.hash_one(key.key(line)), line);
+        }
+        let sorted: Vec<&[u8]> = store.sorted().into_iter().map(|(line, _)| line).collect();
+        assert_eq!(sorted, vec![&b"alice:hunter2"[..], b"carol:letmein"]);
+    }
+}
@@ -1,72 +1,204 @@
-use anyhow::Result; // Import the Result type from the anyhow crate for error handling
 use clap::Parser; // Import the Parser trait from the clap crate for command-line argument parsing
-use log::{info, error}; // Import the info macro and error macro from the log crate for logging
-use std::sync::Arc; // Import the Arc type from the std::sync crate for shared ownership
-use ctrlc; // Import the ctrlc crate for handling Ctrl+C signals
 
 // Declare the modules used in the application
 mod cli; // Module for command-line interface definitions
 mod commands; // Module for handling different commands
 mod config; // Module for configuration management
+mod config_template; // Module for commented configuration files
+mod preset; // Module for named bundles of settings
 mod core; // Module for core processing logic
 mod app_state; // Module for application state management
 mod progress; // Module for progress tracking
 mod signal_handler; // Module for signal handling
 mod errors; // Add this line
+mod docs; // Module for man page and markdown reference generation
+mod emit_config; // Module for folding merge options into a reusable config
+mod estimate; // Module for sampling-based resource estimates
+mod plan; // Module for previewing the processing schedule
+mod selftest; // Module for the built-in self-test suite
+mod mmap_reader; // Module for memory-mapped input reading
+mod io_backend; // Module for selecting the file I/O backend
+mod line_store; // Module for compact storage of unique lines
+mod shards; // Module for hash-partitioned deduplication shards
+mod memory_monitor; // Module for memory-pressure-aware chunk sizing
+mod spill; // Module for spilling deduplication sets to disk
+mod dedup_backend; // Module for choosing how the set of seen lines is kept
+mod stages; // Module for the groups and intermediates of hierarchical merges
+mod distributed; // Module for the worker and coordinator of distributed merges
+mod tuning; // Module for buffer and chunk tuning knobs
+mod scratch; // Module for the temporary directory
+mod preflight; // Module for the disk-space preflight
+mod fd_limit; // Module for open file descriptor limits
+mod retry; // Module for retrying transient read errors
+mod input_list; // Module for reading and normalizing the input list
+mod rules; // Module for merging rule files
+mod rule_parser; // Module for splitting hashcat rules into their functions
+mod failed_files; // Module for the machine-readable skip list of failed inputs
+mod report; // Module for the end-of-run issue summary and JSON report
+mod exit_code; // Module for the documented process exit codes
+mod contribution; // Module for per-file contribution statistics
+mod manifest; // Module for checksummed input manifests
+mod check; // Module for verifying merged outputs
+mod encoding; // Module for encoding detection and conversion
+mod hex_format; // Module for hashcat $HEX[...] notation
+mod line_options; // Module for options applied to every input line
+mod input_format; // Module for dictionary and other input line layouts
+mod sqlite_source; // Module for candidates read from SQLite queries
+mod output_format; // Module for SQLite and Parquet outputs
+mod dedup_key; // Module for choosing which part of combo lines identifies them
+mod redundancy; // Module for ranking inputs by unique contribution
+mod near_dupes; // Module for clustering lines within one edit of each other
+mod strength; // Module for zxcvbn-style password strength scores
+mod transform; // Module for case and leet variants of each word
+mod language; // Module for splitting the output by detected language
+mod patterns; // Module for spotting keyboard walks and sequences
+mod side_output; // Module for extra files written beside the main output
+mod routing; // Module for diverting kinds of candidates to their own files
+mod plugin; // Module for per-line filter and transform plugins in shared libraries
+mod script; // Module for Rhai scripts deciding the fate of each line
+mod pipeline; // Module for the ordered filter and transform stages run on every candidate
+mod tee; // Module for extra filtered outputs written in the same pass
+mod base_words; // Module for the base word list written in the same pass
+mod status; // Module for the live figures of a running merge
+mod web_ui; // Module for the HTTP status page of a running merge
+mod tui; // Module for the full-screen dashboard of a running merge
+mod notify; // Module for webhook notifications when a merge ends
+mod history; // Module for the journal of finished runs
+mod session; // Module for named resume sessions
+mod pause; // Module for pausing a running merge
+mod throttle; // Module for read and write rate limits
+mod priority; // Module for process priority controls
+mod cardinality; // Module for HyperLogLog estimates of the final unique count
+mod encoding_cache; // Module for encodings remembered across runs
+mod homoglyph; // Module for folding look-alike characters to ASCII
+mod content_type; // Module for recognizing non-text inputs by their magic bytes
+mod fetch; // Module for downloading well-known lists into a local cache
+mod download_cache; // Module for the cache of downloaded URL inputs
+#[allow(dead_code)]
+mod file_utils; // Module for file utility helpers
 
 // Import specific items from the cli and commands modules
 use cli::{Cli, Commands}; // Import the Cli struct and Commands enum from the cli module
 use commands::CommandHandler; // Import the CommandHandler struct from the commands module
-use crate::core::ProcessingCore;
-use crate::app_state::AppState;
-use crate::errors::{MergerError, MergerResult};
+use crate::errors::MergerResult;
+use crate::exit_code::{RunOutcome, EXIT_FATAL};
+use std::process::ExitCode; // Import ExitCode to report the run outcome
 
 // Main asynchronous function
 #[tokio::main] // Macro to set up the Tokio runtime
-async fn main() -> MergerResult<()> {
+async fn main() -> ExitCode {
     // Parse command-line arguments into the Cli struct
     let cli = Cli::parse();
     
     // Initialize the logger with the log level specified in the command-line arguments
     env_logger::builder().filter_level(cli.log_level()).init();
 
-    // Match on the command provided in the command-line arguments
-    match cli.command {
+    // Map the result onto the documented exit codes
+    match run(cli).await {
+        Ok(outcome) => outcome.exit_code(),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(EXIT_FATAL)
+        }
+    }
+}
+
+// Run the command provided in the command-line arguments
+async fn run(cli: Cli) -> MergerResult<RunOutcome> {
+    let outcome = match cli.command {
         // Handle the "merge" command
         Commands::Merge(ref args) => {
-            CommandHandler::handle_merge(&cli, args.clone()).await?;
+            CommandHandler::handle_merge(&cli, args.clone()).await?
         }
         // Handle the "generate-config" command
         Commands::GenerateConfig(args) => {
             CommandHandler::handle_generate_config(args).await?;
+            RunOutcome::Success
         }
         // Handle the "guided-setup" command
         Commands::GuidedSetup(args) => {
             CommandHandler::handle_guided_setup(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "mangen" command
+        Commands::Mangen(args) => {
+            CommandHandler::handle_mangen(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "estimate" command
+        Commands::Estimate(args) => {
+            CommandHandler::handle_estimate(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "plan" command
+        Commands::Plan(args) => {
+            CommandHandler::handle_plan(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "selftest" command
+        Commands::Selftest(args) => {
+            CommandHandler::handle_selftest(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "retry-failed" command
+        Commands::RetryFailed(args) => {
+            CommandHandler::handle_retry_failed(args).await?
+        }
+        // Handle the "manifest" command
+        Commands::Manifest(args) => {
+            CommandHandler::handle_manifest(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "check" command
+        Commands::Check(args) => {
+            CommandHandler::handle_check(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "convert-encoding" command
+        Commands::ConvertEncoding(args) => {
+            CommandHandler::handle_convert_encoding(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "hex" command
+        Commands::Hex(args) => {
+            CommandHandler::handle_hex(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "redundancy" command
+        Commands::Redundancy(args) => {
+            CommandHandler::handle_redundancy(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "analyze" command
+        Commands::Analyze(args) => {
+            CommandHandler::handle_analyze(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "worker" command
+        Commands::Worker(args) => {
+            CommandHandler::handle_worker(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "history" command
+        Commands::History(args) => {
+            CommandHandler::handle_history(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "sessions" command
+        Commands::Sessions(args) => {
+            CommandHandler::handle_sessions(args).await?;
+            RunOutcome::Success
+        }
+        // Handle the "fetch" command
+        Commands::Fetch(args) => {
+            CommandHandler::handle_fetch(args).await?;
+            RunOutcome::Success
         }
         // Handle the "resume" command
         Commands::Resume(args) => {
-            let state: AppState = AppState::from_resume(args.progress_file).await?;
-            let state = Arc::new(state);
-            
-            // Set up Ctrl+C handler
-            let state_clone = Arc::clone(&state);
-            ctrlc::set_handler(move || {
-                let state = state_clone.clone();
-                tokio::spawn(async move {
-                    info!("Received Ctrl+C, saving progress...");
-                    if let Err(e) = state.save_progress().await {
-                        error!("Failed to save progress: {}", e);
-                    }
-                    state.request_shutdown().await;
-                });
-            })?;
-
-            // Resume merger
-            let mut core = ProcessingCore::new(state.clone(), true, true).await?;
-            core.process().await?;
+            CommandHandler::handle_resume(args).await?
         }
-    }
+    };
 
-    Ok(())
+    Ok(outcome)
 }
@@ -0,0 +1,187 @@
+use chrono::{DateTime, Local}; // Import chrono for modification times and the creation date
+use futures::stream::{self, StreamExt}; // Import stream helpers to hash several inputs at once
+use serde::{Deserialize, Serialize}; // Import serde traits for the JSON manifest
+use sha2::{Digest, Sha256}; // Import SHA-256 for content checksums
+use std::collections::HashMap; // Import HashMap to look up recorded entries by path
+use std::io::Read; // Import Read to stream files through the hasher
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for input paths
+use crate::errors::{MergerError, MergerResult}; // Import the error types for I/O and mismatches
+use crate::input_list::InputList; // Import InputList to read the wordlist listing
+use crate::sqlite_source::{self, SqliteSource}; // Import sqlite_source to hash the database behind a query
+
+const HASH_BUFFER_SIZE: usize = 1024 * 1024; // Read 1MB at a time while hashing
+const CONCURRENT_HASHES: usize = 4; // Inputs hashed at once
+
+// Recorded identity of one input file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: PathBuf,              // Path as listed in the wordlists file
+    pub size: u64,                  // Size in bytes
+    pub modified: DateTime<Local>,  // Last modification time
+    pub sha256: String,             // Hex-encoded SHA-256 of the contents
+}
+
+impl ManifestEntry {
+    // Stat and hash `path`; for a database query, the database it reads
+    pub async fn compute(path: &Path) -> MergerResult<Self> {
+        let file = if sqlite_source::is_sqlite(path) {
+            SqliteSource::parse(path)?.database
+        } else {
+            path.to_path_buf()
+        };
+        let metadata = tokio::fs::metadata(&file).await?;
+        let sha256 = tokio::task::spawn_blocking({
+            move || sha256_file(&file)
+        }).await??;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified: metadata.modified()?.into(),
+            sha256,
+        })
+    }
+}
+
+// Checksummed record of every input of a merge, for reproducible and auditable builds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub created_at: DateTime<Local>, // When the manifest was generated
+    pub files: Vec<ManifestEntry>,   // Inputs in listing order
+}
+
+impl Manifest {
+    // Record every input listed in `input_list`
+    pub async fn build(input_list: &Path) -> MergerResult<Self> {
+        let list = InputList::load(input_list).await?;
+        let files = Self::compute_all(&list.files).await?;
+        Ok(Self { created_at: Local::now(), files })
+    }
+
+    // Load a manifest written by `rustmerger manifest`
+    pub async fn load(path: &Path) -> MergerResult<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+        serde_json::from_str(&content)
+            .map_err(|e| MergerError::InputValidation(format!("{}: {}", path.display(), e)))
+    }
+
+    // Write the manifest as pretty-printed JSON
+    pub async fn save(&self, path: &Path) -> MergerResult<()> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| MergerError::Processing(e.to_string()))?;
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+
+    // Differences between the manifest and the inputs now listed in `input_list`
+    //
+    // Inputs count as changed when their size or checksum differs; a new modification
+    // time alone (a copy or `touch`) is not a change of content.
+    pub async fn verify(&self, input_list: &Path) -> MergerResult<Vec<String>> {
+        let list = InputList::load(input_list).await?;
+        let recorded: HashMap<&Path, &ManifestEntry> = self.files.iter()
+            .map(|entry| (entry.path.as_path(), entry))
+            .collect();
+        let mut problems = Vec::new();
+
+        for path in &list.files {
+            let Some(expected) = recorded.get(path.as_path()) else {
+                problems.push(format!("{}: not in the manifest", path.display()));
+                continue;
+            };
+            let size = match sqlite_source::input_size(path).await {
+                Ok(size) => size,
+                Err(e) => {
+                    problems.push(format!("{}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            if size != expected.size {
+                problems.push(format!("{}: size is {} bytes, manifest has {}", path.display(), size, expected.size));
+                continue;
+            }
+            let actual = ManifestEntry::compute(path).await?;
+            if actual.sha256 != expected.sha256 {
+                problems.push(format!("{}: SHA-256 differs from the manifest", path.display()));
+            }
+        }
+
+        let listed: Vec<&Path> = list.files.iter().map(PathBuf::as_path).collect();
+        for entry in &self.files {
+            if !listed.contains(&entry.path.as_path()) {
+                problems.push(format!("{}: in the manifest but no longer listed", entry.path.display()));
+            }
+        }
+
+        Ok(problems)
+    }
+
+    // Stat and hash `paths`, a few at a time, keeping their order
+    async fn compute_all(paths: &[PathBuf]) -> MergerResult<Vec<ManifestEntry>> {
+        stream::iter(paths)
+            .map(|path| async move {
+                ManifestEntry::compute(path).await
+                    .map_err(|e| MergerError::InputValidation(format!("{}: {}", path.display(), e)))
+            })
+            .buffered(CONCURRENT_HASHES)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+// Hex-encoded SHA-256 of the file at `path`
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; HASH_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sha256_file, Manifest};
+
+    #[test]
+    fn checksums_are_hex_sha256() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("empty.txt");
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(sha256_file(&path).unwrap(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[tokio::test]
+    async fn verification_reports_changed_added_and_removed_inputs() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let [kept, changed, resized, dropped, added] = ["kept", "changed", "resized", "dropped", "added"].map(|name| dir.join(format!("{}.txt", name)));
+        for path in [&kept, &changed, &resized, &dropped, &added] {
+            std::fs::write(path, "alpha\n").unwrap();
+        }
+        let list = dir.join("list.txt");
+        let listing = |paths: &[&std::path::PathBuf]| paths.iter().map(|path| format!("{}\n", path.display())).collect::<String>();
+        std::fs::write(&list, listing(&[&kept, &changed, &resized, &dropped])).unwrap();
+        let manifest = Manifest::build(&list).await.unwrap();
+        assert_eq!(manifest.files.len(), 4);
+        assert!(manifest.verify(&list).await.unwrap().is_empty());
+
+        std::fs::write(&changed, "bravo\n").unwrap();
+        std::fs::write(&resized, "alpha\nbravo\n").unwrap();
+        std::fs::write(&list, listing(&[&kept, &changed, &resized, &added])).unwrap();
+        let problems = manifest.verify(&list).await.unwrap();
+        assert_eq!(problems, [
+            format!("{}: SHA-256 differs from the manifest", changed.display()),
+            format!("{}: size is 12 bytes, manifest has 6", resized.display()),
+            format!("{}: not in the manifest", added.display()),
+            format!("{}: in the manifest but no longer listed", dropped.display()),
+        ]);
+    }
+}
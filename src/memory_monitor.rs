@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering}; // Import atomics for limits shared with reader tasks
+use std::sync::Arc; // Import Arc to share the monitor with its sampling task
+use std::time::Duration; // Import Duration for the sampling interval
+use tokio::task::JoinHandle; // Import JoinHandle so the sampler can be stopped
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500); // How often memory is sampled
+const COMFORTABLE_FRACTION: f64 = 0.25; // Available/total ratio at which limits are not reduced
+const MIN_SCALE: f64 = 1.0 / 16.0; // Smallest fraction of the initial limits ever used
+const MIN_CHUNK_BYTES: usize = 1024 * 1024; // Floor for the per-chunk byte limit
+const MIN_CHUNK_LINES: usize = 1024; // Floor for the per-chunk line limit
+
+// Chunk limits that shrink as system memory tightens during a merge
+pub struct MemoryMonitor {
+    max_bytes: usize,         // Byte limit when memory is plentiful
+    max_lines: usize,         // Line limit when memory is plentiful
+    chunk_bytes: AtomicUsize, // Current byte limit per reader chunk
+    chunk_lines: AtomicUsize, // Current line limit per reader chunk
+    resident: AtomicU64,      // Resident set size at the latest sample, 0 when unknown
+    peak_resident: AtomicU64, // Largest resident set size sampled so far
+}
+
+impl MemoryMonitor {
+    // Create a monitor starting at the given limits
+    pub fn new(max_bytes: usize, max_lines: usize) -> Self {
+        Self {
+            max_bytes,
+            max_lines,
+            chunk_bytes: AtomicUsize::new(max_bytes),
+            chunk_lines: AtomicUsize::new(max_lines),
+            resident: AtomicU64::new(0),
+            peak_resident: AtomicU64::new(0),
+        }
+    }
+
+    // Bytes a reader may buffer before sending its chunk
+    pub fn chunk_bytes(&self) -> usize {
+        self.chunk_bytes.load(Ordering::Relaxed)
+    }
+
+    // Unique lines a reader may buffer before sending its chunk
+    pub fn chunk_lines(&self) -> usize {
+        self.chunk_lines.load(Ordering::Relaxed)
+    }
+
+    // Resident set size of the process at the latest sample, if the platform exposes it
+    pub fn resident(&self) -> Option<u64> {
+        Some(self.resident.load(Ordering::Relaxed)).filter(|&rss| rss > 0)
+    }
+
+    // Largest resident set size sampled during the merge
+    pub fn peak_resident(&self) -> Option<u64> {
+        Some(self.peak_resident.load(Ordering::Relaxed)).filter(|&rss| rss > 0)
+    }
+
+    // Record a resident set size sample
+    pub fn record_resident(&self, rss: u64) {
+        self.resident.store(rss, Ordering::Relaxed);
+        self.peak_resident.fetch_max(rss, Ordering::Relaxed);
+    }
+
+    // Sample memory in the background until the returned task is aborted
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Some(rss) = resident_set_size() {
+                    monitor.record_resident(rss);
+                }
+                if let Ok(mem) = sys_info::mem_info() {
+                    monitor.adjust(mem.avail, mem.total);
+                }
+            }
+        })
+    }
+
+    // Scale the limits by how much memory is still available
+    pub fn adjust(&self, available: u64, total: u64) {
+        if total == 0 {
+            return;
+        }
+
+        // Full limits at COMFORTABLE_FRACTION available or more, shrinking linearly below it
+        let fraction = available as f64 / total as f64;
+        let scale = (fraction / COMFORTABLE_FRACTION).clamp(MIN_SCALE, 1.0);
+        let bytes = ((self.max_bytes as f64 * scale) as usize).clamp(MIN_CHUNK_BYTES.min(self.max_bytes), self.max_bytes);
+        let lines = ((self.max_lines as f64 * scale) as usize).clamp(MIN_CHUNK_LINES.min(self.max_lines), self.max_lines);
+
+        let previous = self.chunk_bytes.swap(bytes, Ordering::Relaxed);
+        self.chunk_lines.store(lines, Ordering::Relaxed);
+
+        if previous != bytes {
+            log::debug!(
+                "Memory {:.0}% available (RSS {}): chunk limits now {} bytes / {} lines",
+                fraction * 100.0,
+                self.resident().map_or_else(|| "unknown".to_string(), |rss| format!("{} KB", rss / 1024)),
+                bytes,
+                lines
+            );
+        }
+    }
+}
+
+// Resident set size of this process in bytes, where the platform exposes it
+pub fn resident_set_size() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        // Second field of /proc/self/statm is resident pages, sized by the kernel's page size
+        let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+        let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+        // Safety: sysconf only reads a system constant
+        let page_size = u64::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).ok().filter(|&size| size > 0)?;
+        Some(pages * page_size)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryMonitor;
+
+    #[test]
+    fn peak_resident_keeps_the_largest_sample() {
+        let monitor = MemoryMonitor::new(1 << 20, 1000);
+        assert_eq!((monitor.resident(), monitor.peak_resident()), (None, None));
+        monitor.record_resident(300);
+        monitor.record_resident(100);
+        assert_eq!((monitor.resident(), monitor.peak_resident()), (Some(100), Some(300)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn resident_size_is_a_whole_number_of_pages() {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+        let rss = super::resident_set_size().expect("statm is readable on Linux");
+        assert!(rss > 0);
+        assert_eq!(rss % page_size, 0);
+    }
+}
@@ -0,0 +1,90 @@
+use memchr::memchr_iter; // Import the SIMD-accelerated newline iterator
+use memmap2::Mmap; // Import Mmap for read-only memory-mapped files
+use std::fs::File; // Import File to open the file being mapped
+use std::io; // Import io for error types
+use std::path::Path; // Import Path for file path handling
+
+// Struct providing memory-mapped access to local input files
+pub struct MmapReader;
+
+impl MmapReader {
+    // Map a regular, non-empty file read-only; returns None when mapping does not apply
+    pub fn open(path: &Path) -> io::Result<Option<Mmap>> {
+        let file = File::open(path)?;
+        let meta = file.metadata()?;
+        if !meta.is_file() || meta.len() == 0 {
+            return Ok(None);
+        }
+
+        // Safety: the map is read-only and inputs are not expected to change during a merge;
+        // a concurrent truncation would fault, the same trade-off every mmap reader makes
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        // Lines are consumed front to back, so let the kernel read ahead aggressively
+        #[cfg(unix)]
+        let _ = mmap.advise(memmap2::Advice::Sequential);
+
+        Ok(Some(mmap))
+    }
+
+    // Iterate over the lines in `data`, without their trailing newline
+    pub fn lines(data: &[u8]) -> MappedLines<'_> {
+        MappedLines {
+            data,
+            newlines: memchr_iter(b'\n', data),
+            start: 0,
+        }
+    }
+}
+
+// Iterator over newline-separated lines of a mapped file
+pub struct MappedLines<'a> {
+    data: &'a [u8],                      // Whole mapped file
+    newlines: memchr::Memchr<'a>,        // Positions of the remaining newlines
+    start: usize,                        // Start offset of the next line
+}
+
+impl<'a> Iterator for MappedLines<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.newlines.next() {
+            Some(end) => {
+                let line = &self.data[self.start..end];
+                self.start = end + 1;
+                Some(line)
+            }
+            // A final line without a trailing newline is still a line
+            None if self.start < self.data.len() => {
+                let line = &self.data[self.start..];
+                self.start = self.data.len();
+                Some(line)
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MmapReader;
+
+    #[test]
+    fn lines_split_on_newlines_with_or_without_a_final_one() {
+        let lines: Vec<&[u8]> = MmapReader::lines(b"alpha\n\nbeta\r\ngamma").collect();
+        assert_eq!(lines, [&b"alpha"[..], b"", b"beta\r", b"gamma"]);
+        assert_eq!(MmapReader::lines(b"alpha\n").count(), 1);
+        assert_eq!(MmapReader::lines(b"").count(), 0);
+    }
+
+    #[test]
+    fn only_non_empty_files_are_mapped() {
+        let temp = tempfile::tempdir().unwrap();
+        let (empty, words) = (temp.path().join("empty.txt"), temp.path().join("words.txt"));
+        std::fs::write(&empty, "").unwrap();
+        std::fs::write(&words, "alpha\nbeta\n").unwrap();
+        assert!(MmapReader::open(&empty).unwrap().is_none());
+        assert_eq!(&MmapReader::open(&words).unwrap().unwrap()[..], b"alpha\nbeta\n");
+        assert!(MmapReader::open(&temp.path().join("missing.txt")).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use serde::Serialize; // Import Serialize for the JSON report
+use std::collections::hash_map::RandomState; // Import RandomState to hash lines and their deletes
+use std::collections::{HashMap, HashSet}; // Import maps keyed by hashes
+use std::hash::BuildHasher; // Import BuildHasher to hash byte slices
+use std::io::{BufRead, BufReader}; // Import buffered reading to stream the input
+use std::path::Path; // Import Path for the input
+use crate::errors::MergerResult; // Import MergerResult for read errors
+
+const READ_BUFFER_SIZE: usize = 1024 * 1024; // Read the input 1MB at a time
+
+// Lines within one edit of each other, linked directly or through other members
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDupeCluster {
+    pub lines: Vec<String>, // Members in input order
+}
+
+// Clusters of near-duplicate lines in one file
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDupeReport {
+    pub distinct_lines: u64,            // Different lines compared
+    pub skipped_lines: u64,             // Lines outside the compared length range
+    pub clustered_lines: u64,           // Lines in a cluster of two or more
+    pub clusters: Vec<NearDupeCluster>, // Clusters, largest first
+}
+
+impl NearDupeReport {
+    // Cluster the distinct lines of `input` that are one insertion, deletion or substitution apart
+    //
+    // Uses a SymSpell-style index: every line is filed under itself and each single-character
+    // deletion of it, so two lines one edit apart always share an entry. Memory grows with the
+    // total characters of the compared lines, which `min_chars` and `max_chars` bound.
+    pub fn build(input: &Path, min_chars: usize, max_chars: usize) -> MergerResult<Self> {
+        let hasher = RandomState::new();
+        let mut seen: HashSet<u64> = HashSet::new();
+        let mut lines: Vec<String> = Vec::new();
+        let mut index: HashMap<u64, Vec<u32>> = HashMap::new(); // Lines filed under each variant
+        let mut skipped_lines = 0;
+
+        let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, std::fs::File::open(input)?);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.is_empty() || !seen.insert(hasher.hash_one(line)) {
+                continue;
+            }
+            let line = String::from_utf8_lossy(line).into_owned();
+            let chars = line.chars().count();
+            if chars < min_chars || chars > max_chars {
+                skipped_lines += 1;
+                continue;
+            }
+
+            let id = lines.len() as u32;
+            let mut variants: Vec<u64> = vec![hasher.hash_one(line.as_bytes())];
+            let mut variant = Vec::with_capacity(line.len());
+            for (start, c) in line.char_indices() {
+                variant.clear();
+                variant.extend_from_slice(&line.as_bytes()[..start]);
+                variant.extend_from_slice(&line.as_bytes()[start + c.len_utf8()..]);
+                variants.push(hasher.hash_one(&variant));
+            }
+            // A repeated letter gives the same deletion more than once
+            variants.sort_unstable();
+            variants.dedup();
+            for variant in variants {
+                index.entry(variant).or_default().push(id);
+            }
+            lines.push(line);
+        }
+
+        // Lines sharing an entry may still be two edits apart, such as a swapped pair
+        let mut clusters = UnionFind::new(lines.len());
+        for ids in index.values().filter(|ids| ids.len() > 1) {
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    if clusters.find(*a) != clusters.find(*b) && within_one_edit(&lines[*a as usize], &lines[*b as usize]) {
+                        clusters.union(*a, *b);
+                    }
+                }
+            }
+        }
+
+        let mut members: HashMap<u32, Vec<u32>> = HashMap::new();
+        for id in 0..lines.len() as u32 {
+            members.entry(clusters.find(id)).or_default().push(id);
+        }
+        let mut found: Vec<NearDupeCluster> = members.into_values()
+            .filter(|ids| ids.len() > 1)
+            .map(|mut ids| {
+                ids.sort_unstable();
+                NearDupeCluster { lines: ids.into_iter().map(|id| lines[id as usize].clone()).collect() }
+            })
+            .collect();
+        found.sort_by(|a, b| b.lines.len().cmp(&a.lines.len()).then_with(|| a.lines.cmp(&b.lines)));
+
+        Ok(Self {
+            distinct_lines: lines.len() as u64 + skipped_lines,
+            skipped_lines,
+            clustered_lines: found.iter().map(|cluster| cluster.lines.len() as u64).sum(),
+            clusters: found,
+        })
+    }
+
+    // Print the totals and the `show` largest clusters
+    pub fn print_summary(&self, show: usize) {
+        println!(
+            "{} of {} distinct lines fall in {} clusters of lines within one edit of each other",
+            self.clustered_lines, self.distinct_lines, self.clusters.len()
+        );
+        if self.skipped_lines > 0 {
+            println!("{} lines outside the compared length range were not compared", self.skipped_lines);
+        }
+        for cluster in self.clusters.iter().take(show) {
+            println!("  {:>6}  {}", cluster.lines.len(), cluster.lines.join("  "));
+        }
+        if self.clusters.len() > show {
+            println!("  ... and {} smaller clusters", self.clusters.len() - show);
+        }
+    }
+}
+
+// Whether `a` becomes `b` with at most one insertion, deletion or substitution of a character
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(long.iter()).take_while(|(x, y)| x == y).count();
+    if short.len() == long.len() {
+        short[prefix..].iter().skip(1).eq(long[prefix..].iter().skip(1))
+    } else {
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+// Disjoint sets of line ids, merged as near-duplicate pairs are found
+struct UnionFind {
+    parents: Vec<u32>, // Parent of each id; a root is its own parent
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self { parents: (0..len as u32).collect() }
+    }
+
+    // Root of the set holding `id`, shortening the path on the way
+    fn find(&mut self, mut id: u32) -> u32 {
+        while self.parents[id as usize] != id {
+            let parent = self.parents[id as usize];
+            self.parents[id as usize] = self.parents[parent as usize];
+            id = parent;
+        }
+        id
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b) as usize] = a.min(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{within_one_edit, NearDupeReport};
+
+    #[test]
+    fn one_insertion_deletion_or_substitution_is_one_edit() {
+        assert!(within_one_edit("password", "passwords"));
+        assert!(within_one_edit("password", "pasword"));
+        assert!(within_one_edit("password", "passw0rd"));
+        assert!(within_one_edit("café", "cafe"));
+        assert!(!within_one_edit("password", "psasword")); // A swapped pair is two edits
+        assert!(!within_one_edit("pass", "password"));
+    }
+
+    #[test]
+    fn clusters_link_lines_through_other_members() {
+        let temp = tempfile::tempdir().unwrap();
+        let input = temp.path().join("words.txt");
+        std::fs::write(&input, "summer\nsummer1\nsummer12\nwinter\nwinter\nab\nba\nx\n").unwrap();
+
+        let report = NearDupeReport::build(&input, 2, 64).unwrap();
+        assert_eq!((report.distinct_lines, report.skipped_lines, report.clustered_lines), (7, 1, 3));
+        assert_eq!(report.clusters.len(), 1);
+        assert_eq!(report.clusters[0].lines, ["summer", "summer1", "summer12"]);
+    }
+}
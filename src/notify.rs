@@ -0,0 +1,124 @@
+use serde::Serialize; // Import Serialize for the webhook body
+use std::time::Duration; // Import Duration for the request timeout
+use url::Url; // Import Url for the webhook address
+use crate::exit_code::RunOutcome; // Import RunOutcome to describe how the merge ended
+use crate::report::RunReport; // Import RunReport, sent with every notification
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30); // Longest wait for the webhook to answer
+
+// Parse a webhook address, which must be http or https
+pub fn parse_webhook(value: &str) -> Result<Url, String> {
+    let url = Url::parse(value).map_err(|e| format!("invalid webhook URL '{}': {}", value, e))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(format!("webhook URL '{}' must use http or https, not {}", value, scheme)),
+    }
+}
+
+// JSON body POSTed when a merge ends
+//
+// `text` is what Slack and Teams show and `content` what Discord shows; other receivers
+// can read `status`, `report` and `error`.
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    pub status: &'static str,  // "success", "skipped_files", "interrupted" or "failed"
+    pub text: String,          // One-line summary
+    pub content: String,       // The same summary under Discord's key
+    pub host: Option<String>,  // Machine the merge ran on
+    pub error: Option<String>, // What failed, for failed merges
+    pub report: RunReport,     // Final report, as written by --report
+}
+
+impl Notification {
+    // Notification for a merge that ran to the end, completely or not
+    pub fn finished(outcome: RunOutcome, report: RunReport) -> Self {
+        let (status, verb) = match outcome {
+            RunOutcome::Success => ("success", "finished"),
+            RunOutcome::SkippedFiles => ("skipped_files", "finished with skipped files"),
+            RunOutcome::Interrupted => ("interrupted", "was interrupted; progress saved"),
+        };
+        let skipped = report.issues.iter().filter(|issue| issue.skipped).count();
+        let mut summary = format!(
+            "merge into {} {}: {} unique of {} lines",
+            report.output.display(), verb, report.unique_lines, report.lines_processed
+        );
+        if skipped > 0 {
+            summary.push_str(&format!(", {} files skipped", skipped));
+        }
+        Self::new(status, summary, None, report)
+    }
+
+    // Notification for a merge that stopped with `error`
+    pub fn failed(error: String, report: RunReport) -> Self {
+        let summary = format!("merge into {} failed: {}", report.output.display(), error);
+        Self::new("failed", summary, Some(error), report)
+    }
+
+    fn new(status: &'static str, summary: String, error: Option<String>, report: RunReport) -> Self {
+        let host = sys_info::hostname().ok();
+        let text = match &host {
+            Some(host) => format!("rustmerger on {}: {}", host, summary),
+            None => format!("rustmerger: {}", summary),
+        };
+        Self { status, content: text.clone(), text, host, error, report }
+    }
+}
+
+// POST `notification` to `url`; a webhook that cannot be reached only logs a warning
+pub async fn send(url: &Url, notification: &Notification) {
+    let sent = async {
+        let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+        client.post(url.clone()).json(notification).send().await?.error_for_status()
+    };
+    match sent.await {
+        Ok(_) => log::info!("Notified {}", url.host_str().unwrap_or("webhook")),
+        Err(e) => log::warn!("Could not notify the webhook: {}", e.without_url()), // The URL often holds a secret token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_webhook, Notification};
+    use crate::exit_code::RunOutcome;
+    use crate::report::{FileIssue, IssueKind, RunReport};
+    use chrono::Local;
+    use std::path::PathBuf;
+
+    // Report of a merge into `merged.txt` that skipped `skipped` inputs
+    fn report(skipped: usize) -> RunReport {
+        let issue = FileIssue { path: PathBuf::from("gone.txt"), kind: IssueKind::Io, skipped: true, detail: "not found".to_string() };
+        RunReport {
+            input_list: PathBuf::from("inputs.txt"),
+            output: PathBuf::from("merged.txt"),
+            lines_processed: 40,
+            unique_lines: 10,
+            duplicate_percent: 75.0,
+            retries: Default::default(),
+            issues: vec![issue; skipped],
+            files: Vec::new(),
+            tags: Vec::new(),
+            encodings: Default::default(),
+            peak_memory: None,
+            generated_at: Local::now(),
+        }
+    }
+
+    #[test]
+    fn webhooks_must_be_http_or_https() {
+        assert!(parse_webhook("https://hooks.example.com/T0/B0/secret").is_ok());
+        assert!(parse_webhook("ftp://example.com/hook").unwrap_err().contains("not ftp"));
+        assert!(parse_webhook("not a url").is_err());
+    }
+
+    #[test]
+    fn summaries_say_how_the_merge_ended() {
+        let notification = Notification::finished(RunOutcome::SkippedFiles, report(2));
+        assert_eq!(notification.status, "skipped_files");
+        assert!(notification.text.ends_with("merge into merged.txt finished with skipped files: 10 unique of 40 lines, 2 files skipped"));
+        assert_eq!(notification.content, notification.text);
+
+        let notification = Notification::failed("disk full".to_string(), report(0));
+        assert_eq!((notification.status, notification.error.as_deref()), ("failed", Some("disk full")));
+        assert!(notification.text.ends_with("merge into merged.txt failed: disk full"));
+    }
+}
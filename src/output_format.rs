@@ -0,0 +1,390 @@
+use clap::ValueEnum; // Import ValueEnum to choose a format on the command line
+use parquet::basic::Compression; // Import Compression for Parquet column chunks
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type}; // Import Parquet value types
+use parquet::file::properties::WriterProperties; // Import WriterProperties for Parquet files
+use parquet::file::writer::SerializedFileWriter; // Import the Parquet file writer
+use parquet::schema::parser::parse_message_type; // Import the schema parser for the Parquet layout
+use rusqlite::types::Value; // Import Value to store each candidate as text or blob
+use rusqlite::{params_from_iter, Connection}; // Import rusqlite to write SQLite stores
+use serde::{Deserialize, Serialize}; // Import serde traits for the config file
+use std::fs::File; // Import File for the Parquet output
+use std::io::{self, BufWriter, Write}; // Import io for the sink error type and buffered text output
+use std::path::Path; // Import Path for output destinations
+use std::sync::Arc; // Import Arc for the shared Parquet schema and properties
+use crate::errors::ConfigError; // Import ConfigError for incompatible options
+use crate::line_store::Tally; // Import Tally, the per-line count and source
+
+const TEXT_BUFFER_SIZE: usize = 1024 * 1024; // Bytes buffered before text and counted lines are written
+const PARQUET_ROW_GROUP_ROWS: usize = 1024 * 1024; // Rows buffered per Parquet row group
+const SQLITE_TABLE: &str = "candidates"; // Table receiving the unique lines
+
+// Shape of the merged output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,    // One unique line per line of text
+    Sqlite,  // A `candidates` table in a SQLite database
+    Parquet, // A Parquet file with one row per unique line
+    Counted, // `count<TAB>line` text, like `sort | uniq -c`
+}
+
+impl OutputFormat {
+    // Name used on the command line and in messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Sqlite => "sqlite",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Counted => "counted",
+        }
+    }
+}
+
+// Which unique lines make the cut when the output is capped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CapOrder {
+    #[default]
+    Stable,    // The first lines in output order
+    Frequency, // The lines occurring most often across all inputs, kept in output order
+}
+
+// Most lines and bytes written to the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputCap {
+    pub lines: Option<usize>, // Most unique lines written
+    pub bytes: Option<u64>,   // Most bytes written, counting each line with its newline
+    pub order: CapOrder,      // Which lines are kept once the cap is reached
+}
+
+impl OutputCap {
+    // Whether `lines` lines taking `bytes` bytes in all stay within the cap
+    pub fn fits(&self, lines: usize, bytes: u64) -> bool {
+        self.lines.is_none_or(|max| lines <= max) && self.bytes.is_none_or(|max| bytes <= max)
+    }
+}
+
+// Extra columns written next to each unique line in analytical outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputColumns {
+    #[serde(default)]
+    pub frequency: bool,  // How often the line occurred across every input
+    #[serde(default)]
+    pub provenance: bool, // The first input, in processing order, that contained the line
+}
+
+impl OutputColumns {
+    // Check that the columns fit `format` and the streaming mode
+    pub fn validate(&self, format: OutputFormat, stream_output: bool) -> Result<(), ConfigError> {
+        if format == OutputFormat::Text && (self.frequency || self.provenance) {
+            return Err(ConfigError::InvalidOutputFormat("--with-frequency and --with-provenance need sqlite or parquet output".to_string()));
+        }
+        if format == OutputFormat::Counted && self.provenance {
+            return Err(ConfigError::InvalidOutputFormat("--with-provenance needs sqlite or parquet output".to_string()));
+        }
+        if format != OutputFormat::Text && stream_output {
+            return Err(ConfigError::InvalidOutputFormat(format!("{} output cannot be streamed", format.label())));
+        }
+        Ok(())
+    }
+
+    // Whether any column needs per-line tallies during deduplication
+    pub fn needs_tally(&self) -> bool {
+        self.frequency || self.provenance
+    }
+}
+
+// Destination of unique lines written one at a time, such as an analytical output format
+pub trait RecordSink {
+    // Add one unique line with its tally
+    fn write(&mut self, candidate: &[u8], tally: Tally) -> io::Result<()>;
+
+    // Flush buffered rows and close the output
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+// Open a sink writing `format` to `path`; `sources` names the inputs by position
+pub fn create_sink(
+    format: OutputFormat,
+    path: &Path,
+    columns: OutputColumns,
+    sources: Vec<String>,
+) -> io::Result<Box<dyn RecordSink + Send>> {
+    match format {
+        OutputFormat::Text => Ok(Box::new(TextSink::create(path)?)),
+        OutputFormat::Sqlite => Ok(Box::new(SqliteSink::create(path, columns, sources)?)),
+        OutputFormat::Parquet => Ok(Box::new(ParquetSink::create(path, columns, sources)?)),
+        OutputFormat::Counted => Ok(Box::new(CountedSink::create(path)?)),
+    }
+}
+
+// Unique lines written one per line, for text output that goes through a sink
+struct TextSink {
+    writer: BufWriter<File>, // Output being written
+}
+
+impl TextSink {
+    // Create or truncate `path`
+    fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self { writer: BufWriter::with_capacity(TEXT_BUFFER_SIZE, File::create(path)?) })
+    }
+}
+
+impl RecordSink for TextSink {
+    fn write(&mut self, candidate: &[u8], _tally: Tally) -> io::Result<()> {
+        self.writer.write_all(candidate)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// Unique lines written as `count<TAB>line` text
+struct CountedSink {
+    writer: BufWriter<File>, // Output being written
+}
+
+impl CountedSink {
+    // Create or truncate `path`
+    fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self { writer: BufWriter::with_capacity(TEXT_BUFFER_SIZE, File::create(path)?) })
+    }
+}
+
+impl RecordSink for CountedSink {
+    fn write(&mut self, candidate: &[u8], tally: Tally) -> io::Result<()> {
+        write!(self.writer, "{}\t", tally.count)?;
+        self.writer.write_all(candidate)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// Unique lines inserted into a SQLite table inside one transaction
+struct SqliteSink {
+    connection: Connection, // Database being written
+    insert: String,         // Insert statement for the chosen columns
+    columns: OutputColumns, // Columns written next to each line
+}
+
+impl SqliteSink {
+    // Replace `path` with a new database holding an empty candidates table
+    fn create(path: &Path, columns: OutputColumns, sources: Vec<String>) -> io::Result<Self> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        let connection = Connection::open(path).map_err(io::Error::other)?;
+        connection.execute_batch("PRAGMA journal_mode = OFF; PRAGMA synchronous = OFF;").map_err(io::Error::other)?;
+
+        // Inputs are stored once and referenced by id
+        let mut definition = vec!["candidate TEXT NOT NULL".to_string()];
+        if columns.frequency {
+            definition.push("frequency INTEGER NOT NULL".to_string());
+        }
+        if columns.provenance {
+            connection.execute_batch("CREATE TABLE sources (id INTEGER PRIMARY KEY, path TEXT NOT NULL);").map_err(io::Error::other)?;
+            for (id, source) in sources.iter().enumerate() {
+                connection.execute("INSERT INTO sources (id, path) VALUES (?1, ?2)", (id as i64, source)).map_err(io::Error::other)?;
+            }
+            definition.push("source INTEGER REFERENCES sources(id)".to_string());
+        }
+        connection.execute_batch(&format!("CREATE TABLE {} ({}); BEGIN;", SQLITE_TABLE, definition.join(", ")))
+            .map_err(io::Error::other)?;
+
+        let placeholders: Vec<String> = (1..=definition.len()).map(|i| format!("?{}", i)).collect();
+        let insert = format!("INSERT INTO {} VALUES ({})", SQLITE_TABLE, placeholders.join(", "));
+        Ok(Self { connection, insert, columns })
+    }
+}
+
+impl RecordSink for SqliteSink {
+    fn write(&mut self, candidate: &[u8], tally: Tally) -> io::Result<()> {
+        // Lines that are not UTF-8 go in as blobs, so distinct byte sequences stay distinct
+        let candidate = match std::str::from_utf8(candidate) {
+            Ok(text) => Value::Text(text.to_string()),
+            Err(_) => Value::Blob(candidate.to_vec()),
+        };
+        let mut values: Vec<Value> = vec![candidate];
+        if self.columns.frequency {
+            values.push((tally.count as i64).into());
+        }
+        if self.columns.provenance {
+            values.push((tally.source as i64).into());
+        }
+        let mut statement = self.connection.prepare_cached(&self.insert).map_err(io::Error::other)?;
+        statement.execute(params_from_iter(values)).map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        self.connection.execute_batch("COMMIT;").map_err(io::Error::other)?;
+        self.connection.close().map_err(|(_, e)| io::Error::other(e))
+    }
+}
+
+// Unique lines buffered into Parquet row groups
+struct ParquetSink {
+    writer: SerializedFileWriter<File>, // File being written
+    columns: OutputColumns,             // Columns written next to each line
+    sources: Vec<ByteArray>,            // Input paths by position, for the source column
+    candidate_values: Vec<ByteArray>,   // Buffered candidate column
+    frequency_values: Vec<i64>,         // Buffered frequency column
+    source_values: Vec<ByteArray>,      // Buffered source column
+}
+
+impl ParquetSink {
+    // Create `path` with a schema holding the chosen columns
+    fn create(path: &Path, columns: OutputColumns, sources: Vec<String>) -> io::Result<Self> {
+        // Candidates are raw bytes, which need not be UTF-8, so they carry no string annotation
+        let mut fields = vec!["required binary candidate;"];
+        if columns.frequency {
+            fields.push("required int64 frequency;");
+        }
+        if columns.provenance {
+            fields.push("required binary source (UTF8);");
+        }
+        let schema = parse_message_type(&format!("message candidates {{ {} }}", fields.join(" ")))
+            .map_err(io::Error::other)?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = SerializedFileWriter::new(File::create(path)?, Arc::new(schema), Arc::new(properties))
+            .map_err(io::Error::other)?;
+
+        Ok(Self {
+            writer,
+            columns,
+            sources: sources.into_iter().map(|source| ByteArray::from(source.into_bytes())).collect(),
+            candidate_values: Vec::new(),
+            frequency_values: Vec::new(),
+            source_values: Vec::new(),
+        })
+    }
+
+    // Write the buffered rows as one row group
+    fn flush_row_group(&mut self) -> io::Result<()> {
+        if self.candidate_values.is_empty() {
+            return Ok(());
+        }
+        let mut row_group = self.writer.next_row_group().map_err(io::Error::other)?;
+        let mut column = 0;
+        while let Some(mut writer) = row_group.next_column().map_err(io::Error::other)? {
+            // Columns come in schema order: candidate, then frequency and source when present
+            let result = match (column, self.columns.frequency) {
+                (0, _) => writer.typed::<ByteArrayType>().write_batch(&self.candidate_values, None, None),
+                (1, true) => writer.typed::<Int64Type>().write_batch(&self.frequency_values, None, None),
+                _ => writer.typed::<ByteArrayType>().write_batch(&self.source_values, None, None),
+            };
+            result.map_err(io::Error::other)?;
+            writer.close().map_err(io::Error::other)?;
+            column += 1;
+        }
+        row_group.close().map_err(io::Error::other)?;
+
+        self.candidate_values.clear();
+        self.frequency_values.clear();
+        self.source_values.clear();
+        Ok(())
+    }
+}
+
+impl RecordSink for ParquetSink {
+    fn write(&mut self, candidate: &[u8], tally: Tally) -> io::Result<()> {
+        self.candidate_values.push(ByteArray::from(candidate.to_vec()));
+        if self.columns.frequency {
+            self.frequency_values.push(tally.count as i64);
+        }
+        if self.columns.provenance {
+            let source = self.sources.get(tally.source).cloned().unwrap_or_else(|| ByteArray::from(Vec::new()));
+            self.source_values.push(source);
+        }
+        if self.candidate_values.len() >= PARQUET_ROW_GROUP_ROWS {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.flush_row_group()?;
+        self.writer.close().map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_sink, OutputColumns, OutputFormat};
+    use crate::line_store::Tally;
+    use parquet::basic::{ConvertedType, Type as PhysicalType};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+    use rusqlite::types::Value;
+    use rusqlite::Connection;
+
+    const COLUMNS: OutputColumns = OutputColumns { frequency: true, provenance: true };
+    const LINES: [&[u8]; 3] = [b"password", b"p\xe4ss", b"p\xf6ss"]; // Two distinct windows-1252 lines
+
+    // Write LINES to a new `format` output in a temporary directory
+    fn write(format: OutputFormat, dir: &std::path::Path) -> std::path::PathBuf {
+        let path = dir.join("out");
+        let mut sink = create_sink(format, &path, COLUMNS, vec!["a.txt".to_string(), "b.txt".to_string()]).unwrap();
+        for (index, line) in LINES.iter().enumerate() {
+            sink.write(line, Tally { count: index as u64 + 1, source: index % 2 }).unwrap();
+        }
+        sink.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn sqlite_keeps_lines_that_are_not_utf8_as_blobs() {
+        let temp = tempfile::tempdir().unwrap();
+        let connection = Connection::open(write(OutputFormat::Sqlite, temp.path())).unwrap();
+        let mut statement = connection.prepare("SELECT candidate, frequency, source FROM candidates ORDER BY rowid").unwrap();
+        let rows: Vec<(Value, i64, i64)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![
+            (Value::Text("password".to_string()), 1, 0),
+            (Value::Blob(b"p\xe4ss".to_vec()), 2, 1),
+            (Value::Blob(b"p\xf6ss".to_vec()), 3, 0),
+        ]);
+    }
+
+    #[test]
+    fn parquet_candidates_are_plain_binary() {
+        let temp = tempfile::tempdir().unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(write(OutputFormat::Parquet, temp.path())).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr_ptr();
+        let candidate = schema.column(0);
+        assert_eq!(candidate.name(), "candidate");
+        assert_eq!(candidate.physical_type(), PhysicalType::BYTE_ARRAY);
+        assert_eq!(candidate.converted_type(), ConvertedType::NONE);
+        assert_eq!(candidate.logical_type(), None);
+        assert_eq!(schema.column(2).converted_type(), ConvertedType::UTF8); // Source paths are text
+
+        let candidates: Vec<Vec<u8>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().get_bytes(0).unwrap().data().to_vec())
+            .collect();
+        assert_eq!(candidates, LINES.iter().map(|line| line.to_vec()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn counted_output_prefixes_each_line_with_its_count() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("out");
+        let mut sink = create_sink(OutputFormat::Counted, &path, OutputColumns { frequency: true, provenance: false }, Vec::new()).unwrap();
+        sink.write(b"alpha", Tally { count: 3, source: 0 }).unwrap();
+        sink.write(b"beta", Tally::once(0)).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"3\talpha\n1\tbeta\n");
+    }
+}
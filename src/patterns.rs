@@ -0,0 +1,63 @@
+use crate::strength; // Reuse the keyboard layout of the strength estimate
+
+const MIN_WALK_LEN: usize = 4; // Shorter candidates are never treated as walks
+const WALK_SHARE: f64 = 0.75;  // Share of steps that must be predictable for a walk
+const MAX_SEQUENCE_STEP: u8 = 3; // Largest constant step of a sequence such as `13579`
+
+// Whether `candidate` is a keyboard walk or a sequence, such as `qwerty`, `1qaz2wsx`,
+// `asdf1234`, `abcdef` or `97531`
+//
+// A walk mostly moves between touching keys or neighbouring characters; a sequence
+// steps through characters by the same small amount every time.
+pub fn is_walk(candidate: &[u8]) -> bool {
+    if candidate.len() < MIN_WALK_LEN {
+        return false;
+    }
+    if is_sequence(candidate) {
+        return true;
+    }
+    let steps = candidate.len() - 1;
+    let predictable = candidate.windows(2).filter(|pair| is_walk_step(pair[0], pair[1])).count();
+    predictable as f64 >= WALK_SHARE * steps as f64
+}
+
+// Whether `candidate` is made of digits only, such as a PIN, date or phone number
+pub fn is_numeric(candidate: &[u8]) -> bool {
+    !candidate.is_empty() && candidate.iter().all(u8::is_ascii_digit)
+}
+
+// Whether every step between the characters of `candidate` is the same small nonzero amount
+fn is_sequence(candidate: &[u8]) -> bool {
+    let folded: Vec<u8> = candidate.iter().map(u8::to_ascii_lowercase).collect();
+    let step = |pair: &[u8]| i16::from(pair[1]) - i16::from(pair[0]);
+    let first = step(&folded[..2]);
+    first != 0
+        && first.unsigned_abs() <= u16::from(MAX_SEQUENCE_STEP)
+        && folded.iter().all(u8::is_ascii_alphanumeric)
+        && folded.windows(2).all(|pair| step(pair) == first)
+}
+
+// Whether moving from `a` to `b` continues a walk: a touching key or the next or previous character
+fn is_walk_step(a: u8, b: u8) -> bool {
+    let (a, b) = (a.to_ascii_lowercase(), b.to_ascii_lowercase());
+    a != b && (a.abs_diff(b) == 1 || strength::keyboard_adjacent(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_walk;
+
+    #[test]
+    fn walks_and_sequences_are_spotted() {
+        for walk in ["qwerty", "1qaz2wsx", "asdf1234", "abcdef", "97531", "ZXCVBN"] {
+            assert!(is_walk(walk.as_bytes()), "{}", walk);
+        }
+    }
+
+    #[test]
+    fn words_and_short_candidates_are_not_walks() {
+        for other in ["password", "sunshine", "qwe", "aaaa", "hmtpwk"] {
+            assert!(!is_walk(other.as_bytes()), "{}", other);
+        }
+    }
+}
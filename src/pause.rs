@@ -0,0 +1,90 @@
+use std::sync::Arc; // Import Arc to share the switch between readers and signal handlers
+use tokio::sync::watch; // Import watch so waiting readers wake up on resume
+
+// Switch that holds readers between chunks while a merge is paused
+#[derive(Clone)]
+pub struct PauseGate {
+    paused: Arc<watch::Sender<bool>>, // Whether readers must wait
+}
+
+impl Default for PauseGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PauseGate {
+    pub fn new() -> Self {
+        Self { paused: Arc::new(watch::Sender::new(false)) }
+    }
+
+    // Hold readers at their next chunk; false when already paused
+    pub fn pause(&self) -> bool {
+        self.set(true)
+    }
+
+    // Let readers continue; false when not paused
+    pub fn resume(&self) -> bool {
+        self.set(false)
+    }
+
+    fn set(&self, paused: bool) -> bool {
+        self.paused.send_if_modified(|current| std::mem::replace(current, paused) != paused)
+    }
+
+    // Whether readers are being held
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    // Receiver woken on every pause and resume
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+
+    // Wait while paused, from async code
+    pub async fn wait(&self) {
+        if self.is_paused() {
+            let _ = self.subscribe().wait_for(|paused| !*paused).await;
+        }
+    }
+
+    // Wait while paused, from a blocking thread of the runtime
+    pub fn wait_blocking(&self) {
+        if self.is_paused() {
+            tokio::runtime::Handle::current().block_on(self.wait());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PauseGate;
+    use std::time::Duration;
+
+    #[test]
+    fn pausing_twice_or_resuming_twice_changes_nothing() {
+        let gate = PauseGate::new();
+        assert!(gate.pause());
+        assert!(!gate.pause());
+        assert!(gate.is_paused());
+        assert!(gate.resume());
+        assert!(!gate.resume());
+        assert!(!gate.is_paused());
+    }
+
+    #[tokio::test]
+    async fn waiting_readers_continue_on_resume() {
+        let gate = PauseGate::new();
+        gate.wait().await;
+        gate.pause();
+        let waiting = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.wait().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+        gate.resume();
+        tokio::time::timeout(Duration::from_secs(5), waiting).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize}; // Import serde traits for the config file
+use std::path::PathBuf; // Import PathBuf for script and plugin paths
+use std::sync::Arc; // Import Arc to share scripts and plugins between readers
+use crate::config::Config; // Import Config, which decode, dedup and output stages configure
+use crate::dedup_key::DedupKey; // Import DedupKey for the dedup stage
+use crate::errors::{ConfigError, MergerResult}; // Import the error types for invalid pipelines and failing stages
+use crate::input_format::InputFormat; // Import InputFormat for the decode stage
+use crate::line_options::{BinaryMode, StripControl}; // Import the NUL and control-character handling of the decode stage
+use crate::output_format::{CapOrder, OutputFormat}; // Import the output shape and cap order for the output stage
+use crate::patterns; // Import patterns for the walk filter
+use crate::plugin::PluginSet; // Import PluginSet for plugin stages
+use crate::report::FileReadStats; // Import FileReadStats to count what each stage drops
+use crate::script::{LineScript, ScriptDecision}; // Import LineScript for script stages
+use crate::strength::StrengthRange; // Import StrengthRange for the strength filter
+use crate::transform::{self, Expansion, Transform}; // Import the generators for transform stages
+
+const BATCH_LINES: usize = 4096; // Candidates gathered before they go through the stages
+
+// Candidates of one reader waiting to go through the stages, stored back to back
+#[derive(Debug, Default)]
+pub struct CandidateBatch {
+    bytes: Vec<u8>,                        // Candidate bytes
+    items: Vec<(usize, usize, u64, bool)>, // Start, end, weight and whether the candidate was generated
+}
+
+impl CandidateBatch {
+    // Add a candidate standing for `weight` occurrences; `generated` marks transform output
+    pub fn push(&mut self, candidate: &[u8], weight: u64, generated: bool) {
+        let start = self.bytes.len();
+        self.bytes.extend_from_slice(candidate);
+        self.items.push((start, self.bytes.len(), weight, generated));
+    }
+
+    // Whether the batch is large enough to send through the stages
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= BATCH_LINES
+    }
+
+    // Number of waiting candidates
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    // Whether no candidates are waiting
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // Number of waiting candidates read from the input rather than generated
+    fn originals(&self) -> usize {
+        self.items.iter().filter(|(_, _, _, generated)| !generated).count()
+    }
+
+    // Drop every waiting candidate, keeping the buffers
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.items.clear();
+    }
+
+    // Waiting candidates with their weights and whether they were generated
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], u64, bool)> {
+        self.items.iter().map(|(start, end, weight, generated)| (&self.bytes[*start..*end], *weight, *generated))
+    }
+
+    // Keep only the candidates `keep` accepts; their bytes stay where they are
+    fn retain(&mut self, mut keep: impl FnMut(&[u8]) -> bool) {
+        let bytes = &self.bytes;
+        self.items.retain(|(start, end, _, _)| keep(&bytes[*start..*end]));
+    }
+
+    // Replace the batch with what `rewrite` pushes for each candidate
+    fn rebuild(&mut self, mut rewrite: impl FnMut(&[u8], u64, bool, &mut CandidateBatch) -> MergerResult<()>) -> MergerResult<()> {
+        let mut next = CandidateBatch::default();
+        for (candidate, weight, generated) in self.iter() {
+            rewrite(candidate, weight, generated, &mut next)?;
+        }
+        *self = next;
+        Ok(())
+    }
+}
+
+// One step candidates take between decoding and deduplication
+#[derive(Debug, Clone)]
+pub enum LineStage {
+    Strength(StrengthRange),   // Drop candidates scoring outside the range
+    ExcludeWalks,              // Drop keyboard walks and sequences
+    Script(Arc<LineScript>),   // Keep, drop or rewrite each candidate with a Rhai script
+    Plugins(Arc<PluginSet>),   // Hand the candidates to shared-library plugins
+    Generate {
+        transforms: Arc<[Transform]>, // Rewrites added next to each candidate
+        expansion: Arc<Expansion>,    // Templates adding hybrids of each candidate and rewrite
+    },
+}
+
+impl LineStage {
+    // Run the stage over the batch; candidates from input `source` are passed to scripts
+    fn apply(&self, batch: &mut CandidateBatch, source: &str) -> MergerResult<()> {
+        match self {
+            LineStage::Strength(range) => batch.retain(|candidate| range.admits(candidate)),
+            LineStage::ExcludeWalks => batch.retain(|candidate| !patterns::is_walk(candidate)),
+            LineStage::Script(script) => batch.rebuild(|candidate, weight, generated, next| {
+                match script.decide(candidate, source, weight)? {
+                    ScriptDecision::Keep => next.push(candidate, weight, generated),
+                    ScriptDecision::Drop => {}
+                    ScriptDecision::Replace(text) => next.push(text.as_bytes(), weight, generated),
+                }
+                Ok(())
+            })?,
+            LineStage::Plugins(plugins) => plugins.run(batch)?,
+            // Generated candidates count once, whatever the count of the candidate they came from,
+            // so a popular word does not make its rewrites look popular too
+            LineStage::Generate { transforms, expansion } => batch.rebuild(|candidate, weight, generated, next| {
+                next.push(candidate, weight, generated);
+                // Only text is rewritten; other bytes pass through unchanged
+                let Ok(text) = std::str::from_utf8(candidate) else {
+                    return Ok(());
+                };
+                let variants = transform::variants(transforms, text);
+                for variant in &variants {
+                    next.push(variant.as_bytes(), 1, true);
+                }
+                if !expansion.is_empty() {
+                    for form in std::iter::once(text).chain(variants.iter().map(String::as_str)) {
+                        expansion.expand(form, |hybrid| next.push(hybrid.as_bytes(), 1, true));
+                    }
+                }
+                Ok(())
+            })?,
+        }
+        Ok(())
+    }
+
+    // Counter of the input lines this stage drops, if it drops any
+    fn dropped<'a>(&self, stats: &'a mut FileReadStats) -> Option<&'a mut usize> {
+        match self {
+            LineStage::Strength(_) => Some(&mut stats.filtered_lines),
+            LineStage::ExcludeWalks => Some(&mut stats.walk_lines),
+            LineStage::Script(_) => Some(&mut stats.script_lines),
+            LineStage::Plugins(_) => Some(&mut stats.plugin_lines),
+            LineStage::Generate { .. } => None,
+        }
+    }
+}
+
+// Ordered stages every candidate goes through before deduplication
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<LineStage>, // Stages in the order they run
+}
+
+impl Pipeline {
+    // Run candidates through `stages` in order
+    pub fn new(stages: Vec<LineStage>) -> Self {
+        Self { stages }
+    }
+
+    // The stages the individual filter and transform options stand for, in their fixed order
+    //
+    // Generated candidates go through the strength and walk filters again, so they meet the
+    // same bar as the lines they came from.
+    pub fn standard(
+        strength: StrengthRange,
+        exclude_walks: bool,
+        script: Option<Arc<LineScript>>,
+        plugins: PluginSet,
+        transforms: Vec<Transform>,
+        expansion: Expansion,
+    ) -> Self {
+        let mut filters = Vec::new();
+        if strength.is_active() {
+            filters.push(LineStage::Strength(strength));
+        }
+        if exclude_walks {
+            filters.push(LineStage::ExcludeWalks);
+        }
+
+        let mut stages = filters.clone();
+        stages.extend(script.map(LineStage::Script));
+        if !plugins.is_empty() {
+            stages.push(LineStage::Plugins(Arc::new(plugins)));
+        }
+        if !transforms.is_empty() || !expansion.is_empty() {
+            stages.push(LineStage::Generate { transforms: transforms.into(), expansion: Arc::new(expansion) });
+            stages.extend(filters);
+        }
+        Self { stages }
+    }
+
+    // Whether candidates go straight to deduplication
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    // Run the batch through every stage, counting the input lines each one drops
+    pub fn run(&self, batch: &mut CandidateBatch, source: &str, stats: &mut FileReadStats) -> MergerResult<()> {
+        for stage in &self.stages {
+            if batch.is_empty() {
+                break;
+            }
+            let before = batch.originals();
+            stage.apply(batch, source)?;
+            if let Some(dropped) = stage.dropped(stats) {
+                *dropped += before - batch.originals();
+            }
+        }
+        Ok(())
+    }
+}
+
+// One stage of a `pipeline` section in the config file
+//
+// Decode, dedup and output stages set the matching config keys; the other stages
+// run in the order they are listed, between decoding and deduplication.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "lowercase", deny_unknown_fields)]
+pub enum StageConfig {
+    Decode {
+        #[serde(default)]
+        input_format: Option<InputFormat>, // Layout of the input lines
+        #[serde(default)]
+        json_field: Option<String>,         // Candidate field of JSON Lines inputs
+        #[serde(default)]
+        hex_invalid: bool,                  // Keep invalid UTF-8 lines as `$HEX[...]`
+        #[serde(default)]
+        binary_mode: Option<BinaryMode>,    // Keep lines with NUL bytes as read or as `$HEX[...]`
+        #[serde(default)]
+        strip_control: Option<StripControl>, // Control characters removed before deduplication
+        #[serde(default)]
+        normalize_homoglyphs: bool,         // Fold fullwidth forms and look-alike letters to ASCII
+    },
+    Filter {
+        #[serde(default)]
+        min_strength: Option<u8>,           // Weakest strength score kept, 0-4
+        #[serde(default)]
+        max_strength: Option<u8>,           // Strongest strength score kept, 0-4
+        #[serde(default)]
+        exclude_walks: bool,                // Drop keyboard walks and sequences
+    },
+    Script {
+        path: PathBuf,                      // Rhai script deciding each candidate
+    },
+    Plugin {
+        path: PathBuf,                      // Shared-library plugin
+    },
+    Transform {
+        #[serde(default)]
+        transforms: Vec<Transform>,         // Rewrites added next to each candidate
+        #[serde(default)]
+        append_years: Option<String>,       // Years substituted for `{suffix}`, such as "2015-2025"
+        #[serde(default)]
+        append_strings: Vec<String>,        // Strings substituted for `{suffix}`
+        #[serde(default)]
+        templates: Vec<String>,             // Templates building hybrids of each candidate
+    },
+    Dedup {
+        key: DedupKey,                      // Part of each candidate that identifies it
+    },
+    Output {
+        #[serde(default)]
+        format: Option<OutputFormat>,       // Shape of the merged output
+        #[serde(default)]
+        with_frequency: bool,               // Occurrence column in analytical outputs
+        #[serde(default)]
+        with_provenance: bool,              // First-source column in analytical outputs
+        #[serde(default)]
+        target_lines: Option<u64>,          // Keep only this many of the most frequent lines
+        #[serde(default)]
+        max_output_lines: Option<u64>,      // Most unique lines written
+        #[serde(default)]
+        max_output_bytes: Option<u64>,      // Most bytes written
+        #[serde(default)]
+        cap_order: Option<CapOrder>,        // Which lines make the cut under a cap
+        #[serde(default)]
+        route_walks: Option<PathBuf>,       // File receiving keyboard walks and sequences
+        #[serde(default)]
+        route_numeric: Option<PathBuf>,     // File receiving all-digit candidates
+        #[serde(default)]
+        numeric_lengths: Vec<String>,       // Digit count ranges routed, such as "4-8"
+    },
+}
+
+impl StageConfig {
+    // Position of the stage kind in decode -> line stages -> dedup -> output
+    fn phase(&self) -> u8 {
+        match self {
+            StageConfig::Decode { .. } => 0,
+            StageConfig::Dedup { .. } => 2,
+            StageConfig::Output { .. } => 3,
+            _ => 1,
+        }
+    }
+
+    // Name used in messages
+    fn label(&self) -> &'static str {
+        match self {
+            StageConfig::Decode { .. } => "decode",
+            StageConfig::Filter { .. } => "filter",
+            StageConfig::Script { .. } => "script",
+            StageConfig::Plugin { .. } => "plugin",
+            StageConfig::Transform { .. } => "transform",
+            StageConfig::Dedup { .. } => "dedup",
+            StageConfig::Output { .. } => "output",
+        }
+    }
+
+    // Whether the stage runs between decoding and deduplication
+    pub fn is_line_stage(&self) -> bool {
+        self.phase() == 1
+    }
+}
+
+// Check the order of the `pipeline` section and copy its decode, dedup and output settings into `config`
+//
+// Decode comes first, dedup and output last, each at most once; the stages in between may repeat.
+pub fn apply_settings(stages: &[StageConfig], config: &mut Config) -> Result<(), ConfigError> {
+    for pair in stages.windows(2) {
+        if pair[1].phase() < pair[0].phase() || (pair[0].phase() != 1 && pair[0].phase() == pair[1].phase()) {
+            return Err(ConfigError::InvalidPipeline(format!(
+                "a {} stage cannot follow a {} stage; stages run decode -> filter/script/plugin/transform -> dedup -> output",
+                pair[1].label(), pair[0].label()
+            )));
+        }
+    }
+
+    for stage in stages {
+        match stage {
+            StageConfig::Decode { input_format, json_field, hex_invalid, binary_mode, strip_control, normalize_homoglyphs } => {
+                config.input_format = input_format.unwrap_or(config.input_format);
+                config.json_field = json_field.clone().or(config.json_field.take());
+                config.hex_invalid |= hex_invalid;
+                config.binary_mode = binary_mode.unwrap_or(config.binary_mode);
+                config.strip_control = strip_control.or(config.strip_control);
+                config.normalize_homoglyphs |= normalize_homoglyphs;
+            }
+            StageConfig::Dedup { key } => config.dedup_key = *key,
+            StageConfig::Output {
+                format, with_frequency, with_provenance, target_lines, max_output_lines, max_output_bytes, cap_order,
+                route_walks, route_numeric, numeric_lengths,
+            } => {
+                config.output_format = format.unwrap_or(config.output_format);
+                config.output_columns.frequency |= with_frequency;
+                config.output_columns.provenance |= with_provenance;
+                config.target_lines = target_lines.or(config.target_lines);
+                config.max_output_lines = max_output_lines.or(config.max_output_lines);
+                config.max_output_bytes = max_output_bytes.or(config.max_output_bytes);
+                config.cap_order = cap_order.or(config.cap_order);
+                config.route_walks = route_walks.clone().or(config.route_walks.take());
+                config.route_numeric = route_numeric.clone().or(config.route_numeric.take());
+                if !numeric_lengths.is_empty() {
+                    config.numeric_lengths = numeric_lengths.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Build the line stages of the `pipeline` section, loading scripts and plugins
+pub fn build_line_stages(stages: &[StageConfig]) -> MergerResult<Pipeline> {
+    let mut built = Vec::new();
+    for stage in stages {
+        match stage {
+            StageConfig::Filter { min_strength, max_strength, exclude_walks } => {
+                let range = StrengthRange {
+                    min: min_strength.unwrap_or(0),
+                    max: max_strength.unwrap_or(crate::strength::MAX_SCORE),
+                };
+                range.validate()?;
+                if range.is_active() {
+                    built.push(LineStage::Strength(range));
+                }
+                if *exclude_walks {
+                    built.push(LineStage::ExcludeWalks);
+                }
+            }
+            StageConfig::Script { path } => built.push(LineStage::Script(Arc::new(LineScript::load(path)?))),
+            StageConfig::Plugin { path } => built.push(LineStage::Plugins(Arc::new(PluginSet::load(std::slice::from_ref(path))?))),
+            StageConfig::Transform { transforms, append_years, append_strings, templates } => {
+                let years = append_years.as_deref().map(transform::parse_years).transpose()
+                    .map_err(ConfigError::InvalidTemplate)?;
+                let expansion = Expansion::new(years, append_strings.clone(), templates.clone())?;
+                built.push(LineStage::Generate { transforms: transforms.clone().into(), expansion: Arc::new(expansion) });
+            }
+            _ => {}
+        }
+    }
+    Ok(Pipeline::new(built))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CandidateBatch, LineStage, Pipeline};
+    use crate::report::FileReadStats;
+    use crate::transform::{Expansion, Transform};
+    use std::sync::Arc;
+
+    #[test]
+    fn generated_candidates_count_once() {
+        let pipeline = Pipeline::new(vec![LineStage::Generate {
+            transforms: Arc::from([Transform::Upper]),
+            expansion: Arc::new(Expansion::default()),
+        }]);
+        let mut batch = CandidateBatch::default();
+        batch.push(b"password", 40, false);
+        pipeline.run(&mut batch, "counted.txt", &mut FileReadStats::default()).unwrap();
+
+        let candidates: Vec<(&[u8], u64, bool)> = batch.iter().collect();
+        assert_eq!(candidates, [(&b"password"[..], 40, false), (&b"PASSWORD"[..], 1, true)]);
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result; // Import Result type from anyhow for error handling
+use std::collections::HashMap; // Import HashMap to look up sizes by path
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for file path handling
+use tokio::fs::File; // Import async File for reading the detection sample
+use tokio::io::AsyncReadExt; // Import AsyncReadExt for bounded reads
+use crate::core::optimize_processing_order; // Reuse the scheduler used by the merge
+use crate::estimate::format_bytes; // Reuse the byte formatter from the dry-run report
+use crate::input_list::InputList; // Reuse the normalized input list reader
+use crate::encoding; // Reuse the encoding detector
+use crate::encoding_cache::EncodingCache; // Reuse encodings detected by earlier runs
+use crate::sqlite_source; // Recognize database query entries
+
+// A single input file as it will be scheduled
+#[derive(Debug, Clone)]
+pub struct PlannedFile {
+    pub path: PathBuf,           // Path as listed in the input file
+    pub size: u64,               // File size in bytes
+    pub encoding: String,        // Detected encoding label
+}
+
+// The resolved processing schedule for an input list
+#[derive(Debug, Clone)]
+pub struct ProcessingPlan {
+    pub batches: Vec<Vec<PlannedFile>>, // Files in processing order, grouped by parallel batch
+    pub missing: Vec<PathBuf>,          // Entries that do not exist or are not regular files
+    pub total_bytes: u64,               // Combined size of all scheduled files
+    pub parallel_files: usize,          // Files per batch
+}
+
+impl ProcessingPlan {
+    // Read the input list and build the schedule the merge would use
+    pub async fn build(input_file: &Path, parallel_files: usize) -> Result<Self> {
+        let files = InputList::load(input_file).await?.files;
+        Self::build_from_files(&files, parallel_files).await
+    }
+
+    // Build the schedule from an already-resolved list of files
+    pub async fn build_from_files(files: &[PathBuf], parallel_files: usize) -> Result<Self> {
+        let mut sized = Vec::with_capacity(files.len());
+        let mut missing = Vec::new();
+
+        for path in files {
+            if sqlite_source::is_sqlite(path) {
+                match sqlite_source::input_size(path).await {
+                    Ok(size) => sized.push((path.clone(), size)),
+                    Err(_) => missing.push(path.clone()),
+                }
+                continue;
+            }
+            match tokio::fs::metadata(path).await {
+                Ok(meta) if meta.is_file() => sized.push((path.clone(), meta.len())),
+                _ => missing.push(path.clone()),
+            }
+        }
+
+        let sizes: HashMap<PathBuf, u64> = sized.iter().cloned().collect();
+        let total_bytes = sized.iter().map(|(_, size)| size).sum();
+        let ordered = optimize_processing_order(sized).await;
+
+        let mut planned = Vec::with_capacity(ordered.len());
+        let mut encoding_cache = EncodingCache::load().await;
+        for path in ordered {
+            let encoding = encoding_cache.detect(&path).await?;
+            let size = sizes.get(&path).copied().unwrap_or(0);
+            planned.push(PlannedFile { path, size, encoding });
+        }
+        encoding_cache.save().await;
+
+        let batches = planned.chunks(parallel_files.max(1)).map(|c| c.to_vec()).collect();
+
+        Ok(Self { batches, missing, total_bytes, parallel_files })
+    }
+
+    // Print the schedule to stdout
+    pub fn print_report(&self) {
+        let scheduled: usize = self.batches.iter().map(Vec::len).sum();
+        println!("Processing plan");
+        println!("  Files scheduled:    {} ({} missing or unreadable)", scheduled, self.missing.len());
+        println!("  Total input size:   {}", format_bytes(self.total_bytes));
+        println!("  Parallel batch:     {} files", self.parallel_files);
+
+        for (i, batch) in self.batches.iter().enumerate() {
+            println!();
+            println!("  Batch {} (processed in parallel):", i + 1);
+            for file in batch {
+                println!(
+                    "    {:>10}  {:<12}  {}",
+                    format_bytes(file.size),
+                    file.encoding,
+                    file.path.display()
+                );
+            }
+        }
+
+        if !self.missing.is_empty() {
+            println!();
+            println!("  Skipped:");
+            for path in &self.missing {
+                println!("    missing: {}", path.display());
+            }
+        }
+    }
+}
+
+// Detect a file's encoding from a sample of its start
+pub async fn detect_encoding(path: &Path) -> Result<String> {
+    if sqlite_source::is_sqlite(path) {
+        return Ok("sqlite".to_string());
+    }
+    let file = File::open(path).await?;
+    let mut buffer = Vec::new();
+    file.take(encoding::DETECT_SAMPLE_BYTES as u64).read_to_end(&mut buffer).await?;
+
+    Ok(encoding::detect(&buffer).name().to_string())
+}
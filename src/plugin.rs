@@ -0,0 +1,219 @@
+//! Per-line filter and transform plugins loaded from shared libraries.
+//!
+//! A plugin is a `.so`/`.dylib`/`.dll` exporting two C functions:
+//!
+//! - `uint32_t rustmerger_plugin_abi_version(void)`, returning
+//!   `RUSTMERGER_PLUGIN_ABI_VERSION`
+//! - `int32_t rustmerger_plugin_process(const RustmergerPluginLine *lines,
+//!   size_t count, RustmergerPluginDecision *decisions)`, filling in one
+//!   decision per line and returning 0, or nonzero to fail the input
+//!
+//! Decisions start out as `RUSTMERGER_PLUGIN_KEEP`. Replacement bytes belong
+//! to the plugin and must stay valid until its next call on the same thread.
+//! Readers call `rustmerger_plugin_process` from several threads at once.
+
+use libloading::Library; // Import Library to load the shared libraries
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for plugin locations
+use crate::errors::{MergerError, MergerResult}; // Import the error types for load and call failures
+use crate::pipeline::CandidateBatch; // Import CandidateBatch, the candidates handed over per call
+
+/// Version of the plugin interface; plugins built for another version are refused.
+pub const RUSTMERGER_PLUGIN_ABI_VERSION: u32 = 1;
+/// Keep the candidate as it is.
+pub const RUSTMERGER_PLUGIN_KEEP: u32 = 0;
+/// Drop the candidate.
+pub const RUSTMERGER_PLUGIN_DROP: u32 = 1;
+/// Replace the candidate with the decision's bytes.
+pub const RUSTMERGER_PLUGIN_REPLACE: u32 = 2;
+
+/// One candidate handed to a plugin.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RustmergerPluginLine {
+    /// Bytes of the candidate, without a newline.
+    pub data: *const u8,
+    /// Length of the candidate in bytes.
+    pub len: usize,
+}
+
+/// What a plugin decided for one candidate.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RustmergerPluginDecision {
+    /// `RUSTMERGER_PLUGIN_KEEP`, `RUSTMERGER_PLUGIN_DROP` or `RUSTMERGER_PLUGIN_REPLACE`.
+    pub action: u32,
+    /// Replacement bytes, read only for `RUSTMERGER_PLUGIN_REPLACE`.
+    pub data: *const u8,
+    /// Length of the replacement in bytes.
+    pub len: usize,
+}
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type ProcessFn = unsafe extern "C" fn(*const RustmergerPluginLine, usize, *mut RustmergerPluginDecision) -> i32;
+
+// One loaded plugin
+struct Plugin {
+    path: PathBuf,      // Library the plugin was loaded from
+    process: ProcessFn, // Its batch entry point
+    _library: Library,  // Keeps the library mapped while `process` is in use
+}
+
+impl std::fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin").field("path", &self.path).finish()
+    }
+}
+
+// Plugins applied in order to every candidate
+#[derive(Debug, Default)]
+pub struct PluginSet {
+    plugins: Vec<Plugin>, // Loaded plugins, each fed what the previous one kept
+}
+
+impl PluginSet {
+    // Load every plugin, refusing libraries without the entry points or built for another version
+    pub fn load(paths: &[PathBuf]) -> MergerResult<Self> {
+        let plugins = paths.iter().map(|path| Self::load_one(path)).collect::<MergerResult<Vec<_>>>()?;
+        Ok(Self { plugins })
+    }
+
+    fn load_one(path: &Path) -> MergerResult<Plugin> {
+        let failed = |e: libloading::Error| MergerError::Plugin(format!("{}: {}", path.display(), e));
+        // Loading runs the library's initializers, which is what the user asked for by naming it
+        let library = unsafe { Library::new(path) }.map_err(failed)?;
+        let version = unsafe { library.get::<AbiVersionFn>(b"rustmerger_plugin_abi_version\0").map_err(failed)?() };
+        if version != RUSTMERGER_PLUGIN_ABI_VERSION {
+            return Err(MergerError::Plugin(format!(
+                "{}: built for plugin interface {}, expected {}",
+                path.display(), version, RUSTMERGER_PLUGIN_ABI_VERSION
+            )));
+        }
+        let process = *unsafe { library.get::<ProcessFn>(b"rustmerger_plugin_process\0") }.map_err(failed)?;
+        Ok(Plugin { path: path.to_path_buf(), process, _library: library })
+    }
+
+    // Whether no plugins are loaded
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    // Run the batch through every plugin in turn, leaving what the last one kept
+    pub fn run(&self, batch: &mut CandidateBatch) -> MergerResult<()> {
+        for plugin in &self.plugins {
+            if batch.is_empty() {
+                break;
+            }
+            *batch = plugin.apply(batch)?;
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    // Hand the batch to the plugin and collect what it keeps
+    fn apply(&self, batch: &CandidateBatch) -> MergerResult<CandidateBatch> {
+        let lines: Vec<RustmergerPluginLine> = batch.iter()
+            .map(|(candidate, _, _)| RustmergerPluginLine { data: candidate.as_ptr(), len: candidate.len() })
+            .collect();
+        let keep = RustmergerPluginDecision { action: RUSTMERGER_PLUGIN_KEEP, data: std::ptr::null(), len: 0 };
+        let mut decisions = vec![keep; lines.len()];
+        let status = unsafe { (self.process)(lines.as_ptr(), lines.len(), decisions.as_mut_ptr()) };
+        if status != 0 {
+            return Err(MergerError::Plugin(format!("{} failed with status {}", self.path.display(), status)));
+        }
+
+        let mut kept = CandidateBatch::default();
+        for ((candidate, weight, generated), decision) in batch.iter().zip(&decisions) {
+            match decision.action {
+                RUSTMERGER_PLUGIN_KEEP => kept.push(candidate, weight, generated),
+                RUSTMERGER_PLUGIN_DROP => {}
+                RUSTMERGER_PLUGIN_REPLACE if decision.data.is_null() && decision.len > 0 => {
+                    return Err(MergerError::Plugin(format!("{} replaced a line with a null pointer", self.path.display())));
+                }
+                RUSTMERGER_PLUGIN_REPLACE => {
+                    // The plugin keeps the replacement alive until its next call on this thread
+                    let replacement = if decision.len == 0 {
+                        &[][..]
+                    } else {
+                        unsafe { std::slice::from_raw_parts(decision.data, decision.len) }
+                    };
+                    // Empty replacements and ones spanning lines drop the candidate
+                    if !replacement.is_empty() && memchr::memchr2(b'\n', b'\r', replacement).is_none() {
+                        kept.push(replacement, weight, generated);
+                    }
+                }
+                action => {
+                    return Err(MergerError::Plugin(format!("{} returned unknown action {}", self.path.display(), action)));
+                }
+            }
+        }
+        Ok(kept)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Plugin, PluginSet, ProcessFn, RustmergerPluginDecision, RustmergerPluginLine, RUSTMERGER_PLUGIN_DROP,
+        RUSTMERGER_PLUGIN_REPLACE,
+    };
+    use crate::pipeline::CandidateBatch;
+    use std::path::PathBuf;
+
+    // Plugin that drops lines starting with `x`, replaces `a` lines and splits `s` lines in two
+    unsafe extern "C" fn process(lines: *const RustmergerPluginLine, count: usize, decisions: *mut RustmergerPluginDecision) -> i32 {
+        static REPLACED: &[u8] = b"REPLACED";
+        static SPLIT: &[u8] = b"two\nlines";
+        let lines = unsafe { std::slice::from_raw_parts(lines, count) };
+        let decisions = unsafe { std::slice::from_raw_parts_mut(decisions, count) };
+        for (line, decision) in lines.iter().zip(decisions) {
+            let replace = |with: &'static [u8]| RustmergerPluginDecision { action: RUSTMERGER_PLUGIN_REPLACE, data: with.as_ptr(), len: with.len() };
+            match unsafe { *line.data } {
+                b'x' => decision.action = RUSTMERGER_PLUGIN_DROP,
+                b'a' => *decision = replace(REPLACED),
+                b's' => *decision = replace(SPLIT),
+                _ => {}
+            }
+        }
+        0
+    }
+
+    // Plugin that fails every batch
+    unsafe extern "C" fn fail(_lines: *const RustmergerPluginLine, _count: usize, _decisions: *mut RustmergerPluginDecision) -> i32 {
+        7
+    }
+
+    // Plugin set running `process`, kept in the running program instead of a library
+    #[cfg(unix)]
+    fn set(process: ProcessFn) -> PluginSet {
+        let library = libloading::os::unix::Library::this().into();
+        PluginSet { plugins: vec![Plugin { path: PathBuf::from("test-plugin"), process, _library: library }] }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn decisions_keep_drop_and_replace_candidates() {
+        let mut batch = CandidateBatch::default();
+        for (candidate, weight) in [("keep", 1), ("xdrop", 2), ("apple", 3), ("split", 4)] {
+            batch.push(candidate.as_bytes(), weight, false);
+        }
+        set(process).run(&mut batch).unwrap();
+        let kept: Vec<_> = batch.iter().map(|(candidate, weight, _)| (candidate.to_vec(), weight)).collect();
+        assert_eq!(kept, [(b"keep".to_vec(), 1), (b"REPLACED".to_vec(), 3)]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_plugins_fail_the_batch() {
+        let mut batch = CandidateBatch::default();
+        batch.push(b"alpha", 1, false);
+        let error = set(fail).run(&mut batch).unwrap_err();
+        assert!(error.to_string().contains("test-plugin failed with status 7"));
+    }
+
+    #[test]
+    fn libraries_that_do_not_exist_are_refused() {
+        assert!(PluginSet::load(&[PathBuf::from("/nonexistent/plugin.so")]).is_err());
+        assert!(PluginSet::load(&[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the checked locations
+use crate::estimate::{format_bytes, ResourceEstimate}; // Import the estimate the requirements are derived from
+use crate::tuning::Tuning; // Import Tuning for the reader chunks held in memory
+use crate::dedup_backend::DedupBackend; // Import DedupBackend, which decides the size of the set
+
+// Free space needed on one filesystem before a merge starts
+#[derive(Debug, Clone)]
+pub struct SpaceRequirement {
+    pub purpose: String,  // What the space is for, e.g. "output"
+    pub path: PathBuf,    // Directory on the filesystem being checked
+    pub needed: u64,      // Estimated bytes that will be written there
+    pub available: u64,   // Bytes currently free there
+}
+
+impl SpaceRequirement {
+    // Bytes missing for the requirement to be met
+    pub fn shortfall(&self) -> u64 {
+        self.needed.saturating_sub(self.available)
+    }
+}
+
+// Disk-space preflight for the output and temporary directories
+#[derive(Debug, Clone)]
+pub struct DiskPreflight {
+    pub requirements: Vec<SpaceRequirement>, // One entry per filesystem written to
+}
+
+impl DiskPreflight {
+    // Derive the space requirements of a merge with `backend` from its resource estimate
+    pub fn check(estimate: &ResourceEstimate, output: &Path, temp_dir: &Path, backend: DedupBackend, spilling: bool) -> std::io::Result<Self> {
+        let output_dir = match output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        // An existing output is truncated first, so its space is reclaimed
+        let reclaimed = std::fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+        let output_need = estimate.disk_bytes.saturating_sub(reclaimed);
+
+        // Spill runs are only written when the set outgrows the backend's high-water mark
+        let spill_need = if spilling { backend.spill_bytes(estimate) } else { 0 };
+
+        let mut requirements = Vec::new();
+        if same_filesystem(&output_dir, temp_dir) {
+            let purpose = if spill_need > 0 { "output and spill runs" } else { "output" };
+            requirements.push(Self::requirement(purpose, &output_dir, output_need + spill_need)?);
+        } else {
+            requirements.push(Self::requirement("output", &output_dir, output_need)?);
+            if spill_need > 0 {
+                requirements.push(Self::requirement("spill runs", temp_dir, spill_need)?);
+            }
+        }
+
+        Ok(Self { requirements })
+    }
+
+    // Requirements that the filesystem cannot currently meet
+    pub fn shortfalls(&self) -> impl Iterator<Item = &SpaceRequirement> {
+        self.requirements.iter().filter(|r| r.shortfall() > 0)
+    }
+
+    // One line per unmet requirement, for errors and warnings
+    pub fn describe_shortfalls(&self) -> String {
+        self.shortfalls()
+            .map(|r| format!(
+                "{} in {} needs about {} but only {} is free ({} short)",
+                r.purpose,
+                r.path.display(),
+                format_bytes(r.needed),
+                format_bytes(r.available),
+                format_bytes(r.shortfall())
+            ))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn requirement(purpose: &str, path: &Path, needed: u64) -> std::io::Result<SpaceRequirement> {
+        Ok(SpaceRequirement {
+            purpose: purpose.to_string(),
+            path: path.to_path_buf(),
+            needed,
+            available: fs4::available_space(path)?,
+        })
+    }
+}
+
+// Peak-memory preflight, predicted from the sampled line length and duplication rate
+#[derive(Debug, Clone)]
+pub struct MemoryPreflight {
+    pub set_bytes: u64,         // Estimated deduplication set
+    pub buffer_bytes: u64,      // Reader chunks being filled and handed to the shards
+    pub available: Option<u64>, // Memory available right now, if known
+}
+
+impl MemoryPreflight {
+    // Derive the memory requirement of a merge from its resource estimate, tuning and dedup backend
+    pub fn check(estimate: &ResourceEstimate, tuning: &Tuning, backend: DedupBackend) -> Self {
+        // Every reader fills one chunk while the previous one waits for its shard
+        let buffer_bytes = tuning.read_concurrency as u64 * tuning.chunk_size as u64 * 2;
+        Self { set_bytes: backend.set_bytes(estimate), buffer_bytes, available: estimate.available_memory }
+    }
+
+    // Predicted peak memory of the merge
+    pub fn predicted_peak(&self) -> u64 {
+        self.set_bytes + self.buffer_bytes
+    }
+
+    // Bytes missing for the predicted peak to fit in available memory
+    pub fn shortfall(&self) -> u64 {
+        self.available.map_or(0, |available| self.predicted_peak().saturating_sub(available))
+    }
+
+    // The prediction, with what is available, for logs and errors
+    pub fn describe(&self) -> String {
+        let peak = format!(
+            "predicted peak memory {} ({} deduplication set, {} read buffers)",
+            format_bytes(self.predicted_peak()),
+            format_bytes(self.set_bytes),
+            format_bytes(self.buffer_bytes)
+        );
+        match self.available {
+            Some(available) => format!("{}, {} available", peak, format_bytes(available)),
+            None => peak,
+        }
+    }
+}
+
+// Whether two directories live on the same filesystem, so their requirements add up
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiskPreflight, MemoryPreflight, SpaceRequirement};
+    use crate::dedup_backend::DedupBackend;
+    use crate::estimate::ResourceEstimate;
+    use crate::tuning::Tuning;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    // Estimate predicting an output of `disk_bytes` and a set of `memory_bytes`
+    fn estimate(disk_bytes: u64, memory_bytes: u64, available_memory: Option<u64>) -> ResourceEstimate {
+        ResourceEstimate {
+            files_total: 1,
+            files_missing: Vec::new(),
+            files_sampled: 1,
+            total_bytes: disk_bytes,
+            estimated_lines: 1000,
+            estimated_unique: 1000,
+            avg_line_bytes: 8.0,
+            memory_bytes,
+            disk_bytes,
+            temp_bytes: 0,
+            estimated_runtime: Duration::from_secs(1),
+            available_memory,
+        }
+    }
+
+    #[test]
+    fn shortfalls_are_what_the_free_space_misses() {
+        let requirement = |needed, available| SpaceRequirement { purpose: "output".to_string(), path: PathBuf::from("."), needed, available };
+        assert_eq!(requirement(100, 40).shortfall(), 60);
+        assert_eq!(requirement(100, 400).shortfall(), 0);
+        let preflight = DiskPreflight { requirements: vec![requirement(100, 400), requirement(2048, 1024)] };
+        assert_eq!(preflight.shortfalls().count(), 1);
+        assert!(preflight.describe_shortfalls().contains("output in . needs about 2.00 KB"));
+    }
+
+    #[test]
+    fn an_existing_output_counts_as_free_space() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path().join("merged.txt");
+        std::fs::write(&output, vec![b'x'; 300]).unwrap();
+        let preflight = DiskPreflight::check(&estimate(1000, 0, None), &output, temp.path(), DedupBackend::ExternalSort, false).unwrap();
+        assert_eq!(preflight.requirements.len(), 1);
+        assert_eq!((preflight.requirements[0].purpose.as_str(), preflight.requirements[0].needed), ("output", 700));
+    }
+
+    #[test]
+    fn spill_runs_share_the_output_filesystem() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path().join("merged.txt");
+        // A set far past any high-water mark spills the whole output size again
+        let estimate = estimate(1000, u64::MAX / 2, None);
+        let preflight = DiskPreflight::check(&estimate, &output, temp.path(), DedupBackend::ExternalSort, true).unwrap();
+        assert_eq!(preflight.requirements.len(), 1);
+        assert_eq!((preflight.requirements[0].purpose.as_str(), preflight.requirements[0].needed), ("output and spill runs", 2000));
+    }
+
+    #[test]
+    fn peak_memory_adds_the_set_to_two_chunks_per_reader() {
+        let tuning = Tuning { chunk_size: 1024, read_concurrency: 4, ..Tuning::default() };
+        let preflight = MemoryPreflight::check(&estimate(0, 10_000, Some(12_000)), &tuning, DedupBackend::Sharded);
+        assert_eq!((preflight.set_bytes, preflight.buffer_bytes), (10_000, 8192));
+        assert_eq!(preflight.predicted_peak(), 18_192);
+        assert_eq!(preflight.shortfall(), 6192);
+        assert!(preflight.describe().ends_with("available"));
+    }
+
+    #[test]
+    fn unknown_memory_is_never_short() {
+        let preflight = MemoryPreflight::check(&estimate(0, u64::MAX / 4, None), &Tuning::default(), DedupBackend::Memory);
+        assert_eq!(preflight.shortfall(), 0);
+        assert!(!preflight.describe().contains("available"));
+    }
+}
@@ -0,0 +1,86 @@
+use clap::ValueEnum; // Import ValueEnum so a preset can be chosen with --preset
+use crate::config::Config; // Import Config, which a preset adjusts
+use crate::dedup_key::DedupKey; // Import DedupKey, which the combo preset pins to whole pairs
+use crate::line_options::{BinaryMode, StripControl}; // Import the decode settings presets choose
+use crate::rules::RuleComments; // Import the rule comment modes presets choose
+
+// Named bundle of settings for a common job, so a new user gets sensible behavior from one flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    MergeWordlists, // Scraped wordlists: keep every byte sequence, drop stray NULs
+    MergeRules,     // Hand-edited rule files: merge near-duplicates, keep section comments together
+    ComboCleanup,   // user:password combo lists: strip control characters but tab, keep every distinct pair
+}
+
+impl Preset {
+    // Name used on the command line and in messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            Preset::MergeWordlists => "merge-wordlists",
+            Preset::MergeRules => "merge-rules",
+            Preset::ComboCleanup => "combo-cleanup",
+        }
+    }
+
+    // Set the keys this preset covers, leaving every other key of `config` as it is
+    pub fn apply(self, config: &mut Config) {
+        match self {
+            Preset::MergeWordlists => {
+                // Lines in legacy encodings and binary debris survive as $HEX[...] instead of being lost
+                config.hex_invalid = true;
+                config.binary_mode = BinaryMode::Hex;
+                config.strip_control = Some(StripControl::Nul);
+            }
+            Preset::MergeRules => {
+                config.rule_ignore_whitespace = true;
+                config.rule_comments = RuleComments::Separate;
+            }
+            Preset::ComboCleanup => {
+                // Control characters in combos are copy-paste debris, but a tab may be the separator
+                config.hex_invalid = true;
+                config.binary_mode = BinaryMode::Hex;
+                config.strip_control.get_or_insert(StripControl::AllControlExceptTab);
+                config.dedup_key = DedupKey::Pair;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Preset;
+    use crate::config::Config;
+    use crate::dedup_key::DedupKey;
+    use crate::line_options::{BinaryMode, StripControl};
+
+    #[test]
+    fn combo_cleanup_keeps_tab_separators() {
+        let mut config = Config::default();
+        Preset::ComboCleanup.apply(&mut config);
+        let mode = config.strip_control.expect("combo-cleanup strips control characters");
+        assert_eq!(mode.strip(b"alice\thunter2\r\x07").as_deref(), Some(&b"alice\thunter2"[..]));
+    }
+
+    #[test]
+    fn combo_cleanup_keys_on_whole_pairs() {
+        let mut config = Config { dedup_key: DedupKey::Password, ..Config::default() };
+        Preset::ComboCleanup.apply(&mut config);
+        assert_eq!(config.dedup_key, DedupKey::Pair);
+        assert!(config.hex_invalid);
+        assert_eq!(config.binary_mode, BinaryMode::Hex);
+    }
+
+    #[test]
+    fn combo_cleanup_leaves_unrelated_keys_alone() {
+        let mut config = Config {
+            strip_control: Some(StripControl::Nul),
+            normalize_homoglyphs: true,
+            exclude_walks: true,
+            ..Config::default()
+        };
+        Preset::ComboCleanup.apply(&mut config);
+        assert_eq!(config.strip_control, Some(StripControl::Nul));
+        assert!(config.normalize_homoglyphs);
+        assert!(config.exclude_walks);
+    }
+}
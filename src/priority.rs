@@ -0,0 +1,167 @@
+use std::fmt; // Import fmt to print I/O priorities as they are written on the command line
+
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_int = 1; // ioprio_set target kind: one thread or process
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_SHIFT: u32 = 13; // Bits below the class hold the level
+const DEFAULT_IO_LEVEL: u8 = 4; // Level of realtime and best-effort without one, as ionice uses
+
+// I/O scheduling class, as set by ionice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoClass {
+    Realtime,   // Served before everyone else; needs root
+    BestEffort, // The default class, ordered by level
+    Idle,       // Served only when no other process needs the disk
+}
+
+// I/O scheduling class and level within it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoClass, // Scheduling class
+    pub level: u8,      // 0 (highest) to 7 (lowest); unused by the idle class
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.class {
+            IoClass::Realtime => write!(f, "realtime:{}", self.level),
+            IoClass::BestEffort => write!(f, "best-effort:{}", self.level),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+// Parse an I/O priority such as `idle`, `best-effort:7` or `realtime`
+pub fn parse_io_priority(value: &str) -> Result<IoPriority, String> {
+    let (name, level) = match value.trim().split_once(':') {
+        Some((name, level)) => (name, Some(level)),
+        None => (value.trim(), None),
+    };
+    let class = match name.to_ascii_lowercase().as_str() {
+        "realtime" | "rt" => IoClass::Realtime,
+        "best-effort" | "be" => IoClass::BestEffort,
+        "idle" => IoClass::Idle,
+        _ => return Err(format!("invalid I/O priority '{}': expected idle, best-effort[:LEVEL] or realtime[:LEVEL]", value)),
+    };
+    let level = match (class, level) {
+        (IoClass::Idle, Some(_)) => return Err("the idle I/O class takes no level".to_string()),
+        (_, None) => DEFAULT_IO_LEVEL,
+        (_, Some(level)) => level.trim().parse().ok().filter(|level| *level <= 7)
+            .ok_or_else(|| format!("invalid I/O priority level '{}': expected 0 to 7", level))?,
+    };
+    Ok(IoPriority { class, level })
+}
+
+// Check a niceness is within -20 to 19
+pub fn validate_nice(nice: i32) -> Result<i32, String> {
+    if (-20..=19).contains(&nice) {
+        Ok(nice)
+    } else {
+        Err(format!("niceness {} is outside -20 to 19", nice))
+    }
+}
+
+// Run every thread of the process at `nice` and `io`, warning about what cannot be applied
+//
+// Linux keeps both per thread, so every running thread is changed; threads started later
+// inherit them. Lowering priority always works; raising it needs root or CAP_SYS_NICE.
+pub fn apply(nice: Option<i32>, io: Option<IoPriority>) {
+    if let Some(nice) = nice {
+        match set_nice(nice) {
+            Ok(()) => log::info!("Running at niceness {}", nice),
+            Err(e) => log::warn!("Could not set niceness {}: {}", nice, e),
+        }
+    }
+    if let Some(io) = io {
+        match set_io_priority(io) {
+            Ok(()) => log::info!("Running at I/O priority {}", io),
+            Err(e) => log::warn!("Could not set I/O priority {}: {}", io, e),
+        }
+    }
+}
+
+// Thread IDs of the process, or the process itself where priorities are per process
+#[cfg(unix)]
+fn targets() -> Vec<libc::id_t> {
+    #[cfg(target_os = "linux")]
+    if let Ok(tasks) = std::fs::read_dir("/proc/self/task") {
+        return tasks.flatten().filter_map(|task| task.file_name().to_str()?.parse().ok()).collect();
+    }
+    vec![0] // The calling process
+}
+
+// Ignore `error` when it only says the thread exited since the IDs were listed
+#[cfg(unix)]
+fn exited_or(error: std::io::Error) -> std::io::Result<()> {
+    match error.raw_os_error() {
+        Some(libc::ESRCH) => Ok(()),
+        _ => Err(error),
+    }
+}
+
+#[cfg(unix)]
+fn set_nice(nice: i32) -> std::io::Result<()> {
+    for target in targets() {
+        // Safety: setpriority only reads its integer arguments
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, target, nice) } != 0 {
+            exited_or(std::io::Error::last_os_error())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_nice(_nice: i32) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "niceness is only supported on Unix"))
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(io: IoPriority) -> std::io::Result<()> {
+    let (class, level) = match io.class {
+        IoClass::Realtime => (1, io.level),
+        IoClass::BestEffort => (2, io.level),
+        IoClass::Idle => (3, 0),
+    };
+    let ioprio = (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level);
+    for target in targets() {
+        // Safety: ioprio_set only reads its integer arguments
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, target, ioprio) } != 0 {
+            exited_or(std::io::Error::last_os_error())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_io_priority(_io: IoPriority) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "I/O priorities are only supported on Linux"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_io_priority, validate_nice, IoClass, IoPriority};
+
+    #[test]
+    fn io_priorities_parse_with_optional_levels() {
+        assert_eq!(parse_io_priority("idle"), Ok(IoPriority { class: IoClass::Idle, level: 4 }));
+        assert_eq!(parse_io_priority("be:7"), Ok(IoPriority { class: IoClass::BestEffort, level: 7 }));
+        assert_eq!(parse_io_priority(" Realtime "), Ok(IoPriority { class: IoClass::Realtime, level: 4 }));
+        assert!(parse_io_priority("idle:3").is_err());
+        assert!(parse_io_priority("best-effort:8").is_err());
+        assert!(parse_io_priority("low").is_err());
+    }
+
+    #[test]
+    fn io_priorities_print_as_written() {
+        for value in ["idle", "best-effort:7", "realtime:0"] {
+            assert_eq!(parse_io_priority(value).unwrap().to_string(), value);
+        }
+    }
+
+    #[test]
+    fn niceness_stays_within_range() {
+        assert_eq!(validate_nice(-20), Ok(-20));
+        assert_eq!(validate_nice(19), Ok(19));
+        assert!(validate_nice(20).is_err());
+    }
+}
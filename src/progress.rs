@@ -1,10 +1,12 @@
 // Import required dependencies
 use anyhow::Result;                   // For error handling
 use serde::{Serialize, Deserialize};  // For JSON serialization/deserialization
-use std::path::PathBuf;              // For file path handling
+use std::path::{Path, PathBuf};      // For file path handling
 use tokio::fs;                       // For async file operations
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use std::time::{Duration, Instant};
+use crate::config::Config;
+use crate::report;
 
 // Metrics tracking structures
 pub struct ProcessingMetrics {
@@ -12,6 +14,13 @@ pub struct ProcessingMetrics {
     files_processed: usize,
     lines_processed: usize,
     errors_count: usize,
+    memory_usage: usize,
+}
+
+impl Default for ProcessingMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProcessingMetrics {
@@ -21,6 +30,7 @@ impl ProcessingMetrics {
             files_processed: 0,
             lines_processed: 0,
             errors_count: 0,
+            memory_usage: 0,
         }
     }
 
@@ -32,13 +42,18 @@ impl ProcessingMetrics {
         self.lines_processed += count;
     }
 
+    // Record the latest resident set size sample, in bytes
+    pub fn set_memory_usage(&mut self, bytes: usize) {
+        self.memory_usage = bytes;
+    }
+
     pub fn get_summary(&self) -> ProcessingSummary {
         ProcessingSummary {
             elapsed_time: self.start_time.elapsed(),
             files_processed: self.files_processed,
             lines_processed: self.lines_processed,
             errors_count: self.errors_count,
-            memory_usage: 0,
+            memory_usage: self.memory_usage,
         }
     }
 }
@@ -52,7 +67,7 @@ pub struct ProcessingSummary {
 }
 
 // Progress tracking structure that can be serialized to/from JSON
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Progress {
     pub input_file: PathBuf,         // Source file containing list of files to process
     pub output_file: PathBuf,        // Destination file for merged content
@@ -60,6 +75,50 @@ pub struct Progress {
     pub processed_files: Vec<PathBuf>, // List of successfully processed files
     pub current_position: usize,     // Current processing position for resume capability
     pub save_path: Option<PathBuf>,  // Path where progress state is saved
+    #[serde(default)]
+    pub finished: bool,              // The merge ran to the end, leaving nothing to resume
+    #[serde(default)]
+    pub root: Option<PathBuf>,       // Directory the paths above are saved relative to, so the checkpoint can move with the data
+    #[serde(default)]
+    pub remaps: Vec<PathRemap>,      // Prefix rewrites given on resume, also applied to the input list's entries
+    #[serde(default)]
+    pub config: Option<Config>,      // Options the merge was started with, which a resume runs with again
+    #[serde(default)]
+    pub snapshot: Option<PathBuf>,   // Unique lines of the first `snapshot_files` processed files, saved when the merge was interrupted
+    #[serde(default)]
+    pub snapshot_files: usize,       // How many of the processed files the snapshot holds
+}
+
+// Prefix rewrite of checkpoint paths, given on resume as OLD=NEW
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathRemap {
+    pub from: PathBuf, // Prefix the data used to live under
+    pub to: PathBuf,   // Prefix it lives under now
+}
+
+impl PathRemap {
+    // `path` with the `from` prefix replaced by `to`, or None when it is not under `from`
+    //
+    // Prefixes match whole components, so /data never matches /database.
+    pub fn apply(&self, path: &Path) -> Option<PathBuf> {
+        let rest = path.strip_prefix(&self.from).ok()?;
+        Some(if rest.as_os_str().is_empty() { self.to.clone() } else { self.to.join(rest) })
+    }
+}
+
+// `path` rewritten by the first of `remaps` it is under, or unchanged
+pub fn remap(path: &Path, remaps: &[PathRemap]) -> PathBuf {
+    remaps.iter().find_map(|remap| remap.apply(path)).unwrap_or_else(|| path.to_path_buf())
+}
+
+// Parse a --remap argument: OLD=NEW, both non-empty
+pub fn parse_remap(value: &str) -> Result<PathRemap, String> {
+    let (from, to) = value.split_once('=')
+        .ok_or_else(|| format!("'{}' is not OLD=NEW, such as /mnt/old=/mnt/new", value))?;
+    if from.is_empty() || to.is_empty() {
+        return Err(format!("'{}' needs both an old and a new prefix", value));
+    }
+    Ok(PathRemap { from: PathBuf::from(from), to: PathBuf::from(to) })
 }
 
 // Implement Default trait for Progress
@@ -72,6 +131,12 @@ impl Default for Progress {
             processed_files: Vec::new(),
             current_position: 0,
             save_path: None,
+            finished: false,
+            root: None,
+            remaps: Vec::new(),
+            config: None,
+            snapshot: None,
+            snapshot_files: 0,
         }
     }
 }
@@ -80,8 +145,11 @@ impl Progress {
     // Save current progress state to JSON file
     pub async fn save(&self) -> Result<()> {
         if let Some(path) = &self.save_path {
-            // Convert progress state to pretty-printed JSON
-            let content = serde_json::to_string_pretty(&self)?;
+            // Convert progress state to pretty-printed JSON, with paths under the root made relative
+            let content = match &self.root {
+                Some(root) => serde_json::to_string_pretty(&self.relative_to(root)?)?,
+                None => serde_json::to_string_pretty(&self)?,
+            };
             // Write to file asynchronously
             fs::write(path, content).await?;
         }
@@ -90,15 +158,68 @@ impl Progress {
 
     // Load progress state from a JSON file
     pub async fn load(path: &PathBuf) -> Result<Self> {
+        Self::load_with_root(path, None).await
+    }
+
+    // Load progress state from a JSON file, resolving relative paths against `root` instead of
+    // the root it was saved with
+    pub async fn load_with_root(path: &PathBuf, root: Option<PathBuf>) -> Result<Self> {
         // Read file content asynchronously
         let content = fs::read_to_string(path).await?;
         // Parse JSON into Progress struct
         let mut progress: Progress = serde_json::from_str(&content)?;
         // Store save path for future updates
         progress.save_path = Some(path.clone());
+        if let Some(root) = root {
+            progress.root = Some(std::path::absolute(root)?);
+        }
+        if let Some(root) = progress.root.clone() {
+            progress.input_file = root.join(&progress.input_file);
+            progress.output_file = root.join(&progress.output_file);
+            progress.processed_files = progress.processed_files.iter().map(|file| root.join(file)).collect();
+            progress.snapshot = progress.snapshot.map(|snapshot| root.join(snapshot));
+        }
         Ok(progress)
     }
 
+    // Rewrite every path of the checkpoint with `remaps`, keeping them for the input list's
+    // entries, and return how many paths changed
+    pub fn remap(&mut self, remaps: &[PathRemap]) -> usize {
+        let mut changed = 0;
+        let mut rewrite = |path: &mut PathBuf| {
+            let remapped = remap(path, remaps);
+            if remapped != *path {
+                *path = remapped;
+                changed += 1;
+            }
+        };
+        rewrite(&mut self.input_file);
+        rewrite(&mut self.output_file);
+        self.processed_files.iter_mut().for_each(&mut rewrite);
+        self.snapshot.iter_mut().for_each(&mut rewrite);
+        if let Some(root) = self.root.as_mut() {
+            rewrite(root);
+        }
+        // The latest remaps go first, so they win over earlier ones for the same prefix
+        self.remaps.splice(0..0, remaps.iter().cloned());
+        changed
+    }
+
+    // Copy with every path under `root` relative to it; paths outside it stay absolute
+    fn relative_to(&self, root: &Path) -> Result<Self> {
+        let relative = |path: &Path| -> Result<PathBuf> {
+            let path = std::path::absolute(path)?;
+            Ok(path.strip_prefix(root).map(Path::to_path_buf).unwrap_or(path))
+        };
+        Ok(Self {
+            input_file: relative(&self.input_file)?,
+            output_file: relative(&self.output_file)?,
+            processed_files: self.processed_files.iter().map(|file| relative(file)).collect::<Result<_>>()?,
+            snapshot: self.snapshot.as_deref().map(relative).transpose()?,
+            ..self.clone()
+        })
+    }
+
     // Add a processed file to the progress tracking
     #[allow(dead_code)]  // Suppress unused function warning
     pub async fn add_processed_file(&mut self, file: PathBuf) -> Result<()> {
@@ -111,12 +232,14 @@ impl Progress {
     }
 }
 
+#[allow(dead_code)]
 pub struct ProgressTracker {
     multi_progress: MultiProgress,
     overall_progress: ProgressBar,
     dedup_progress: ProgressBar,
     metrics: ProcessingMetrics,
     refresh_rate: Duration,
+    unique_forecast: Option<u64>,
 }
 
 impl ProgressTracker {
@@ -147,9 +270,20 @@ impl ProgressTracker {
             dedup_progress: dedup_pb,
             metrics: ProcessingMetrics::new(),
             refresh_rate: Duration::from_millis(100),
+            unique_forecast: None,
         }
     }
 
+    // Stop drawing the bars, for when a dashboard shows the progress instead
+    pub fn hide(&self) {
+        self.multi_progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    // Show `bytes` as the memory in use on the next progress update
+    pub fn set_memory_usage(&mut self, bytes: u64) {
+        self.metrics.set_memory_usage(bytes as usize);
+    }
+
     pub fn update_overall_progress(&mut self, files_processed: usize) {
         self.metrics.increment_files();
         let summary = self.metrics.get_summary();
@@ -163,16 +297,25 @@ impl ProgressTracker {
         ));
     }
 
+    // Show `unique` as the estimated final unique count on the next progress update
+    pub fn set_unique_forecast(&mut self, unique: u64) {
+        self.unique_forecast = Some(unique);
+    }
+
     pub fn update_dedup_progress(&mut self, lines_processed: usize, total_lines: usize) {
         self.metrics.add_lines(lines_processed);
         let summary = self.metrics.get_summary();
         
         self.dedup_progress.set_length(total_lines as u64);
         self.dedup_progress.set_position(lines_processed as u64);
+        let forecast = self.unique_forecast
+            .map_or_else(String::new, |unique| format!(" | Est. final unique: ~{}", unique));
         self.dedup_progress.set_message(format!(
-            "Speed: {:.2} lines/s | Unique lines: {}",
+            "Speed: {:.2} lines/s | Unique lines: {} | Duplicates: {:.1}%{}",
             summary.lines_processed as f64 / summary.elapsed_time.as_secs_f64(),
-            lines_processed
+            lines_processed,
+            report::duplicate_percent(total_lines, lines_processed),
+            forecast
         ));
     }
 
@@ -188,6 +331,7 @@ impl ProgressTracker {
         self.dedup_progress.finish();
     }
 
+    #[allow(dead_code)]
     pub fn get_metrics(&self) -> &ProcessingMetrics {
         &self.metrics
     }
@@ -0,0 +1,227 @@
+use serde::Serialize; // Import Serialize for the JSON ranking
+use std::collections::hash_map::RandomState; // Import RandomState to hash lines
+use std::collections::{HashMap, HashSet}; // Import maps keyed by line hashes
+use std::hash::BuildHasher; // Import BuildHasher to hash byte slices
+use std::io::{BufRead, BufReader}; // Import buffered reading to stream each input
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for input entries
+use crate::errors::MergerResult; // Import MergerResult for read and query errors
+use crate::estimate::format_bytes; // Reuse the byte formatter for the printed table
+use crate::sqlite_source::{self, SqliteSource}; // Import sqlite_source to read query entries
+
+const READ_BUFFER_SIZE: usize = 1024 * 1024; // Read each input 1MB at a time
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0; // Bytes in a megabyte for the per-MB ranking
+
+// How much of one input no other input already provides
+#[derive(Debug, Clone, Serialize)]
+pub struct InputRedundancy {
+    pub path: PathBuf,          // Input entry
+    pub bytes: u64,             // Size of the file, or of the database for query entries
+    pub distinct_lines: u64,    // Different non-empty lines in the input
+    pub exclusive_lines: u64,   // Lines found in no other input
+    pub coverage: f64,          // Share of distinct lines also found elsewhere, 0.0-1.0
+    pub unique_per_mb: f64,     // Exclusive lines per MB of input
+    pub redundant: bool,        // Still covered once the other flagged inputs are gone, so it can be dropped
+}
+
+// Ranking of every input by what it alone contributes
+#[derive(Debug, Clone, Serialize)]
+pub struct RedundancyReport {
+    pub threshold: f64,               // Coverage at which an input is flagged, 0.0-1.0
+    pub inputs: Vec<InputRedundancy>, // Inputs from most to least exclusive lines per MB
+}
+
+impl RedundancyReport {
+    // Read every input once, rank them by exclusive lines per MB and flag the redundant ones
+    //
+    // Memory grows with the number of distinct lines across all inputs: one hash, first
+    // input and input count per line, plus a hash per distinct line of the input being read.
+    pub fn build(inputs: &[PathBuf], threshold: f64) -> MergerResult<Self> {
+        let hasher = RandomState::new();
+        let mut holders: HashMap<u64, (u32, u32)> = HashMap::new(); // First input and number of inputs per line
+        let mut distinct = Vec::with_capacity(inputs.len());
+
+        for (position, input) in inputs.iter().enumerate() {
+            let mut seen: HashSet<u64> = HashSet::new();
+            for_each_line(input, |line| {
+                let hash = hasher.hash_one(line);
+                if seen.insert(hash) {
+                    holders.entry(hash).or_insert((position as u32, 0)).1 += 1;
+                }
+            })?;
+            distinct.push(seen.len() as u64);
+        }
+
+        let mut exclusive = vec![0u64; inputs.len()];
+        for (owner, _) in holders.values().filter(|(_, count)| *count == 1) {
+            exclusive[*owner as usize] += 1;
+        }
+
+        let mut ranked = Vec::with_capacity(inputs.len());
+        for (position, input) in inputs.iter().enumerate() {
+            let bytes = input_size(input)?;
+            let (distinct_lines, exclusive_lines) = (distinct[position], exclusive[position]);
+            let coverage = if distinct_lines == 0 {
+                1.0
+            } else {
+                1.0 - exclusive_lines as f64 / distinct_lines as f64
+            };
+            ranked.push(InputRedundancy {
+                path: input.clone(),
+                bytes,
+                distinct_lines,
+                exclusive_lines,
+                coverage,
+                unique_per_mb: exclusive_lines as f64 / (bytes.max(1) as f64 / BYTES_PER_MB),
+                redundant: false,
+            });
+        }
+
+        // Two inputs can each cover the other, so flag the weakest first and only
+        // count lines still held by an input that is kept
+        let mut candidates: Vec<usize> = (0..ranked.len()).filter(|i| ranked[*i].coverage >= threshold).collect();
+        candidates.sort_by(|a, b| {
+            let (a, b) = (&ranked[*a], &ranked[*b]);
+            a.unique_per_mb.total_cmp(&b.unique_per_mb).then(b.bytes.cmp(&a.bytes))
+        });
+        for position in candidates {
+            let mut seen: HashSet<u64> = HashSet::new();
+            for_each_line(&inputs[position], |line| {
+                seen.insert(hasher.hash_one(line));
+            })?;
+            let sole = seen.iter().filter(|hash| holders.get(*hash).is_some_and(|(_, count)| *count <= 1)).count();
+            if seen.is_empty() || 1.0 - sole as f64 / seen.len() as f64 >= threshold {
+                ranked[position].redundant = true;
+                for hash in &seen {
+                    if let Some((_, count)) = holders.get_mut(hash) {
+                        *count -= 1;
+                    }
+                }
+            }
+        }
+        ranked.sort_by(|a, b| b.unique_per_mb.total_cmp(&a.unique_per_mb));
+
+        Ok(Self { threshold, inputs: ranked })
+    }
+
+    // Inputs flagged for removal
+    pub fn redundant(&self) -> impl Iterator<Item = &InputRedundancy> {
+        self.inputs.iter().filter(|input| input.redundant)
+    }
+
+    // Print one row per input, best first, marking the ones to remove
+    pub fn print_table(&self) {
+        println!(
+            "  {:>10} {:>12} {:>12} {:>9} {:>12}  File",
+            "Size", "Distinct", "Exclusive", "Covered", "Unique/MB"
+        );
+        for input in &self.inputs {
+            println!(
+                "  {:>10} {:>12} {:>12} {:>8.2}% {:>12.1}  {}{}",
+                format_bytes(input.bytes),
+                input.distinct_lines,
+                input.exclusive_lines,
+                input.coverage * 100.0,
+                input.unique_per_mb,
+                input.path.display(),
+                if input.redundant { "  [remove]" } else { "" }
+            );
+        }
+
+        let redundant: Vec<&InputRedundancy> = self.redundant().collect();
+        let freed: u64 = redundant.iter().map(|input| input.bytes).sum();
+        println!(
+            "{} of {} inputs are at least {:.1}% covered by the inputs that are kept; removing them frees {}",
+            redundant.len(), self.inputs.len(), self.threshold * 100.0, format_bytes(freed)
+        );
+    }
+}
+
+// Pass every non-empty line of a file or query entry to `on_line`
+fn for_each_line(input: &Path, mut on_line: impl FnMut(&[u8])) -> MergerResult<()> {
+    if sqlite_source::is_sqlite(input) {
+        return SqliteSource::parse(input)?.for_each_row(|value| {
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                on_line(value);
+            }
+            Ok(())
+        });
+    }
+
+    let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, std::fs::File::open(input)?);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            return Ok(());
+        }
+        let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        if !line.is_empty() {
+            on_line(line);
+        }
+    }
+}
+
+// Size of an input entry; a query counts as the size of its database
+fn input_size(input: &Path) -> MergerResult<u64> {
+    if sqlite_source::is_sqlite(input) {
+        return Ok(std::fs::metadata(SqliteSource::parse(input)?.database)?.len());
+    }
+    Ok(std::fs::metadata(input)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedundancyReport;
+    use std::path::PathBuf;
+
+    // Write each input to a temp directory; returns the directory and the input paths
+    fn inputs(contents: &[&str]) -> (tempfile::TempDir, Vec<PathBuf>) {
+        let temp = tempfile::tempdir().unwrap();
+        let paths = contents.iter().enumerate()
+            .map(|(i, content)| {
+                let path = temp.path().join(format!("input{}.txt", i));
+                std::fs::write(&path, content).unwrap();
+                path
+            })
+            .collect();
+        (temp, paths)
+    }
+
+    // The ranked entry for `path`
+    fn entry<'a>(report: &'a RedundancyReport, path: &PathBuf) -> &'a super::InputRedundancy {
+        report.inputs.iter().find(|input| &input.path == path).unwrap()
+    }
+
+    #[test]
+    fn counts_exclusive_lines_and_coverage() {
+        let (_temp, paths) = inputs(&["alpha\nbeta\n", "alpha\nbeta\ngamma\n\n", "delta\n"]);
+        let report = RedundancyReport::build(&paths, 0.9).unwrap();
+        let covered = entry(&report, &paths[0]);
+        assert_eq!((covered.distinct_lines, covered.exclusive_lines), (2, 0));
+        assert_eq!(covered.coverage, 1.0);
+        assert!(covered.redundant);
+
+        let wider = entry(&report, &paths[1]);
+        assert_eq!((wider.distinct_lines, wider.exclusive_lines), (3, 1));
+        assert!(!wider.redundant);
+        assert!(!entry(&report, &paths[2]).redundant);
+        assert_eq!(report.redundant().count(), 1);
+    }
+
+    #[test]
+    fn of_two_identical_inputs_only_the_larger_is_flagged() {
+        let (_temp, paths) = inputs(&["alpha\nbeta\n", "alpha\nbeta\nalpha\n"]);
+        let report = RedundancyReport::build(&paths, 0.9).unwrap();
+        assert!(!entry(&report, &paths[0]).redundant);
+        assert!(entry(&report, &paths[1]).redundant);
+    }
+
+    #[test]
+    fn ranks_by_exclusive_lines_per_mb() {
+        let (_temp, paths) = inputs(&["alpha\n", "beta\nlonger gamma\n"]);
+        let report = RedundancyReport::build(&paths, 1.0).unwrap();
+        let order: Vec<_> = report.inputs.iter().map(|input| &input.path).collect();
+        assert_eq!(order, [&paths[0], &paths[1]]);
+        assert!(report.inputs[0].unique_per_mb > report.inputs[1].unique_per_mb);
+    }
+}
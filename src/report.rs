@@ -0,0 +1,193 @@
+use chrono::{DateTime, Local}; // Import chrono to timestamp the report
+use serde::Serialize; // Import Serialize to write the report as JSON
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for file paths
+use crate::errors::{MergerError, MergerResult}; // Import the error types being classified
+use crate::retry::RetryStats; // Import RetryStats to include retry outcomes
+use crate::contribution::{FileContribution, TagContribution}; // Import the per-file and per-tag statistics
+use crate::encoding::EncodingSummary; // Import EncodingSummary for the encodings of the inputs
+
+// Why a file was skipped or only partly merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+    Binary,    // Contains NUL bytes, so most of it is probably not text
+    Encoding,  // Lines that are not valid UTF-8 were dropped
+    Io,        // Could not be opened or read
+    Timeout,   // Reading timed out
+    Oversized, // Larger than --skip-larger-than, so never read
+    NotText,   // Starts with the magic bytes of a binary format, so never read
+}
+
+impl IssueKind {
+    // Classify the error that made a file fail
+    pub fn of_error(error: &MergerError) -> Self {
+        match error {
+            MergerError::Io(e) if e.kind() == std::io::ErrorKind::TimedOut => IssueKind::Timeout,
+            _ => IssueKind::Io,
+        }
+    }
+
+    // Short label used in the printed summary
+    pub fn label(&self) -> &'static str {
+        match self {
+            IssueKind::Binary => "binary",
+            IssueKind::Encoding => "encoding",
+            IssueKind::Io => "I/O error",
+            IssueKind::Timeout => "timeout",
+            IssueKind::Oversized => "too large",
+            IssueKind::NotText => "not text",
+        }
+    }
+}
+
+// One file that was skipped or degraded
+#[derive(Debug, Clone, Serialize)]
+pub struct FileIssue {
+    pub path: PathBuf,   // Input file concerned
+    pub kind: IssueKind, // Category of the problem
+    pub skipped: bool,   // Whether nothing from the file was merged
+    pub detail: String,  // Error message or what was dropped
+}
+
+// Line counts gathered while reading one input
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileReadStats {
+    pub lines: usize,         // Lines passed on to deduplication
+    pub invalid_lines: usize, // Lines dropped because they are not valid UTF-8
+    pub nul_lines: usize,     // Lines containing NUL bytes, merged or not
+    pub hex_encoded: usize,   // Invalid UTF-8 lines kept as `$HEX[...]` (counted in `lines`)
+    pub binary_hex: usize,    // Lines with NUL bytes written as `$HEX[...]` (counted in `lines`, not in `nul_lines`)
+    pub skipped_lines: usize, // Lines without a candidate, such as a dictionary's word count
+    pub stripped_lines: usize, // Candidates that had control characters removed (counted where they ended up)
+    pub normalized_lines: usize, // Candidates with fullwidth or look-alike characters folded to ASCII (counted where they ended up)
+    pub filtered_lines: usize, // Candidates dropped by the strength filter
+    pub walk_lines: usize,     // Candidates dropped as keyboard walks or sequences
+    pub plugin_lines: usize,   // Candidates dropped by plugins
+    pub script_lines: usize,   // Candidates dropped by the line script
+    pub entry_filtered_lines: usize, // Candidates dropped by the filters of their input list entry
+    pub transformed_lines: usize, // Extra candidates generated by transforms and templates (not counted in `lines`)
+    pub encoding: Option<&'static str>, // Encoding detected from the start of the bytes read, None before reading
+}
+
+impl FileReadStats {
+    // Lines read from the input, whether merged or dropped
+    pub fn read_lines(&self) -> usize {
+        self.lines + self.invalid_lines + self.skipped_lines + self.filtered_lines
+            + self.walk_lines + self.plugin_lines + self.script_lines + self.entry_filtered_lines
+    }
+
+    // The degradation this read amounts to, if any
+    //
+    // A file is treated as binary once at least half of its lines contain NUL bytes.
+    pub fn issue(&self, path: &Path) -> Option<FileIssue> {
+        let seen = self.lines + self.invalid_lines;
+        let (kind, detail) = if self.nul_lines > 0 && self.nul_lines * 2 >= seen {
+            (IssueKind::Binary, format!(
+                "{} of {} lines contain NUL bytes; {} lines that are not valid UTF-8 were dropped",
+                self.nul_lines, seen, self.invalid_lines
+            ))
+        } else if self.invalid_lines > 0 {
+            (IssueKind::Encoding, format!("{} of {} lines are not valid UTF-8 and were dropped", self.invalid_lines, seen))
+        } else {
+            return None;
+        };
+
+        Some(FileIssue { path: path.to_path_buf(), kind, skipped: false, detail })
+    }
+}
+
+// Machine-readable summary of a finished merge, written with `--report`
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub input_list: PathBuf,       // Listing of the inputs
+    pub output: PathBuf,           // Merged output file
+    pub lines_processed: usize,    // Lines fed into deduplication
+    pub unique_lines: usize,       // Unique lines written
+    pub duplicate_percent: f64,    // Share of the lines processed that were duplicates
+    pub retries: RetryStats,       // Outcomes of transient-error retries
+    pub issues: Vec<FileIssue>,    // Files that were skipped or degraded
+    pub files: Vec<FileContribution>, // What each merged input contributed
+    pub tags: Vec<TagContribution>, // What the inputs of each input list tag contributed; empty when none are tagged
+    pub encodings: EncodingSummary, // Inputs per encoding and lines that were not valid UTF-8
+    pub peak_memory: Option<u64>,  // Largest resident set size sampled, in bytes, where the platform exposes it
+    pub generated_at: DateTime<Local>, // When the run finished
+}
+
+impl RunReport {
+    // Write the report as pretty-printed JSON
+    pub async fn save(&self, path: &Path) -> MergerResult<()> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| MergerError::Processing(e.to_string()))?;
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+}
+
+// Share of `lines` that were duplicates of an earlier line, as a percentage
+pub fn duplicate_percent(lines: usize, unique: usize) -> f64 {
+    if lines == 0 {
+        return 0.0;
+    }
+    lines.saturating_sub(unique) as f64 * 100.0 / lines as f64
+}
+
+// Print one consolidated section listing every skipped or degraded file
+pub fn print_issue_summary(issues: &[FileIssue]) {
+    if issues.is_empty() {
+        return;
+    }
+
+    let skipped = issues.iter().filter(|issue| issue.skipped).count();
+    println!("\n{} files skipped, {} degraded:", skipped, issues.len() - skipped);
+    // Skipping a whole input on purpose can hide most of a corpus, so it is called out first
+    let oversized = issues.iter().filter(|issue| issue.kind == IssueKind::Oversized).count();
+    if oversized > 0 {
+        println!("  !! {} inputs were not merged at all because they exceed --skip-larger-than", oversized);
+    }
+    for issue in issues {
+        println!(
+            "  {:<8} {:<10} {}: {}",
+            if issue.skipped { "skipped" } else { "degraded" },
+            issue.kind.label(),
+            issue.path.display(),
+            issue.detail
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{duplicate_percent, FileReadStats, IssueKind};
+    use crate::errors::MergerError;
+    use std::path::Path;
+
+    #[test]
+    fn mostly_nul_reads_are_binary() {
+        let stats = FileReadStats { lines: 3, invalid_lines: 1, nul_lines: 2, ..FileReadStats::default() };
+        let issue = stats.issue(Path::new("dump.bin")).unwrap();
+        assert_eq!(issue.kind, IssueKind::Binary);
+        assert!(!issue.skipped);
+        assert!(issue.detail.starts_with("2 of 4 lines contain NUL bytes"));
+    }
+
+    #[test]
+    fn invalid_lines_without_much_nul_are_an_encoding_issue() {
+        let stats = FileReadStats { lines: 9, invalid_lines: 1, nul_lines: 1, ..FileReadStats::default() };
+        assert_eq!(stats.issue(Path::new("words.txt")).unwrap().kind, IssueKind::Encoding);
+        assert!(FileReadStats { lines: 9, ..FileReadStats::default() }.issue(Path::new("words.txt")).is_none());
+    }
+
+    #[test]
+    fn timeouts_are_told_apart_from_other_read_errors() {
+        let timeout = MergerError::Io(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        let missing = MergerError::Io(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(IssueKind::of_error(&timeout), IssueKind::Timeout);
+        assert_eq!(IssueKind::of_error(&missing), IssueKind::Io);
+    }
+
+    #[test]
+    fn duplicate_percents_are_shares_of_the_lines() {
+        assert_eq!(duplicate_percent(0, 0), 0.0);
+        assert_eq!(duplicate_percent(200, 50), 75.0);
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize}; // Import serde traits so the policy can be set in the config
+use std::io; // Import io to classify error kinds
+use std::time::Duration; // Import Duration for backoff delays
+use crate::errors::MergerError; // Import MergerError, the error type being classified
+
+// How often and how patiently to retry reads that failed transiently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    pub max_retries: u32,       // Retries per file after the first attempt
+    pub initial_backoff_ms: u64, // Delay before the first retry, doubled for each further retry
+    pub max_backoff_ms: u64,    // Upper bound on a single delay
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Delay before retry number `attempt` (starting at 1)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(20);
+        Duration::from_millis(self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms))
+    }
+}
+
+// Retry outcomes accumulated over a merge
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RetryStats {
+    pub retries: usize,   // Retries performed in total
+    pub recovered: usize, // Files that succeeded after at least one retry
+    pub exhausted: usize, // Files that still failed after every retry
+}
+
+impl RetryStats {
+    // Add the outcomes of another read
+    pub fn add(&mut self, other: RetryStats) {
+        self.retries += other.retries;
+        self.recovered += other.recovered;
+        self.exhausted += other.exhausted;
+    }
+}
+
+// Whether `error` may go away if the read is attempted again
+//
+// Missing files, permission problems and bad data are permanent; timeouts, interrupted
+// calls and the EIO/ESTALE family seen on network filesystems and flaky USB drives are not.
+pub fn is_transient(error: &MergerError) -> bool {
+    let MergerError::Io(error) = error else {
+        return false;
+    };
+
+    match error.kind() {
+        io::ErrorKind::Interrupted
+        | io::ErrorKind::TimedOut
+        | io::ErrorKind::WouldBlock
+        | io::ErrorKind::UnexpectedEof
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::BrokenPipe => true,
+        _ => is_transient_os_error(error.raw_os_error()),
+    }
+}
+
+#[cfg(unix)]
+fn is_transient_os_error(code: Option<i32>) -> bool {
+    matches!(
+        code,
+        Some(libc::EIO | libc::EAGAIN | libc::ESTALE | libc::ETIMEDOUT | libc::ENOLCK | libc::EBUSY)
+    )
+}
+
+#[cfg(not(unix))]
+fn is_transient_os_error(_code: Option<i32>) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_transient, RetryPolicy};
+    use crate::errors::MergerError;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::default();
+        let millis: Vec<u128> = (1..=7).map(|attempt| policy.backoff(attempt).as_millis()).collect();
+        assert_eq!(millis, [500, 1000, 2000, 4000, 8000, 10_000, 10_000]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn only_passing_io_errors_are_retried() {
+        assert!(is_transient(&MergerError::Io(io::Error::from(io::ErrorKind::TimedOut))));
+        assert!(!is_transient(&MergerError::Io(io::Error::from(io::ErrorKind::NotFound))));
+        assert!(!is_transient(&MergerError::Io(io::Error::from(io::ErrorKind::PermissionDenied))));
+        assert!(!is_transient(&MergerError::Processing("bad data".to_string())));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn network_filesystem_errors_are_retried() {
+        assert!(is_transient(&MergerError::Io(io::Error::from_raw_os_error(libc::ESTALE))));
+        assert!(is_transient(&MergerError::Io(io::Error::from_raw_os_error(libc::EIO))));
+        assert!(!is_transient(&MergerError::Io(io::Error::from_raw_os_error(libc::ENOENT))));
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the routed output paths
+use crate::patterns; // Import patterns to spot numbers and keyboard walks
+use crate::side_output::{parse_range, SideOutput, SideSink}; // Import the shared side output writer and range parser
+
+// Inclusive range of digit counts such as `4-8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigitLengths {
+    pub min: usize, // Fewest digits matched
+    pub max: usize, // Most digits matched
+}
+
+impl DigitLengths {
+    // Whether `len` digits fall within the range
+    pub fn contains(&self, len: usize) -> bool {
+        (self.min..=self.max).contains(&len)
+    }
+}
+
+// Parse a digit count range such as `4-8`, or a single count
+pub fn parse_digit_lengths(value: &str) -> Result<DigitLengths, String> {
+    parse_range(value).filter(|(min, _)| *min > 0).map(|(min, max)| DigitLengths { min, max })
+        .ok_or_else(|| format!("invalid digit length range '{}': expected MIN-MAX such as 4-8", value))
+}
+
+// Kind of candidate a route diverts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteMatch {
+    Numeric(Vec<DigitLengths>), // All-digit candidates such as PINs and dates, of these lengths or any when empty
+    Walks,                      // Keyboard walks and sequences such as `qwerty` or `abcd`
+}
+
+impl RouteMatch {
+    // Name used in messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            RouteMatch::Numeric(_) => "numeric",
+            RouteMatch::Walks => "walks",
+        }
+    }
+
+    // Whether `candidate` is of this kind
+    pub fn matches(&self, candidate: &[u8]) -> bool {
+        match self {
+            RouteMatch::Numeric(lengths) => {
+                patterns::is_numeric(candidate)
+                    && (lengths.is_empty() || lengths.iter().any(|range| range.contains(candidate.len())))
+            }
+            RouteMatch::Walks => patterns::is_walk(candidate),
+        }
+    }
+}
+
+// Candidates of one kind written to their own text file instead of the main output
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub matcher: RouteMatch, // Candidates diverted
+    pub path: PathBuf,       // File receiving them, one per line
+}
+
+impl SideOutput for Route {
+    const DIVERTS: bool = true;
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn takes(&self, candidate: &[u8]) -> bool {
+        self.matcher.matches(candidate)
+    }
+
+    fn summary(&self, lines: u64, size: &str) -> String {
+        format!("{}: {} lines ({}) routed to {}", self.matcher.label(), lines, size, self.path.display())
+    }
+}
+
+// Output that diverts each line to the first route matching it and passes the rest on
+pub type RouteSink = SideSink<Route>;
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_digit_lengths, DigitLengths, RouteMatch};
+
+    #[test]
+    fn digit_lengths_parse_a_range_or_one_count() {
+        assert_eq!(parse_digit_lengths("4-8"), Ok(DigitLengths { min: 4, max: 8 }));
+        assert_eq!(parse_digit_lengths("6"), Ok(DigitLengths { min: 6, max: 6 }));
+        assert!(parse_digit_lengths("0-4").is_err());
+        assert!(parse_digit_lengths("8-4").is_err());
+    }
+
+    #[test]
+    fn numeric_routes_take_digits_of_the_listed_lengths() {
+        let any = RouteMatch::Numeric(Vec::new());
+        assert!(any.matches(b"0451"));
+        assert!(!any.matches(b"12-34"));
+        assert!(!any.matches(b""));
+
+        let pins_and_dates = RouteMatch::Numeric(vec![DigitLengths { min: 4, max: 4 }, DigitLengths { min: 8, max: 8 }]);
+        assert!(pins_and_dates.matches(b"1234"));
+        assert!(pins_and_dates.matches(b"19870523"));
+        assert!(!pins_and_dates.matches(b"123456"));
+    }
+}
@@ -0,0 +1,54 @@
+// Split hashcat-style rules into their functions
+//
+// Each function is one letter or symbol followed by a fixed number of argument bytes, and
+// spaces between functions are ignored. An argument may itself be a space, as in `$ `.
+
+// Argument bytes taken by each rule function, or None for an unknown function
+fn arity(function: u8) -> Option<usize> {
+    match function {
+        // No argument: case, order and duplication functions, memory and rejection without arguments
+        b':' | b'l' | b'u' | b'c' | b'C' | b't' | b'r' | b'd' | b'f' | b'{' | b'}' | b'[' | b']'
+        | b'k' | b'K' | b'q' | b'E' | b'M' | b'4' | b'6' | b'Q' => Some(0),
+        // One position or character
+        b'T' | b'p' | b'D' | b'z' | b'Z' | b'\'' | b'$' | b'^' | b'@' | b'L' | b'R' | b'+' | b'-'
+        | b'.' | b',' | b'y' | b'Y' | b'e' | b'<' | b'>' | b'_' | b'!' | b'/' | b'(' | b')' => Some(1),
+        // Two positions, or a position and a character, or two characters
+        b'x' | b'O' | b'i' | b'o' | b's' | b'*' | b'3' | b'=' | b'%' | b'?' => Some(2),
+        // Memory extraction: offset, length and insertion position
+        b'X' => Some(3),
+        _ => None,
+    }
+}
+
+// Whether `function` is a rule function this parser knows
+pub fn is_function(function: u8) -> bool {
+    arity(function).is_some()
+}
+
+// The functions of `rule`, each with its arguments, in order
+pub fn parse(rule: &[u8]) -> Result<Vec<&[u8]>, String> {
+    let mut functions = Vec::new();
+    let mut position = 0;
+    while position < rule.len() {
+        let function = rule[position];
+        if function == b' ' || function == b'\t' {
+            position += 1;
+            continue;
+        }
+        let arguments = arity(function).ok_or_else(|| format!("unknown rule function '{}'", char::from(function)))?;
+        let end = position + 1 + arguments;
+        if end > rule.len() {
+            return Err(format!("rule function '{}' needs {} argument characters", char::from(function), arguments));
+        }
+        functions.push(&rule[position..end]);
+        position = end;
+    }
+    Ok(functions)
+}
+
+// `rule` with the spaces between its functions removed, or None when it cannot be parsed
+//
+// Functions take a fixed number of argument bytes, so the joined functions still read back the same.
+pub fn canonical(rule: &[u8]) -> Option<Vec<u8>> {
+    parse(rule).ok().map(|functions| functions.concat())
+}
@@ -0,0 +1,312 @@
+use clap::ValueEnum; // Import ValueEnum so the mode can be chosen with --rule-comments
+use serde::{Deserialize, Serialize}; // Import serde traits so the mode can be set in the config
+use std::borrow::Cow; // Import Cow for rule keys that are usually the rule itself
+use std::collections::{HashMap, HashSet}; // Import HashSet to deduplicate rules and HashMap to count them
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for rule file paths
+use tokio::io::AsyncWriteExt; // Import AsyncWriteExt to write the merged rules
+use crate::errors::MergerResult; // Import MergerResult for I/O failures
+use crate::input_list::InputList; // Import InputList to read the rules listing
+use crate::rule_parser; // Import the rule parser for the complexity filters
+
+// What to do with `#` comment lines inside rule files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleComments {
+    #[default]
+    Preserve, // Keep comments where they appear, so section headers stay with their rules
+    Strip,    // Drop comments entirely
+    Separate, // Deduplicate comments among themselves and write them ahead of the rules
+}
+
+// How often one rule occurred across the merged rule files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleFrequency {
+    pub rule: Vec<u8>,    // Rule as written
+    pub occurrences: u64, // Lines holding the rule, over all files
+    pub files: usize,     // Rule files holding it at least once
+}
+
+// Counts reported after a rule merge
+#[derive(Debug, Clone, Default)]
+pub struct RuleMergeSummary {
+    pub files: usize,      // Rule files read
+    pub rules: usize,      // Rule lines read
+    pub unique_rules: usize, // Rules written after deduplication
+    pub comments: usize,   // Comment lines written
+    pub rejected: usize,   // Rule lines dropped by the complexity filters
+    pub ranking: Vec<RuleFrequency>, // Rules from most to least common, when frequency tracking is on
+}
+
+impl RuleMergeSummary {
+    // Write the ranking as tab-separated occurrences, files and rule, most common first
+    pub async fn write_ranking(&self, path: &Path) -> MergerResult<()> {
+        let mut content = b"occurrences\tfiles\trule\n".to_vec();
+        for entry in &self.ranking {
+            content.extend_from_slice(format!("{}\t{}\t", entry.occurrences, entry.files).as_bytes());
+            content.extend_from_slice(&entry.rule);
+            content.push(b'\n');
+        }
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+}
+
+// Limits on what merged rules may contain, such as functions a GPU cannot run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleFilter {
+    pub max_ops: Option<usize>, // Most functions in one rule
+    pub rejected: Vec<u8>,      // Functions a rule may not use
+}
+
+impl RuleFilter {
+    // Whether any limit is set
+    pub fn is_active(&self) -> bool {
+        self.max_ops.is_some() || !self.rejected.is_empty()
+    }
+
+    // Why `rule` is dropped, or None when it is kept; a rule that cannot be parsed is dropped
+    pub fn reject(&self, rule: &[u8]) -> Option<String> {
+        let functions = match rule_parser::parse(rule) {
+            Ok(functions) => functions,
+            Err(e) => return Some(e),
+        };
+        if let Some(max_ops) = self.max_ops.filter(|max_ops| functions.len() > *max_ops) {
+            return Some(format!("{} functions, more than {}", functions.len(), max_ops));
+        }
+        functions.iter().find(|function| self.rejected.contains(&function[0]))
+            .map(|function| format!("uses rejected function '{}'", char::from(function[0])))
+    }
+}
+
+// Parse one rejected rule function, a single function character such as `X` or `M`
+pub fn parse_rule_function(value: &str) -> Result<u8, String> {
+    match value.trim().as_bytes() {
+        [function] if rule_parser::is_function(*function) => Ok(*function),
+        _ => Err(format!("'{}' is not a rule function; give the single function character, such as X or M", value)),
+    }
+}
+
+// Path of the frequency ranking for merged rules at `output`: `all.rule` becomes `all.rule.frequency.tsv`
+pub fn ranking_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_os_string();
+    name.push(".frequency.tsv");
+    PathBuf::from(name)
+}
+
+// Merges hashcat-style rule files, keeping the first occurrence of every rule in order
+//
+// Rule files are small compared to wordlists, so they are merged in memory.
+pub struct RuleMerger {
+    comments: RuleComments, // Comment handling mode
+    frequency: bool,        // Count every rule and rank them in the summary
+    filter: RuleFilter,     // Complexity limits rules must meet
+    ignore_whitespace: bool, // Treat rules differing only in the spaces between functions as one
+}
+
+impl RuleMerger {
+    // Create a merger with the given comment handling
+    pub fn new(comments: RuleComments) -> Self {
+        Self { comments, frequency: false, filter: RuleFilter::default(), ignore_whitespace: false }
+    }
+
+    // Count how often and in how many files each rule occurs
+    pub fn with_frequency(mut self, enabled: bool) -> Self {
+        self.frequency = enabled;
+        self
+    }
+
+    // Drop rules that break the limits of `filter`
+    pub fn with_filter(mut self, filter: RuleFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // Deduplicate rules by their functions, ignoring the spaces between them
+    pub fn with_ignore_whitespace(mut self, enabled: bool) -> Self {
+        self.ignore_whitespace = enabled;
+        self
+    }
+
+    // Merge the rule files listed in `rules_file` into `output`
+    pub async fn merge(&self, rules_file: &Path, output: &Path) -> MergerResult<RuleMergeSummary> {
+        let list = InputList::load(rules_file).await?;
+        if let Some(summary) = list.normalization.describe() {
+            log::info!("{} (rules)", summary);
+        }
+
+        let mut summary = RuleMergeSummary::default();
+        let mut seen_rules = HashSet::new();
+        let mut seen_comments = HashSet::new();
+        let mut counts: HashMap<Vec<u8>, (u64, usize, usize)> = HashMap::new(); // Occurrences, files and last file per rule
+        let mut order = Vec::new(); // Keys and first-seen rules in order, to break ties in the ranking
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+
+        for path in &list.files {
+            let content = match tokio::fs::read(path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Skipping rule file {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            summary.files += 1;
+            let file = summary.files;
+
+            for line in content.split(|&b| b == b'\n') {
+                // CRLF files must not produce rules that differ only by a trailing '\r';
+                // other whitespace is significant in rules and kept as-is
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                if line.is_empty() {
+                    continue;
+                }
+
+                if line.starts_with(b"#") {
+                    match self.comments {
+                        RuleComments::Preserve => Self::push_line(&mut body, line),
+                        RuleComments::Strip => continue,
+                        RuleComments::Separate if seen_comments.insert(line.to_vec()) => Self::push_line(&mut header, line),
+                        RuleComments::Separate => continue,
+                    }
+                    summary.comments += 1;
+                    continue;
+                }
+
+                summary.rules += 1;
+                if self.filter.is_active() {
+                    if let Some(reason) = self.filter.reject(line) {
+                        log::debug!("Dropping rule {}: {}", String::from_utf8_lossy(line), reason);
+                        summary.rejected += 1;
+                        continue;
+                    }
+                }
+                // A rule the parser cannot read is kept as written, spaces included
+                let key = match self.ignore_whitespace.then(|| rule_parser::canonical(line)).flatten() {
+                    Some(canonical) => Cow::Owned(canonical),
+                    None => Cow::Borrowed(line),
+                };
+                if self.frequency {
+                    let count = counts.entry(key.to_vec()).or_insert_with(|| {
+                        order.push((key.to_vec(), line.to_vec()));
+                        (0, 0, 0)
+                    });
+                    count.0 += 1;
+                    if count.2 != file {
+                        count.1 += 1;
+                        count.2 = file;
+                    }
+                }
+                if seen_rules.insert(key.into_owned()) {
+                    Self::push_line(&mut body, line);
+                    summary.unique_rules += 1;
+                }
+            }
+        }
+
+        let mut file = tokio::fs::File::create(output).await?;
+        file.write_all(&header).await?;
+        file.write_all(&body).await?;
+        file.flush().await?;
+
+        // The sort is stable, so equally common rules stay in first-seen order
+        summary.ranking = order.into_iter()
+            .map(|(key, rule)| {
+                let (occurrences, files, _) = counts[&key];
+                RuleFrequency { rule, occurrences, files }
+            })
+            .collect();
+        summary.ranking.sort_by(|a, b| b.occurrences.cmp(&a.occurrences).then(b.files.cmp(&a.files)));
+
+        Ok(summary)
+    }
+
+    fn push_line(buffer: &mut Vec<u8>, line: &[u8]) {
+        buffer.extend_from_slice(line);
+        buffer.push(b'\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rule_function, ranking_path, RuleComments, RuleFilter, RuleMergeSummary, RuleMerger};
+    use std::path::Path;
+
+    // Merge rule files holding `files` with `merger`; returns the merged rules and the summary
+    async fn merge(merger: RuleMerger, files: &[&str]) -> (String, RuleMergeSummary) {
+        let temp = tempfile::tempdir().unwrap();
+        let mut list = String::new();
+        for (position, content) in files.iter().enumerate() {
+            let path = temp.path().join(format!("{}.rule", position));
+            std::fs::write(&path, content).unwrap();
+            list.push_str(&format!("{}\n", path.display()));
+        }
+        std::fs::write(temp.path().join("rules.txt"), list).unwrap();
+        let output = temp.path().join("merged.rule");
+        let summary = merger.merge(&temp.path().join("rules.txt"), &output).await.unwrap();
+        (std::fs::read_to_string(&output).unwrap(), summary)
+    }
+
+    const FILES: [&str; 2] = ["# case\r\nl\r\nu\r\n", "# case\nu\nc\n# append\n$1\n"];
+
+    #[tokio::test]
+    async fn preserved_comments_stay_with_their_rules() {
+        let (merged, summary) = merge(RuleMerger::new(RuleComments::Preserve), &FILES).await;
+        assert_eq!(merged, "# case\nl\nu\n# case\nc\n# append\n$1\n");
+        assert_eq!((summary.files, summary.rules, summary.unique_rules, summary.comments), (2, 5, 4, 3));
+    }
+
+    #[tokio::test]
+    async fn stripped_comments_are_dropped() {
+        let (merged, _) = merge(RuleMerger::new(RuleComments::Strip), &FILES).await;
+        assert_eq!(merged, "l\nu\nc\n$1\n");
+    }
+
+    #[tokio::test]
+    async fn separated_comments_are_deduplicated_ahead_of_the_rules() {
+        let (merged, summary) = merge(RuleMerger::new(RuleComments::Separate), &FILES).await;
+        assert_eq!(merged, "# case\n# append\nl\nu\nc\n$1\n");
+        assert_eq!(summary.comments, 2);
+    }
+
+    #[tokio::test]
+    async fn ranking_orders_rules_by_occurrences_then_files() {
+        let merger = RuleMerger::new(RuleComments::Strip).with_frequency(true);
+        let (_, summary) = merge(merger, &["u\nc\nc\nl\n", "l\nu\n", "u\n"]).await;
+        let ranking: Vec<(String, u64, usize)> = summary.ranking.iter()
+            .map(|entry| (String::from_utf8(entry.rule.clone()).unwrap(), entry.occurrences, entry.files))
+            .collect();
+        assert_eq!(ranking, [("u".to_string(), 3, 3), ("l".to_string(), 2, 2), ("c".to_string(), 2, 1)]);
+        assert_eq!(ranking_path(Path::new("all.rule")), Path::new("all.rule.frequency.tsv"));
+    }
+
+    #[test]
+    fn filters_reject_long_rules_and_listed_functions() {
+        let filter = RuleFilter { max_ops: Some(2), rejected: vec![b'X'] };
+        assert_eq!(filter.reject(b"$1 $2"), None);
+        assert!(filter.reject(b"$1$2$3").is_some());
+        assert!(filter.reject(b"X012").is_some());
+        assert!(filter.reject(b"$").is_some()); // Unparsable rules are dropped
+        assert_eq!(parse_rule_function(" M "), Ok(b'M'));
+        assert!(parse_rule_function("MX").is_err());
+        assert!(parse_rule_function("w").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejected_rules_are_counted_and_left_out() {
+        let merger = RuleMerger::new(RuleComments::Strip).with_filter(RuleFilter { max_ops: Some(1), rejected: Vec::new() });
+        let (merged, summary) = merge(merger, &["u\n$1$2\nc\n"]).await;
+        assert_eq!(merged, "u\nc\n");
+        assert_eq!((summary.rules, summary.rejected), (3, 1));
+    }
+
+    #[tokio::test]
+    async fn rules_differing_in_spaces_merge_when_whitespace_is_ignored() {
+        let files = ["$1 $2\n$1$2\n$ \n$  \n"];
+        let (merged, _) = merge(RuleMerger::new(RuleComments::Strip), &files).await;
+        assert_eq!(merged, "$1 $2\n$1$2\n$ \n$  \n");
+
+        let (merged, summary) = merge(RuleMerger::new(RuleComments::Strip).with_ignore_whitespace(true), &files).await;
+        assert_eq!(merged, "$1 $2\n$ \n"); // `$ ` appends a space, so only the second space of `$  ` is ignored
+        assert_eq!(summary.unique_rules, 2);
+    }
+}
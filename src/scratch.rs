@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the directory path
+use crate::errors::{ConfigError, MergerResult}; // Import error types for rejected directories
+use crate::estimate::format_bytes; // Reuse the byte formatter for cleanup reports
+use crate::file_utils::FileUtils; // Reuse the prefix-based temp file cleanup
+
+pub const SPILL_PREFIX: &str = "rustmerger-spill-"; // Spill directories are named <prefix><pid>-<random>
+const WRITE_TEST_PREFIX: &str = ".rustmerger-write-test-"; // Probe files left behind if a run dies mid-check
+#[cfg(not(target_os = "linux"))]
+const ORPHAN_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60); // Age after which an unverifiable owner is presumed dead
+
+// Prefix for this run's spill directory, embedding the PID so crashed runs can be told apart
+pub fn spill_prefix() -> String {
+    format!("{}{}-", SPILL_PREFIX, std::process::id())
+}
+
+// Resolve the directory for spill runs and other temporary files and check it can be written
+//
+// Falls back to the OS temp dir, which is often a small tmpfs; large merges should point
+// `--temp-dir` at real disk.
+pub fn prepare(dir: Option<PathBuf>) -> MergerResult<PathBuf> {
+    let dir = dir.unwrap_or_else(std::env::temp_dir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|_| ConfigError::TempDirectoryNotWritable(dir.clone()))?;
+
+    // Writability is only certain after actually creating a file there
+    tempfile::Builder::new()
+        .prefix(WRITE_TEST_PREFIX)
+        .tempfile_in(&dir)
+        .map_err(|_| ConfigError::TempDirectoryNotWritable(dir.clone()))?;
+
+    Ok(dir)
+}
+
+// Remove spill directories and probe files left in `dir` by runs that are no longer alive
+//
+// Returns the number of directories removed and the bytes they held.
+pub async fn cleanup_orphans(dir: &Path) -> MergerResult<(usize, u64)> {
+    FileUtils::cleanup_temp_files(dir, WRITE_TEST_PREFIX).await
+        .map_err(|e| crate::errors::MergerError::Processing(e.to_string()))?;
+
+    let mut removed = 0;
+    let mut freed = 0;
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let Some(pid) = path.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_prefix(SPILL_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+
+        let meta = entry.metadata().await?;
+        if !meta.is_dir() || !is_orphan(pid, &meta) {
+            continue;
+        }
+
+        let size = dir_size(&path).await;
+        match tokio::fs::remove_dir_all(&path).await {
+            Ok(()) => {
+                log::info!("Removed orphaned spill directory {} ({})", path.display(), format_bytes(size));
+                removed += 1;
+                freed += size;
+            }
+            Err(e) => log::warn!("Failed to remove orphaned spill directory {}: {}", path.display(), e),
+        }
+    }
+
+    Ok((removed, freed))
+}
+
+// Whether the run with `pid` that owns an artifact has gone away
+fn is_orphan(pid: u32, meta: &std::fs::Metadata) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+
+    // Where process liveness cannot be checked, only old artifacts are presumed orphaned
+    #[cfg(target_os = "linux")]
+    {
+        let _ = meta;
+        !Path::new("/proc").join(pid.to_string()).exists()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        meta.modified().ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= ORPHAN_AGE)
+    }
+}
+
+// Total size of the files directly inside `dir`; spill directories are flat
+async fn dir_size(dir: &Path) -> u64 {
+    let mut size = 0;
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            size += entry.metadata().await.map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cleanup_orphans, prepare, spill_prefix, SPILL_PREFIX};
+
+    #[test]
+    fn prepare_creates_the_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("spill").join("runs");
+        assert_eq!(prepare(Some(dir.clone())).unwrap(), dir);
+        assert!(dir.is_dir());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn only_directories_of_dead_runs_are_removed() {
+        let temp = tempfile::tempdir().unwrap();
+        // No process has a PID this high, as the kernel caps them at 2^22
+        let dead = temp.path().join(format!("{}999999999-abc", SPILL_PREFIX));
+        let alive = temp.path().join(format!("{}abc", spill_prefix()));
+        let other = temp.path().join("unrelated-999999999");
+        for dir in [&dead, &alive, &other] {
+            std::fs::create_dir(dir).unwrap();
+        }
+        std::fs::write(dead.join("run0"), "alpha\n").unwrap();
+
+        assert_eq!(cleanup_orphans(temp.path()).await.unwrap(), (1, 6));
+        assert!(!dead.exists());
+        assert!(alive.exists() && other.exists());
+    }
+}
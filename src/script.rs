@@ -0,0 +1,90 @@
+use rhai::{Dynamic, Engine, Scope, AST}; // Import the Rhai engine to run line scripts
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the script location
+use crate::errors::{MergerError, MergerResult}; // Import the error types for compile and run failures
+
+// What a line script decided for one candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptDecision {
+    Keep,            // Merge the candidate as it is
+    Drop,            // Leave the candidate out
+    Replace(String), // Merge this text instead
+}
+
+// A Rhai script deciding the fate of every candidate
+//
+// The script runs once per candidate with `line` (the candidate), `source` (the input
+// it came from) and `count` (the occurrences it stands for) in scope. Its last value
+// decides: `true` or nothing keeps the line, `false` drops it and a string replaces it.
+pub struct LineScript {
+    path: PathBuf,  // Script file, named in errors
+    engine: Engine, // Engine shared by every reader
+    ast: AST,       // Compiled script
+}
+
+impl std::fmt::Debug for LineScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LineScript").field("path", &self.path).finish()
+    }
+}
+
+impl LineScript {
+    // Compile the script at `path`, failing before any input is read
+    pub fn load(path: &Path) -> MergerResult<Self> {
+        let engine = Engine::new();
+        let ast = engine.compile_file(path.to_path_buf())
+            .map_err(|e| MergerError::Script(format!("{}: {}", path.display(), e)))?;
+        Ok(Self { path: path.to_path_buf(), engine, ast })
+    }
+
+    // Run the script for `candidate` from input `source` standing for `count` occurrences
+    pub fn decide(&self, candidate: &[u8], source: &str, count: u64) -> MergerResult<ScriptDecision> {
+        // Invalid UTF-8 reaches the script with replacement characters
+        let line = String::from_utf8_lossy(candidate);
+        let mut scope = Scope::new();
+        scope.push_constant("line", line.to_string());
+        scope.push_constant("source", source.to_string());
+        scope.push_constant("count", count as rhai::INT);
+        let failed = |detail: String| MergerError::Script(format!("{}: {}", self.path.display(), detail));
+
+        let result: Dynamic = self.engine.eval_ast_with_scope(&mut scope, &self.ast).map_err(|e| failed(e.to_string()))?;
+        if result.is_unit() {
+            return Ok(ScriptDecision::Keep);
+        }
+        if let Ok(keep) = result.as_bool() {
+            return Ok(if keep { ScriptDecision::Keep } else { ScriptDecision::Drop });
+        }
+        match result.into_string() {
+            // An empty replacement or one spanning lines would not come back out as one candidate
+            Ok(text) if text.is_empty() || text.contains(['\n', '\r']) => Ok(ScriptDecision::Drop),
+            // The line handed back unchanged keeps its original bytes
+            Ok(text) if text == line => Ok(ScriptDecision::Keep),
+            Ok(text) => Ok(ScriptDecision::Replace(text)),
+            Err(kind) => Err(failed(format!("returned {}; expected true, false or a string", kind))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineScript, ScriptDecision};
+
+    fn script(source: &str) -> LineScript {
+        let file = tempfile::Builder::new().suffix(".rhai").tempfile().unwrap();
+        std::fs::write(file.path(), source).unwrap();
+        LineScript::load(file.path()).unwrap()
+    }
+
+    #[test]
+    fn unchanged_invalid_utf8_keeps_its_bytes() {
+        let script = script("line");
+        assert_eq!(script.decide(b"caf\xe9", "latin1.txt", 1).unwrap(), ScriptDecision::Keep);
+    }
+
+    #[test]
+    fn booleans_and_strings_decide() {
+        let script = script(r#"if line.len() < 4 { false } else if source == "upper.txt" { line.to_upper() } else { true }"#);
+        assert_eq!(script.decide(b"abc", "list.txt", 1).unwrap(), ScriptDecision::Drop);
+        assert_eq!(script.decide(b"abcd", "list.txt", 1).unwrap(), ScriptDecision::Keep);
+        assert_eq!(script.decide(b"abcd", "upper.txt", 1).unwrap(), ScriptDecision::Replace("ABCD".to_string()));
+    }
+}
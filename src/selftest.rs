@@ -0,0 +1,295 @@
+use anyhow::Result; // Import Result type from anyhow for error handling
+use encoding_rs::{UTF_16LE, UTF_8}; // Import the encodings the UTF-16 case converts between
+use std::collections::HashSet; // Import HashSet to compare merged output against expectations
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for file path handling
+use std::sync::Arc; // Import Arc to share the application state with the core
+use tempfile::TempDir; // Import TempDir so every run starts from a clean directory
+use crate::app_state::AppState; // Import AppState to drive the real pipeline
+use crate::core::ProcessingCore; // Import ProcessingCore to run the real merge
+use crate::encoding::{self, SourceEncoding}; // Import the converter the UTF-16 case goes through
+use crate::progress::Progress; // Import Progress to simulate an interrupted run
+
+const HUGE_LINE_BYTES: usize = 16 * 1024 * 1024; // Longer than a single processing chunk
+
+// Outcome of a single self-test case
+#[derive(Debug, Clone)]
+pub struct SelfTestResult {
+    pub name: &'static str, // Short name of the case
+    pub passed: bool,       // Whether the expectations held
+    pub detail: String,     // Explanation shown next to the result
+}
+
+// Struct that runs the built-in self-test suite against the real pipeline
+pub struct SelfTest {
+    dir: TempDir,          // Scratch directory holding inputs and outputs
+}
+
+impl SelfTest {
+    // Create a new suite rooted in a fresh temporary directory
+    pub fn new() -> Result<Self> {
+        Ok(Self { dir: tempfile::Builder::new().prefix("rustmerger-selftest-").tempdir()? })
+    }
+
+    // Path of the scratch directory
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    // Keep the scratch directory on disk after the suite finishes
+    pub fn keep(self) -> PathBuf {
+        self.dir.keep()
+    }
+
+    // Run every case and collect the results
+    pub async fn run(&self) -> Result<Vec<SelfTestResult>> {
+        let mut results = Vec::new();
+        results.push(self.case_utf8().await);
+        results.push(self.case_windows_1252().await);
+        results.push(self.case_utf16().await);
+        results.push(self.case_binary().await);
+        results.push(self.case_huge_line().await);
+        results.push(self.case_interrupted_resume().await);
+        Ok(results)
+    }
+
+    // UTF-8 input with duplicates and multibyte characters is merged exactly
+    async fn case_utf8(&self) -> SelfTestResult {
+        let outcome = async {
+            let a = self.write_input("utf8", "a.txt", "password\nçà va\n密码\npassword\n".as_bytes()).await?;
+            let b = self.write_input("utf8", "b.txt", "密码\nletmein\n".as_bytes()).await?;
+            let merged = self.merge("utf8", &[a, b]).await?;
+            Self::expect_lines(&merged, &["password", "çà va", "密码", "letmein"])
+        }.await;
+        Self::result("utf8", outcome)
+    }
+
+    // A Windows-1252 file listed with its encoding is decoded to UTF-8, and one listed without
+    // it loses only its undecodable lines
+    async fn case_windows_1252(&self) -> SelfTestResult {
+        let outcome = async {
+            let legacy = self.write_input("cp1252", "legacy.txt", b"caf\xe9\nna\xefve\n").await?;
+            let unlisted = self.write_input("cp1252", "unlisted.txt", b"r\xe9sum\xe9\nalpha\n").await?;
+            let clean = self.write_input("cp1252", "clean.txt", b"alpha\nbeta\n").await?;
+            let list = format!("{}\tencoding=windows-1252\n{}", legacy.display(), Self::list_contents(&[unlisted, clean]));
+            let merged = self.merge_list("cp1252", &list).await?;
+            Self::expect_bytes(&merged, &["café", "naïve", "alpha", "beta"])
+        }.await;
+        Self::result("windows-1252", outcome)
+    }
+
+    // A UTF-16 file with a BOM is detected, converted to UTF-8 and merged exactly
+    async fn case_utf16(&self) -> SelfTestResult {
+        let outcome = async {
+            let mut bytes = vec![0xFF, 0xFE];
+            for unit in "hunter2\nqwerty\n".encode_utf16() {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+            let wide = self.write_input("utf16", "wide.txt", &bytes).await?;
+            let converted = self.path().join("utf16").join("wide-utf8.txt");
+            let (detected, _) = tokio::task::spawn_blocking({
+                let (wide, converted) = (wide.clone(), converted.clone());
+                move || encoding::convert_file(&wide, &converted, SourceEncoding::Auto, UTF_8)
+            }).await??;
+            if detected != UTF_16LE {
+                return Err(anyhow::anyhow!("detected {} instead of UTF-16LE", detected.name()));
+            }
+            let clean = self.write_input("utf16", "clean.txt", b"gamma\nhunter2\n").await?;
+            let merged = self.merge_list("utf16", &Self::list_contents(&[converted, clean])).await?;
+            Self::expect_bytes(&merged, &["hunter2", "qwerty", "gamma"])
+        }.await;
+        Self::result("utf16", outcome)
+    }
+
+    // A binary file does not abort the job or corrupt the output
+    async fn case_binary(&self) -> SelfTestResult {
+        let outcome = async {
+            let bytes: Vec<u8> = (0..64 * 1024u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+            let blob = self.write_input("binary", "blob.bin", &bytes).await?;
+            let clean = self.write_input("binary", "clean.txt", b"delta\n").await?;
+            let merged = self.merge("binary", &[blob, clean]).await?;
+            Self::expect_contains(&merged, &["delta"])
+        }.await;
+        Self::result("binary", outcome)
+    }
+
+    // A single line longer than a processing chunk survives intact
+    async fn case_huge_line(&self) -> SelfTestResult {
+        let outcome = async {
+            let mut bytes = vec![b'x'; HUGE_LINE_BYTES];
+            bytes.extend_from_slice(b"\nshort\n");
+            let huge = self.write_input("huge-line", "huge.txt", &bytes).await?;
+            let merged = self.merge("huge-line", &[huge]).await?;
+
+            let lines: HashSet<&str> = merged.lines().collect();
+            let has_huge = lines.iter().any(|l| l.len() == HUGE_LINE_BYTES && l.bytes().all(|b| b == b'x'));
+            if has_huge && lines.contains("short") && lines.len() == 2 {
+                Ok(format!("{} byte line preserved", HUGE_LINE_BYTES))
+            } else {
+                Err(anyhow::anyhow!("expected 2 lines including the {} byte line, got {}", HUGE_LINE_BYTES, lines.len()))
+            }
+        }.await;
+        Self::result("huge-line", outcome)
+    }
+
+    // A progress file left by an interrupted run resumes to a complete output
+    async fn case_interrupted_resume(&self) -> SelfTestResult {
+        let outcome = async {
+            let first = self.write_input("resume", "first.txt", b"one\ntwo\n").await?;
+            let second = self.write_input("resume", "second.txt", b"two\nthree\n").await?;
+            let list = self.write_input("resume", "list.txt", Self::list_contents(&[first.clone(), second]).as_bytes()).await?;
+            let output = self.path().join("resume").join("output.txt");
+            let progress_file = self.path().join("resume").join("progress.json");
+
+            // State as it would be saved after Ctrl+C once the first file was done
+            let progress = Progress {
+                input_file: list,
+                output_file: output.clone(),
+                threads: 1,
+                processed_files: vec![first],
+                current_position: 2,
+                save_path: Some(progress_file.clone()),
+                finished: false,
+                root: None,
+                remaps: Vec::new(),
+                config: None,
+                snapshot: None,
+                snapshot_files: 0,
+            };
+            progress.save().await?;
+
+            let state = Arc::new(AppState::from_resume(progress_file.clone(), None, &[]).await?);
+            let mut core = ProcessingCore::new(state, false, false).await?;
+            core.process().await?;
+
+            // The progress file must still be loadable after the resumed run
+            Progress::load(&progress_file).await?;
+
+            let merged = tokio::fs::read_to_string(&output).await?;
+            Self::expect_lines(&merged, &["one", "two", "three"])
+        }.await;
+        Self::result("interrupted-resume", outcome)
+    }
+
+    // Write an input file for a case, creating the case directory on demand
+    async fn write_input(&self, case: &str, name: &str, bytes: &[u8]) -> Result<PathBuf> {
+        let dir = self.path().join(case);
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join(name);
+        tokio::fs::write(&path, bytes).await?;
+        Ok(path)
+    }
+
+    // Run the full merge over `inputs` and return the merged output
+    async fn merge(&self, case: &str, inputs: &[PathBuf]) -> Result<String> {
+        let merged = self.merge_list(case, &Self::list_contents(inputs)).await?;
+        // Reading as a String doubles as the check that the output is valid UTF-8
+        Ok(String::from_utf8(merged)?)
+    }
+
+    // Run the full merge over the input list `list` and return the merged output as written
+    async fn merge_list(&self, case: &str, list: &str) -> Result<Vec<u8>> {
+        let list = self.write_input(case, "list.txt", list.as_bytes()).await?;
+        let output = self.path().join(case).join("output.txt");
+
+        let state = Arc::new(AppState::new(list, output.clone(), 1).await?);
+        let mut core = ProcessingCore::new(state, false, false).await?
+            .with_temp_dir(self.path().to_path_buf());
+        core.process().await?;
+
+        Ok(tokio::fs::read(&output).await?)
+    }
+
+    // Render an input list with one path per line
+    fn list_contents(inputs: &[PathBuf]) -> String {
+        inputs.iter().map(|p| format!("{}\n", p.display())).collect()
+    }
+
+    // The output holds exactly the expected lines, once each
+    fn expect_lines(merged: &str, expected: &[&str]) -> Result<String> {
+        let actual: Vec<&str> = merged.lines().collect();
+        let unique: HashSet<&str> = actual.iter().copied().collect();
+        let wanted: HashSet<&str> = expected.iter().copied().collect();
+
+        if unique.len() != actual.len() {
+            return Err(anyhow::anyhow!("output contains duplicate lines"));
+        }
+        if unique != wanted {
+            return Err(anyhow::anyhow!("expected {:?}, got {:?}", wanted, unique));
+        }
+        Ok(format!("{} unique lines", actual.len()))
+    }
+
+    // The output holds at least the expected lines
+    fn expect_contains(merged: &str, expected: &[&str]) -> Result<String> {
+        let actual: HashSet<&str> = merged.lines().collect();
+        let missing: Vec<&&str> = expected.iter().filter(|l| !actual.contains(**l)).collect();
+
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("missing lines {:?}", missing));
+        }
+        Ok(format!("job completed, {} lines in valid UTF-8 output", actual.len()))
+    }
+
+    // The output is exactly the expected lines as UTF-8, each once and newline-terminated, in any order
+    fn expect_bytes(merged: &[u8], expected: &[&str]) -> Result<String> {
+        let mut actual: Vec<&[u8]> = merged.split_inclusive(|&b| b == b'\n').collect();
+        let wanted: Vec<String> = expected.iter().map(|line| format!("{}\n", line)).collect();
+        let mut wanted: Vec<&[u8]> = wanted.iter().map(|line| line.as_bytes()).collect();
+        actual.sort_unstable();
+        wanted.sort_unstable();
+
+        if actual != wanted {
+            return Err(anyhow::anyhow!(
+                "expected lines {:?}, got {:?}",
+                wanted.iter().map(|line| String::from_utf8_lossy(line)).collect::<Vec<_>>(),
+                actual.iter().map(|line| String::from_utf8_lossy(line)).collect::<Vec<_>>()
+            ));
+        }
+        Ok(format!("{} lines, {} bytes as expected", actual.len(), merged.len()))
+    }
+
+    // Turn a case outcome into a reportable result
+    fn result(name: &'static str, outcome: Result<String>) -> SelfTestResult {
+        match outcome {
+            Ok(detail) => SelfTestResult { name, passed: true, detail },
+            Err(e) => SelfTestResult { name, passed: false, detail: e.to_string() },
+        }
+    }
+}
+
+// Print the results to stdout and return the number of failures
+pub fn print_report(results: &[SelfTestResult]) -> usize {
+    println!("Self-test results");
+    for result in results {
+        let status = if result.passed { "PASS" } else { "FAIL" };
+        println!("  [{}] {:<20} {}", status, result.name, result.detail);
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    println!("  {} passed, {} failed", results.len() - failed, failed);
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{print_report, SelfTest};
+
+    #[tokio::test]
+    async fn every_case_passes() {
+        let suite = SelfTest::new().unwrap();
+        let results = suite.run().await.unwrap();
+        assert_eq!(results.len(), 6);
+        let failed: Vec<_> = results.iter().filter(|result| !result.passed).map(|result| (result.name, &result.detail)).collect();
+        assert!(failed.is_empty(), "failed cases: {:?}", failed);
+        assert_eq!(print_report(&results), 0);
+    }
+
+    #[test]
+    fn expectations_reject_duplicates_and_missing_lines() {
+        assert!(SelfTest::expect_lines("a\nb\n", &["b", "a"]).is_ok());
+        assert!(SelfTest::expect_lines("a\na\n", &["a"]).is_err());
+        assert!(SelfTest::expect_contains("a\nb\n", &["a", "c"]).unwrap_err().to_string().contains("\"c\""));
+        assert!(SelfTest::expect_bytes(b"b\na\n", &["a", "b"]).is_ok());
+        assert!(SelfTest::expect_bytes(b"a\nb", &["a", "b"]).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::Result; // Import Result for checkpoint I/O
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the sessions directory and checkpoints
+use std::time::SystemTime; // Import SystemTime for when a checkpoint was last saved
+use chrono::{DateTime, Local}; // Import chrono to show save times
+use crate::errors::ConfigError; // Import ConfigError for unusable session names
+use crate::history; // Import the history module for the per-user data directory
+use crate::progress::Progress; // Import Progress, the checkpoint each session holds
+
+const SESSIONS_DIR: &str = "sessions"; // Directory under the data directory holding the checkpoints
+const MAX_NAME_LEN: usize = 64; // Longest session name
+
+// A named checkpoint of a merge
+pub struct Session {
+    pub name: String,              // Name given with --session
+    pub path: PathBuf,             // Checkpoint file
+    pub saved: Option<SystemTime>, // When the checkpoint was last written
+    pub progress: Progress,        // Saved merge state
+}
+
+impl Session {
+    // Whether the merge ran to the end, leaving nothing to resume
+    pub fn state(&self) -> &'static str {
+        if self.progress.finished { "finished" } else { "interrupted" }
+    }
+}
+
+// Sessions location: `sessions` in the data directory
+pub fn sessions_dir() -> Option<PathBuf> {
+    history::data_dir().map(|dir| dir.join(SESSIONS_DIR))
+}
+
+// Check that `name` is usable as a file name: letters, digits, `.`, `_` and `-`, not starting with `.`
+pub fn validate_name(name: &str) -> Result<(), ConfigError> {
+    let invalid = |reason: &str| Err(ConfigError::InvalidSession(format!("'{}' {}", name, reason)));
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return invalid(&format!("must be 1 to {} characters long", MAX_NAME_LEN));
+    }
+    if name.starts_with('.') {
+        return invalid("must not start with '.'");
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')) {
+        return invalid("may only contain letters, digits, '.', '_' and '-'");
+    }
+    Ok(())
+}
+
+// Checkpoint file of the session `name`
+pub fn checkpoint_path(name: &str) -> Result<PathBuf> {
+    validate_name(name)?;
+    let dir = sessions_dir().ok_or_else(|| anyhow::anyhow!("No home directory to keep sessions in"))?;
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+// Checkpoint for a new merge in the session `name`
+//
+// A finished session is reused; an interrupted one is refused so its progress is not lost.
+pub async fn prepare(name: &str) -> Result<PathBuf> {
+    let path = checkpoint_path(name)?;
+    if path.exists() {
+        let progress = Progress::load(&path).await?;
+        if !progress.finished {
+            return Err(anyhow::anyhow!(
+                "Session '{}' holds an interrupted merge; continue it with `rustmerger resume --session {}` or remove it with `rustmerger sessions clean {}`",
+                name, name, name
+            ));
+        }
+    }
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    Ok(path)
+}
+
+// Checkpoint a resume reads: the progress file given, or the named session's
+pub fn resume_path(progress_file: Option<&Path>, session: Option<&str>) -> Result<PathBuf> {
+    match (progress_file, session) {
+        (_, Some(name)) => {
+            let path = checkpoint_path(name)?;
+            if !path.exists() {
+                return Err(anyhow::anyhow!("No session named '{}'; see `rustmerger sessions list`", name));
+            }
+            Ok(path)
+        }
+        (Some(path), None) => Ok(path.to_path_buf()),
+        (None, None) => Err(anyhow::anyhow!("Give a progress file or --session")),
+    }
+}
+
+// The session `name`
+pub async fn load(name: &str) -> Result<Session> {
+    let path = checkpoint_path(name)?;
+    if !path.exists() {
+        return Err(anyhow::anyhow!("No session named '{}'; see `rustmerger sessions list`", name));
+    }
+    read(name.to_string(), path).await
+}
+
+async fn read(name: String, path: PathBuf) -> Result<Session> {
+    let saved = tokio::fs::metadata(&path).await?.modified().ok();
+    let progress = Progress::load(&path).await?;
+    Ok(Session { name, path, saved, progress })
+}
+
+// Every session in `dir`, by name; a missing directory has none
+//
+// Checkpoints that cannot be read are skipped with a warning.
+pub async fn list(dir: &Path) -> Result<Vec<Session>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut sessions = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string) else { continue };
+        if path.extension().is_none_or(|ext| ext != "json") || validate_name(&name).is_err() {
+            continue;
+        }
+        match read(name, path.clone()).await {
+            Ok(session) => sessions.push(session),
+            Err(e) => log::warn!("Skipping unreadable session {}: {}", path.display(), e),
+        }
+    }
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sessions)
+}
+
+// When `session` was last saved, for display
+pub fn format_saved(session: &Session) -> String {
+    session.saved
+        .map(|time| DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+// Print one line per session
+pub fn print_table(sessions: &[Session]) {
+    if sessions.is_empty() {
+        println!("No sessions saved");
+        return;
+    }
+    let width = sessions.iter().map(|session| session.name.len()).max().unwrap_or(0).max("Session".len());
+    println!("{:<width$}  {:<11}  {:<19}  {:>10}  Output", "Session", "State", "Saved", "Files done");
+    for session in sessions {
+        println!(
+            "{:<width$}  {:<11}  {:<19}  {:>10}  {}",
+            session.name,
+            session.state(),
+            format_saved(session),
+            session.progress.processed_files.len(),
+            session.progress.output_file.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{list, resume_path, validate_name};
+    use crate::progress::Progress;
+    use std::path::Path;
+
+    #[test]
+    fn names_must_be_plain_file_names() {
+        for valid in ["nightly", "corp-2024.v2", "a_b"] {
+            assert!(validate_name(valid).is_ok(), "{}", valid);
+        }
+        for invalid in ["", ".hidden", "../escape", "with space", &"x".repeat(65)] {
+            assert!(validate_name(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn resumes_read_the_given_progress_file() {
+        assert_eq!(resume_path(Some(Path::new("progress.json")), None).unwrap(), Path::new("progress.json"));
+        assert!(resume_path(None, None).is_err());
+        assert!(resume_path(None, Some("../escape")).is_err());
+    }
+
+    #[tokio::test]
+    async fn listing_skips_other_and_unreadable_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for (name, finished) in [("zeta", true), ("alpha", false)] {
+            let progress = Progress { finished, save_path: Some(dir.join(format!("{}.json", name))), ..Progress::default() };
+            progress.save().await.unwrap();
+        }
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let sessions = list(dir).await.unwrap();
+        let listed: Vec<(&str, &str)> = sessions.iter().map(|session| (session.name.as_str(), session.state())).collect();
+        assert_eq!(listed, [("alpha", "interrupted"), ("zeta", "finished")]);
+        assert!(list(&dir.join("missing")).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+use tokio::sync::mpsc; // Import mpsc for the per-shard channels
+use crate::errors::MergerResult; // Import MergerResult for send errors
+use crate::dedup_key::DedupKey; // Import DedupKey to compare lines by their key
+use crate::line_store::LineChunk; // Import LineChunk, the unit sent to a shard
+use crate::pause::PauseGate; // Import PauseGate to hold readers while the merge is paused
+use crate::throttle::RateLimit; // Import RateLimit to pace reads
+use std::sync::Arc; // Import Arc to share the read limit between readers
+
+// Sending side of every shard's channel; lines are routed by hash
+#[derive(Clone)]
+pub struct ShardRouter {
+    senders: Vec<mpsc::Sender<LineChunk>>, // One channel per shard
+    gate: PauseGate,                       // Holds chunks back while the merge is paused
+    read_limit: Option<Arc<RateLimit>>,    // Pace of the bytes read, shared by every reader
+}
+
+impl ShardRouter {
+    // Create `shards` channels of `capacity` chunks and return the receivers in shard order
+    pub fn new(shards: usize, capacity: usize) -> (Self, Vec<mpsc::Receiver<LineChunk>>) {
+        let (senders, receivers) = (0..shards.max(1))
+            .map(|_| mpsc::channel::<LineChunk>(capacity))
+            .unzip();
+        (Self { senders, gate: PauseGate::new(), read_limit: None }, receivers)
+    }
+
+    // Hold every chunk back while `gate` is paused
+    pub fn with_pause(mut self, gate: PauseGate) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    // Pace readers to `limit`
+    pub fn with_read_limit(mut self, limit: Option<Arc<RateLimit>>) -> Self {
+        self.read_limit = limit;
+        self
+    }
+
+    // Pace readers must keep to, if any
+    pub fn read_limit(&self) -> Option<&RateLimit> {
+        self.read_limit.as_deref()
+    }
+
+    // Number of shards
+    pub fn len(&self) -> usize {
+        self.senders.len()
+    }
+
+    // Whether there are no shards (never true; a router always has one)
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    // Shard owning `hash`
+    //
+    // Uses bits 32..56: the low bits pick the bucket and the top 7 bits form the
+    // control byte inside each shard's table, so neither is skewed by the split.
+    pub fn shard_of(&self, hash: u64) -> usize {
+        ((((hash >> 32) & 0x00FF_FFFF) * self.senders.len() as u64) >> 24) as usize
+    }
+}
+
+// Lines gathered by a reader, already split into one chunk per shard
+pub struct ShardedChunk {
+    chunks: Vec<LineChunk>, // Pending chunk for each shard
+    lines: usize,           // Unique lines pending across all shards
+    capacity: usize,        // Initial capacity of each per-shard chunk
+    source: usize,          // Position of the input being read
+}
+
+impl ShardedChunk {
+    // Create empty per-shard chunks for input `source`, sized for `capacity` lines in total
+    pub fn new(router: &ShardRouter, capacity: usize, source: usize) -> Self {
+        let capacity = capacity / router.len();
+        Self {
+            chunks: (0..router.len()).map(|_| LineChunk::with_capacity(capacity, source)).collect(),
+            lines: 0,
+            capacity,
+            source,
+        }
+    }
+
+    // Add `weight` occurrences of `line` to its shard's chunk under the precomputed `hash` of its `key`
+    pub fn insert(&mut self, router: &ShardRouter, hash: u64, line: &str, weight: u64, key: DedupKey) {
+        if self.chunks[router.shard_of(hash)].insert(hash, line, weight, key) {
+            self.lines += 1;
+        }
+    }
+
+    // Unique lines pending across all shards
+    pub fn len(&self) -> usize {
+        self.lines
+    }
+
+    // Whether no lines are pending
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.lines == 0
+    }
+
+    // Send every non-empty chunk to its shard from async code
+    pub async fn flush(&mut self, router: &ShardRouter) -> MergerResult<()> {
+        router.gate.wait().await;
+        for (shard, chunk) in self.take_chunks() {
+            router.senders[shard].send(chunk).await?;
+        }
+        Ok(())
+    }
+
+    // Send every non-empty chunk to its shard from a blocking thread
+    pub fn flush_blocking(&mut self, router: &ShardRouter) -> MergerResult<()> {
+        router.gate.wait_blocking();
+        for (shard, chunk) in self.take_chunks() {
+            router.senders[shard].blocking_send(chunk)?;
+        }
+        Ok(())
+    }
+
+    // Swap out the non-empty chunks, leaving fresh ones behind
+    fn take_chunks(&mut self) -> Vec<(usize, LineChunk)> {
+        self.lines = 0;
+        let (capacity, source) = (self.capacity, self.source);
+        self.chunks.iter_mut()
+            .enumerate()
+            .filter(|(_, chunk)| !chunk.is_empty())
+            .map(|(shard, chunk)| (shard, std::mem::replace(chunk, LineChunk::with_capacity(capacity, source))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ShardRouter, ShardedChunk};
+    use crate::dedup_key::DedupKey;
+
+    #[test]
+    fn hashes_map_onto_every_shard_by_their_middle_bits() {
+        let (router, _receivers) = ShardRouter::new(4, 1);
+        assert_eq!(router.shard_of(0), 0);
+        assert_eq!(router.shard_of(0x00FF_FFFF_0000_0000), 3);
+        assert_eq!(router.shard_of(0x0080_0000_0000_0000), 2);
+        // Neither the low bits nor the top byte move a line to another shard
+        assert_eq!(router.shard_of(0xFF00_0000_FFFF_FFFF), 0);
+        assert_eq!(ShardRouter::new(0, 1).0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn chunks_reach_the_shard_owning_each_line() {
+        let (router, mut receivers) = ShardRouter::new(2, 4);
+        let mut chunk = ShardedChunk::new(&router, 8, 3);
+        let (low, high) = (0x0000_0001_0000_0000, 0x00FF_0000_0000_0000);
+        chunk.insert(&router, low, "alpha", 1, DedupKey::default());
+        chunk.insert(&router, high, "beta", 1, DedupKey::default());
+        chunk.insert(&router, low, "alpha", 2, DedupKey::default());
+        assert_eq!(chunk.len(), 2);
+
+        chunk.flush(&router).await.unwrap();
+        assert!(chunk.is_empty());
+        let drained: Vec<_> = receivers[0].try_recv().unwrap().drain().collect();
+        assert_eq!(drained, [(low, "alpha".to_string(), 3)]);
+        let drained: Vec<_> = receivers[1].try_recv().unwrap().drain().collect();
+        assert_eq!(drained, [(high, "beta".to_string(), 1)]);
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs::File; // Import File for the side outputs
+use std::io::{self, BufWriter, Write}; // Import buffered writing for the side outputs
+use std::path::Path; // Import Path for the side output paths
+use std::str::FromStr; // Import FromStr to parse either end of a range
+use crate::estimate::format_bytes; // Reuse the byte formatter for the summary
+use crate::line_store::Tally; // Import Tally, passed along with each line
+use crate::output_format::RecordSink; // Import RecordSink, wrapped by the side output writer
+
+const OUTPUT_BUFFER_SIZE: usize = 1024 * 1024; // Bytes buffered per side output
+
+// Parse an inclusive range such as `8-16`, or a single value standing for both ends
+//
+// Returns None when either end does not parse or the first is past the last, so each
+// caller can word its own error.
+pub fn parse_range<T: FromStr + PartialOrd>(value: &str) -> Option<(T, T)> {
+    let value = value.trim();
+    let (first, last) = value.split_once('-').unwrap_or((value, value));
+    let first: T = first.trim().parse().ok()?;
+    let last: T = last.trim().parse().ok()?;
+    (first <= last).then_some((first, last))
+}
+
+// Text file receiving some of the lines written to an output
+pub trait SideOutput: Clone + Send + 'static {
+    // Whether a line taken here is kept from the main output and the side outputs after it
+    const DIVERTS: bool;
+
+    // File receiving the lines
+    fn path(&self) -> &Path;
+
+    // Whether `candidate` is written to this file
+    fn takes(&self, candidate: &[u8]) -> bool;
+
+    // Summary printed once the output is finished, given the lines written and file size
+    fn summary(&self, lines: u64, size: &str) -> String;
+}
+
+// Output that writes lines to the side outputs taking them and on to the main output
+//
+// Copying side outputs (tees) each get every line they take, and the main output gets
+// every line. Diverting side outputs (routes) take a line away: the first one taking it
+// gets it and nothing else does.
+pub struct SideSink<O: SideOutput> {
+    inner: Box<dyn RecordSink + Send>,       // Main output
+    outputs: Vec<(O, BufWriter<File>, u64)>, // Each side output with its writer and line count
+}
+
+impl<O: SideOutput> SideSink<O> {
+    // Wrap `inner` so lines are also written to `outputs`; without outputs `inner` is returned as is
+    pub fn wrap(inner: Box<dyn RecordSink + Send>, outputs: &[O]) -> io::Result<Box<dyn RecordSink + Send>> {
+        if outputs.is_empty() {
+            return Ok(inner);
+        }
+        let outputs = outputs.iter()
+            .map(|output| {
+                let file = File::create(output.path())?;
+                Ok((output.clone(), BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, file), 0))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Box::new(Self { inner, outputs }))
+    }
+}
+
+impl<O: SideOutput> RecordSink for SideSink<O> {
+    fn write(&mut self, candidate: &[u8], tally: Tally) -> io::Result<()> {
+        for (output, writer, lines) in &mut self.outputs {
+            if output.takes(candidate) {
+                writer.write_all(candidate)?;
+                writer.write_all(b"\n")?;
+                *lines += 1;
+                if O::DIVERTS {
+                    return Ok(());
+                }
+            }
+        }
+        self.inner.write(candidate, tally)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        let Self { inner, outputs } = *self;
+        inner.finish()?;
+        for (output, mut writer, lines) in outputs {
+            writer.flush()?;
+            let size = std::fs::metadata(output.path()).map(|meta| meta.len()).unwrap_or(0);
+            println!("  {}", output.summary(lines, &format_bytes(size)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_range, SideOutput, SideSink};
+    use crate::line_store::Tally;
+    use crate::output_format::RecordSink;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    // Main output collecting the lines it is given
+    struct Collect(Arc<Mutex<Vec<String>>>);
+
+    impl RecordSink for Collect {
+        fn write(&mut self, candidate: &[u8], _tally: Tally) -> io::Result<()> {
+            self.0.lock().unwrap().push(String::from_utf8_lossy(candidate).into_owned());
+            Ok(())
+        }
+
+        fn finish(self: Box<Self>) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Side output taking lines that start with `prefix`
+    #[derive(Clone)]
+    struct Prefixed<const DIVERT: bool> {
+        path: PathBuf,
+        prefix: u8,
+    }
+
+    impl<const DIVERT: bool> SideOutput for Prefixed<DIVERT> {
+        const DIVERTS: bool = DIVERT;
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+
+        fn takes(&self, candidate: &[u8]) -> bool {
+            candidate.first() == Some(&self.prefix)
+        }
+
+        fn summary(&self, lines: u64, size: &str) -> String {
+            format!("{} lines ({})", lines, size)
+        }
+    }
+
+    // Write `candidates` through two side outputs both taking `a` lines; returns the main output and both files
+    fn write_all<const DIVERT: bool>(candidates: &[&str]) -> (Vec<String>, String, String) {
+        let temp = tempfile::tempdir().unwrap();
+        let outputs = [
+            Prefixed::<DIVERT> { path: temp.path().join("first.txt"), prefix: b'a' },
+            Prefixed::<DIVERT> { path: temp.path().join("second.txt"), prefix: b'a' },
+        ];
+        let main = Arc::new(Mutex::new(Vec::new()));
+        let mut sink = SideSink::wrap(Box::new(Collect(main.clone())), &outputs).unwrap();
+        for candidate in candidates {
+            sink.write(candidate.as_bytes(), Tally::once(0)).unwrap();
+        }
+        sink.finish().unwrap();
+        let read = |name: &str| std::fs::read_to_string(temp.path().join(name)).unwrap();
+        let main = main.lock().unwrap().clone();
+        (main, read("first.txt"), read("second.txt"))
+    }
+
+    #[test]
+    fn ranges_parse_both_ends_or_one_value() {
+        assert_eq!(parse_range::<usize>(" 8-16 "), Some((8, 16)));
+        assert_eq!(parse_range::<u16>("2024"), Some((2024, 2024)));
+        assert_eq!(parse_range::<usize>("16-8"), None);
+        assert_eq!(parse_range::<usize>("8-"), None);
+        assert_eq!(parse_range::<usize>("eight"), None);
+    }
+
+    #[test]
+    fn copies_reach_every_taking_output_and_the_main_output() {
+        let (main, first, second) = write_all::<false>(&["apple", "berry", "avocado"]);
+        assert_eq!(main, ["apple", "berry", "avocado"]);
+        assert_eq!(first, "apple\navocado\n");
+        assert_eq!(second, "apple\navocado\n");
+    }
+
+    #[test]
+    fn diversions_go_to_the_first_taking_output_only() {
+        let (main, first, second) = write_all::<true>(&["apple", "berry", "avocado"]);
+        assert_eq!(main, ["berry"]);
+        assert_eq!(first, "apple\navocado\n");
+        assert_eq!(second, "");
+    }
+}
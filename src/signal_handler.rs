@@ -1,7 +1,7 @@
 use std::sync::Arc; // Importing Arc for thread-safe reference counting
 use tokio::sync::broadcast; // Importing broadcast channel from tokio for sending shutdown signals
 use anyhow::Result; // Importing Result type from anyhow for error handling
-use log::{info, error}; // Importing logging macros for info and error messages
+use log::{info, error, warn}; // Importing logging macros for info, error and warning messages
 use crate::app_state::AppState; // Importing the AppState struct from the app_state module
 
 // Struct to handle OS signals and manage application state
@@ -36,6 +36,8 @@ impl SignalHandler {
         let shutdown_tx = self.shutdown_tx.clone();
         // Clone the app_state for use in the signal handler
         let app_state = self.app_state.clone();
+        // The handler runs on its own thread, outside the runtime, so it spawns through a handle
+        let runtime = tokio::runtime::Handle::current();
 
         // Set up a handler for the Ctrl+C signal
         ctrlc::set_handler(move || {
@@ -46,19 +48,60 @@ impl SignalHandler {
             let app_state = app_state.clone();
             let shutdown_tx = shutdown_tx.clone();
             
-            tokio::spawn(async move {
+            runtime.spawn(async move {
                 // Attempt to save the progress
                 if let Err(e) = app_state.save_progress().await {
                     error!("Failed to save progress: {}", e);
                 }
 
-                // Attempt to send the shutdown signal
-                if let Err(e) = shutdown_tx.send(()) {
-                    error!("Failed to send shutdown signal: {}", e);
-                }
+                // Let the processing loop stop after the current file
+                app_state.request_shutdown().await;
+
+                // Notify subscribers; having none is not an error
+                let _ = shutdown_tx.send(());
             });
         })?;
 
+        handle_pause_signals(self.app_state.clone())?;
+
         Ok(())
     }
+}
+
+// Pause the merge on SIGUSR1 and resume it on SIGUSR2
+//
+// Pausing holds the readers at their next chunk, flushes streamed output and saves progress,
+// so I/O stops without losing the run. Other platforms have no such signals; this does nothing there.
+#[cfg(unix)]
+pub fn handle_pause_signals(app_state: Arc<AppState>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut pause = signal(SignalKind::user_defined1())?;
+    let mut resume = signal(SignalKind::user_defined2())?;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(()) = pause.recv() => {
+                    if app_state.pause.pause() {
+                        warn!("Received SIGUSR1, pausing after the current chunk; send SIGUSR2 to resume");
+                        if let Err(e) = app_state.save_progress().await {
+                            error!("Failed to save progress: {}", e);
+                        }
+                    }
+                }
+                Some(()) = resume.recv() => {
+                    if app_state.pause.resume() {
+                        warn!("Received SIGUSR2, resuming");
+                    }
+                }
+                else => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn handle_pause_signals(_app_state: Arc<AppState>) -> Result<()> {
+    Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,370 @@
+use std::cmp::Reverse; // Import Reverse to turn BinaryHeap into a min-heap
+use std::collections::BinaryHeap; // Import BinaryHeap for the k-way merge of spill runs
+use std::fs::File; // Import File for reading and writing run files
+use std::io::{self, BufRead, BufReader, BufWriter, Write}; // Import buffered I/O for run files
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for run file paths
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}; // Import atomics shared with shard tasks
+use std::sync::{Arc, Mutex}; // Import Arc and Mutex for the lazily created spill directory
+use std::time::Duration; // Import Duration for the sampling interval
+use tempfile::TempDir; // Import TempDir so spill runs are removed when the merge ends
+use tokio::task::JoinHandle; // Import JoinHandle so the watchdog can be stopped
+use crate::dedup_key::DedupKey; // Import DedupKey to merge runs by the key of each line
+use crate::line_store::{LineStore, Tally}; // Import LineStore, the set being spilled, and its per-line tallies
+use crate::memory_monitor::MemoryMonitor; // Import MemoryMonitor for the sampled resident set size
+use crate::scratch; // Import scratch for the run-ID naming of spill directories
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(250); // How often footprints are checked
+const HIGH_WATER_FRACTION: f64 = 0.75; // Share of the memory available at start the sets may use
+const CRITICAL_AVAILABLE_FRACTION: f64 = 0.05; // System memory share below which shards spill regardless
+const RUN_BUFFER_SIZE: usize = 1024 * 1024 * 8; // 8MB buffer for run files
+
+// Watchdog that asks shards to spill their sets to disk before memory runs out
+pub struct SpillWatchdog {
+    high_water: u64,                // Combined set footprint that triggers a spill
+    enabled: bool,                  // Whether spilling is allowed for this merge
+    footprints: Vec<AtomicU64>,     // Last reported footprint of each shard's set
+    requested: Vec<AtomicBool>,     // Pending spill request for each shard
+    runs: AtomicUsize,              // Number of runs written so far
+    root: PathBuf,                  // Directory the spill directory is created in
+    dir: Mutex<Option<TempDir>>,    // Spill directory, created on the first spill
+    memory: Option<Arc<MemoryMonitor>>, // Resident set size samples, when the merge takes them
+}
+
+impl SpillWatchdog {
+    // Create a watchdog for `shards` sets allowed to use `high_water` bytes combined, spilling under `root`
+    pub fn new(shards: usize, high_water: u64, enabled: bool, root: PathBuf) -> Self {
+        Self {
+            high_water,
+            enabled,
+            footprints: (0..shards).map(|_| AtomicU64::new(0)).collect(),
+            requested: (0..shards).map(|_| AtomicBool::new(false)).collect(),
+            runs: AtomicUsize::new(0),
+            root,
+            dir: Mutex::new(None),
+            memory: None,
+        }
+    }
+
+    // Also spill once the process itself outgrows the memory available at start, as sampled by `monitor`
+    pub fn with_memory(mut self, monitor: Arc<MemoryMonitor>) -> Self {
+        self.memory = Some(monitor);
+        self
+    }
+
+    // High-water mark derived from the memory available right now
+    pub fn default_high_water() -> u64 {
+        sys_info::mem_info()
+            .map(|mem| (mem.avail as f64 * 1024.0 * HIGH_WATER_FRACTION) as u64)
+            .unwrap_or(u64::MAX)
+    }
+
+    // Check footprints in the background until the returned task is aborted
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let watchdog = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            let mut warned = false;
+            loop {
+                interval.tick().await;
+                let total: u64 = watchdog.footprints.iter().map(|f| f.load(Ordering::Relaxed)).sum();
+                let critical = sys_info::mem_info()
+                    .map(|mem| mem.total > 0 && (mem.avail as f64) < mem.total as f64 * CRITICAL_AVAILABLE_FRACTION)
+                    .unwrap_or(false)
+                    || watchdog.memory.as_ref()
+                        .and_then(|memory| memory.resident())
+                        .is_some_and(|rss| rss as f64 * HIGH_WATER_FRACTION >= watchdog.high_water as f64);
+                if total < watchdog.high_water && !critical {
+                    continue;
+                }
+
+                if !watchdog.enabled {
+                    if !warned {
+                        log::warn!("Deduplication set is using {} MB; spilling is unavailable with streamed output", total / (1024 * 1024));
+                        warned = true;
+                    }
+                    continue;
+                }
+
+                // Spill the shards holding at least an average share, largest first in effect
+                let average = total / watchdog.footprints.len().max(1) as u64;
+                for (footprint, requested) in watchdog.footprints.iter().zip(&watchdog.requested) {
+                    if footprint.load(Ordering::Relaxed) >= average {
+                        requested.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        })
+    }
+
+    // Record the current footprint of `shard`'s set
+    pub fn report(&self, shard: usize, bytes: u64) {
+        self.footprints[shard].store(bytes, Ordering::Relaxed);
+    }
+
+    // Whether `shard` has been asked to spill; clears the request
+    pub fn take_request(&self, shard: usize) -> bool {
+        self.requested[shard].swap(false, Ordering::Relaxed)
+    }
+
+    // Number of runs written so far
+    pub fn run_count(&self) -> usize {
+        self.runs.load(Ordering::Relaxed)
+    }
+
+    // Write `store` to a sorted run file and empty it
+    pub fn spill(&self, shard: usize, store: &mut LineStore) -> io::Result<PathBuf> {
+        let path = self.next_run_path(shard)?;
+        let mut writer = BufWriter::with_capacity(RUN_BUFFER_SIZE, File::create(&path)?);
+        let tallied = store.is_tallied();
+        for (line, tally) in store.sorted() {
+            write_record(&mut writer, line, tally, tallied)?;
+        }
+        writer.flush()?;
+
+        log::info!("Spilled {} unique lines from shard {} to {}", store.len(), shard, path.display());
+        store.clear();
+        self.report(shard, 0);
+        Ok(path)
+    }
+
+    // Merge `runs` in passes until at most `fan_in` remain, so merging never exceeds the descriptor limit
+    pub fn compact(&self, shard: usize, runs: &mut Vec<PathBuf>, fan_in: usize, tallied: bool, key: DedupKey) -> io::Result<()> {
+        let fan_in = fan_in.max(2);
+        while runs.len() > fan_in {
+            let batch: Vec<PathBuf> = runs.drain(..fan_in).collect();
+            let path = self.next_run_path(shard)?;
+            let mut writer = BufWriter::with_capacity(RUN_BUFFER_SIZE, File::create(&path)?);
+            merge_runs(&batch, tallied, key, |line, tally| write_record(&mut writer, line, tally, tallied))?;
+            writer.flush()?;
+            batch.iter().for_each(|run| remove_run(run));
+            // The merged run holds the oldest lines, so it goes first to keep winning key ties
+            runs.insert(0, path);
+        }
+        Ok(())
+    }
+
+    // Path for the next run of `shard`, creating the spill directory on first use
+    fn next_run_path(&self, shard: usize) -> io::Result<PathBuf> {
+        let run = self.runs.fetch_add(1, Ordering::Relaxed);
+        let mut dir = self.dir.lock().unwrap();
+        if dir.is_none() {
+            *dir = Some(tempfile::Builder::new().prefix(&scratch::spill_prefix()).tempdir_in(&self.root)?);
+        }
+        Ok(dir.as_ref().expect("spill directory created above").path().join(format!("shard{}-run{}.txt", shard, run)))
+    }
+}
+
+// Next record of one run during a merge, ordered by its key and then by run
+struct RunHead {
+    line: Vec<u8>, // Line of the record
+    key: DedupKey, // Part of the line that orders and identifies it
+    run: usize,    // Run the record came from; earlier runs win ties
+    tally: Tally,  // Count and source of the record
+}
+
+impl RunHead {
+    fn sort_key(&self) -> (&[u8], usize) {
+        (self.key.key(&self.line), self.run)
+    }
+}
+
+impl PartialEq for RunHead {
+    fn eq(&self, other: &Self) -> bool {
+        self.sort_key() == other.sort_key()
+    }
+}
+
+impl Eq for RunHead {}
+
+impl PartialOrd for RunHead {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RunHead {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+// Merge run files sorted by `key`, passing the first line of each distinct key once to `emit`;
+// returns the unique count
+//
+// Tallied runs hold `count<TAB>source<TAB>line` records, and the tallies of a key
+// repeated across runs are added together before it is emitted.
+pub fn merge_runs<F>(runs: &[PathBuf], tallied: bool, key: DedupKey, mut emit: F) -> io::Result<usize>
+where
+    F: FnMut(&[u8], Tally) -> io::Result<()>,
+{
+    let mut readers = runs.iter()
+        .map(|path| File::open(path).map(|f| BufReader::with_capacity(RUN_BUFFER_SIZE, f)))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // Min-heap of the next record from each run, tagged with the run it came from
+    let mut heap = BinaryHeap::with_capacity(readers.len());
+    for (run, reader) in readers.iter_mut().enumerate() {
+        if let Some((line, tally)) = next_record(reader, tallied)? {
+            heap.push(Reverse(RunHead { line, key, run, tally }));
+        }
+    }
+
+    let mut pending: Option<(Vec<u8>, Tally)> = None;
+    let mut unique = 0;
+    while let Some(Reverse(RunHead { line, run, tally, .. })) = heap.pop() {
+        if let Some((next, next_tally)) = next_record(&mut readers[run], tallied)? {
+            heap.push(Reverse(RunHead { line: next, key, run, tally: next_tally }));
+        }
+        match pending.as_mut() {
+            Some((last, last_tally)) if key.key(last) == key.key(&line) => last_tally.merge(tally),
+            _ => {
+                if let Some((last, last_tally)) = pending.replace((line, tally)) {
+                    emit(&last, last_tally)?;
+                    unique += 1;
+                }
+            }
+        }
+    }
+    if let Some((last, last_tally)) = pending {
+        emit(&last, last_tally)?;
+        unique += 1;
+    }
+
+    Ok(unique)
+}
+
+// Write one run record: the bare line, or its tally and the line when tallied
+fn write_record<W: Write>(writer: &mut W, line: &[u8], tally: Tally, tallied: bool) -> io::Result<()> {
+    if tallied {
+        write!(writer, "{}\t{}\t", tally.count, tally.source)?;
+    }
+    writer.write_all(line)?;
+    writer.write_all(b"\n")
+}
+
+// Next record of a run file, without its newline
+fn next_record(reader: &mut BufReader<File>, tallied: bool) -> io::Result<Option<(Vec<u8>, Tally)>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    if !tallied {
+        return Ok(Some((line, Tally::once(0))));
+    }
+
+    let mut fields = line.splitn(3, |&b| b == b'\t');
+    let count = parse_field(fields.next())?;
+    let source = parse_field(fields.next())? as usize;
+    let text = fields.next().ok_or_else(malformed_record)?.to_vec();
+    Ok(Some((text, Tally { count, source })))
+}
+
+// Number held by a tally field of a run record
+fn parse_field(field: Option<&[u8]>) -> io::Result<u64> {
+    field.and_then(|f| std::str::from_utf8(f).ok())
+        .and_then(|f| f.parse().ok())
+        .ok_or_else(malformed_record)
+}
+
+// Error for a run record that does not have the tallied layout
+fn malformed_record() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed tallied spill record")
+}
+
+// Remove a run file once it has been merged
+pub fn remove_run(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        log::debug!("Failed to remove spill run {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_runs, SpillWatchdog};
+    use crate::dedup_key::DedupKey;
+    use crate::line_store::{LineStore, Tally};
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+
+    // Tallied store holding `lines`, each seen once in input `source`
+    fn store(hasher: &RandomState, lines: &[&str], source: usize) -> LineStore {
+        let mut store = LineStore::with_capacity(0).with_tally(true);
+        for line in lines {
+            store.insert_tallied(hasher.hash_one(line.as_bytes()), line.as_bytes(), Tally::once(source));
+        }
+        store
+    }
+
+    #[test]
+    fn runs_merge_sorted_without_duplicates() {
+        let temp = tempfile::tempdir().unwrap();
+        let watchdog = SpillWatchdog::new(1, u64::MAX, true, temp.path().to_path_buf());
+        let hasher = RandomState::new();
+        let runs = vec![
+            watchdog.spill(0, &mut store(&hasher, &["pear", "apple", "fig"], 2)).unwrap(),
+            watchdog.spill(0, &mut store(&hasher, &["fig", "banana", "apple"], 0)).unwrap(),
+        ];
+        assert_eq!(watchdog.run_count(), 2);
+
+        let mut merged = Vec::new();
+        let unique = merge_runs(&runs, true, DedupKey::Pair, |line, tally| {
+            merged.push((String::from_utf8(line.to_vec()).unwrap(), tally));
+            Ok(())
+        }).unwrap();
+        assert_eq!(unique, 4);
+        assert_eq!(merged, vec![
+            ("apple".to_string(), Tally { count: 2, source: 0 }),
+            ("banana".to_string(), Tally { count: 1, source: 0 }),
+            ("fig".to_string(), Tally { count: 2, source: 0 }),
+            ("pear".to_string(), Tally { count: 1, source: 2 }),
+        ]);
+    }
+
+    #[test]
+    fn compaction_keeps_every_line_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let watchdog = SpillWatchdog::new(1, u64::MAX, true, temp.path().to_path_buf());
+        let hasher = RandomState::new();
+        let mut runs: Vec<_> = (0..5)
+            .map(|source| watchdog.spill(0, &mut store(&hasher, &["shared", &format!("only{}", source)], source)).unwrap())
+            .collect();
+        watchdog.compact(0, &mut runs, 2, true, DedupKey::Pair).unwrap();
+        assert!(runs.len() <= 2);
+
+        let mut merged = Vec::new();
+        merge_runs(&runs, true, DedupKey::Pair, |line, tally| {
+            merged.push((line.to_vec(), tally.count));
+            Ok(())
+        }).unwrap();
+        assert_eq!(merged.len(), 6);
+        assert!(merged.contains(&(b"shared".to_vec(), 5)));
+    }
+
+    #[test]
+    fn compaction_keeps_the_first_line_of_each_key() {
+        let temp = tempfile::tempdir().unwrap();
+        let watchdog = SpillWatchdog::new(1, u64::MAX, true, temp.path().to_path_buf());
+        let hasher = RandomState::new();
+        let key = DedupKey::Password;
+        let mut runs: Vec<_> = (0..5)
+            .map(|user| {
+                let line = format!("user{}:hunter2", user);
+                let mut store = LineStore::with_capacity(0).with_key(key);
+                store.insert(hasher.hash_one(key.key(line.as_bytes())), line.as_bytes());
+                watchdog.spill(0, &mut store).unwrap()
+            })
+            .collect();
+        watchdog.compact(0, &mut runs, 2, false, key).unwrap();
+        assert!(runs.len() <= 2);
+
+        let mut merged = Vec::new();
+        merge_runs(&runs, false, key, |line, _| {
+            merged.push(String::from_utf8(line.to_vec()).unwrap());
+            Ok(())
+        }).unwrap();
+        assert_eq!(merged, vec!["user0:hunter2".to_string()]);
+    }
+}
@@ -0,0 +1,108 @@
+use rusqlite::types::ValueRef; // Import ValueRef to read column values without copying
+use rusqlite::{Connection, OpenFlags}; // Import the SQLite connection types
+use std::borrow::Cow; // Import Cow so text and blob values are not copied
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for list entries and databases
+use crate::errors::{MergerError, MergerResult}; // Import the error types for bad entries and queries
+
+const SQLITE_PREFIX: &str = "sqlite:"; // Marks a list entry as a database query
+const QUERY_SEPARATOR: &str = "?query="; // Separates the database path from the query
+
+// Whether a list entry names a SQLite query rather than a file
+pub fn is_sqlite(entry: &Path) -> bool {
+    entry.to_str().is_some_and(|entry| entry.starts_with(SQLITE_PREFIX))
+}
+
+// Size of a list entry in bytes; a query counts as the size of its database
+pub async fn input_size(entry: &Path) -> MergerResult<u64> {
+    if is_sqlite(entry) {
+        let source = SqliteSource::parse(entry)?;
+        return Ok(tokio::fs::metadata(&source.database).await?.len());
+    }
+    Ok(tokio::fs::metadata(entry).await?.len())
+}
+
+// A list entry such as `sqlite:breach.db?query=SELECT pass FROM creds`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteSource {
+    pub database: PathBuf, // Database file, opened read-only
+    pub query: String,     // Query whose first column holds the candidates
+}
+
+impl SqliteSource {
+    // Split a `sqlite:` list entry into its database and query
+    pub fn parse(entry: &Path) -> MergerResult<Self> {
+        let invalid = |reason: &str| MergerError::InputValidation(format!("{}: {}", entry.display(), reason));
+        let text = entry.to_str().ok_or_else(|| invalid("not valid UTF-8"))?;
+        let rest = text.strip_prefix(SQLITE_PREFIX).ok_or_else(|| invalid("not a sqlite: entry"))?;
+        let (database, query) = rest.split_once(QUERY_SEPARATOR)
+            .ok_or_else(|| invalid("expected sqlite:<database>?query=<SELECT ...>"))?;
+        if database.is_empty() || query.trim().is_empty() {
+            return Err(invalid("expected sqlite:<database>?query=<SELECT ...>"));
+        }
+        Ok(Self { database: PathBuf::from(database), query: query.to_string() })
+    }
+
+    // Run the query and pass the first column of every row to `on_row`, one row at a time
+    //
+    // Text and blobs are passed as stored, numbers in their decimal spelling and NULL as None.
+    pub fn for_each_row(&self, mut on_row: impl FnMut(Option<&[u8]>) -> MergerResult<()>) -> MergerResult<()> {
+        let connection = Connection::open_with_flags(&self.database, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+        let mut statement = connection.prepare(&self.query)?;
+        if statement.column_count() == 0 {
+            return Err(MergerError::InputValidation(format!("query returns no columns: {}", self.query)));
+        }
+
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let value = match row.get_ref(0)? {
+                ValueRef::Null => None,
+                ValueRef::Integer(number) => Some(Cow::Owned(number.to_string().into_bytes())),
+                ValueRef::Real(number) => Some(Cow::Owned(number.to_string().into_bytes())),
+                ValueRef::Text(bytes) | ValueRef::Blob(bytes) => Some(Cow::Borrowed(bytes)),
+            };
+            on_row(value.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_sqlite, SqliteSource};
+    use rusqlite::Connection;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn entries_split_into_database_and_query() {
+        let entry = Path::new("sqlite:breach.db?query=SELECT pass FROM creds");
+        assert!(is_sqlite(entry));
+        assert!(!is_sqlite(Path::new("breach.db")));
+        assert_eq!(SqliteSource::parse(entry).unwrap(), SqliteSource {
+            database: PathBuf::from("breach.db"),
+            query: "SELECT pass FROM creds".to_string(),
+        });
+        assert!(SqliteSource::parse(Path::new("sqlite:breach.db")).is_err());
+        assert!(SqliteSource::parse(Path::new("sqlite:?query=SELECT 1")).is_err());
+        assert!(SqliteSource::parse(Path::new("sqlite:breach.db?query= ")).is_err());
+    }
+
+    #[test]
+    fn rows_pass_their_first_column() {
+        let temp = tempfile::tempdir().unwrap();
+        let database = temp.path().join("breach.db");
+        let connection = Connection::open(&database).unwrap();
+        connection.execute_batch(
+            "CREATE TABLE creds (pass, user);
+             INSERT INTO creds VALUES ('hunter2', 'alice'), (1234, 'bob'), (NULL, 'carol'), (x'6869', 'dave');",
+        ).unwrap();
+        drop(connection);
+
+        let source = SqliteSource { database, query: "SELECT pass, user FROM creds".to_string() };
+        let mut rows = Vec::new();
+        source.for_each_row(|value| {
+            rows.push(value.map(<[u8]>::to_vec));
+            Ok(())
+        }).unwrap();
+        assert_eq!(rows, [Some(b"hunter2".to_vec()), Some(b"1234".to_vec()), None, Some(b"hi".to_vec())]);
+    }
+}
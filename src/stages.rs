@@ -0,0 +1,73 @@
+use std::io; // Import io for directory and list errors
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for group lists and intermediates
+use tempfile::TempDir; // Import TempDir so intermediates are removed when the merge ends
+use crate::scratch; // Import scratch so a crashed merge's intermediates are cleaned up like spill runs
+
+// Inputs merged together per group so `files` inputs take `stages` stages to become one output
+pub fn fan_in(stages: usize, files: usize) -> usize {
+    ((files as f64).powf(1.0 / stages.max(1) as f64).ceil() as usize).max(2)
+}
+
+// Directory holding the group lists and intermediate outputs of a hierarchical merge
+//
+// It is named like a spill directory, so `cleanup_orphans` removes it after a crash.
+pub struct StageDir {
+    dir: TempDir, // Removed with everything in it when dropped
+}
+
+impl StageDir {
+    // Create the directory under `root`
+    pub fn create(root: &Path) -> io::Result<Self> {
+        Ok(Self { dir: tempfile::Builder::new().prefix(&scratch::spill_prefix()).tempdir_in(root)? })
+    }
+
+    // Write the input list of `group` in `stage`, returning it and the intermediate the group merges into
+    pub fn group(&self, stage: usize, group: usize, inputs: &[PathBuf]) -> io::Result<(PathBuf, PathBuf)> {
+        let list = self.dir.path().join(format!("stage{}-group{}.list", stage, group));
+        let mut content = String::new();
+        for input in inputs {
+            content.push_str(&input.display().to_string());
+            content.push('\n');
+        }
+        std::fs::write(&list, content)?;
+        Ok((list, self.intermediate(stage, group)))
+    }
+
+    // Intermediate that `group` of `stage` merges into
+    pub fn intermediate(&self, stage: usize, group: usize) -> PathBuf {
+        self.dir.path().join(format!("stage{}-group{}.txt", stage, group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fan_in, StageDir};
+    use crate::scratch::SPILL_PREFIX;
+    use std::path::PathBuf;
+
+    #[test]
+    fn fan_in_spreads_the_inputs_over_the_stages() {
+        assert_eq!(fan_in(1, 100), 100);
+        assert_eq!(fan_in(2, 100), 10);
+        assert_eq!(fan_in(3, 100), 5);
+        assert_eq!(fan_in(2, 3), 2);
+        assert_eq!(fan_in(0, 1), 2);
+    }
+
+    #[test]
+    fn groups_list_their_inputs_next_to_the_intermediate() {
+        let temp = tempfile::tempdir().unwrap();
+        let stages = StageDir::create(temp.path()).unwrap();
+        let inputs = [PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+        let (list, intermediate) = stages.group(1, 2, &inputs).unwrap();
+        assert_eq!(std::fs::read_to_string(&list).unwrap(), "a.txt\nb.txt\n");
+        assert_eq!(intermediate, stages.intermediate(1, 2));
+        assert_eq!(list.parent(), intermediate.parent());
+
+        // Named like a spill directory and removed with the StageDir
+        let dir = list.parent().unwrap().to_path_buf();
+        assert!(dir.file_name().unwrap().to_string_lossy().starts_with(SPILL_PREFIX));
+        drop(stages);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,257 @@
+use serde::Serialize; // Import Serialize to publish snapshots as JSON
+use std::collections::VecDeque; // Import VecDeque for the bounded list of recent errors
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the file being read
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering}; // Import atomics for counters updated while merging
+use std::sync::Mutex; // Import Mutex for the phase, current file and errors
+use std::time::Instant; // Import Instant to measure elapsed time and throughput
+use crate::report; // Import report for the duplicate share
+
+const RECENT_ERRORS: usize = 20; // Errors kept for status readers, newest last
+
+// Stage a merge is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Reading,     // Inputs are being read and deduplicated
+    Writing,     // The unique lines are being written to the output
+    Done,        // The merge finished
+    Interrupted, // The merge stopped early; progress was saved
+}
+
+// Where an input stands in the merge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileState {
+    Pending, // Not read yet
+    Reading, // Being read
+    Done,    // Read, possibly with lines left out
+    Failed,  // Skipped because of an error
+}
+
+// One input in processing order
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStatus {
+    pub path: PathBuf,    // Input file
+    pub size: u64,        // Size in bytes
+    pub state: FileState, // Where it stands
+    pub lines: usize,     // Lines it fed into deduplication
+}
+
+// A file that failed or was only partly merged
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentError {
+    pub elapsed_secs: u64, // Seconds into the run when it happened
+    pub path: PathBuf,     // File concerned
+    pub detail: String,    // What went wrong
+}
+
+// Live figures of a running merge, updated by the processing core and read by status displays
+pub struct RunStatus {
+    started: Instant,                     // When the merge started
+    files_total: AtomicUsize,             // Inputs to read
+    files_done: AtomicUsize,              // Inputs read, including failed ones
+    files_failed: AtomicUsize,            // Inputs skipped because of errors
+    bytes_total: AtomicU64,               // Size of all inputs
+    bytes_done: AtomicU64,                // Size of the inputs read so far
+    lines: AtomicUsize,                   // Lines fed into deduplication
+    unique: AtomicUsize,                  // Unique lines found
+    phase: Mutex<Phase>,                  // Stage the merge is in
+    files: Mutex<Vec<FileStatus>>,        // Every input in processing order
+    current: Mutex<Option<usize>>,        // Position of the input being read
+    errors: Mutex<VecDeque<RecentError>>, // Latest errors, oldest first
+}
+
+// Point-in-time copy of a RunStatus with derived rates
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusSnapshot {
+    pub phase: Phase,                  // Stage the merge is in
+    pub elapsed_secs: u64,             // Seconds since the merge started
+    pub files_total: usize,            // Inputs to read
+    pub files_done: usize,             // Inputs read, including failed ones
+    pub files_failed: usize,           // Inputs skipped because of errors
+    pub bytes_total: u64,              // Size of all inputs
+    pub bytes_done: u64,               // Size of the inputs read so far
+    pub lines: usize,                  // Lines fed into deduplication
+    pub unique: usize,                 // Unique lines found
+    pub duplicate_percent: f64,        // Share of the lines read that were duplicates
+    pub lines_per_sec: f64,            // Average read throughput in lines
+    pub bytes_per_sec: f64,            // Average read throughput in bytes
+    pub eta_secs: Option<u64>,         // Estimated seconds until reading finishes
+    pub current_file: Option<PathBuf>, // Input being read
+    pub recent_errors: Vec<RecentError>, // Latest errors, oldest first
+}
+
+impl Default for RunStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunStatus {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            files_total: AtomicUsize::new(0),
+            files_done: AtomicUsize::new(0),
+            files_failed: AtomicUsize::new(0),
+            bytes_total: AtomicU64::new(0),
+            bytes_done: AtomicU64::new(0),
+            lines: AtomicUsize::new(0),
+            unique: AtomicUsize::new(0),
+            phase: Mutex::new(Phase::Reading),
+            files: Mutex::new(Vec::new()),
+            current: Mutex::new(None),
+            errors: Mutex::new(VecDeque::with_capacity(RECENT_ERRORS)),
+        }
+    }
+
+    // Record the inputs about to be read, with their sizes, in processing order
+    pub fn set_inputs(&self, files: impl IntoIterator<Item = (PathBuf, u64)>) {
+        let files: Vec<FileStatus> = files.into_iter()
+            .map(|(path, size)| FileStatus { path, size, state: FileState::Pending, lines: 0 })
+            .collect();
+        self.files_total.store(files.len(), Ordering::Relaxed);
+        self.bytes_total.store(files.iter().map(|file| file.size).sum(), Ordering::Relaxed);
+        *self.files.lock().unwrap() = files;
+    }
+
+    // Record that the input at `position` in processing order is being read
+    pub fn start_file(&self, position: usize) {
+        if let Some(file) = self.files.lock().unwrap().get_mut(position) {
+            file.state = FileState::Reading;
+        }
+        *self.current.lock().unwrap() = Some(position);
+    }
+
+    // Record that the input at `position` fed `lines` lines, or was skipped when `failed`
+    pub fn finish_file(&self, position: usize, lines: usize, failed: bool) {
+        let mut files = self.files.lock().unwrap();
+        let Some(file) = files.get_mut(position) else { return };
+        file.state = if failed { FileState::Failed } else { FileState::Done };
+        file.lines = lines;
+        self.files_done.fetch_add(1, Ordering::Relaxed);
+        self.bytes_done.fetch_add(file.size, Ordering::Relaxed);
+        if failed {
+            self.files_failed.fetch_add(1, Ordering::Relaxed);
+        }
+        // With several readers another input may still be in progress
+        let mut current = self.current.lock().unwrap();
+        if *current == Some(position) {
+            *current = files.iter().position(|file| file.state == FileState::Reading);
+        }
+    }
+
+    // Run `view` on every input in processing order, without copying the list
+    pub fn with_files<R>(&self, view: impl FnOnce(&[FileStatus], Option<usize>) -> R) -> R {
+        let current = *self.current.lock().unwrap();
+        view(&self.files.lock().unwrap(), current)
+    }
+
+    // Record the deduplication totals so far
+    pub fn set_counts(&self, lines: usize, unique: usize) {
+        self.lines.store(lines, Ordering::Relaxed);
+        self.unique.store(unique, Ordering::Relaxed);
+    }
+
+    // Record the stage the merge entered
+    pub fn set_phase(&self, phase: Phase) {
+        *self.phase.lock().unwrap() = phase;
+    }
+
+    // Remember an error, dropping the oldest once RECENT_ERRORS are kept
+    pub fn push_error(&self, path: &Path, detail: impl Into<String>) {
+        let mut errors = self.errors.lock().unwrap();
+        if errors.len() == RECENT_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(RecentError {
+            elapsed_secs: self.started.elapsed().as_secs(),
+            path: path.to_path_buf(),
+            detail: detail.into(),
+        });
+    }
+
+    // Copy the figures and derive throughput and the time left
+    //
+    // The ETA assumes the remaining bytes read at the average rate so far.
+    pub fn snapshot(&self) -> StatusSnapshot {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let (bytes_total, bytes_done) = (self.bytes_total.load(Ordering::Relaxed), self.bytes_done.load(Ordering::Relaxed));
+        let (lines, unique) = (self.lines.load(Ordering::Relaxed), self.unique.load(Ordering::Relaxed));
+        let rate = |amount: f64| if elapsed > 0.0 { amount / elapsed } else { 0.0 };
+        let bytes_per_sec = rate(bytes_done as f64);
+        let phase = *self.phase.lock().unwrap();
+        let eta_secs = match phase {
+            Phase::Reading if bytes_per_sec > 0.0 => Some((bytes_total.saturating_sub(bytes_done) as f64 / bytes_per_sec) as u64),
+            _ => None,
+        };
+        StatusSnapshot {
+            phase,
+            elapsed_secs: elapsed as u64,
+            files_total: self.files_total.load(Ordering::Relaxed),
+            files_done: self.files_done.load(Ordering::Relaxed),
+            files_failed: self.files_failed.load(Ordering::Relaxed),
+            bytes_total,
+            bytes_done,
+            lines,
+            unique,
+            duplicate_percent: report::duplicate_percent(lines, unique),
+            lines_per_sec: rate(lines as f64),
+            bytes_per_sec,
+            eta_secs,
+            current_file: self.with_files(|files, current| current.and_then(|i| files.get(i)).map(|file| file.path.clone())),
+            recent_errors: self.errors.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileState, Phase, RunStatus, RECENT_ERRORS};
+    use std::path::{Path, PathBuf};
+
+    // Status of a merge reading `a.txt` (100 bytes) and `b.txt` (300 bytes)
+    fn two_inputs() -> RunStatus {
+        let status = RunStatus::new();
+        status.set_inputs([(PathBuf::from("a.txt"), 100), (PathBuf::from("b.txt"), 300)]);
+        status
+    }
+
+    #[test]
+    fn finished_inputs_move_the_current_file_on() {
+        let status = two_inputs();
+        status.start_file(0);
+        status.start_file(1);
+        status.finish_file(1, 0, true);
+        assert_eq!(status.snapshot().current_file, Some(PathBuf::from("a.txt")));
+        status.finish_file(0, 12, false);
+
+        let snapshot = status.snapshot();
+        assert_eq!(snapshot.current_file, None);
+        assert_eq!((snapshot.files_total, snapshot.files_done, snapshot.files_failed), (2, 2, 1));
+        assert_eq!((snapshot.bytes_total, snapshot.bytes_done), (400, 400));
+        let states = status.with_files(|files, _| files.iter().map(|file| (file.state, file.lines)).collect::<Vec<_>>());
+        assert_eq!(states, [(FileState::Done, 12), (FileState::Failed, 0)]);
+    }
+
+    #[test]
+    fn snapshots_derive_the_duplicate_share() {
+        let status = two_inputs();
+        status.set_counts(40, 10);
+        status.set_phase(Phase::Writing);
+        let snapshot = status.snapshot();
+        assert_eq!(snapshot.duplicate_percent, 75.0);
+        assert_eq!((snapshot.phase, snapshot.eta_secs), (Phase::Writing, None));
+    }
+
+    #[test]
+    fn only_the_latest_errors_are_kept() {
+        let status = RunStatus::new();
+        for i in 0..RECENT_ERRORS + 2 {
+            status.push_error(Path::new("a.txt"), format!("error {}", i));
+        }
+        let errors = status.snapshot().recent_errors;
+        assert_eq!(errors.len(), RECENT_ERRORS);
+        assert_eq!(errors[0].detail, "error 2");
+    }
+}
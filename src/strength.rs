@@ -0,0 +1,197 @@
+use crate::errors::ConfigError; // Import ConfigError for invalid ranges
+
+pub const MAX_SCORE: u8 = 4; // Strongest score, as in zxcvbn
+
+// Bits of guessing effort at which each score from 1 to 4 starts, the log2 of
+// zxcvbn's 10^3, 10^6, 10^8 and 10^10 guess boundaries
+const SCORE_BITS: [f64; 4] = [9.97, 19.93, 26.58, 33.22];
+const REPEAT_BITS: f64 = 0.5;      // A character repeating the previous one
+const PREDICTABLE_BITS: f64 = 0.5; // A character continuing a sequence or keyboard walk
+const YEAR_BITS: f64 = 7.0;        // A year from 1900 to 2029
+const OTHER_POOL: f64 = 100.0;     // Guessing pool of bytes outside printable ASCII
+
+// Keyboard rows for spotting walks such as `qwerty` or `asdf`
+const KEYBOARD_ROWS: [&[u8]; 4] = [b"1234567890-=", b"qwertyuiop[]", b"asdfghjkl;'", b"zxcvbnm,./"];
+
+// Very common passwords and base words, matched case-insensitively and through basic leet
+const COMMON_WORDS: &[&str] = &[
+    "password", "passw", "qwerty", "letmein", "welcome", "admin", "login", "master", "monkey",
+    "dragon", "iloveyou", "love", "sunshine", "princess", "shadow", "superman", "batman",
+    "football", "baseball", "soccer", "hockey", "trustno", "secret", "freedom", "whatever",
+    "starwars", "pokemon", "computer", "internet", "summer", "winter", "spring", "autumn",
+    "hello", "michael", "jennifer", "jordan", "hunter", "ranger", "killer", "charlie", "thomas",
+    "george", "pepper", "ginger", "cheese", "cookie", "flower", "orange", "banana", "purple",
+    "matrix", "mustang", "harley", "austin", "dallas", "chelsea", "liverpool", "arsenal",
+    "access", "changeme", "default", "guest", "root", "test", "user", "abc", "qwe", "asd", "zxc",
+];
+
+// Allowed range of strength scores, from 0 (trivially guessed) to 4 (very hard to guess)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrengthRange {
+    pub min: u8, // Weakest score kept
+    pub max: u8, // Strongest score kept
+}
+
+impl Default for StrengthRange {
+    fn default() -> Self {
+        Self { min: 0, max: MAX_SCORE }
+    }
+}
+
+impl StrengthRange {
+    // Check that both bounds are scores and the range is not empty
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max > MAX_SCORE || self.min > self.max {
+            return Err(ConfigError::InvalidStrength(format!(
+                "strength range {}-{} must lie within 0-{} with the minimum first",
+                self.min, self.max, MAX_SCORE
+            )));
+        }
+        Ok(())
+    }
+
+    // Whether any candidate can be dropped, so scoring is needed at all
+    pub fn is_active(&self) -> bool {
+        self.min > 0 || self.max < MAX_SCORE
+    }
+
+    // Whether `candidate` scores within the range
+    pub fn admits(&self, candidate: &[u8]) -> bool {
+        (self.min..=self.max).contains(&score(candidate))
+    }
+}
+
+// Strength score of a candidate from 0 to 4, in the spirit of zxcvbn
+pub fn score(candidate: &[u8]) -> u8 {
+    let bits = guess_bits(candidate);
+    SCORE_BITS.iter().take_while(|limit| bits >= **limit).count() as u8
+}
+
+// Estimated log2 of the guesses needed to find `candidate`
+//
+// Each character costs the log2 of the character pool the candidate draws from, except
+// characters that repeat the previous one or continue a sequence or keyboard walk.
+// Common words and years cost a flat amount however long they are.
+pub fn guess_bits(candidate: &[u8]) -> f64 {
+    let pool_bits = pool_size(candidate).log2();
+    let folded: Vec<u8> = candidate.iter().map(|b| unleet(b.to_ascii_lowercase())).collect();
+    let word_bits = (COMMON_WORDS.len() as f64).log2() + 1.0; // One bit for capitalization
+
+    let mut bits = 0.0;
+    let mut i = 0;
+    while i < candidate.len() {
+        if let Some(len) = common_word_at(&folded[i..]) {
+            bits += word_bits;
+            i += len;
+            continue;
+        }
+        if is_year(&candidate[i..]) {
+            bits += YEAR_BITS;
+            i += 4;
+            continue;
+        }
+        bits += match i.checked_sub(1).map(|prev| (candidate[prev], candidate[i])) {
+            Some((prev, current)) if prev == current => REPEAT_BITS,
+            Some((prev, current)) if prev.abs_diff(current) == 1 || keyboard_adjacent(prev, current) => PREDICTABLE_BITS,
+            _ => pool_bits,
+        };
+        i += 1;
+    }
+    bits
+}
+
+// Number of characters an attacker would try per position for this candidate
+fn pool_size(candidate: &[u8]) -> f64 {
+    let has = |test: fn(&u8) -> bool| candidate.iter().any(test);
+    let mut pool = 0.0;
+    if has(u8::is_ascii_lowercase) {
+        pool += 26.0;
+    }
+    if has(u8::is_ascii_uppercase) {
+        pool += 26.0;
+    }
+    if has(u8::is_ascii_digit) {
+        pool += 10.0;
+    }
+    if has(|b| b.is_ascii_punctuation() || *b == b' ') {
+        pool += 33.0;
+    }
+    if has(|b| !b.is_ascii_graphic() && *b != b' ') {
+        pool += OTHER_POOL;
+    }
+    f64::max(pool, 2.0)
+}
+
+// The letter a basic leet substitution stands for
+fn unleet(byte: u8) -> u8 {
+    match byte {
+        b'0' => b'o',
+        b'1' | b'!' => b'i',
+        b'3' => b'e',
+        b'4' | b'@' => b'a',
+        b'5' | b'$' => b's',
+        b'7' => b't',
+        other => other,
+    }
+}
+
+// Length of the longest common word at the start of the folded `rest`
+fn common_word_at(rest: &[u8]) -> Option<usize> {
+    COMMON_WORDS.iter()
+        .map(|word| word.as_bytes())
+        .filter(|word| rest.starts_with(word))
+        .map(<[u8]>::len)
+        .max()
+}
+
+// Whether `rest` starts with a year from 1900 to 2029
+fn is_year(rest: &[u8]) -> bool {
+    rest.len() >= 4
+        && rest[..4].iter().all(u8::is_ascii_digit)
+        && (rest.starts_with(b"19") || (rest.starts_with(b"20") && rest[2] <= b'2'))
+}
+
+// Whether two keys touch on a US keyboard, in the same row or the row above or below
+pub fn keyboard_adjacent(a: u8, b: u8) -> bool {
+    let locate = |key: u8| {
+        let key = key.to_ascii_lowercase();
+        KEYBOARD_ROWS.iter().enumerate().find_map(|(row, keys)| keys.iter().position(|k| *k == key).map(|col| (row, col)))
+    };
+    match (locate(a), locate(b)) {
+        (Some((row_a, col_a)), Some((row_b, col_b))) => {
+            let (rows, cols) = (row_a.abs_diff(row_b), col_a.abs_diff(col_b));
+            (rows == 0 && cols == 1) || (rows == 1 && cols <= 1)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{score, StrengthRange};
+
+    #[test]
+    fn junk_scores_zero() {
+        for junk in ["a", "asdfgh", "qwertyuiop", "aaaaaaaa", "123456789", "password", "P@ssw0rd"] {
+            assert_eq!(score(junk.as_bytes()), 0, "{}", junk);
+        }
+    }
+
+    #[test]
+    fn varied_candidates_score_higher() {
+        assert_eq!(score(b"zjqxvb"), 2);
+        assert_eq!(score(b"x7#Kq!9zLm@2vR"), 4);
+        assert_eq!(score(b"Summer2019"), 1);
+        assert!(score(b"abcdef123") < score(b"hmtpwk382"));
+    }
+
+    #[test]
+    fn ranges_admit_scores_within_their_bounds() {
+        let weak_only = StrengthRange { min: 0, max: 1 };
+        assert!(weak_only.admits(b"asdfgh"));
+        assert!(!weak_only.admits(b"x7#Kq!9zLm@2vR"));
+        assert!(!StrengthRange::default().is_active());
+        assert!(StrengthRange { min: 3, max: 2 }.validate().is_err());
+        assert!(StrengthRange { min: 0, max: 5 }.validate().is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt; // Import fmt to write filters back in their command-line form
+use std::path::{Path, PathBuf}; // Import Path and PathBuf for the extra output paths
+use crate::patterns; // Import patterns for the walk and numeric filters
+use crate::side_output::{parse_range, SideOutput, SideSink}; // Import the shared side output writer and range parser
+use crate::strength; // Import strength for the strength filters
+
+// Condition a line must meet to be written to an extra output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeeFilter {
+    Length(usize, usize), // `length:8-16`, characters from the first to the second count
+    Ascii,                // `ascii`, printable ASCII only
+    MinStrength(u8),      // `min-strength:2`, strength score at least this
+    MaxStrength(u8),      // `max-strength:1`, strength score at most this
+    Walks,                // `walks`, keyboard walks and sequences only
+    NoWalks,              // `no-walks`, anything but keyboard walks and sequences
+    Numeric,              // `numeric`, digits only
+    NoNumeric,            // `no-numeric`, anything but digits only
+}
+
+impl TeeFilter {
+    // Whether `candidate` meets the condition
+    pub fn admits(&self, candidate: &[u8]) -> bool {
+        match *self {
+            TeeFilter::Length(min, max) => (min..=max).contains(&char_count(candidate)),
+            TeeFilter::Ascii => candidate.iter().all(|b| b.is_ascii_graphic() || *b == b' '),
+            TeeFilter::MinStrength(min) => strength::score(candidate) >= min,
+            TeeFilter::MaxStrength(max) => strength::score(candidate) <= max,
+            TeeFilter::Walks => patterns::is_walk(candidate),
+            TeeFilter::NoWalks => !patterns::is_walk(candidate),
+            TeeFilter::Numeric => patterns::is_numeric(candidate),
+            TeeFilter::NoNumeric => !patterns::is_numeric(candidate),
+        }
+    }
+}
+
+impl fmt::Display for TeeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TeeFilter::Length(min, max) => write!(f, "length:{}-{}", min, max),
+            TeeFilter::Ascii => write!(f, "ascii"),
+            TeeFilter::MinStrength(min) => write!(f, "min-strength:{}", min),
+            TeeFilter::MaxStrength(max) => write!(f, "max-strength:{}", max),
+            TeeFilter::Walks => write!(f, "walks"),
+            TeeFilter::NoWalks => write!(f, "no-walks"),
+            TeeFilter::Numeric => write!(f, "numeric"),
+            TeeFilter::NoNumeric => write!(f, "no-numeric"),
+        }
+    }
+}
+
+// Number of UTF-8 characters in `candidate`, counting stray bytes as one each
+fn char_count(candidate: &[u8]) -> usize {
+    candidate.iter().filter(|b| (**b & 0xC0) != 0x80).count()
+}
+
+// Parse one filter such as `length:8-16`, `ascii` or `min-strength:2`
+pub fn parse_filter(value: &str) -> Result<TeeFilter, String> {
+    let (name, argument) = value.trim().split_once(':').map_or((value.trim(), None), |(name, arg)| (name, Some(arg.trim())));
+    let score = |argument: Option<&str>| {
+        argument.and_then(|arg| arg.parse::<u8>().ok()).filter(|score| *score <= strength::MAX_SCORE)
+            .ok_or_else(|| format!("'{}' needs a score from 0 to {}", value, strength::MAX_SCORE))
+    };
+    match (name, argument) {
+        ("length", Some(range)) => parse_range(range).map(|(min, max)| TeeFilter::Length(min, max))
+            .ok_or_else(|| format!("invalid length range '{}': expected length:MIN-MAX such as length:8-16", value)),
+        ("min-strength", _) => Ok(TeeFilter::MinStrength(score(argument)?)),
+        ("max-strength", _) => Ok(TeeFilter::MaxStrength(score(argument)?)),
+        ("ascii", None) => Ok(TeeFilter::Ascii),
+        ("walks", None) => Ok(TeeFilter::Walks),
+        ("no-walks", None) => Ok(TeeFilter::NoWalks),
+        ("numeric", None) => Ok(TeeFilter::Numeric),
+        ("no-numeric", None) => Ok(TeeFilter::NoNumeric),
+        _ => Err(format!(
+            "unknown filter '{}': expected length:MIN-MAX, ascii, min-strength:N, max-strength:N, walks, no-walks, numeric or no-numeric",
+            value
+        )),
+    }
+}
+
+// An extra text output receiving the unique lines that pass all of its filters
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeeOutput {
+    pub path: PathBuf,           // File receiving the lines
+    pub filters: Vec<TeeFilter>, // Conditions every line must meet; none for every line
+}
+
+impl fmt::Display for TeeOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        for (position, filter) in self.filters.iter().enumerate() {
+            write!(f, "{}{}", if position == 0 { '=' } else { ',' }, filter)?;
+        }
+        Ok(())
+    }
+}
+
+// Parse an extra output such as `8to16.txt=length:8-16,ascii`, or a bare path for every line
+pub fn parse_tee(value: &str) -> Result<TeeOutput, String> {
+    let Some((path, filters)) = value.rsplit_once('=') else {
+        return Ok(TeeOutput { path: PathBuf::from(value), filters: Vec::new() });
+    };
+    if path.is_empty() {
+        return Err(format!("'{}' names no file before '='", value));
+    }
+    let filters = filters.split(',').filter(|filter| !filter.trim().is_empty()).map(parse_filter).collect::<Result<_, _>>()?;
+    Ok(TeeOutput { path: PathBuf::from(path), filters })
+}
+
+impl SideOutput for TeeOutput {
+    const DIVERTS: bool = false;
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn takes(&self, candidate: &[u8]) -> bool {
+        self.filters.iter().all(|filter| filter.admits(candidate))
+    }
+
+    fn summary(&self, lines: u64, size: &str) -> String {
+        format!("{} lines ({}) written to {}", lines, size, self.path.display())
+    }
+}
+
+// Output that writes every line on to the main output and to each extra output it passes
+pub type TeeSink = SideSink<TeeOutput>;
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_filter, parse_tee, TeeFilter};
+    use std::path::Path;
+
+    #[test]
+    fn outputs_parse_back_to_their_written_form() {
+        let output = parse_tee("8to16.txt=length:8-16, ascii,min-strength:2").unwrap();
+        assert_eq!(output.path, Path::new("8to16.txt"));
+        assert_eq!(output.filters, [TeeFilter::Length(8, 16), TeeFilter::Ascii, TeeFilter::MinStrength(2)]);
+        assert_eq!(output.to_string(), "8to16.txt=length:8-16,ascii,min-strength:2");
+        assert!(parse_tee("all.txt").unwrap().filters.is_empty());
+    }
+
+    #[test]
+    fn invalid_filters_are_refused() {
+        assert!(parse_tee("=ascii").is_err());
+        assert!(parse_filter("length:16-8").is_err());
+        assert!(parse_filter("min-strength:5").is_err());
+        assert!(parse_filter("ascii:1").is_err());
+        assert!(parse_filter("shiny").is_err());
+    }
+
+    #[test]
+    fn filters_admit_matching_lines() {
+        assert!(TeeFilter::Length(4, 4).admits("café".as_bytes())); // Characters, not bytes
+        assert!(!TeeFilter::Ascii.admits("café".as_bytes()));
+        assert!(TeeFilter::Ascii.admits(b"pass word!"));
+        assert!(TeeFilter::Walks.admits(b"qwerty"));
+        assert!(TeeFilter::NoNumeric.admits(b"pin1234"));
+        assert!(!TeeFilter::MinStrength(1).admits(b"asdfgh"));
+    }
+}
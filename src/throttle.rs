@@ -0,0 +1,126 @@
+use std::sync::Mutex; // Import Mutex for the shared schedule
+use std::time::{Duration, Instant}; // Import Duration and Instant to schedule charged bytes
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0; // Binary megabytes, like the size options
+const BURST: Duration = Duration::from_millis(250); // Transfer allowed ahead of the schedule before callers wait
+const METER_STEP: u64 = 64 * 1024; // Bytes a Meter gathers before charging its limit
+
+// Shared cap on the bytes per second of one kind of I/O, read or write
+//
+// Every caller charges the bytes it moved and then waits until the schedule has caught up,
+// so concurrent readers or writers share the one rate between them.
+#[derive(Debug)]
+pub struct RateLimit {
+    bytes_per_sec: f64,         // Allowed throughput
+    next_free: Mutex<Instant>,  // When every byte charged so far has been paid for
+}
+
+impl RateLimit {
+    // Limit of `mbps` megabytes per second, which must be positive
+    pub fn new(mbps: f64) -> Result<Self, String> {
+        if !(mbps.is_finite() && mbps > 0.0) {
+            return Err(format!("rate must be a positive number of MB/s, got {}", mbps));
+        }
+        Ok(Self { bytes_per_sec: mbps * BYTES_PER_MB, next_free: Mutex::new(Instant::now()) })
+    }
+
+    // Charge `bytes` and return how long the caller must wait
+    fn charge(&self, bytes: u64) -> Duration {
+        let now = Instant::now();
+        let mut next_free = self.next_free.lock().unwrap();
+        // Idle time is not banked, so a quiet spell does not allow a long burst afterwards
+        *next_free = (*next_free).max(now) + Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec);
+        next_free.saturating_duration_since(now + BURST)
+    }
+
+    // Charge `bytes` and wait for the schedule, from async code
+    pub async fn pace(&self, bytes: u64) {
+        let wait = self.charge(bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Charge `bytes` and wait for the schedule, from a blocking thread
+    pub fn pace_blocking(&self, bytes: u64) {
+        let wait = self.charge(bytes);
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+// Parse a rate in MB/s such as `50` or `0.5`
+pub fn parse_rate(value: &str) -> Result<f64, String> {
+    let mbps: f64 = value.trim().parse()
+        .map_err(|_| format!("invalid rate '{}': expected a number of MB/s", value))?;
+    RateLimit::new(mbps).map(|_| mbps)
+}
+
+// Tally of bytes read or written one line at a time, charged to a limit in steps
+//
+// Charging each line would take the lock millions of times; a step of 64KB keeps
+// the pace smooth at a fraction of the cost.
+pub struct Meter<'a> {
+    limit: Option<&'a RateLimit>, // Limit charged, if any
+    pending: u64,                 // Bytes not charged yet
+}
+
+impl<'a> Meter<'a> {
+    pub fn new(limit: Option<&'a RateLimit>) -> Self {
+        Self { limit, pending: 0 }
+    }
+
+    // Count `bytes` moved, waiting whenever a step is charged, from a blocking thread
+    pub fn add(&mut self, bytes: usize) {
+        if let Some((limit, due)) = self.step(bytes) {
+            limit.pace_blocking(due);
+        }
+    }
+
+    // Count `bytes` moved, waiting whenever a step is charged, from async code
+    pub async fn add_async(&mut self, bytes: usize) {
+        if let Some((limit, due)) = self.step(bytes) {
+            limit.pace(due).await;
+        }
+    }
+
+    // The limit and the bytes to charge it once a step has gathered
+    fn step(&mut self, bytes: usize) -> Option<(&'a RateLimit, u64)> {
+        let limit = self.limit?;
+        self.pending += bytes as u64;
+        (self.pending >= METER_STEP).then(|| (limit, std::mem::take(&mut self.pending)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rate, Meter, RateLimit, BYTES_PER_MB, METER_STEP};
+    use std::time::Duration;
+
+    #[test]
+    fn charges_past_the_burst_wait_for_the_schedule() {
+        let limit = RateLimit::new(1.0).unwrap();
+        assert_eq!(limit.charge(BYTES_PER_MB as u64 / 8), Duration::ZERO); // Within the 250ms burst
+        let wait = limit.charge(BYTES_PER_MB as u64);
+        assert!(wait > Duration::from_millis(800) && wait <= Duration::from_millis(875), "{:?}", wait);
+    }
+
+    #[test]
+    fn meters_charge_in_steps() {
+        let limit = RateLimit::new(1.0).unwrap();
+        let mut meter = Meter::new(Some(&limit));
+        assert!(meter.step(METER_STEP as usize - 1).is_none());
+        let (_, due) = meter.step(2).unwrap();
+        assert_eq!(due, METER_STEP + 1);
+        assert!(Meter::new(None).step(usize::MAX).is_none());
+    }
+
+    #[test]
+    fn rates_must_be_positive_numbers() {
+        assert_eq!(parse_rate(" 0.5 "), Ok(0.5));
+        for invalid in ["0", "-5", "fast", "inf", "NaN"] {
+            assert!(parse_rate(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
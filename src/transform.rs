@@ -0,0 +1,200 @@
+use clap::ValueEnum; // Import ValueEnum to choose transforms on the command line
+use serde::{Deserialize, Serialize}; // Import serde traits for the config file
+use crate::errors::ConfigError; // Import ConfigError for invalid templates
+use crate::side_output::parse_range; // Import the shared range parser for year ranges
+
+// A rewrite producing an extra candidate from each word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transform {
+    Lower,       // `Password` -> `password`
+    Upper,       // `password` -> `PASSWORD`
+    Capitalize,  // `pASSWORD` -> `Password`
+    ToggleFirst, // `password` -> `Password`, `Password` -> `password`
+    LeetBasic,   // `password` -> `p455w0rd`: a=4, e=3, i=1, o=0, s=5, t=7
+    Reverse,     // `password` -> `drowssap`
+}
+
+impl Transform {
+    // The rewritten word, or None when the transform leaves it unchanged
+    pub fn apply(self, word: &str) -> Option<String> {
+        let rewritten = match self {
+            Transform::Lower => word.to_lowercase(),
+            Transform::Upper => word.to_uppercase(),
+            Transform::Capitalize => {
+                let mut chars = word.chars();
+                let first = chars.next()?;
+                first.to_uppercase().chain(chars.as_str().to_lowercase().chars()).collect()
+            }
+            Transform::ToggleFirst => {
+                let mut chars = word.chars();
+                let first = chars.next()?;
+                let toggled: String = if first.is_uppercase() {
+                    first.to_lowercase().collect()
+                } else {
+                    first.to_uppercase().collect()
+                };
+                toggled + chars.as_str()
+            }
+            Transform::LeetBasic => word.chars().map(leet).collect(),
+            Transform::Reverse => word.chars().rev().collect(),
+        };
+        (rewritten != word).then_some(rewritten)
+    }
+}
+
+// Every distinct rewrite of `word` by `transforms`, in the order the transforms are listed
+pub fn variants(transforms: &[Transform], word: &str) -> Vec<String> {
+    let mut variants: Vec<String> = Vec::with_capacity(transforms.len());
+    for transform in transforms {
+        if let Some(variant) = transform.apply(word) {
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+    }
+    variants
+}
+
+// The basic leet substitute of a character
+fn leet(c: char) -> char {
+    match c.to_ascii_lowercase() {
+        'a' => '4',
+        'e' => '3',
+        'i' => '1',
+        'o' => '0',
+        's' => '5',
+        't' => '7',
+        _ => c,
+    }
+}
+
+pub const WORD_PLACEHOLDER: &str = "{word}"; // Replaced by the word in templates
+pub const SUFFIX_PLACEHOLDER: &str = "{suffix}"; // Replaced by each year or string in templates
+const DEFAULT_TEMPLATE: &str = "{word}{suffix}"; // Appends every suffix when no template is given
+
+// Inclusive range of years such as `2015-2025`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YearRange {
+    pub first: u16, // First year emitted
+    pub last: u16,  // Last year emitted
+}
+
+// Parse a year range such as `2015-2025`, or a single year
+pub fn parse_years(value: &str) -> Result<YearRange, String> {
+    parse_range(value).map(|(first, last)| YearRange { first, last })
+        .ok_or_else(|| format!("invalid year range '{}': expected FIRST-LAST such as 2015-2025", value))
+}
+
+// Hybrid candidates built from each word by filling in templates
+#[derive(Debug, Clone, Default)]
+pub struct Expansion {
+    suffixes: Vec<String>,  // Years and strings substituted for `{suffix}`
+    templates: Vec<String>, // Templates holding `{word}` and optionally `{suffix}`
+}
+
+impl Expansion {
+    // Combine the years and strings into one suffix list and check the templates
+    pub fn new(years: Option<YearRange>, strings: Vec<String>, templates: Vec<String>) -> Result<Self, ConfigError> {
+        let mut suffixes: Vec<String> = years
+            .map_or_else(Vec::new, |years| (years.first..=years.last).map(|year| year.to_string()).collect());
+        for string in strings {
+            if !suffixes.contains(&string) {
+                suffixes.push(string);
+            }
+        }
+
+        let templates = if templates.is_empty() && !suffixes.is_empty() {
+            vec![DEFAULT_TEMPLATE.to_string()]
+        } else {
+            templates
+        };
+        for template in &templates {
+            if !template.contains(WORD_PLACEHOLDER) {
+                return Err(ConfigError::InvalidTemplate(format!("'{}' has no {} placeholder", template, WORD_PLACEHOLDER)));
+            }
+            if template.contains(SUFFIX_PLACEHOLDER) && suffixes.is_empty() {
+                return Err(ConfigError::InvalidTemplate(format!("'{}' uses {} but no --append-years or --append-strings were given", template, SUFFIX_PLACEHOLDER)));
+            }
+        }
+        Ok(Self { suffixes, templates })
+    }
+
+    // Whether no candidates are generated
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    // Pass every candidate the templates build from `word` to `emit`
+    pub fn expand(&self, word: &str, mut emit: impl FnMut(&str)) {
+        // The word goes in last so placeholders inside it are left alone
+        for template in &self.templates {
+            if !template.contains(SUFFIX_PLACEHOLDER) {
+                emit(&template.replace(WORD_PLACEHOLDER, word));
+                continue;
+            }
+            for suffix in &self.suffixes {
+                emit(&template.replace(SUFFIX_PLACEHOLDER, suffix).replace(WORD_PLACEHOLDER, word));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_years, variants, Expansion, Transform, YearRange};
+
+    // Every candidate `expansion` builds from `word`
+    fn expand(expansion: &Expansion, word: &str) -> Vec<String> {
+        let mut built = Vec::new();
+        expansion.expand(word, |candidate| built.push(candidate.to_string()));
+        built
+    }
+
+    #[test]
+    fn transforms_rewrite_the_case_and_letters() {
+        assert_eq!(Transform::Capitalize.apply("pASSWORD").as_deref(), Some("Password"));
+        assert_eq!(Transform::ToggleFirst.apply("Password").as_deref(), Some("password"));
+        assert_eq!(Transform::LeetBasic.apply("Testing").as_deref(), Some("73571ng"));
+        assert_eq!(Transform::Upper.apply("émile").as_deref(), Some("ÉMILE"));
+        assert_eq!(Transform::Lower.apply("password"), None); // Unchanged words give no variant
+        assert_eq!(Transform::Capitalize.apply(""), None);
+    }
+
+    #[test]
+    fn variants_are_distinct_and_in_transform_order() {
+        let transforms = [Transform::Upper, Transform::Capitalize, Transform::ToggleFirst, Transform::Lower];
+        assert_eq!(variants(&transforms, "summer"), ["SUMMER", "Summer"]);
+    }
+
+    #[test]
+    fn year_ranges_parse_one_year_or_first_to_last() {
+        assert_eq!(parse_years("2023-2025"), Ok(YearRange { first: 2023, last: 2025 }));
+        assert_eq!(parse_years("2024"), Ok(YearRange { first: 2024, last: 2024 }));
+        assert!(parse_years("2025-2023").is_err());
+    }
+
+    #[test]
+    fn suffixes_are_appended_without_templates() {
+        let years = Some(YearRange { first: 2023, last: 2024 });
+        let expansion = Expansion::new(years, vec!["!".to_string(), "2024".to_string()], Vec::new()).unwrap();
+        assert_eq!(expand(&expansion, "summer"), ["summer2023", "summer2024", "summer!"]);
+    }
+
+    #[test]
+    fn templates_place_the_word_and_each_suffix() {
+        let templates = vec!["{suffix}{word}".to_string(), "{word}_corp".to_string()];
+        let expansion = Expansion::new(None, vec!["@".to_string()], templates).unwrap();
+        assert_eq!(expand(&expansion, "{suffix}"), ["@{suffix}", "{suffix}_corp"]);
+        assert!(Expansion::new(None, Vec::new(), vec!["{word}{suffix}".to_string()]).is_err());
+        assert!(Expansion::new(None, vec!["1".to_string()], vec!["{suffix}".to_string()]).is_err());
+        assert!(Expansion::new(None, Vec::new(), Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reversal_keeps_characters_whole() {
+        assert_eq!(Transform::Reverse.apply("password").as_deref(), Some("drowssap"));
+        assert_eq!(Transform::Reverse.apply("café1").as_deref(), Some("1éfac"));
+        assert_eq!(Transform::Reverse.apply("racecar"), None); // Palindromes give no variant
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::VecDeque; // Import VecDeque for the captured output lines
+use std::io::{self, Write}; // Import io for terminal errors and writes
+use std::sync::atomic::{AtomicBool, Ordering}; // Import AtomicBool to tell the drawing thread to stop
+use std::sync::{Arc, Mutex}; // Import Arc and Mutex to share state with the drawing thread
+use std::thread::JoinHandle; // Import JoinHandle for the drawing thread
+use std::time::Duration; // Import Duration for the refresh interval
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers}; // Import key events to stop the merge
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen}; // Import raw mode and the alternate screen
+use crossterm::{cursor, execute}; // Import cursor control and command execution
+use ratatui::backend::CrosstermBackend; // Import the crossterm backend for ratatui
+use ratatui::layout::{Constraint, Layout, Rect}; // Import layout types to split the screen into panes
+use ratatui::style::{Color, Modifier, Style}; // Import styles for states and headers
+use ratatui::text::Line; // Import Line for pane text
+use ratatui::widgets::{Block, Gauge, Paragraph, Row, Table}; // Import the widgets the panes are built from
+use ratatui::{Frame, Terminal}; // Import the terminal and frame to draw on
+use crate::app_state::AppState; // Import AppState to request a graceful stop
+use crate::estimate::{format_bytes, format_duration}; // Reuse the byte and duration formatters
+use crate::memory_monitor; // Import memory_monitor for the resident set size
+use crate::status::{FileState, Phase, RunStatus, StatusSnapshot}; // Import the live figures the panes show
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250); // Time between redraws
+const OUTPUT_LINES: usize = 200; // Captured output lines kept for the output pane
+
+type DashboardTerminal = Terminal<CrosstermBackend<Box<dyn Write + Send>>>;
+type OutputLines = Arc<Mutex<VecDeque<String>>>; // Latest captured output lines, oldest first
+
+// Full-screen dashboard drawn on the terminal while a merge runs
+pub struct Dashboard {
+    stop: Arc<AtomicBool>,            // Set to make the drawing thread restore the terminal
+    task: JoinHandle<io::Result<()>>, // Drawing thread
+    #[cfg(unix)]
+    capture: capture::OutputCapture,  // Output printed while the dashboard is open
+}
+
+impl Dashboard {
+    // Take over the terminal and draw `status` until stopped
+    //
+    // On Unix, output printed meanwhile is shown in a pane and printed again once the dashboard closes.
+    pub fn start(status: Arc<RunStatus>, app_state: Arc<AppState>) -> io::Result<Self> {
+        let mut terminal = open_terminal()
+            .map_err(|e| io::Error::new(e.kind(), format!("--tui needs an interactive terminal: {}", e)))?;
+        let output: OutputLines = Arc::default();
+        #[cfg(unix)]
+        let capture = match capture::OutputCapture::start(output.clone()) {
+            Ok(capture) => capture,
+            Err(e) => {
+                let _ = close_terminal(&mut terminal);
+                return Err(e);
+            }
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let runtime = tokio::runtime::Handle::current();
+        let task = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let drawn = draw_until_stopped(&mut terminal, &status, &app_state, &output, &stop, &runtime);
+                close_terminal(&mut terminal)?;
+                drawn
+            })
+        };
+        Ok(Self {
+            stop,
+            task,
+            #[cfg(unix)]
+            capture,
+        })
+    }
+
+    // Restore the terminal and print the output held back while the dashboard was open
+    pub fn stop(self) -> io::Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        let drawn = self.task.join().unwrap_or_else(|_| Err(io::Error::other("the dashboard thread panicked")));
+        #[cfg(unix)]
+        self.capture.finish()?;
+        drawn
+    }
+}
+
+// Switch the terminal to raw mode and the alternate screen
+fn open_terminal() -> io::Result<DashboardTerminal> {
+    // Drawing goes to the terminal itself so captured stdout and stderr never mix with the panes
+    #[cfg(unix)]
+    let mut writer: Box<dyn Write + Send> = Box::new(std::fs::OpenOptions::new().write(true).open("/dev/tty")?);
+    #[cfg(not(unix))]
+    let mut writer: Box<dyn Write + Send> = Box::new(io::stdout());
+    terminal::enable_raw_mode()?;
+    execute!(writer, EnterAlternateScreen, cursor::Hide)?;
+    Terminal::new(CrosstermBackend::new(writer))
+}
+
+// Give the terminal back in the state it was found
+fn close_terminal(terminal: &mut DashboardTerminal) -> io::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, cursor::Show)
+}
+
+// Redraw every REFRESH_INTERVAL until `stop` is set; `q` or Ctrl+C stops the merge after the current file
+fn draw_until_stopped(
+    terminal: &mut DashboardTerminal,
+    status: &RunStatus,
+    app_state: &Arc<AppState>,
+    output: &OutputLines,
+    stop: &AtomicBool,
+    runtime: &tokio::runtime::Handle,
+) -> io::Result<()> {
+    let mut stopping = false;
+    while !stop.load(Ordering::Relaxed) {
+        terminal.draw(|frame| draw(frame, status, output, stopping))?;
+        if !event::poll(REFRESH_INTERVAL)? {
+            continue;
+        }
+        // Raw mode turns Ctrl+C into a key press, so it is handled here like the interrupt signal
+        if let Event::Key(key) = event::read()? {
+            let quit = key.code == KeyCode::Char('q')
+                || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL));
+            if quit && key.kind == KeyEventKind::Press && !stopping {
+                stopping = true;
+                let app_state = app_state.clone();
+                runtime.spawn(async move {
+                    if let Err(e) = app_state.save_progress().await {
+                        log::error!("Failed to save progress: {}", e);
+                    }
+                    app_state.request_shutdown().await;
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// Lay out and draw every pane
+fn draw(frame: &mut Frame, status: &RunStatus, output: &OutputLines, stopping: bool) {
+    let snapshot = status.snapshot();
+    let [overall, figures, files, feeds, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(8),
+        Constraint::Min(5),
+        Constraint::Length(9),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+    let [counts, memory] = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(figures);
+    let [errors, captured] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(feeds);
+
+    draw_overall(frame, overall, &snapshot);
+    draw_counts(frame, counts, &snapshot);
+    draw_memory(frame, memory);
+    draw_files(frame, files, status);
+    draw_errors(frame, errors, &snapshot);
+    draw_output(frame, captured, output);
+
+    let hint = if stopping {
+        " Stopping after the current file; progress is saved"
+    } else {
+        " q or Ctrl+C: stop after the current file and save progress"
+    };
+    frame.render_widget(Paragraph::new(hint).style(Style::default().fg(Color::DarkGray)), footer);
+}
+
+// Gauge of the input bytes read
+fn draw_overall(frame: &mut Frame, area: Rect, snapshot: &StatusSnapshot) {
+    let ratio = if snapshot.bytes_total > 0 {
+        (snapshot.bytes_done as f64 / snapshot.bytes_total as f64).min(1.0)
+    } else {
+        0.0
+    };
+    let phase = match snapshot.phase {
+        Phase::Reading => "reading inputs",
+        Phase::Writing => "writing output",
+        Phase::Done => "done",
+        Phase::Interrupted => "interrupted",
+    };
+    let label = format!(
+        "{}/{} files, {} of {} ({:.1}%)",
+        snapshot.files_done, snapshot.files_total, format_bytes(snapshot.bytes_done), format_bytes(snapshot.bytes_total), ratio * 100.0
+    );
+    let gauge = Gauge::default()
+        .block(Block::bordered().title(format!(" rustmerger: {} ", phase)))
+        .gauge_style(Style::default().fg(Color::Cyan))
+        .ratio(ratio)
+        .label(label);
+    frame.render_widget(gauge, area);
+}
+
+// Line counts, throughput and timing
+fn draw_counts(frame: &mut Frame, area: Rect, snapshot: &StatusSnapshot) {
+    let duplicates = snapshot.lines.saturating_sub(snapshot.unique);
+    let lines = vec![
+        Line::from(format!("Lines read     {}", snapshot.lines)),
+        Line::from(format!("Unique lines   {}", snapshot.unique)),
+        Line::from(format!("Duplicates     {} ({:.1}%)", duplicates, snapshot.duplicate_percent)),
+        Line::from(format!("Throughput     {:.0} lines/s, {}/s", snapshot.lines_per_sec, format_bytes(snapshot.bytes_per_sec as u64))),
+        Line::from(format!("Elapsed        {}", format_duration(snapshot.elapsed_secs))),
+        Line::from(format!("ETA            {}", snapshot.eta_secs.map_or_else(|| "-".to_string(), format_duration))),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Progress ")), area);
+}
+
+// Memory used by the merge and left on the machine
+fn draw_memory(frame: &mut Frame, area: Rect) {
+    let [text, bar] = Layout::vertical([Constraint::Min(2), Constraint::Length(1)])
+        .areas(Block::bordered().inner(area));
+    frame.render_widget(Block::bordered().title(" Memory "), area);
+
+    let resident = memory_monitor::resident_set_size().map_or_else(|| "unknown".to_string(), format_bytes);
+    let mut lines = vec![Line::from(format!("This process   {}", resident))];
+    let mut used = None;
+    if let Ok(info) = sys_info::mem_info() {
+        let (total, available) = (info.total * 1024, info.avail * 1024);
+        lines.push(Line::from(format!("Available      {} of {}", format_bytes(available), format_bytes(total))));
+        used = (total > 0).then(|| 1.0 - available as f64 / total as f64);
+    }
+    frame.render_widget(Paragraph::new(lines), text);
+    if let Some(used) = used {
+        let color = if used > 0.9 { Color::Red } else { Color::Green };
+        let gauge = Gauge::default().gauge_style(Style::default().fg(color)).ratio(used.clamp(0.0, 1.0))
+            .label(format!("system {:.0}% used", used * 100.0));
+        frame.render_widget(gauge, bar);
+    }
+}
+
+// Inputs around the one being read, with their state and line counts
+fn draw_files(frame: &mut Frame, area: Rect, status: &RunStatus) {
+    let visible = area.height.saturating_sub(3) as usize; // Borders and header row
+    let (rows, title) = status.with_files(|files, current| {
+        let done = files.iter().filter(|file| matches!(file.state, FileState::Done | FileState::Failed)).count();
+        // Keep the input being read a third of the way down, with what finished just before it above
+        let anchor = current.unwrap_or(done.min(files.len().saturating_sub(1)));
+        let start = anchor.saturating_sub(visible / 3).min(files.len().saturating_sub(visible));
+        let rows: Vec<Row> = files.iter().skip(start).take(visible)
+            .map(|file| {
+                let (state, style) = match file.state {
+                    FileState::Pending => ("pending", Style::default().fg(Color::DarkGray)),
+                    FileState::Reading => ("reading", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                    FileState::Done => ("done", Style::default().fg(Color::Green)),
+                    FileState::Failed => ("failed", Style::default().fg(Color::Red)),
+                };
+                let lines = if file.state == FileState::Done { file.lines.to_string() } else { String::new() };
+                Row::new(vec![state.to_string(), format_bytes(file.size), lines, file.path.display().to_string()]).style(style)
+            })
+            .collect();
+        (rows, format!(" Files {}/{} ", done, files.len()))
+    });
+    let widths = [Constraint::Length(8), Constraint::Length(10), Constraint::Length(12), Constraint::Fill(1)];
+    let table = Table::new(rows, widths)
+        .header(Row::new(vec!["State", "Size", "Lines", "File"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::bordered().title(title));
+    frame.render_widget(table, area);
+}
+
+// Failed and degraded files, newest first
+fn draw_errors(frame: &mut Frame, area: Rect, snapshot: &StatusSnapshot) {
+    let lines: Vec<Line> = snapshot.recent_errors.iter().rev()
+        .map(|error| Line::from(format!("{} {}: {}", format_duration(error.elapsed_secs), error.path.display(), error.detail)))
+        .collect();
+    let title = format!(" Errors ({} failed) ", snapshot.files_failed);
+    let style = if lines.is_empty() { Style::default() } else { Style::default().fg(Color::Red) };
+    frame.render_widget(Paragraph::new(lines).style(style).block(Block::bordered().title(title)), area);
+}
+
+// The latest output printed while the dashboard is open
+fn draw_output(frame: &mut Frame, area: Rect, output: &OutputLines) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let output = output.lock().unwrap();
+    let lines: Vec<Line> = output.iter().skip(output.len().saturating_sub(visible)).map(|line| Line::from(line.clone())).collect();
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Output ")), area);
+}
+
+// Keep a captured line for the output pane, without terminal escape sequences
+fn push_output(output: &OutputLines, line: &[u8]) {
+    let text = String::from_utf8_lossy(line);
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                // Skip a CSI sequence such as a color change, up to its final letter
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            '\r' => {}
+            c => plain.push(c),
+        }
+    }
+    let mut output = output.lock().unwrap();
+    if output.len() == OUTPUT_LINES {
+        output.pop_front();
+    }
+    output.push_back(plain);
+}
+
+// Redirection of stdout and stderr into a pipe while the dashboard owns the terminal
+#[cfg(unix)]
+mod capture {
+    use std::fs::File; // Import File to read the pipe
+    use std::io::{self, Read, Write}; // Import io to drain the pipe and replay it
+    use std::os::unix::io::FromRawFd; // Import FromRawFd to wrap the pipe's read end
+    use std::thread::JoinHandle; // Import JoinHandle for the draining thread
+    use super::{push_output, OutputLines}; // Import the output pane's line store
+
+    // Output held back while the dashboard is open
+    pub struct OutputCapture {
+        saved: [libc::c_int; 2],     // Copies of the original stdout and stderr
+        reader: JoinHandle<Vec<u8>>, // Thread draining the pipe; returns everything read
+    }
+
+    impl OutputCapture {
+        // Point stdout and stderr at a pipe whose lines are fed to `output`
+        pub fn start(output: OutputLines) -> io::Result<Self> {
+            io::stdout().flush()?;
+            let mut fds = [0; 2];
+            // SAFETY: plain descriptor calls; every result is checked before use
+            unsafe {
+                if libc::pipe(fds.as_mut_ptr()) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let saved = [libc::dup(libc::STDOUT_FILENO), libc::dup(libc::STDERR_FILENO)];
+                if saved.contains(&-1)
+                    || libc::dup2(fds[1], libc::STDOUT_FILENO) == -1
+                    || libc::dup2(fds[1], libc::STDERR_FILENO) == -1
+                {
+                    let error = io::Error::last_os_error();
+                    restore(saved);
+                    libc::close(fds[0]);
+                    libc::close(fds[1]);
+                    return Err(error);
+                }
+                libc::close(fds[1]); // Only stdout and stderr write to the pipe now
+                let mut pipe = File::from_raw_fd(fds[0]);
+                let reader = std::thread::spawn(move || {
+                    let (mut captured, mut buffer, mut line_start) = (Vec::new(), [0u8; 8192], 0);
+                    while let Ok(read) = pipe.read(&mut buffer) {
+                        if read == 0 {
+                            break;
+                        }
+                        captured.extend_from_slice(&buffer[..read]);
+                        while let Some(end) = memchr::memchr(b'\n', &captured[line_start..]) {
+                            push_output(&output, &captured[line_start..line_start + end]);
+                            line_start += end + 1;
+                        }
+                    }
+                    captured
+                });
+                Ok(Self { saved, reader })
+            }
+        }
+
+        // Point stdout and stderr back at the terminal and print what was captured
+        pub fn finish(self) -> io::Result<()> {
+            io::stdout().flush()?;
+            io::stderr().flush()?;
+            // SAFETY: the saved descriptors are open copies made in start
+            unsafe { restore(self.saved) }; // Closes the pipe's last write ends, so the reader sees the end
+            let captured = self.reader.join().unwrap_or_default();
+            let mut stdout = io::stdout();
+            stdout.write_all(&captured)?;
+            stdout.flush()
+        }
+    }
+
+    // Put the original stdout and stderr back and close the copies
+    unsafe fn restore(saved: [libc::c_int; 2]) {
+        for (copy, target) in saved.into_iter().zip([libc::STDOUT_FILENO, libc::STDERR_FILENO]) {
+            if copy != -1 {
+                libc::dup2(copy, target);
+                libc::close(copy);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{draw, push_output, OutputLines, OUTPUT_LINES};
+    use crate::status::RunStatus;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn captured_lines_lose_escapes_and_only_the_latest_are_kept() {
+        let output = OutputLines::default();
+        push_output(&output, b"\x1b[32mINFO\x1b[0m merged\r");
+        assert_eq!(output.lock().unwrap()[0], "INFO merged");
+        for i in 0..OUTPUT_LINES {
+            push_output(&output, format!("line {}", i).as_bytes());
+        }
+        let output = output.lock().unwrap();
+        assert_eq!(output.len(), OUTPUT_LINES);
+        assert_eq!(output[0], "line 0");
+    }
+
+    #[test]
+    fn every_pane_is_drawn() {
+        let status = RunStatus::new();
+        status.set_inputs([(PathBuf::from("words.txt"), 100)]);
+        status.start_file(0);
+        status.push_error(Path::new("gone.txt"), "not found");
+        let output = OutputLines::default();
+        push_output(&output, b"captured line");
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|frame| draw(frame, &status, &output, true)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        for text in ["words.txt", "gone.txt", "captured line", "Stopping after the current file"] {
+            assert!(screen.contains(text), "{} is not on screen", text);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize}; // Import serde traits so the knobs can be set in the config
+use crate::errors::ConfigError; // Import ConfigError for rejected values
+
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024 * 10; // 10MB chunks
+const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024 * 32; // 32MB buffer
+const DEFAULT_CHANNEL_SIZE: usize = 1000; // Number of chunks to keep in memory
+const DEFAULT_PARALLEL_FILES: usize = 4; // Number of files to process in parallel
+const DEFAULT_READ_CONCURRENCY: usize = 1; // Inputs read at once, which suits spinning disks
+
+// Buffer sizes, chunk sizes and concurrency used while reading and deduplicating inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    pub chunk_size: usize,          // Bytes (and at most as many lines) a reader buffers before sending
+    pub buffer_size: usize,         // Read buffer size in bytes for inputs that cannot be memory-mapped
+    pub channel_size: usize,        // Chunks queued per shard before readers wait
+    pub parallel_files: usize,      // Files scheduled per batch
+    pub read_concurrency: usize,    // Inputs read at the same time
+    pub cpu_workers: Option<usize>, // Shards hashing and deduplicating lines; None follows the thread count
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            channel_size: DEFAULT_CHANNEL_SIZE,
+            parallel_files: DEFAULT_PARALLEL_FILES,
+            read_concurrency: DEFAULT_READ_CONCURRENCY,
+            cpu_workers: None,
+        }
+    }
+}
+
+impl Tuning {
+    // Reject knobs that would stall or break processing
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let knobs = [
+            ("chunk_size", self.chunk_size),
+            ("buffer_size", self.buffer_size),
+            ("channel_size", self.channel_size),
+            ("parallel_files", self.parallel_files),
+            ("read_concurrency", self.read_concurrency),
+            ("cpu_workers", self.cpu_workers.unwrap_or(1)),
+        ];
+        match knobs.iter().find(|(_, value)| *value == 0) {
+            Some((name, _)) => Err(ConfigError::InvalidTuning(format!("{} must be greater than 0", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+// Parse a size such as `4096`, `64K`, `10M` or `1G` (binary units) into bytes
+pub fn parse_size(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1024),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024 * 1024),
+        Some((i, 'g' | 'G')) => (&value[..i], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    let number: usize = digits.trim().parse()
+        .map_err(|_| format!("invalid size '{}': expected a number with an optional K, M or G suffix", value))?;
+    number.checked_mul(multiplier)
+        .ok_or_else(|| format!("size '{}' is too large", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_size, Tuning};
+
+    #[test]
+    fn sizes_take_binary_suffixes() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size(" 64k "), Ok(64 * 1024));
+        assert_eq!(parse_size("10M"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("10T").is_err());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size(&format!("{}G", usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn zero_knobs_are_refused() {
+        assert!(Tuning::default().validate().is_ok());
+        let tuning = Tuning { read_concurrency: 0, ..Tuning::default() };
+        assert!(tuning.validate().unwrap_err().to_string().contains("read_concurrency"));
+        assert!(Tuning { cpu_workers: Some(0), ..Tuning::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn config_keys_left_out_keep_their_defaults() {
+        let tuning: Tuning = serde_json::from_str(r#"{"chunk_size": 4096}"#).unwrap();
+        assert_eq!(tuning, Tuning { chunk_size: 4096, ..Tuning::default() });
+        assert!(serde_json::from_str::<Tuning>(r#"{"chunk": 4096}"#).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::net::SocketAddr; // Import SocketAddr for the listening address
+use std::sync::Arc; // Import Arc to share the run status with connections
+use tokio::io::{AsyncReadExt, AsyncWriteExt}; // Import async read and write for the HTTP exchange
+use tokio::net::{TcpListener, TcpStream}; // Import the TCP listener and connections
+use tokio::task::JoinHandle; // Import JoinHandle so the server can be stopped
+use crate::status::RunStatus; // Import RunStatus, the figures the page shows
+
+const MAX_REQUEST_BYTES: usize = 8192; // Longest request head read before giving up
+
+// Status page: polls /status.json and fills in the figures
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>rustmerger</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 52em; padding: 0 1em; color: #222; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; }
+th { width: 12em; font-weight: 600; }
+progress { width: 100%; height: 1.2em; }
+.muted { color: #888; }
+</style>
+</head>
+<body>
+<h1>rustmerger <span id="phase" class="muted"></span></h1>
+<progress id="bar" max="1" value="0"></progress>
+<table>
+<tr><th>Files</th><td id="files"></td></tr>
+<tr><th>Input read</th><td id="bytes"></td></tr>
+<tr><th>Lines</th><td id="lines"></td></tr>
+<tr><th>Unique lines</th><td id="unique"></td></tr>
+<tr><th>Duplicates</th><td id="duplicates"></td></tr>
+<tr><th>Throughput</th><td id="rate"></td></tr>
+<tr><th>Elapsed</th><td id="elapsed"></td></tr>
+<tr><th>ETA</th><td id="eta"></td></tr>
+<tr><th>Reading</th><td id="current"></td></tr>
+</table>
+<h2>Recent errors</h2>
+<table id="errors"></table>
+<p id="state" class="muted"></p>
+<script>
+const units = ["B", "KB", "MB", "GB", "TB"];
+function size(bytes) {
+  let i = 0;
+  while (bytes >= 1024 && i < units.length - 1) { bytes /= 1024; i++; }
+  return bytes.toFixed(i ? 2 : 0) + " " + units[i];
+}
+function duration(secs) {
+  const h = Math.floor(secs / 3600), m = Math.floor(secs % 3600 / 60), s = secs % 60;
+  return (h ? h + "h " : "") + (h || m ? m + "m " : "") + s + "s";
+}
+function set(id, text) { document.getElementById(id).textContent = text; }
+async function refresh() {
+  try {
+    const s = await (await fetch("status.json", { cache: "no-store" })).json();
+    set("phase", s.phase);
+    const bar = document.getElementById("bar");
+    bar.value = s.bytes_total ? s.bytes_done / s.bytes_total : 0;
+    set("files", s.files_done + " / " + s.files_total + (s.files_failed ? " (" + s.files_failed + " failed)" : ""));
+    set("bytes", size(s.bytes_done) + " / " + size(s.bytes_total));
+    set("lines", s.lines.toLocaleString());
+    set("unique", s.unique.toLocaleString());
+    set("duplicates", s.duplicate_percent.toFixed(1) + "%");
+    set("rate", Math.round(s.lines_per_sec).toLocaleString() + " lines/s, " + size(s.bytes_per_sec) + "/s");
+    set("elapsed", duration(s.elapsed_secs));
+    set("eta", s.eta_secs === null ? "-" : duration(s.eta_secs));
+    set("current", s.current_file || "-");
+    const errors = document.getElementById("errors");
+    errors.replaceChildren(...s.recent_errors.slice().reverse().map(e => {
+      const row = document.createElement("tr");
+      for (const text of [duration(e.elapsed_secs), e.path, e.detail]) {
+        const cell = document.createElement("td");
+        cell.textContent = text;
+        row.appendChild(cell);
+      }
+      return row;
+    }));
+    if (!s.recent_errors.length) set("errors", "");
+    set("state", "Updated " + new Date().toLocaleTimeString());
+  } catch (e) {
+    set("state", "The merge is no longer reachable");
+  }
+}
+refresh();
+setInterval(refresh, 2000);
+</script>
+</body>
+</html>
+"#;
+
+// Read-only HTTP status page of a running merge
+pub struct WebUi {
+    task: JoinHandle<()>, // Accept loop serving the page
+}
+
+impl WebUi {
+    // Listen on `addr` and serve `status` until stopped
+    //
+    // Binding happens before returning, so an address in use fails the merge before it starts.
+    pub async fn start(addr: SocketAddr, status: Arc<RunStatus>) -> std::io::Result<(Self, SocketAddr)> {
+        let listener = TcpListener::bind(addr).await?;
+        let local = listener.local_addr()?;
+        let task = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else { continue };
+                let status = status.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, &status).await {
+                        log::debug!("Status page connection failed: {}", e);
+                    }
+                });
+            }
+        });
+        Ok((Self { task }, local))
+    }
+
+    // Stop accepting connections
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+// Answer one request: the page, the JSON figures, or an error
+async fn serve(mut stream: TcpStream, status: &RunStatus) -> std::io::Result<()> {
+    let mut request = Vec::with_capacity(1024);
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&request);
+    let mut parts = head.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+    let (code, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.as_bytes().to_vec()),
+        ("GET", "/status.json") => {
+            let body = serde_json::to_vec(&status.snapshot()).map_err(std::io::Error::other)?;
+            ("200 OK", "application/json", body)
+        }
+        ("GET", _) => ("404 Not Found", "text/plain; charset=utf-8", b"Not found\n".to_vec()),
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", b"Only GET is supported\n".to_vec()),
+    };
+
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        code, content_type, body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WebUi;
+    use crate::status::RunStatus;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    // Send `request` to the page at `addr` and return the whole response
+    async fn fetch(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn serves_the_page_and_the_figures() {
+        let status = Arc::new(RunStatus::new());
+        status.set_counts(40, 10);
+        let (web_ui, addr) = WebUi::start("127.0.0.1:0".parse().unwrap(), status).await.unwrap();
+
+        let page = fetch(addr, "GET / HTTP/1.1\r\nHost: x\r\n\r\n").await;
+        assert!(page.starts_with("HTTP/1.1 200 OK") && page.contains("text/html"));
+
+        let figures = fetch(addr, "GET /status.json?t=1 HTTP/1.1\r\n\r\n").await;
+        let (head, body) = figures.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains("application/json"));
+        let snapshot: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!((snapshot["lines"].as_u64(), snapshot["unique"].as_u64()), (Some(40), Some(10)));
+
+        assert!(fetch(addr, "GET /missing HTTP/1.1\r\n\r\n").await.starts_with("HTTP/1.1 404"));
+        assert!(fetch(addr, "POST / HTTP/1.1\r\n\r\n").await.starts_with("HTTP/1.1 405"));
+        web_ui.stop();
+    }
+}